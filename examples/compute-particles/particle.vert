@@ -0,0 +1,9 @@
+out vec3 v_color;
+
+void main() {
+    vec4 particle = texelFetch(particles, ivec2(gl_VertexID, 0), 0);
+    gl_Position = vec4(particle.xy, 0.0, 1.0);
+    gl_PointSize = 2.0;
+    float speed = length(particle.zw);
+    v_color = mix(vec3(0.1, 0.3, 1.0), vec3(1.0, 0.5, 0.1), clamp(speed, 0.0, 1.0)) * 0.5;
+}
@@ -0,0 +1,26 @@
+// Each invocation moves one particle. xy is the position in clip space and zw the
+// velocity, both start at zero, so the first frame scatters the particles.
+layout(local_size_x = 64) in;
+
+float hash(float n) {
+    return fract(sin(n) * 43758.5453);
+}
+
+void main() {
+    ivec2 id = ivec2(gl_GlobalInvocationID.xy);
+    if (id.x >= int(particles_Resolution.x)) {
+        return;
+    }
+    vec4 particle = imageLoad(particles, id);
+    float seed = float(id.x);
+    if (iFrame < 1.0 || abs(particle.x) > 1.2 || abs(particle.y) > 1.2) {
+        float angle = 6.2831853 * hash(seed + iTime);
+        particle = vec4(0.0, 0.0, vec2(cos(angle), sin(angle)) * (0.1 + 0.4 * hash(seed * 1.7 + iTime)));
+    }
+    // Pull towards the mouse while a button is down
+    vec2 mouse = iMouse.xy / iWindowResolution.xy * 2.0 - 1.0;
+    vec2 force = iMouse.z > 0.0 ? normalize(mouse - particle.xy + 1e-5) * 2.0 : vec2(0.0, -0.5);
+    particle.zw += force * iTimeDelta;
+    particle.xy += particle.zw * iTimeDelta;
+    imageStore(particles, id, particle);
+}
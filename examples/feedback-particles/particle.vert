@@ -0,0 +1,12 @@
+in vec2 position;
+in vec2 velocity;
+in float age;
+
+out vec3 v_color;
+
+void main() {
+    gl_Position = vec4(position, 0.0, 1.0);
+    gl_PointSize = 2.0;
+    float fade = 1.0 - age / 4.0;
+    v_color = mix(vec3(1.0, 0.5, 0.1), vec3(0.1, 0.3, 1.0), clamp(length(velocity), 0.0, 1.0)) * fade * 0.5;
+}
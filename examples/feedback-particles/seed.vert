@@ -0,0 +1,15 @@
+// Scatter the particles through their lifetime so that they don't all respawn at once
+out vec2 position;
+out vec2 velocity;
+out float age;
+
+float hash(float n) {
+    return fract(sin(n) * 43758.5453);
+}
+
+void main() {
+    float seed = float(gl_VertexID);
+    position = vec2(0.0, -0.8);
+    velocity = vec2(hash(seed) - 0.5, 1.0 + hash(seed * 1.3)) * 0.6;
+    age = hash(seed * 1.7) * 4.0;
+}
@@ -0,0 +1,26 @@
+// Each vertex is one particle, which falls under gravity and respawns at the fountain
+// when it grows too old
+in vec2 position;
+in vec2 velocity;
+in float age;
+
+out vec2 o_position;
+out vec2 o_velocity;
+out float o_age;
+
+float hash(float n) {
+    return fract(sin(n) * 43758.5453);
+}
+
+void main() {
+    float dt = min(iTimeDelta, 0.1);
+    o_position = position + velocity * dt;
+    o_velocity = velocity + vec2(0.0, -0.8) * dt;
+    o_age = age + dt;
+    if (o_age > 4.0) {
+        float seed = float(gl_VertexID) + iTime;
+        o_position = vec2(0.0, -0.8);
+        o_velocity = vec2(hash(seed) - 0.5, 1.0 + hash(seed * 1.3)) * 0.6;
+        o_age = 0.0;
+    }
+}
@@ -0,0 +1,30 @@
+/*{
+    "DESCRIPTION": "A ring that fades into a persistent feedback buffer",
+    "ISFVSN": "2",
+    "CATEGORIES": ["Generator"],
+    "INPUTS": [
+        {"NAME": "decay", "TYPE": "float", "DEFAULT": 0.95, "MIN": 0.5, "MAX": 1.0},
+        {"NAME": "radius", "TYPE": "float", "DEFAULT": 0.25, "MIN": 0.0, "MAX": 0.5},
+        {"NAME": "center", "TYPE": "point2D", "DEFAULT": [0.5, 0.5]},
+        {"NAME": "tint", "TYPE": "color", "DEFAULT": [1.0, 0.6, 0.2, 1.0]},
+        {"NAME": "invert", "TYPE": "bool", "DEFAULT": false}
+    ],
+    "PASSES": [
+        {"TARGET": "accum", "PERSISTENT": true, "FLOAT": true},
+        {}
+    ]
+}*/
+
+void main() {
+    if (PASSINDEX == 0) {
+        vec2 uv = isf_FragNormCoord;
+        vec2 orbit = center + 0.1 * vec2(cos(TIME), sin(TIME));
+        vec2 p = (uv - orbit) * RENDERSIZE / RENDERSIZE.y;
+        float ring = smoothstep(0.01, 0.0, abs(length(p) - radius));
+        vec4 previous = IMG_THIS_NORM_PIXEL(accum);
+        gl_FragColor = max(previous * decay, ring * tint);
+    } else {
+        vec4 color = IMG_THIS_NORM_PIXEL(accum);
+        gl_FragColor = invert ? vec4(1.0 - color.rgb, 1.0) : vec4(color.rgb, 1.0);
+    }
+}
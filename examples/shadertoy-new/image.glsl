@@ -6,6 +6,3 @@ void mainImage(out vec4 fragColor, in vec2 fragCoord) {
   // Output to screen
   fragColor = vec4(col, 1.0);
 }
-
-out vec4 fragColor;
-void main() { mainImage(fragColor, gl_FragCoord.xy); }
@@ -0,0 +1,9 @@
+// The spectrum of the sound in the first row of iChannel0, the waveform in the second
+void mainImage(out vec4 fragColor, in vec2 fragCoord) {
+    vec2 uv = fragCoord / iResolution.xy;
+    float fft = texture(iChannel0, vec2(uv.x, 0.25)).x;
+    float wave = texture(iChannel0, vec2(uv.x, 0.75)).x;
+    vec3 col = vec3(0.1, 0.4, 0.8) * step(uv.y, fft);
+    col += vec3(1.0) * (1.0 - smoothstep(0.0, 0.01, abs(wave - uv.y)));
+    fragColor = vec4(col, 1.0);
+}
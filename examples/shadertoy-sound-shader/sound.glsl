@@ -0,0 +1,10 @@
+// A kick drum and an arpeggio, 120 beats per minute
+vec2 mainSound(int samp, float time) {
+    float beat = fract(time * 2.0);
+    float kick = sin(6.2831 * 50.0 * beat - 10.0 * exp(-30.0 * beat)) * exp(-6.0 * beat);
+    float notes[4] = float[](220.0, 277.18, 329.63, 440.0);
+    float arpTime = fract(time * 8.0);
+    float note = notes[int(time * 8.0) % 4];
+    float arp = sin(6.2831 * note * time) * exp(-4.0 * arpTime) * 0.3;
+    return vec2(kick * 0.6 + arp, kick * 0.6 + arp * 0.8);
+}
@@ -0,0 +1,8 @@
+out vec3 v_color;
+
+void main() {
+    gl_Position = vec4(boids[gl_VertexID].position, 0.0, 1.0);
+    gl_PointSize = 3.0;
+    vec2 heading = normalize(boids[gl_VertexID].velocity + 1e-5);
+    v_color = vec3(0.5 + 0.5 * heading, 1.0);
+}
@@ -0,0 +1,51 @@
+// Each invocation steers one boid towards the center and heading of its neighbours,
+// and away from the ones that are too close. The buffer starts zeroed, so the first
+// frame scatters the boids.
+layout(local_size_x = 64) in;
+
+float hash(float n) {
+    return fract(sin(n) * 43758.5453);
+}
+
+void main() {
+    uint id = gl_GlobalInvocationID.x;
+    if (id >= uint(boids.length())) {
+        return;
+    }
+    float seed = float(id);
+    if (iFrame < 1.0) {
+        boids[id].position = vec2(hash(seed), hash(seed * 1.3)) * 2.0 - 1.0;
+        boids[id].velocity = vec2(hash(seed * 1.7), hash(seed * 2.1)) - 0.5;
+        return;
+    }
+    vec2 position = boids[id].position;
+    vec2 velocity = boids[id].velocity;
+    vec2 center = vec2(0.0);
+    vec2 heading = vec2(0.0);
+    vec2 separation = vec2(0.0);
+    float neighbours = 0.0;
+    for (int i = 0; i < boids.length(); i++) {
+        vec2 offset = boids[i].position - position;
+        float dist = length(offset);
+        if (uint(i) == id || dist > 0.15) {
+            continue;
+        }
+        center += boids[i].position;
+        heading += boids[i].velocity;
+        separation -= offset / max(dist * dist, 1e-4);
+        neighbours += 1.0;
+    }
+    if (neighbours > 0.0) {
+        velocity += (center / neighbours - position) * 0.5 * iTimeDelta;
+        velocity += (heading / neighbours - velocity) * 1.0 * iTimeDelta;
+        velocity += separation * 0.0005 * iTimeDelta;
+    }
+    float speed = clamp(length(velocity), 0.1, 0.5);
+    velocity = normalize(velocity + 1e-5) * speed;
+    position += velocity * iTimeDelta;
+    // Wrap around the edges of the window
+    position = mod(position + 1.0, 2.0) - 1.0;
+    // Other boids may already have moved this frame, which a flock doesn't mind
+    boids[id].position = position;
+    boids[id].velocity = velocity;
+}
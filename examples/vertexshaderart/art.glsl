@@ -0,0 +1,22 @@
+// Each vertex samples the spectrum at its angle around the spiral, and older rows
+// of the sound history move outwards
+#define PI 3.14159265359
+
+void main() {
+  float t = vertexId / vertexCount;
+  float angle = t * PI * 2.0 * 24.0;
+  float band = fract(t * 24.0);
+  float history = floor(t * 24.0) / 24.0;
+  float level = texture2D(sound, vec2(band * 0.25, history)).r;
+
+  float radius = 0.2 + history * 0.6 + level * 0.2;
+  vec2 xy = vec2(cos(angle), sin(angle)) * radius;
+  xy.x *= resolution.y / resolution.x;
+  xy += mouse * 0.1;
+  gl_Position = vec4(xy, history, 1.0);
+  gl_PointSize = 2.0;
+
+  float hue = fract(time * 0.05 + band);
+  vec3 color = clamp(abs(fract(hue + vec3(0.0, 2.0, 1.0) / 3.0) * 6.0 - 3.0) - 1.0, 0.0, 1.0);
+  v_color = vec4(color * (0.3 + level), 1.0) * (1.0 - history);
+}
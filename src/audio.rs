@@ -16,7 +16,12 @@ use std::sync::Mutex;
 #[derive(Debug)]
 pub struct Audio {
     pipeline: gst::Element,
+    // set for sounds generated by a shader, whose samples are pushed in by push_samples
+    source: Option<gst_app::AppSrc>,
+    // sample index of the first pushed sample, which plays at the start of the pipeline
+    first_sample: Option<u64>,
     receiver: Receiver<ResourceData2D>,
+    sample_rate: u64,
     bands: usize,
     previous_fft: Vec<u8>,
 }
@@ -25,8 +30,8 @@ pub struct Audio {
 // https://developer.mozilla.org/en-US/docs/Web/API/AnalyserNode/minDecibels
 // https://developer.mozilla.org/en-US/docs/Web/API/AnalyserNode/maxDecibels
 // https://developer.mozilla.org/en-US/docs/Web/API/AnalyserNode/smoothingTimeConstant
-static MIN_DB: f32 = -100.0;
-static MAX_DB: f32 = -30.0;
+pub(crate) static MIN_DB: f32 = -100.0;
+pub(crate) static MAX_DB: f32 = -30.0;
 static SMOOTH: f32 = 0.8;
 
 impl Audio {
@@ -51,6 +56,64 @@ impl Audio {
         Audio::from_pipeline(&pipeline, bands)
     }
 
+    /// Play the stereo samples given to `push_samples`, and analyse them like an audio file
+    pub fn new_sound(sample_rate: u32, bands: usize) -> Result<Self> {
+        let pipeline = format!(
+                "appsrc name=soundsrc format=time \
+                    caps=audio/x-raw,format=F32LE,layout=interleaved,channels=2,rate={rate} ! tee name=t ! \
+                queue ! audioconvert ! audioresample ! audio/x-raw,format=U8,channels=1 ! appsink name=appsink async=false sync=true t. ! \
+                queue ! audioconvert ! audioresample ! audio/x-raw,channels=1  ! spectrum bands={bands} threshold={thresh} interval=16000000 \
+                    post-messages=true message-magnitude=true ! fakesink async=false sync=true t. ! \
+                queue ! audioconvert ! audioresample ! autoaudiosink async=true
+                ", rate=sample_rate, bands=2*bands, thresh=MIN_DB);
+        let mut sound = Audio::from_pipeline(&pipeline, bands)?;
+        let source = sound
+            .pipeline
+            .clone()
+            .dynamic_cast::<gst::Bin>()
+            .map_err(|_| Error::bug("[GRIMOIRE/AUDIO] Expected the pipeline to be a bin"))?
+            .get_by_name("soundsrc")
+            .ok_or_else(|| {
+                Error::bug(
+                    "[GRIMOIRE/AUDIO] Pipeline does not contain element with name 'soundsrc'",
+                )
+            })?
+            .dynamic_cast::<gst_app::AppSrc>()
+            .map_err(|_| Error::bug("[GRIMOIRE/AUDIO] Expected source element to be an appsrc"))?;
+        sound.source = Some(source);
+        sound.sample_rate = u64::from(sample_rate);
+        Ok(sound)
+    }
+
+    /// Queue interleaved stereo samples of a generated sound, starting at the sample
+    /// index `start`, to be played when the pipeline clock reaches them
+    pub fn push_samples(&mut self, start: u64, samples: &[f32]) -> Result<()> {
+        let source = match self.source {
+            Some(ref source) => source,
+            None => return Ok(()),
+        };
+        let first_sample = *self.first_sample.get_or_insert(start);
+        let frames = samples.len() as u64 / 2;
+        let pts = start.saturating_sub(first_sample) * 1_000_000_000 / self.sample_rate;
+        let duration = frames * 1_000_000_000 / self.sample_rate;
+        let mut bytes = Vec::with_capacity(samples.len() * 4);
+        for sample in samples {
+            bytes.extend_from_slice(&sample.to_le_bytes());
+        }
+        let mut buffer = gst::Buffer::from_mut_slice(bytes);
+        {
+            let buffer = buffer
+                .get_mut()
+                .ok_or_else(|| Error::bug("[GRIMOIRE/AUDIO] Expected a writable buffer"))?;
+            buffer.set_pts(gst::ClockTime::from_nseconds(pts));
+            buffer.set_duration(gst::ClockTime::from_nseconds(duration));
+        }
+        source
+            .push_buffer(buffer)
+            .map_err(|e| Error::gstreamer(format!("error pushing sound samples: {:?}", e)))?;
+        Ok(())
+    }
+
     pub fn from_pipeline(pipeline: &str, bands: usize) -> Result<Self> {
         let pipeline = gst::parse_launch(&pipeline).map_err(|e| Error::gstreamer(e.to_string()))?;
         pipeline
@@ -59,7 +122,7 @@ impl Audio {
         let sink = pipeline
             .clone()
             .dynamic_cast::<gst::Bin>()
-            .unwrap()
+            .map_err(|_| Error::bug("[GRIMOIRE/AUDIO] Expected the pipeline to be a bin"))?
             .get_by_name("appsink")
             .ok_or_else(|| {
                 Error::bug("[GRIMOIRE/AUDIO] Pipelink does not contain element with name 'appsink'")
@@ -71,6 +134,9 @@ impl Audio {
         let receiver = gst_sample_receiver_from_appsink(&appsink, bands)?;
         Ok(Self {
             pipeline,
+            source: None,
+            first_sample: None,
+            sample_rate: 0,
             bands,
             receiver,
             previous_fft: vec![0; bands],
@@ -100,6 +166,19 @@ impl Stream for Audio {
     }
 
     fn restart(&mut self) -> Result<()> {
+        if self.source.is_some() {
+            // A generated sound can't seek, so start the pipeline over and let the
+            // samples be pushed again from the first one
+            self.first_sample = None;
+            let (_, state, _) = self.pipeline.get_state(gst::ClockTime::from_seconds(0));
+            self.pipeline
+                .set_state(gst::State::Ready)
+                .map_err(|e| Error::gstreamer(e.to_string()))?;
+            self.pipeline
+                .set_state(state)
+                .map_err(|e| Error::gstreamer(e.to_string()))?;
+            return Ok(());
+        }
         self.pipeline
             .seek_simple(
                 gst::SeekFlags::FLUSH | gst::SeekFlags::KEY_UNIT,
@@ -0,0 +1,51 @@
+/// Vertex shader of passes that don't name one, which draws a fullscreen triangle
+pub const FULLSCREEN_VERTEX: &str = "builtin:fullscreen.vert";
+
+/// Vertex shader of ISF passes without a `.vs` file of their own
+pub const ISF_VERTEX: &str = "builtin:isf.vs";
+
+/// Fragment shader of passes that don't name one, which draws the `v_color` output of
+/// the vertex shader like vertexshaderart
+pub const VERTEX_COLOR_FRAGMENT: &str = "builtin:color.frag";
+
+/// Fragment shader of passes that capture their vertex shader outputs without naming
+/// one, and of seed shaders, which draw nothing
+pub const DISCARD_FRAGMENT: &str = "builtin:discard.frag";
+
+/// Appended to the fragment shader of passes that use `FULLSCREEN_VERTEX`, so that the
+/// shader only has to define Shadertoy's `mainImage`
+pub const MAIN_IMAGE_WRAPPER: &str = "out vec4 grim_FragColor;
+void main() { mainImage(grim_FragColor, gl_FragCoord.xy); }
+";
+
+/// Appended to the shaders of sound resources, which define Shadertoy's `mainSound`.
+/// Each pixel of the block is one stereo sample, counted from `grim_SoundBlockStart`.
+pub const MAIN_SOUND_WRAPPER: &str = "uniform int grim_SoundBlockStart;
+out vec4 grim_FragColor;
+void main() {
+  ivec2 pixel = ivec2(gl_FragCoord.xy);
+  int samp = grim_SoundBlockStart + pixel.y * GRIM_SOUND_BLOCK_WIDTH + pixel.x;
+  grim_FragColor = vec4(mainSound(samp, float(samp) / iSampleRate), 0.0, 1.0);
+}
+";
+
+const FULLSCREEN_VERTEX_SOURCE: &str = "void main() {
+    float x = -1.0 + float((gl_VertexID & 1) << 2);
+    float y = -1.0 + float((gl_VertexID & 2) << 1);
+    gl_Position = vec4(x, y, 0, 1);
+}
+";
+
+/// The source of a shader built into grimoire, which passes reference by a `builtin:`
+/// path instead of a file
+pub fn shader(path: &str) -> Option<&'static str> {
+    match path {
+        FULLSCREEN_VERTEX => Some(FULLSCREEN_VERTEX_SOURCE),
+        ISF_VERTEX => Some("void main() { isf_vertShaderInit(); }\n"),
+        VERTEX_COLOR_FRAGMENT => Some(
+            "in vec4 v_color;\nout vec4 grim_FragColor;\nvoid main() { grim_FragColor = v_color; }\n",
+        ),
+        DISCARD_FRAGMENT => Some("out vec4 grim_FragColor;\nvoid main() { discard; }\n"),
+        _ => None,
+    }
+}
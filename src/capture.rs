@@ -0,0 +1,445 @@
+use crate::config::BufferFormat;
+use crate::error::{Error, Result};
+use crate::gl;
+use crate::gl::{GLRc, GLenum, GLuint};
+use std::io::Write;
+use std::path::Path;
+
+/// The pixel format that captured frames are read back in, and the file type they are saved as
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CaptureFormat {
+    /// 8-bit RGB PNG
+    Png,
+    /// 8-bit RGBA PNG
+    PngRgba,
+    /// 16-bit RGBA PNG
+    Png16,
+    /// 32-bit float RGBA OpenEXR, for F16 and F32 buffers
+    Exr,
+}
+
+/// A color attachment of a framebuffer to capture
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CaptureTarget {
+    pub framebuffer: GLuint,
+    /// The glReadBuffer mode: BACK for the window, otherwise a COLOR_ATTACHMENTi
+    pub read_buffer: GLenum,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// One color attachment dumped by `capture_frame`
+#[derive(Debug, Clone, PartialEq)]
+pub struct BufferAttachment {
+    /// The buffer resource name, or "window"
+    pub name: String,
+    /// "read" or "write" for ping-pong buffers, None otherwise
+    pub side: Option<&'static str>,
+    pub attachment: usize,
+    pub format: BufferFormat,
+    pub target: CaptureTarget,
+}
+
+/// Describes a frame written by `capture_frame`, saved alongside it as manifest.json
+#[derive(Debug, Serialize)]
+pub struct FrameManifest {
+    pub frame: u32,
+    pub time: f32,
+    pub passes: Vec<PassManifest>,
+    pub attachments: Vec<AttachmentManifest>,
+}
+
+/// A [[pass]] in draw order
+#[derive(Debug, Serialize)]
+pub struct PassManifest {
+    pub index: usize,
+    pub vertex: String,
+    pub fragment: String,
+    /// The compute shader of a compute pass, which dispatches instead of drawing
+    pub compute: Option<String>,
+    /// The buffer the pass draws into, None for the window
+    pub buffer: Option<String>,
+    pub width: u32,
+    pub height: u32,
+    pub disable: bool,
+    #[serde(rename = "loop")]
+    pub loop_count: u32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AttachmentManifest {
+    pub buffer: String,
+    pub side: Option<&'static str>,
+    pub attachment: usize,
+    pub format: BufferFormat,
+    pub width: u32,
+    pub height: u32,
+    pub file: String,
+}
+
+impl BufferAttachment {
+    /// U8 attachments are saved as RGBA PNG, F16 and F32 as OpenEXR so nothing is clamped
+    pub fn capture_format(&self) -> CaptureFormat {
+        match self.format {
+            BufferFormat::U8 => CaptureFormat::PngRgba,
+            BufferFormat::F16 | BufferFormat::F32 => CaptureFormat::Exr,
+        }
+    }
+
+    /// `name.attachment.ext`, or `name.side.attachment.ext` for ping-pong buffers
+    pub fn file_name(&self) -> String {
+        match self.side {
+            Some(side) => format!(
+                "{}.{}.{}.{}",
+                self.name,
+                side,
+                self.attachment,
+                self.capture_format().extension()
+            ),
+            None => format!(
+                "{}.{}.{}",
+                self.name,
+                self.attachment,
+                self.capture_format().extension()
+            ),
+        }
+    }
+}
+
+impl CaptureFormat {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "png" => Some(CaptureFormat::Png),
+            "png-rgba" => Some(CaptureFormat::PngRgba),
+            "png16" => Some(CaptureFormat::Png16),
+            "exr" => Some(CaptureFormat::Exr),
+            _ => None,
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            CaptureFormat::Png | CaptureFormat::PngRgba | CaptureFormat::Png16 => "png",
+            CaptureFormat::Exr => "exr",
+        }
+    }
+
+    pub fn gl_format(self) -> GLenum {
+        match self {
+            CaptureFormat::Png => gl::RGB,
+            _ => gl::RGBA,
+        }
+    }
+
+    pub fn gl_type(self) -> GLenum {
+        match self {
+            CaptureFormat::Png | CaptureFormat::PngRgba => gl::UNSIGNED_BYTE,
+            CaptureFormat::Png16 => gl::UNSIGNED_SHORT,
+            CaptureFormat::Exr => gl::FLOAT,
+        }
+    }
+
+    pub fn bytes_per_pixel(self) -> usize {
+        match self {
+            CaptureFormat::Png => 3,
+            CaptureFormat::PngRgba => 4,
+            CaptureFormat::Png16 => 8,
+            CaptureFormat::Exr => 16,
+        }
+    }
+
+    /// Save pixels read back with this format to `path`.
+    /// The rows are flipped in place from glReadPixels' bottom-up order.
+    pub fn save(self, path: &Path, pixels: &mut [u8], width: u32, height: u32) -> Result<()> {
+        flip_rows(pixels, width as usize * self.bytes_per_pixel());
+        match self {
+            CaptureFormat::Png => {
+                image::save_buffer(path, pixels, width, height, image::RGB(8))
+                    .map_err(|err| Error::io(path, err))
+            }
+            CaptureFormat::PngRgba => {
+                image::save_buffer(path, pixels, width, height, image::RGBA(8))
+                    .map_err(|err| Error::io(path, err))
+            }
+            CaptureFormat::Png16 => {
+                // PNG stores 16-bit samples big-endian
+                for sample in pixels.chunks_mut(2) {
+                    let value = u16::from_ne_bytes([sample[0], sample[1]]);
+                    sample.copy_from_slice(&value.to_be_bytes());
+                }
+                image::save_buffer(path, pixels, width, height, image::RGBA(16))
+                    .map_err(|err| Error::io(path, err))
+            }
+            CaptureFormat::Exr => write_exr(path, pixels, width, height),
+        }
+    }
+}
+
+/// Blocking read of the pixels of `target`, bottom-up
+pub fn read_pixels(gl: &GLRc, target: CaptureTarget, format: CaptureFormat) -> Vec<u8> {
+    let mut pixels =
+        vec![0; target.width as usize * target.height as usize * format.bytes_per_pixel()];
+    bind_read_target(gl, target);
+    gl.read_pixels_into_buffer(
+        0,
+        0,
+        target.width as i32,
+        target.height as i32,
+        format.gl_format(),
+        format.gl_type(),
+        pixels.as_mut_slice(),
+    );
+    gl.bind_framebuffer(gl::READ_FRAMEBUFFER, 0);
+    pixels
+}
+
+/// Blocking read of every attachment into `dir`, followed by manifest.json
+/// listing the files along with the pass order and resolutions
+pub fn capture_frame(
+    gl: &GLRc,
+    dir: &Path,
+    mut manifest: FrameManifest,
+    attachments: &[BufferAttachment],
+) -> Result<()> {
+    std::fs::create_dir_all(dir).map_err(|err| Error::io(dir, err))?;
+    for attachment in attachments {
+        let format = attachment.capture_format();
+        let target = attachment.target;
+        let file = attachment.file_name();
+        let mut pixels = read_pixels(gl, target, format);
+        format.save(&dir.join(&file), &mut pixels, target.width, target.height)?;
+        manifest.attachments.push(AttachmentManifest {
+            buffer: attachment.name.clone(),
+            side: attachment.side,
+            attachment: attachment.attachment,
+            format: attachment.format,
+            width: target.width,
+            height: target.height,
+            file,
+        });
+    }
+    let manifest_path = dir.join("manifest.json");
+    let json = serde_json::to_string_pretty(&manifest)
+        .map_err(|err| Error::bug(format!("Failed to serialize the capture manifest: {}", err)))?;
+    std::fs::write(&manifest_path, json).map_err(|err| Error::io(&manifest_path, err))
+}
+
+/// Bind `target` for glReadPixels with tightly packed rows
+pub fn bind_read_target(gl: &GLRc, target: CaptureTarget) {
+    gl.pixel_store_i(gl::PACK_ALIGNMENT, 1);
+    gl.bind_framebuffer(gl::READ_FRAMEBUFFER, target.framebuffer);
+    gl.read_buffer(target.read_buffer);
+}
+
+/// glReadPixels returns rows bottom-up, images expect them top-down
+pub fn flip_rows(pixels: &mut [u8], row_len: usize) {
+    let height = pixels.len() / row_len;
+    for y in 0..height / 2 {
+        let (top, bottom) = pixels.split_at_mut((height - 1 - y) * row_len);
+        top[y * row_len..(y + 1) * row_len].swap_with_slice(&mut bottom[..row_len]);
+    }
+}
+
+/// Write top-down RGBA f32 pixels as an uncompressed, single part, scanline OpenEXR file
+fn write_exr(path: &Path, pixels: &[u8], width: u32, height: u32) -> Result<()> {
+    std::fs::File::create(path)
+        .and_then(|mut f| f.write_all(&exr_bytes(pixels, width, height)))
+        .map_err(|err| Error::io(path, err))
+}
+
+/// The OpenEXR file of top-down RGBA f32 pixels
+fn exr_bytes(pixels: &[u8], width: u32, height: u32) -> Vec<u8> {
+    fn attribute(header: &mut Vec<u8>, name: &str, kind: &str, value: &[u8]) {
+        header.extend_from_slice(name.as_bytes());
+        header.push(0);
+        header.extend_from_slice(kind.as_bytes());
+        header.push(0);
+        header.extend_from_slice(&(value.len() as i32).to_le_bytes());
+        header.extend_from_slice(value);
+    }
+    // Channels must be stored in alphabetical order
+    const CHANNELS: [(&str, usize); 4] = [("A", 3), ("B", 2), ("G", 1), ("R", 0)];
+    let mut channels = Vec::new();
+    for (name, _) in CHANNELS.iter() {
+        channels.extend_from_slice(name.as_bytes());
+        channels.push(0);
+        // pixel type FLOAT, pLinear and 3 reserved bytes, x and y sampling
+        channels.extend_from_slice(&2i32.to_le_bytes());
+        channels.extend_from_slice(&[0, 0, 0, 0]);
+        channels.extend_from_slice(&1i32.to_le_bytes());
+        channels.extend_from_slice(&1i32.to_le_bytes());
+    }
+    channels.push(0);
+    let mut window = Vec::new();
+    for v in &[0, 0, width as i32 - 1, height as i32 - 1] {
+        window.extend_from_slice(&v.to_le_bytes());
+    }
+
+    let mut header = Vec::new();
+    // magic number and version 2, single part scanline
+    header.extend_from_slice(&[0x76, 0x2f, 0x31, 0x01, 2, 0, 0, 0]);
+    attribute(&mut header, "channels", "chlist", &channels);
+    attribute(&mut header, "compression", "compression", &[0]);
+    attribute(&mut header, "dataWindow", "box2i", &window);
+    attribute(&mut header, "displayWindow", "box2i", &window);
+    attribute(&mut header, "lineOrder", "lineOrder", &[0]);
+    attribute(&mut header, "pixelAspectRatio", "float", &1.0f32.to_le_bytes());
+    attribute(&mut header, "screenWindowCenter", "v2f", &[0; 8]);
+    attribute(&mut header, "screenWindowWidth", "float", &1.0f32.to_le_bytes());
+    header.push(0);
+
+    // One scanline per block: y, byte count, then each channel's row of samples
+    let row_bytes = width as usize * 4 * CHANNELS.len();
+    let block_len = 8 + row_bytes;
+    let offsets_start = header.len() + 8 * height as usize;
+    let mut file = header;
+    for y in 0..height as usize {
+        file.extend_from_slice(&((offsets_start + y * block_len) as u64).to_le_bytes());
+    }
+    for (y, row) in pixels.chunks(width as usize * 16).enumerate() {
+        file.extend_from_slice(&(y as i32).to_le_bytes());
+        file.extend_from_slice(&(row_bytes as i32).to_le_bytes());
+        for (_, channel) in CHANNELS.iter() {
+            for pixel in row.chunks(16) {
+                let sample = &pixel[4 * channel..4 * channel + 4];
+                let value = f32::from_ne_bytes([sample[0], sample[1], sample[2], sample[3]]);
+                file.extend_from_slice(&value.to_le_bytes());
+            }
+        }
+    }
+    file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+    use std::convert::TryInto;
+
+    #[test]
+    fn flip_rows_reverses_rows() {
+        let mut pixels = vec![1, 2, 3, 4, 5, 6];
+        flip_rows(&mut pixels, 2);
+        assert_eq!(pixels, vec![5, 6, 3, 4, 1, 2]);
+        let mut pixels = vec![1, 2, 3, 4, 5, 6, 7, 8];
+        flip_rows(&mut pixels, 2);
+        assert_eq!(pixels, vec![7, 8, 5, 6, 3, 4, 1, 2]);
+        flip_rows(&mut pixels, 2);
+        assert_eq!(pixels, vec![1, 2, 3, 4, 5, 6, 7, 8]);
+        let mut row = vec![1, 2, 3];
+        flip_rows(&mut row, 3);
+        assert_eq!(row, vec![1, 2, 3]);
+    }
+
+    fn i32_at(bytes: &[u8], at: usize) -> i32 {
+        i32::from_le_bytes(bytes[at..at + 4].try_into().unwrap())
+    }
+
+    /// The header attributes, the scanline offsets and the RGBA f32 pixels of an EXR file
+    /// written by exr_bytes
+    fn read_exr(bytes: &[u8], height: usize) -> (BTreeMap<String, Vec<u8>>, Vec<u64>, Vec<u8>) {
+        let mut attributes = BTreeMap::new();
+        let mut at = 8;
+        let string = |at: &mut usize| {
+            let end = *at + bytes[*at..].iter().position(|&b| b == 0).unwrap();
+            let string = String::from_utf8(bytes[*at..end].to_vec()).unwrap();
+            *at = end + 1;
+            string
+        };
+        loop {
+            let name = string(&mut at);
+            if name.is_empty() {
+                break;
+            }
+            let kind = string(&mut at);
+            let len = i32_at(bytes, at) as usize;
+            attributes.insert(
+                format!("{} {}", name, kind),
+                bytes[at + 4..at + 4 + len].to_vec(),
+            );
+            at += 4 + len;
+        }
+        let offsets: Vec<u64> = (0..height)
+            .map(|y| u64::from_le_bytes(bytes[at + 8 * y..at + 8 * y + 8].try_into().unwrap()))
+            .collect();
+        let mut pixels = Vec::new();
+        for (y, &offset) in offsets.iter().enumerate() {
+            let offset = offset as usize;
+            assert_eq!(i32_at(bytes, offset), y as i32);
+            let len = i32_at(bytes, offset + 4) as usize;
+            let samples = &bytes[offset + 8..offset + 8 + len];
+            let width = len / 16;
+            // Channels are stored A, B, G, R
+            for x in 0..width {
+                for channel in (0..4).rev() {
+                    let at = 4 * (channel * width + x);
+                    let value = f32::from_le_bytes(samples[at..at + 4].try_into().unwrap());
+                    pixels.extend_from_slice(&value.to_ne_bytes());
+                }
+            }
+        }
+        (attributes, offsets, pixels)
+    }
+
+    fn f32_pixels(values: &[f32]) -> Vec<u8> {
+        values
+            .iter()
+            .flat_map(|v| v.to_ne_bytes().to_vec())
+            .collect()
+    }
+
+    #[test]
+    fn exr_header() {
+        let pixels = f32_pixels(&[0.25, 0.5, 0.75, 1.0]);
+        let bytes = exr_bytes(&pixels, 1, 1);
+        assert_eq!(&bytes[..8], &[0x76, 0x2f, 0x31, 0x01, 2, 0, 0, 0]);
+        let (attributes, offsets, _) = read_exr(&bytes, 1);
+        let names: Vec<&str> = attributes.keys().map(String::as_str).collect();
+        assert_eq!(
+            names,
+            vec![
+                "channels chlist",
+                "compression compression",
+                "dataWindow box2i",
+                "displayWindow box2i",
+                "lineOrder lineOrder",
+                "pixelAspectRatio float",
+                "screenWindowCenter v2f",
+                "screenWindowWidth float",
+            ]
+        );
+        assert_eq!(attributes["compression compression"], vec![0]);
+        assert_eq!(attributes["dataWindow box2i"], vec![0; 16]);
+        let channels = &attributes["channels chlist"];
+        assert_eq!(channels.len(), 4 * 18 + 1);
+        assert_eq!(&channels[..2], b"A\0");
+        assert_eq!(i32_at(channels, 2), 2);
+        assert_eq!(&channels[54..56], b"R\0");
+        // The only scanline block follows the offset table, and ends the file
+        assert_eq!(offsets[0] as usize + 8 + 16, bytes.len());
+        let samples: Vec<u8> = [1.0f32, 0.75, 0.5, 0.25]
+            .iter()
+            .flat_map(|v| v.to_le_bytes().to_vec())
+            .collect();
+        assert_eq!(&bytes[bytes.len() - 16..], samples.as_slice());
+    }
+
+    #[test]
+    fn exr_round_trip() {
+        let (width, height) = (3, 2);
+        let values: Vec<f32> = (0..width * height * 4)
+            .map(|i| i as f32 * 0.5 - 3.0)
+            .collect();
+        let pixels = f32_pixels(&values);
+        let bytes = exr_bytes(&pixels, width as u32, height as u32);
+        let (attributes, offsets, read) = read_exr(&bytes, height);
+        let mut window = Vec::new();
+        for v in &[0i32, 0, width as i32 - 1, height as i32 - 1] {
+            window.extend_from_slice(&v.to_le_bytes());
+        }
+        assert_eq!(attributes["dataWindow box2i"], window);
+        assert_eq!(attributes["displayWindow box2i"], window);
+        assert_eq!(offsets[1] - offsets[0], 8 + 16 * width as u64);
+        assert_eq!(read, pixels);
+    }
+}
@@ -1,5 +1,8 @@
 use std::collections::BTreeMap;
+use std::path::Path;
 
+use crate::builtin;
+use crate::diagnostics::{self, Diagnostic, Span, TomlLocator};
 use crate::error::{Error, Result};
 use toml;
 
@@ -7,6 +10,11 @@ use toml;
 pub struct EffectConfig {
     #[serde(rename = "restart-on-save", default)]
     pub restart_on_save: bool,
+    /// Shader prepended to the fragment shader of every pass, like Shadertoy's Common tab
+    pub common: Option<String>,
+    /// The built-in uniforms shaders are given
+    #[serde(default)]
+    pub profile: ProfileConfig,
     #[serde(rename = "pass", default)]
     pub passes: Vec<PassConfig>,
     #[serde(flatten, default)]
@@ -26,9 +34,12 @@ pub enum ResourceConfig {
     WebCam(WebCamConfig),
     Keyboard(KeyboardConfig),
     Audio(AudioConfig),
+    Sound(SoundConfig),
     Microphone(MicrophoneConfig),
     GstAppSinkPipeline(GstVideoPipelineConfig),
     Model(ModelConfig),
+    Storage(StorageConfig),
+    Feedback(FeedbackConfig),
     Buffer(BufferConfig),
     UniformFloat(UniformFloatConfig),
     UniformVec2(UniformVec2Config),
@@ -68,6 +79,52 @@ pub struct UniformVec4Config {
 pub struct ModelConfig {
     pub model: String,
     pub object: Option<String>,
+    // draws the whole model with this mode instead of the mode of the file
+    pub mode: Option<DrawModeConfig>,
+}
+
+/// The file formats of model resources, known by their file extension
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ModelFormat {
+    Obj,
+    Gltf,
+    Ply,
+    Stl,
+    // XYZ and CSV point files
+    Points,
+}
+
+/// A shader storage buffer, bound to passes as a GLSL `buffer` block holding an array
+/// of `storage` elements
+#[derive(Debug, Deserialize, PartialEq, Clone)]
+pub struct StorageConfig {
+    /// The GLSL type of each element, like "vec4", or struct members like
+    /// "vec2 position; vec2 velocity;"
+    pub storage: String,
+    /// The number of elements, which sets the size when the element type is known
+    pub count: Option<u32>,
+    /// The size in bytes
+    pub size: Option<u32>,
+    /// A file with the initial contents of the buffer
+    pub contents: Option<String>,
+    /// Keep what passes write from frame to frame, rather than resetting the buffer
+    /// to its initial contents at the start of every frame
+    #[serde(default = "default_storage_persist")]
+    pub persist: bool,
+}
+
+/// A vertex buffer that passes capture vertex shader outputs into with transform
+/// feedback, holding `count` vertices with the listed `attributes`
+#[derive(Debug, Deserialize, PartialEq, Clone)]
+pub struct FeedbackConfig {
+    /// The attributes of each vertex, like "vec3 position; vec3 velocity;"
+    pub attributes: String,
+    pub count: u32,
+    /// A file with the initial vertices, as interleaved 32-bit attribute components
+    pub contents: Option<String>,
+    /// A vertex shader drawn once, whose outputs named after the attributes are the
+    /// initial vertices
+    pub seed: Option<String>,
 }
 
 #[derive(Debug, Deserialize, PartialEq, Clone)]
@@ -157,6 +214,14 @@ pub struct AudioConfig {
     pub bands: usize,
 }
 
+/// Audio generated by a shader that defines Shadertoy's `vec2 mainSound(int samp, float time)`
+#[derive(Debug, Deserialize, PartialEq, Clone)]
+pub struct SoundConfig {
+    pub sound: String,
+    #[serde(default = "default_audio_bands")]
+    pub bands: usize,
+}
+
 #[derive(Debug, Deserialize, PartialEq, Clone)]
 pub struct GstVideoPipelineConfig {
     pub pipeline: String,
@@ -173,9 +238,16 @@ pub struct MicrophoneConfig {
 pub struct PassConfig {
     #[serde(default)]
     pub draw: DrawConfig,
+    #[serde(default = "default_pass_config_vertex")]
     pub vertex: String,
+    #[serde(default = "default_pass_config_fragment")]
     pub fragment: String,
     pub geometry: Option<String>,
+    /// A compute shader, dispatched instead of drawing
+    pub compute: Option<String>,
+    pub workgroups: Option<WorkgroupsConfig>,
+    /// Vertex shader outputs captured into a feedback resource, instead of drawing
+    pub capture: Option<CaptureConfig>,
     #[serde(flatten)]
     pub uniform_to_channel: BTreeMap<String, ChannelConfig>,
     // render pass settings
@@ -214,6 +286,41 @@ impl PassConfig {
         }
         false
     }
+
+    /// Whether the pass left out its vertex shader, in which case grimoire draws a
+    /// fullscreen triangle and the fragment shader only defines `mainImage`
+    pub fn is_main_image(&self) -> bool {
+        self.compute.is_none() && self.vertex == builtin::FULLSCREEN_VERTEX
+    }
+
+    /// Whether the pass dispatches a compute shader rather than drawing
+    pub fn is_compute(&self) -> bool {
+        self.compute.is_some()
+    }
+
+    /// Whether the pass captures its vertex shader outputs rather than drawing
+    pub fn is_capture(&self) -> bool {
+        self.compute.is_none() && self.capture.is_some()
+    }
+
+    /// The fragment shader the pass links, which passes that capture their vertex shader
+    /// outputs don't need unless they name one
+    pub fn fragment_shader(&self) -> &str {
+        if self.is_capture() && self.fragment == builtin::VERTEX_COLOR_FRAGMENT {
+            builtin::DISCARD_FRAGMENT
+        } else {
+            &self.fragment
+        }
+    }
+
+    /// The buffer whose resolution the pass covers: the buffer a pass draws into, or the
+    /// buffer a compute pass derives its workgroups from
+    pub fn resolution_buffer(&self) -> Option<&String> {
+        match self.workgroups {
+            Some(WorkgroupsConfig::Buffer(ref buffer)) if self.is_compute() => Some(buffer),
+            _ => self.buffer.as_ref(),
+        }
+    }
 }
 
 impl BufferConfig {
@@ -224,6 +331,28 @@ impl BufferConfig {
             BufferFormatConfig::Complete(v) => v.len(),
         }
     }
+
+    pub fn attachment_format(&self, attachment: usize) -> BufferFormat {
+        match &self.buffer {
+            BufferFormatConfig::Dumb(_) => BufferFormat::F32,
+            BufferFormatConfig::Simple(f) => *f,
+            BufferFormatConfig::Complete(v) => v[attachment],
+        }
+    }
+}
+
+impl ModelConfig {
+    pub fn format(&self) -> Option<ModelFormat> {
+        let extension = Path::new(&self.model).extension()?.to_str()?.to_lowercase();
+        match extension.as_str() {
+            "obj" => Some(ModelFormat::Obj),
+            "gltf" | "glb" => Some(ModelFormat::Gltf),
+            "ply" => Some(ModelFormat::Ply),
+            "stl" => Some(ModelFormat::Stl),
+            "xyz" | "csv" => Some(ModelFormat::Points),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, Deserialize, PartialEq, Clone)]
@@ -234,7 +363,7 @@ pub enum BufferFormatConfig {
     Complete(Vec<BufferFormat>),
 }
 
-#[derive(Debug, Deserialize, PartialEq, Clone, Copy)]
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Copy)]
 #[serde(rename_all = "lowercase")]
 pub enum BufferFormat {
     U8,
@@ -261,10 +390,30 @@ pub enum BufferDepthFormat {
 #[derive(Debug, Deserialize, PartialEq, Clone)]
 #[serde(untagged)]
 pub enum DrawConfig {
+    Feedback(DrawFeedbackConfig),
     Raw(DrawRawConfig),
     Model(DrawModelConfig),
 }
 
+/// The vertex shader outputs a pass captures into a feedback resource, in the order of
+/// the resource's attributes
+#[derive(Debug, Deserialize, PartialEq, Clone)]
+pub struct CaptureConfig {
+    pub buffer: String,
+    pub outputs: Vec<String>,
+}
+
+/// The number of workgroups a compute pass dispatches
+#[derive(Debug, Deserialize, PartialEq, Clone)]
+#[serde(untagged)]
+pub enum WorkgroupsConfig {
+    /// A fixed number of workgroups in x, y and z
+    Count([u32; 3]),
+    /// Enough workgroups to cover every pixel of the named buffer with the local size
+    /// the compute shader declares
+    Buffer(String),
+}
+
 #[derive(Debug, Deserialize, PartialEq, Clone)]
 pub struct DrawRawConfig {
     pub mode: DrawModeConfig,
@@ -278,6 +427,16 @@ pub struct DrawModelConfig {
     pub count: u32,
 }
 
+/// Draws the vertices last captured into a feedback resource, `count` instances of them
+#[derive(Debug, Deserialize, PartialEq, Clone)]
+pub struct DrawFeedbackConfig {
+    pub feedback: String,
+    #[serde(default = "default_draw_feedback_mode")]
+    pub mode: DrawModeConfig,
+    #[serde(default = "default_draw_feedback_count")]
+    pub count: u32,
+}
+
 #[derive(Debug, Deserialize, PartialEq, Clone, Copy)]
 #[serde(untagged)]
 pub enum DepthTestConfig {
@@ -422,6 +581,21 @@ pub enum ChannelConfig {
     },
 }
 
+#[derive(Debug, Deserialize, PartialEq, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum ProfileConfig {
+    /// iFrame is a float, and samplers have `<name>_Resolution` and `<name>_Time` uniforms
+    Grimoire,
+    /// Also declares Shadertoy's iChannelResolution[4] and iChannelTime[4] for
+    /// iChannel0 to iChannel3, and makes iFrame an int, so that Shadertoy code compiles
+    /// unchanged
+    Shadertoy,
+    /// Also declares vertexshaderart's vertexId, time, resolution, mouse, background,
+    /// vertexCount and soundRes uniforms, its v_color output, and its sound, floatSound,
+    /// volume and touch history textures, so that vertexshaderart code compiles unchanged
+    VertexShaderArt,
+}
+
 #[derive(Debug, Deserialize, PartialEq, Clone)]
 #[serde(rename_all = "lowercase")]
 pub enum WrapConfig {
@@ -444,112 +618,738 @@ impl ChannelConfig {
             ChannelConfig::Complete { resource, .. } => &resource,
         }
     }
+
+    pub fn attachment(&self) -> usize {
+        match self {
+            ChannelConfig::Simple(_) => 0,
+            ChannelConfig::Complete { attachment, .. } => *attachment,
+        }
+    }
 }
 
 impl EffectConfig {
     pub fn from_toml(src_str: &str) -> Result<EffectConfig> {
+        let mut config = EffectConfig::parse(src_str).map_err(Error::toml)?;
+        config.validate(src_str);
+        Ok(config)
+    }
+
+    /// Deserialize the config without validating it
+    pub fn parse(src_str: &str) -> std::result::Result<EffectConfig, toml::de::Error> {
         toml::from_str(src_str)
-            .map_err(Error::toml)
-            .map(|mut c: EffectConfig| {
-                c.validate().unwrap();
-                Ok(c)
-            })?
     }
 
     pub fn is_ok(&self) -> bool {
         self.ok
     }
 
-    fn validate(&mut self) -> Result<()> {
-        // check that the buffer names reference valid resources
-        self.ok = true;
-        let resource_names = &self
+    /// The first audio, microphone or sound resource and its number of bands, which the
+    /// sound textures of the vertexshaderart profile follow
+    pub fn first_sound_resource(&self) -> Option<(&String, usize)> {
+        self.resources
+            .iter()
+            .find_map(|(name, resource)| match resource {
+                ResourceConfig::Audio(audio) => Some((name, audio.bands)),
+                ResourceConfig::Microphone(microphone) => Some((name, microphone.bands)),
+                ResourceConfig::Sound(sound) => Some((name, sound.bands)),
+                _ => None,
+            })
+    }
+
+    /// Log the problems found in the config and record whether it can be drawn.
+    /// `src_str` is the TOML the config was parsed from, or empty if it was built in code.
+    pub fn validate(&mut self, src_str: &str) {
+        let diagnostics = self.diagnostics(src_str);
+        for diagnostic in &diagnostics {
+            if diagnostic.is_error() {
+                error!("[TOML] {}", diagnostic);
+            } else {
+                warn!("[TOML] {}", diagnostic);
+            }
+        }
+        self.ok = !diagnostics.iter().any(Diagnostic::is_error);
+    }
+
+    /// Check that the passes reference resources that exist and can be used the way they are.
+    /// `src_str` is the TOML the config was parsed from, used to locate each problem.
+    pub fn diagnostics(&self, src_str: &str) -> Vec<Diagnostic> {
+        let locator = TomlLocator::new(src_str);
+        let mut diagnostics = Vec::new();
+        let is_uniform = |r: &ResourceConfig| match r {
+            ResourceConfig::UniformFloat(_)
+            | ResourceConfig::UniformVec2(_)
+            | ResourceConfig::UniformVec3(_)
+            | ResourceConfig::UniformVec4(_) => true,
+            _ => false,
+        };
+        let resource_names = self
+            .resources
+            .iter()
+            .filter(|(_, r)| !is_uniform(r))
+            .map(|(k, _)| k.as_str())
+            .collect::<Vec<&str>>();
+        let buffer_names = self
             .resources
             .iter()
             .filter(|(_, r)| match r {
-                ResourceConfig::UniformFloat(_)
-                | ResourceConfig::UniformVec2(_)
-                | ResourceConfig::UniformVec3(_)
-                | ResourceConfig::UniformVec4(_) => false,
-                _ => true,
+                ResourceConfig::Buffer(_) => true,
+                _ => false,
             })
             .map(|(k, _)| k.as_str())
             .collect::<Vec<&str>>();
-        let buffer_names = &self
+        let model_names = self
             .resources
             .iter()
             .filter(|(_, r)| match r {
-                ResourceConfig::Buffer(_) => true,
+                ResourceConfig::Model(_) => true,
+                _ => false,
+            })
+            .map(|(k, _)| k.as_str())
+            .collect::<Vec<&str>>();
+        let feedback_names = self
+            .resources
+            .iter()
+            .filter(|(_, r)| match r {
+                ResourceConfig::Feedback(_) => true,
                 _ => false,
             })
             .map(|(k, _)| k.as_str())
             .collect::<Vec<&str>>();
 
-        // Validate buffer names
+        if self.passes.is_empty() {
+            diagnostics.push(Diagnostic::warning(
+                "The configuration has no [[pass]] tables, so nothing will be drawn",
+            ));
+        }
+
         for (pass_index, pass) in self.passes.iter().enumerate() {
+            // Validate buffer names
             if let Some(ref buffer) = pass.buffer {
-                if !self.resources.contains_key(buffer) {
-                    self.ok = false;
-                    error!(
-                        "[TOML] Could not find buffer referenced in pass {} with name \"{}\". Valid buffer names: {:?}",
-                        pass_index, buffer,buffer_names
-                    );
+                match self.resources.get(buffer) {
+                    Some(ResourceConfig::Buffer(_)) => (),
+                    Some(_) => diagnostics.push(
+                        Diagnostic::error(format!(
+                            "Pass {} draws into \"{}\", which is not a buffer. Valid buffer names: {:?}",
+                            pass_index, buffer, buffer_names
+                        ))
+                        .with_span(locator.pass_value(pass_index, "buffer")),
+                    ),
+                    None => diagnostics.push(
+                        Diagnostic::error(format!(
+                            "Could not find buffer referenced in pass {} with name \"{}\". Valid buffer names: {:?}",
+                            pass_index, buffer, buffer_names
+                        ))
+                        .with_span(locator.pass_value(pass_index, "buffer"))
+                        .with_suggestion(diagnostics::suggest(buffer, buffer_names.iter().cloned())),
+                    ),
                 }
             }
-        }
 
-        // Validate resource names
-        for (pass_index, pass) in self.passes.iter().enumerate() {
-            for (uniform_name, channel_config) in &pass.uniform_to_channel {
-                let resource_name = channel_config.resource_name();
-                if !self.resources.contains_key(resource_name) {
-                    self.ok = false;
-                    error!(
-                        "[TOML] Could not find resource referenced in pass {}, {}=\"{}\". Valid resource names: {:?}",
-                        pass_index, uniform_name, resource_name, resource_names
-                    );
+            // Validate model names
+            if let DrawConfig::Model(ref draw) = pass.draw {
+                match self.resources.get(&draw.model) {
+                    Some(ResourceConfig::Model(_)) => (),
+                    _ => diagnostics.push(
+                        Diagnostic::error(format!(
+                            "Could not find model drawn by pass {} with name \"{}\". Valid model names: {:?}",
+                            pass_index, draw.model, model_names
+                        ))
+                        .with_span(locator.pass_string(pass_index, "draw", &draw.model))
+                        .with_suggestion(diagnostics::suggest(&draw.model, model_names.iter().cloned())),
+                    ),
                 }
             }
-        }
 
-        // Validate that all pass resource references are not uniform inputs
-        for (pass_index, pass) in self.passes.iter().enumerate() {
+            // Validate feedback names of passes that draw captured vertices
+            if let DrawConfig::Feedback(ref draw) = pass.draw {
+                match self.resources.get(&draw.feedback) {
+                    Some(ResourceConfig::Feedback(_)) => (),
+                    _ => diagnostics.push(
+                        Diagnostic::error(format!(
+                            "Could not find feedback drawn by pass {} with name \"{}\". Valid feedback names: {:?}",
+                            pass_index, draw.feedback, feedback_names
+                        ))
+                        .with_span(locator.pass_string(pass_index, "draw", &draw.feedback))
+                        .with_suggestion(diagnostics::suggest(&draw.feedback, feedback_names.iter().cloned())),
+                    ),
+                }
+            }
+
+            // Passes that capture vertex shader outputs draw points without rasterizing
+            if pass.is_capture() {
+                self.capture_pass_diagnostics(pass_index, pass, &locator, &mut diagnostics);
+            }
+
+            // The fullscreen triangle has no color for the default fragment shader to draw
+            if pass.is_main_image() && pass.fragment_shader() == builtin::VERTEX_COLOR_FRAGMENT {
+                diagnostics.push(
+                    Diagnostic::error(format!(
+                        "Pass {} has neither a vertex nor a fragment shader",
+                        pass_index
+                    ))
+                    .with_span(locator.pass(pass_index)),
+                );
+            }
+
+            // Passes without a vertex shader always draw a fullscreen triangle
+            if pass.is_main_image() && pass.draw != DrawConfig::default() {
+                diagnostics.push(
+                    Diagnostic::warning(format!(
+                        "Pass {} has no vertex shader, so it draws a fullscreen triangle and its draw settings are ignored",
+                        pass_index
+                    ))
+                    .with_span(locator.pass_value(pass_index, "draw")),
+                );
+            }
+
+            // Compute passes dispatch workgroups instead of drawing
+            if pass.is_compute() {
+                self.compute_pass_diagnostics(pass_index, pass, &locator, &mut diagnostics);
+            } else if pass.workgroups.is_some() {
+                diagnostics.push(
+                    Diagnostic::warning(format!(
+                        "Pass {} has no compute shader, so its workgroups setting is ignored",
+                        pass_index
+                    ))
+                    .with_span(locator.pass_value(pass_index, "workgroups")),
+                );
+            }
+
+            // Validate resource names, and that they are not uniform inputs
             for (uniform_name, channel_config) in &pass.uniform_to_channel {
                 let resource_name = channel_config.resource_name();
-                match self.resources[resource_name] {
-                    ResourceConfig::UniformFloat(_)
-                    | ResourceConfig::UniformVec2(_)
-                    | ResourceConfig::UniformVec3(_)
-                    | ResourceConfig::UniformVec4(_) => {
-                        self.ok = false;
-                        error!(
-                        "[TOML] Cannot reference uniform in pass {}, {}=\"{}\". Valid resource names: {:?}",
-                        pass_index, uniform_name, resource_name, resource_names
-                    );
+                let span = locator.pass_string(pass_index, uniform_name, resource_name);
+                match self.resources.get(resource_name) {
+                    None => diagnostics.push(
+                        Diagnostic::error(format!(
+                            "Could not find resource referenced in pass {}, {}=\"{}\". Valid resource names: {:?}",
+                            pass_index, uniform_name, resource_name, resource_names
+                        ))
+                        .with_span(span)
+                        .with_suggestion(diagnostics::suggest(resource_name, resource_names.iter().cloned())),
+                    ),
+                    Some(resource) if is_uniform(resource) => diagnostics.push(
+                        Diagnostic::error(format!(
+                            "Cannot reference uniform in pass {}, {}=\"{}\". Valid resource names: {:?}",
+                            pass_index, uniform_name, resource_name, resource_names
+                        ))
+                        .with_span(span),
+                    ),
+                    Some(ResourceConfig::Feedback(_)) => diagnostics.push(
+                        Diagnostic::error(format!(
+                            "Cannot sample feedback in pass {}, {}=\"{}\". Draw it with draw = {{feedback = \"{}\"}} instead",
+                            pass_index, uniform_name, resource_name, resource_name
+                        ))
+                        .with_span(span),
+                    ),
+                    Some(ResourceConfig::Model(model)) if model.format() != Some(ModelFormat::Gltf) => {
+                        diagnostics.push(
+                            Diagnostic::error(format!(
+                                "Cannot sample model in pass {}, {}=\"{}\". Only glTF models have textures",
+                                pass_index, uniform_name, resource_name
+                            ))
+                            .with_span(span),
+                        )
+                    }
+                    Some(ResourceConfig::Buffer(buffer)) => {
+                        if let ChannelConfig::Complete { attachment, .. } = channel_config {
+                            if *attachment >= buffer.attachment_count() {
+                                diagnostics.push(
+                                    Diagnostic::error(format!(
+                                        "Pass {} samples attachment {} of buffer \"{}\", which only has {}",
+                                        pass_index,
+                                        attachment,
+                                        resource_name,
+                                        buffer.attachment_count()
+                                    ))
+                                    .with_span(locator.pass_value(pass_index, uniform_name)),
+                                );
+                            }
+                        }
                     }
-                    _ => (),
+                    Some(_) => (),
                 }
             }
         }
 
-        // Validate buffer configuration
+        let profile_sound = match self.profile {
+            ProfileConfig::VertexShaderArt => self.first_sound_resource().map(|(name, _)| name),
+            _ => None,
+        };
         for (resource_name, resource_config) in &self.resources {
+            // Validate buffer configuration
             if let ResourceConfig::Buffer(buffer) = resource_config {
                 // unwrap into dummy values of 1 if not present
                 // we simply want to check if the user set these to 0
                 let buffer_width = buffer.width.unwrap_or(1);
                 let buffer_height = buffer.height.unwrap_or(1);
                 if buffer_width == 0 || buffer_height == 0 {
-                    self.ok = false;
-                    error!(
-                            "[TOML] Buffer \"{}\" must specify non-zero value for the width and height properties",
+                    let key = if buffer_width == 0 { "width" } else { "height" };
+                    diagnostics.push(
+                        Diagnostic::error(format!(
+                            "Buffer \"{}\" must specify non-zero value for the width and height properties",
                             resource_name
-                        );
+                        ))
+                        .with_span(
+                            locator
+                                .resource_value(resource_name, key)
+                                .or_else(|| locator.resource(resource_name)),
+                        ),
+                    );
                 }
             }
+            // Validate storage configuration
+            if let ResourceConfig::Storage(storage) = resource_config {
+                self.storage_diagnostics(resource_name, storage, &locator, &mut diagnostics);
+            }
+            // Validate feedback configuration
+            if let ResourceConfig::Feedback(feedback) = resource_config {
+                self.feedback_diagnostics(resource_name, feedback, &locator, &mut diagnostics);
+            }
+            // Models are loaded by their file extension
+            if let ResourceConfig::Model(model) = resource_config {
+                if model.format().is_none() {
+                    diagnostics.push(
+                        Diagnostic::error(format!(
+                            "Model \"{}\" has an unsupported file extension. Supported extensions: .obj, .gltf, .glb, .ply, .stl, .xyz, .csv",
+                            resource_name
+                        ))
+                        .with_span(locator.resource_value(resource_name, "model")),
+                    );
+                }
+            }
+            // Warn about resources that no pass uses. Sounds are heard even if no pass
+            // samples them, and the vertexshaderart profile samples its sound resource
+            let used = is_uniform(resource_config)
+                || profile_sound == Some(resource_name)
+                || match resource_config {
+                    ResourceConfig::Sound(_) => true,
+                    _ => false,
+                }
+                || self.passes.iter().any(|pass| {
+                    pass.buffer.as_ref() == Some(resource_name)
+                        || pass.resolution_buffer() == Some(resource_name)
+                        || pass
+                            .uniform_to_channel
+                            .values()
+                            .any(|channel| channel.resource_name() == resource_name)
+                        || match pass.draw {
+                            DrawConfig::Model(ref draw) => &draw.model == resource_name,
+                            DrawConfig::Feedback(ref draw) => &draw.feedback == resource_name,
+                            _ => false,
+                        }
+                        || match pass.capture {
+                            Some(ref capture) => &capture.buffer == resource_name,
+                            None => false,
+                        }
+                });
+            if !used {
+                diagnostics.push(
+                    Diagnostic::warning(format!(
+                        "Resource \"{}\" is not used by any pass",
+                        resource_name
+                    ))
+                    .with_span(locator.resource(resource_name)),
+                );
+            }
+        }
+        diagnostics
+    }
+
+    fn compute_pass_diagnostics(
+        &self,
+        pass_index: usize,
+        pass: &PassConfig,
+        locator: &TomlLocator,
+        diagnostics: &mut Vec<Diagnostic>,
+    ) {
+        match pass.workgroups {
+            None => diagnostics.push(
+                Diagnostic::error(format!(
+                    "Compute pass {} has no workgroups, set workgroups = [x, y, z] or the name of a buffer",
+                    pass_index
+                ))
+                .with_span(locator.pass_value(pass_index, "compute")),
+            ),
+            Some(WorkgroupsConfig::Count(count)) => {
+                if count.contains(&0) {
+                    diagnostics.push(
+                        Diagnostic::warning(format!(
+                            "Compute pass {} dispatches no workgroups, since workgroups = {:?}",
+                            pass_index, count
+                        ))
+                        .with_span(locator.pass_value(pass_index, "workgroups")),
+                    );
+                }
+            }
+            Some(WorkgroupsConfig::Buffer(ref buffer)) => match self.resources.get(buffer) {
+                Some(ResourceConfig::Buffer(_)) => (),
+                _ => {
+                    let buffer_names = self
+                        .resources
+                        .iter()
+                        .filter(|(_, r)| match r {
+                            ResourceConfig::Buffer(_) => true,
+                            _ => false,
+                        })
+                        .map(|(k, _)| k.as_str())
+                        .collect::<Vec<&str>>();
+                    diagnostics.push(
+                        Diagnostic::error(format!(
+                            "Could not find buffer that compute pass {} derives its workgroups from with name \"{}\". Valid buffer names: {:?}",
+                            pass_index, buffer, buffer_names
+                        ))
+                        .with_span(locator.pass_string(pass_index, "workgroups", buffer))
+                        .with_suggestion(diagnostics::suggest(buffer, buffer_names.iter().cloned())),
+                    )
+                }
+            },
+        }
+        // Settings that only apply to drawing
+        let ignored = [
+            ("vertex", pass.vertex != builtin::FULLSCREEN_VERTEX),
+            ("fragment", pass.fragment != builtin::VERTEX_COLOR_FRAGMENT),
+            ("geometry", pass.geometry.is_some()),
+            ("buffer", pass.buffer.is_some()),
+            ("draw", pass.draw != DrawConfig::default()),
+            ("clear", pass.clear.is_some()),
+            ("blend", pass.blend.is_some()),
+            ("depth", pass.depth.is_some()),
+            ("capture", pass.capture.is_some()),
+        ];
+        for (key, _) in ignored.iter().filter(|(_, set)| *set) {
+            diagnostics.push(
+                Diagnostic::warning(format!(
+                    "Compute pass {} does not draw, so its {} setting is ignored",
+                    pass_index, key
+                ))
+                .with_span(locator.pass_value(pass_index, key)),
+            );
+        }
+        // Buffers are bound as images, which have no three component formats
+        for (uniform_name, channel_config) in &pass.uniform_to_channel {
+            if let Some(ResourceConfig::Buffer(buffer)) =
+                self.resources.get(channel_config.resource_name())
+            {
+                if buffer.components == 3 {
+                    diagnostics.push(
+                        Diagnostic::error(format!(
+                            "Compute pass {} binds buffer \"{}\" as an image, which needs 1, 2 or 4 components rather than 3",
+                            pass_index,
+                            channel_config.resource_name()
+                        ))
+                        .with_span(locator.pass_value(pass_index, uniform_name)),
+                    );
+                }
+            }
+        }
+    }
+
+    fn capture_pass_diagnostics(
+        &self,
+        pass_index: usize,
+        pass: &PassConfig,
+        locator: &TomlLocator,
+        diagnostics: &mut Vec<Diagnostic>,
+    ) {
+        let capture = match pass.capture {
+            Some(ref capture) => capture,
+            None => return,
+        };
+        if pass.vertex == builtin::FULLSCREEN_VERTEX {
+            diagnostics.push(
+                Diagnostic::error(format!(
+                    "Pass {} captures vertex shader outputs, but has no vertex shader",
+                    pass_index
+                ))
+                .with_span(locator.pass_value(pass_index, "capture")),
+            );
+        }
+        // Outputs are GLSL names, like "velocity", "block.member" or "values[2]"
+        let is_name = |output: &str| {
+            !output.is_empty()
+                && output.chars().all(|c| {
+                    c.is_ascii_alphanumeric() || c == '_' || c == '.' || c == '[' || c == ']'
+                })
+        };
+        for output in capture.outputs.iter().filter(|output| !is_name(output)) {
+            diagnostics.push(
+                Diagnostic::error(format!(
+                    "Pass {} captures {:?}, which is not a vertex shader output name",
+                    pass_index, output
+                ))
+                .with_span(locator.pass_value(pass_index, "capture")),
+            );
+        }
+        match self.resources.get(&capture.buffer) {
+            Some(ResourceConfig::Feedback(feedback)) => {
+                let attributes = feedback.attribute_list();
+                if attributes.len() != capture.outputs.len() {
+                    diagnostics.push(
+                        Diagnostic::error(format!(
+                            "Pass {} captures {} outputs into \"{}\", which has {} attributes",
+                            pass_index,
+                            capture.outputs.len(),
+                            capture.buffer,
+                            attributes.len()
+                        ))
+                        .with_span(locator.pass_value(pass_index, "capture")),
+                    );
+                }
+            }
+            _ => {
+                let feedback_names = self
+                    .resources
+                    .iter()
+                    .filter(|(_, r)| match r {
+                        ResourceConfig::Feedback(_) => true,
+                        _ => false,
+                    })
+                    .map(|(k, _)| k.as_str())
+                    .collect::<Vec<&str>>();
+                diagnostics.push(
+                    Diagnostic::error(format!(
+                        "Could not find feedback captured by pass {} with name \"{}\". Valid feedback names: {:?}",
+                        pass_index, capture.buffer, feedback_names
+                    ))
+                    .with_span(locator.pass_string(pass_index, "capture", &capture.buffer))
+                    .with_suggestion(diagnostics::suggest(&capture.buffer, feedback_names.iter().cloned())),
+                );
+            }
+        }
+        // Settings that only apply to drawing
+        let ignored = [
+            ("buffer", pass.buffer.is_some()),
+            ("draw", pass.draw != DrawConfig::default()),
+            ("clear", pass.clear.is_some()),
+            ("blend", pass.blend.is_some()),
+            ("depth", pass.depth.is_some()),
+        ];
+        for (key, _) in ignored.iter().filter(|(_, set)| *set) {
+            diagnostics.push(
+                Diagnostic::warning(format!(
+                    "Pass {} captures its vertex shader outputs rather than drawing, so its {} setting is ignored",
+                    pass_index, key
+                ))
+                .with_span(locator.pass_value(pass_index, key)),
+            );
+        }
+    }
+
+    fn storage_diagnostics(
+        &self,
+        resource_name: &str,
+        storage: &StorageConfig,
+        locator: &TomlLocator,
+        diagnostics: &mut Vec<Diagnostic>,
+    ) {
+        let span = |key: &str| {
+            locator
+                .resource_value(resource_name, key)
+                .or_else(|| locator.resource(resource_name))
+        };
+        if storage.is_struct() && storage.members().iter().any(|(_, name)| name.is_empty()) {
+            diagnostics.push(
+                Diagnostic::error(format!(
+                    "Storage \"{}\" has a struct member without a name, write members as \"vec2 position; vec2 velocity;\"",
+                    resource_name
+                ))
+                .with_span(span("storage")),
+            );
+        }
+        match (storage.count, storage.size) {
+            (None, None) => diagnostics.push(
+                Diagnostic::error(format!(
+                    "Storage \"{}\" must specify its element count or its size in bytes",
+                    resource_name
+                ))
+                .with_span(locator.resource(resource_name)),
+            ),
+            (Some(_), Some(_)) => diagnostics.push(
+                Diagnostic::warning(format!(
+                    "Storage \"{}\" specifies both count and size, so its size is ignored",
+                    resource_name
+                ))
+                .with_span(span("size")),
+            ),
+            _ => (),
+        }
+        if storage.count.is_some() && storage.element_size().is_none() {
+            diagnostics.push(
+                Diagnostic::error(format!(
+                    "Cannot compute the size of storage \"{}\" from its count, since the layout of \"{}\" is unknown. Specify its size in bytes instead",
+                    resource_name, storage.storage
+                ))
+                .with_span(span("count")),
+            );
+        } else if storage.byte_size() == Some(0) {
+            let key = if storage.count.is_some() {
+                "count"
+            } else {
+                "size"
+            };
+            diagnostics.push(
+                Diagnostic::error(format!(
+                    "Storage \"{}\" must have a non-zero size",
+                    resource_name
+                ))
+                .with_span(span(key)),
+            );
+        }
+    }
+
+    fn feedback_diagnostics(
+        &self,
+        resource_name: &str,
+        feedback: &FeedbackConfig,
+        locator: &TomlLocator,
+        diagnostics: &mut Vec<Diagnostic>,
+    ) {
+        let span = |key: &str| {
+            locator
+                .resource_value(resource_name, key)
+                .or_else(|| locator.resource(resource_name))
+        };
+        let attributes = feedback.attribute_list();
+        if attributes.is_empty() {
+            diagnostics.push(
+                Diagnostic::error(format!(
+                    "Feedback \"{}\" has no attributes, write them as \"vec3 position; vec3 velocity;\"",
+                    resource_name
+                ))
+                .with_span(span("attributes")),
+            );
+        }
+        for (type_str, name) in attributes {
+            if name.is_empty() {
+                diagnostics.push(
+                    Diagnostic::error(format!(
+                        "Feedback \"{}\" has an attribute without a name, write them as \"vec3 position; vec3 velocity;\"",
+                        resource_name
+                    ))
+                    .with_span(span("attributes")),
+                );
+            } else if attribute_format(type_str).is_none() {
+                diagnostics.push(
+                    Diagnostic::error(format!(
+                        "Feedback \"{}\" cannot capture attribute \"{}\" of type \"{}\", which must be a float, int or uint scalar or vector",
+                        resource_name, name, type_str
+                    ))
+                    .with_span(span("attributes")),
+                );
+            }
+        }
+        if feedback.count == 0 {
+            diagnostics.push(
+                Diagnostic::error(format!(
+                    "Feedback \"{}\" must have a non-zero count",
+                    resource_name
+                ))
+                .with_span(span("count")),
+            );
+        }
+        if feedback.contents.is_some() && feedback.seed.is_some() {
+            diagnostics.push(
+                Diagnostic::warning(format!(
+                    "Feedback \"{}\" specifies both contents and a seed shader, so its contents are ignored",
+                    resource_name
+                ))
+                .with_span(span("contents")),
+            );
+        }
+    }
+
+    /// Check that the shader, image, model, and media files referenced by the config exist.
+    /// Relative paths are resolved against `dir`, the directory containing the config.
+    pub fn file_diagnostics(&self, src_str: &str, dir: &Path) -> Vec<Diagnostic> {
+        let locator = TomlLocator::new(src_str);
+        let mut diagnostics = Vec::new();
+        let mut check = |path: &str, what: &str, span: Option<Span>| {
+            if !dir.join(path).is_file() {
+                diagnostics.push(
+                    Diagnostic::error(format!("Could not find {} file \"{}\"", what, path))
+                        .with_span(span),
+                );
+            }
+        };
+        if let Some(ref common) = self.common {
+            check(common, "common shader", locator.root_value("common"));
+        }
+        for (pass_index, pass) in self.passes.iter().enumerate() {
+            if builtin::shader(&pass.vertex).is_none() {
+                check(
+                    &pass.vertex,
+                    "vertex shader",
+                    locator.pass_value(pass_index, "vertex"),
+                );
+            }
+            if builtin::shader(&pass.fragment).is_none() {
+                check(
+                    &pass.fragment,
+                    "fragment shader",
+                    locator.pass_value(pass_index, "fragment"),
+                );
+            }
+            if let Some(ref geometry) = pass.geometry {
+                check(
+                    geometry,
+                    "geometry shader",
+                    locator.pass_value(pass_index, "geometry"),
+                );
+            }
+            if let Some(ref compute) = pass.compute {
+                check(
+                    compute,
+                    "compute shader",
+                    locator.pass_value(pass_index, "compute"),
+                );
+            }
+        }
+        for (name, resource) in &self.resources {
+            let value = |key: &str| locator.resource_value(name, key);
+            match resource {
+                ResourceConfig::Image(image) => check(&image.image, "image", value("image")),
+                ResourceConfig::Texture2D(texture) => {
+                    check(&texture.texture_2d, "texture", value("texture2D"))
+                }
+                ResourceConfig::Texture3D(texture) => {
+                    check(&texture.texture_3d, "texture", value("texture3D"))
+                }
+                ResourceConfig::Cubemap(cubemap) => {
+                    check(&cubemap.right, "cubemap", value("right"));
+                    check(&cubemap.left, "cubemap", value("left"));
+                    check(&cubemap.top, "cubemap", value("top"));
+                    check(&cubemap.bottom, "cubemap", value("bottom"));
+                    check(&cubemap.back, "cubemap", value("back"));
+                    check(&cubemap.front, "cubemap", value("front"));
+                }
+                ResourceConfig::Video(video) => check(&video.video, "video", value("video")),
+                ResourceConfig::Audio(audio) => check(&audio.audio, "audio", value("audio")),
+                ResourceConfig::Sound(sound) => check(&sound.sound, "sound shader", value("sound")),
+                ResourceConfig::Model(model) => check(&model.model, "model", value("model")),
+                ResourceConfig::Storage(storage) => {
+                    if let Some(ref contents) = storage.contents {
+                        check(contents, "storage contents", value("contents"))
+                    }
+                }
+                ResourceConfig::Feedback(feedback) => {
+                    if let Some(ref contents) = feedback.contents {
+                        check(contents, "feedback contents", value("contents"))
+                    }
+                    if let Some(ref seed) = feedback.seed {
+                        check(seed, "seed shader", value("seed"))
+                    }
+                }
+                _ => (),
+            }
         }
+        diagnostics
+    }
+}
 
-        Ok(())
+impl Default for ProfileConfig {
+    fn default() -> Self {
+        ProfileConfig::Grimoire
     }
 }
 
@@ -623,7 +1423,130 @@ impl TextureFormat {
     }
 }
 
-const fn default_audio_bands() -> usize {
+impl StorageConfig {
+    /// Whether `storage` lists struct members rather than naming a single type
+    pub fn is_struct(&self) -> bool {
+        self.storage.contains(';')
+    }
+
+    /// The members of the element struct, as (type, name) pairs
+    pub fn members(&self) -> Vec<(&str, &str)> {
+        glsl_members(&self.storage)
+    }
+
+    /// The std430 array stride of an element, or None if it has a type grimoire
+    /// does not know the layout of
+    pub fn element_size(&self) -> Option<usize> {
+        let members = if self.is_struct() {
+            let members = self.members();
+            // Members that are arrays or have qualifiers don't fit "type name"
+            let simple = self.storage.split(';').all(|member| {
+                let words = member.split_whitespace().count();
+                words == 0 || (words == 2 && !member.contains('['))
+            });
+            if members.is_empty() || !simple {
+                return None;
+            }
+            members.iter().map(|(type_str, _)| *type_str).collect()
+        } else {
+            vec![self.storage.trim()]
+        };
+        let mut size = 0;
+        let mut align = 1;
+        for type_str in members {
+            let (member_size, member_align) = std430_size_align(type_str)?;
+            size = round_up(size, member_align) + member_size;
+            align = align.max(member_align);
+        }
+        Some(round_up(size, align))
+    }
+
+    /// The size of the buffer in bytes, from count if the element size is known
+    pub fn byte_size(&self) -> Option<usize> {
+        match self.count {
+            Some(count) => self.element_size().map(|size| size * count as usize),
+            None => self.size.map(|size| size as usize),
+        }
+    }
+}
+
+/// The scalar type of a vertex attribute
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum AttributeScalar {
+    Float,
+    Int,
+    Uint,
+}
+
+impl FeedbackConfig {
+    /// The attributes of each vertex, as (type, name) pairs
+    pub fn attribute_list(&self) -> Vec<(&str, &str)> {
+        glsl_members(&self.attributes)
+    }
+
+    /// The size of a vertex in bytes, or None if an attribute has a type that can't be
+    /// captured
+    pub fn stride(&self) -> Option<usize> {
+        self.attribute_list()
+            .iter()
+            .map(|(type_str, _)| attribute_format(type_str).map(|(components, _)| components * 4))
+            .sum()
+    }
+}
+
+/// The number of components and scalar type of a vertex attribute type
+pub fn attribute_format(type_str: &str) -> Option<(usize, AttributeScalar)> {
+    match type_str {
+        "float" => Some((1, AttributeScalar::Float)),
+        "vec2" => Some((2, AttributeScalar::Float)),
+        "vec3" => Some((3, AttributeScalar::Float)),
+        "vec4" => Some((4, AttributeScalar::Float)),
+        "int" => Some((1, AttributeScalar::Int)),
+        "ivec2" => Some((2, AttributeScalar::Int)),
+        "ivec3" => Some((3, AttributeScalar::Int)),
+        "ivec4" => Some((4, AttributeScalar::Int)),
+        "uint" => Some((1, AttributeScalar::Uint)),
+        "uvec2" => Some((2, AttributeScalar::Uint)),
+        "uvec3" => Some((3, AttributeScalar::Uint)),
+        "uvec4" => Some((4, AttributeScalar::Uint)),
+        _ => None,
+    }
+}
+
+/// Split GLSL declarations like "vec2 position; vec2 velocity;" into (type, name) pairs
+fn glsl_members(declarations: &str) -> Vec<(&str, &str)> {
+    declarations
+        .split(';')
+        .map(str::trim)
+        .filter(|member| !member.is_empty())
+        .map(|member| {
+            let mut words = member.split_whitespace();
+            let type_str = words.next().unwrap_or("");
+            let name = words.next().unwrap_or("");
+            (type_str, name)
+        })
+        .collect()
+}
+
+/// The std430 size and alignment of a GLSL type
+fn std430_size_align(type_str: &str) -> Option<(usize, usize)> {
+    match type_str {
+        "float" | "int" | "uint" | "bool" => Some((4, 4)),
+        "vec2" | "ivec2" | "uvec2" | "bvec2" => Some((8, 8)),
+        "vec3" | "ivec3" | "uvec3" | "bvec3" => Some((12, 16)),
+        "vec4" | "ivec4" | "uvec4" | "bvec4" => Some((16, 16)),
+        "mat2" => Some((16, 8)),
+        "mat3" => Some((48, 16)),
+        "mat4" => Some((64, 16)),
+        _ => None,
+    }
+}
+
+fn round_up(value: usize, multiple: usize) -> usize {
+    (value + multiple - 1) / multiple * multiple
+}
+
+pub(crate) const fn default_audio_bands() -> usize {
     //NOTE(jshrake): shadertoy default
     512
 }
@@ -632,6 +1555,18 @@ const fn default_flipv() -> bool {
     true
 }
 
+const fn default_storage_persist() -> bool {
+    true
+}
+
+const fn default_draw_feedback_mode() -> DrawModeConfig {
+    DrawModeConfig::Points
+}
+
+const fn default_draw_feedback_count() -> u32 {
+    1
+}
+
 const fn default_buffer_config_components() -> usize {
     4
 }
@@ -644,6 +1579,14 @@ const fn default_buffer_depth_config_format() -> BufferDepthConfig {
     BufferDepthConfig::Complete(BufferDepthFormat::U24)
 }
 
+fn default_pass_config_vertex() -> String {
+    builtin::FULLSCREEN_VERTEX.to_string()
+}
+
+fn default_pass_config_fragment() -> String {
+    builtin::VERTEX_COLOR_FRAGMENT.to_string()
+}
+
 const fn default_pass_config_loop_count() -> u32 {
     1
 }
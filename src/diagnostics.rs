@@ -0,0 +1,684 @@
+use crate::config::EffectConfig;
+use crate::error::{Error, ErrorKind, Result};
+use crate::isf::{self, Isf};
+use failure::Fail;
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// The shader stage a GLSL diagnostic was reported for
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Stage {
+    Vertex,
+    Fragment,
+    Geometry,
+    Compute,
+    Link,
+}
+
+/// A region of a source file. Lines and columns start at 1, and the end is exclusive.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Span {
+    pub line: usize,
+    pub column: usize,
+    pub end_line: usize,
+    pub end_column: usize,
+}
+
+/// A problem found in a project, with enough information to point an editor at it
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// The file the span refers to
+    pub file: Option<PathBuf>,
+    #[serde(flatten)]
+    pub span: Option<Span>,
+    pub stage: Option<Stage>,
+    /// Index of the [[pass]] the diagnostic belongs to
+    pub pass: Option<usize>,
+    pub message: String,
+    /// A likely replacement for the offending value, such as a near-miss resource name
+    pub suggestion: Option<String>,
+}
+
+impl Diagnostic {
+    pub fn error<T: Into<String>>(message: T) -> Self {
+        Self::new(Severity::Error, message.into())
+    }
+
+    pub fn warning<T: Into<String>>(message: T) -> Self {
+        Self::new(Severity::Warning, message.into())
+    }
+
+    fn new(severity: Severity, message: String) -> Self {
+        Self {
+            severity,
+            message,
+            file: None,
+            span: None,
+            stage: None,
+            pass: None,
+            suggestion: None,
+        }
+    }
+
+    pub fn with_span(mut self, span: Option<Span>) -> Self {
+        self.span = span;
+        self
+    }
+
+    pub fn with_suggestion(mut self, suggestion: Option<&str>) -> Self {
+        self.suggestion = suggestion.map(String::from);
+        self
+    }
+
+    pub fn with_file<P: AsRef<Path>>(mut self, file: P) -> Self {
+        self.file = Some(file.as_ref().to_path_buf());
+        self
+    }
+
+    pub fn with_stage(mut self, stage: Option<Stage>) -> Self {
+        self.stage = stage;
+        self
+    }
+
+    pub fn with_pass(mut self, pass: Option<usize>) -> Self {
+        self.pass = pass;
+        self
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.severity, self.message)?;
+        match (&self.file, &self.span) {
+            (Some(file), Some(span)) => write!(
+                f,
+                "\n  --> {}:{}:{}",
+                file.display(),
+                span.line,
+                span.column
+            )?,
+            (Some(file), None) => write!(f, "\n  --> {}", file.display())?,
+            (None, Some(span)) => write!(f, "\n  --> line {}, column {}", span.line, span.column)?,
+            (None, None) => {}
+        }
+        if let Some(ref suggestion) = self.suggestion {
+            write!(f, "\n  help: did you mean \"{}\"?", suggestion)?;
+        }
+        Ok(())
+    }
+}
+
+/// Parse and validate the config at `path` without creating a window or GL context.
+///
+/// Reports TOML syntax errors, references to missing resources, and shader, image,
+/// and model files that don't exist. Relative file paths are resolved against the
+/// directory containing the config.
+pub fn check(path: &Path, source: &str) -> Vec<Diagnostic> {
+    if isf::is_isf(path) {
+        return check_isf(path, source);
+    }
+    let config = match EffectConfig::parse(source) {
+        Ok(config) => config,
+        Err(err) => {
+            let span = err.line_col().map(|(line, column)| Span {
+                line: line + 1,
+                column: column + 1,
+                end_line: line + 1,
+                end_column: column + 2,
+            });
+            return vec![
+                Diagnostic::error(format!("Error parsing configuration: {}", err))
+                    .with_span(span)
+                    .with_file(path),
+            ];
+        }
+    };
+    let dir = path.parent().unwrap_or_else(|| Path::new("."));
+    let mut diagnostics = config.diagnostics(source);
+    diagnostics.extend(config.file_diagnostics(source, dir));
+    diagnostics.sort_by_key(|d| (d.span.map(|s| (s.line, s.column)), d.severity));
+    diagnostics
+        .into_iter()
+        .map(|diagnostic| diagnostic.with_file(path))
+        .collect()
+}
+
+/// Translate and validate the ISF file at `path`. The translated config has no
+/// TOML source, so problems are located in the file but not at a line.
+fn check_isf(path: &Path, source: &str) -> Vec<Diagnostic> {
+    let isf = match Isf::parse(path, source) {
+        Ok(isf) => isf,
+        Err(err) => return vec![Diagnostic::error(err.to_string()).with_file(path)],
+    };
+    let dir = path.parent().unwrap_or_else(|| Path::new("."));
+    let mut diagnostics = isf.diagnostics;
+    diagnostics.extend(isf.config.diagnostics(""));
+    diagnostics.extend(isf.config.file_diagnostics("", dir));
+    diagnostics
+        .into_iter()
+        .map(|diagnostic| diagnostic.with_file(path))
+        .collect()
+}
+
+/// Convert an error into diagnostics. Shader compiler logs are split into one diagnostic
+/// per message, located in the shader file. `config_path` is the file TOML errors refer to.
+pub fn from_error(err: &Error, config_path: &Path) -> Vec<Diagnostic> {
+    lazy_static! {
+        static ref TOML_LINE_COL: Regex = Regex::new(r"at line (\d+) column (\d+)$").unwrap();
+    }
+    let mut pass = None;
+    for cause in (err as &dyn Fail).iter_chain() {
+        let kind = match cause.downcast_ref::<Error>() {
+            Some(err) => err.kind(),
+            None => continue,
+        };
+        let (stage, log, path) = match kind {
+            ErrorKind::GLPass(index) => {
+                pass = Some(*index);
+                continue;
+            }
+            ErrorKind::GlslVertex(log, path) => (Some(Stage::Vertex), log, Some(path)),
+            ErrorKind::GlslFragment(log, path) => (Some(Stage::Fragment), log, Some(path)),
+            ErrorKind::GlslGeometry(log, path) => (Some(Stage::Geometry), log, Some(path)),
+            ErrorKind::GlslCompute(log, path) => (Some(Stage::Compute), log, Some(path)),
+            ErrorKind::GlslInclude(log, path) => (None, log, Some(path)),
+            // Link errors rarely refer to a single file
+            ErrorKind::GlslProgram(log, _, _) => (Some(Stage::Link), log, None),
+            ErrorKind::Toml(message) => {
+                let span = TOML_LINE_COL.captures(message).map(|captures| {
+                    let line = captures[1].parse().unwrap_or(1);
+                    let column = captures[2].parse().unwrap_or(1);
+                    Span {
+                        line,
+                        column,
+                        end_line: line,
+                        end_column: column + 1,
+                    }
+                });
+                return vec![Diagnostic::error(kind.to_string())
+                    .with_file(config_path)
+                    .with_span(span)];
+            }
+            ErrorKind::Isf(_) => {
+                return vec![Diagnostic::error(kind.to_string()).with_file(config_path)];
+            }
+            _ => continue,
+        };
+        // Shader paths are relative to the directory containing the config. Messages
+        // that were mapped back through includes already name their file.
+        let dir = config_path.parent().unwrap_or_else(|| Path::new("."));
+        return parse_glsl_log(log)
+            .into_iter()
+            .map(|diagnostic| {
+                let file = diagnostic.file.clone().or_else(|| path.map(PathBuf::from));
+                let diagnostic = diagnostic.with_stage(stage).with_pass(pass);
+                match file {
+                    Some(file) => diagnostic.with_file(dir.join(file)),
+                    None => diagnostic,
+                }
+            })
+            .collect();
+    }
+    vec![Diagnostic::error(err.to_string())]
+}
+
+/// Split a driver's shader info log into one diagnostic per message.
+///
+/// Understands the Mesa `0:12(5): error: ...`, NVIDIA `0(12) : error C0000: ...`,
+/// and AMD/Apple/ANGLE `ERROR: 0:12: ...` formats, as well as the
+/// `path:12:5: error: ...` and `error: ...` messages of logs rewritten through a
+/// source map. Lines that don't match are appended to the previous message.
+pub fn parse_glsl_log(log: &str) -> Vec<Diagnostic> {
+    lazy_static! {
+        static ref MAPPED: Regex =
+            Regex::new(r"^(.+):(\d+):(\d+): (error|warning): (.*)$").unwrap();
+        static ref UNMAPPED: Regex = Regex::new(r"^(error|warning): (.*)$").unwrap();
+        static ref MESA: Regex =
+            Regex::new(r"^\s*\d+:(\d+)\((\d+)\):\s*(error|warning)[^:]*:\s*(.*)$").unwrap();
+        static ref NVIDIA: Regex =
+            Regex::new(r"^\s*\d+\((\d+)\)\s*:\s*(error|warning)[^:]*:\s*(.*)$").unwrap();
+        static ref AMD: Regex = Regex::new(r"^\s*(ERROR|WARNING):\s*\d+:(\d+):\s*(.*)$").unwrap();
+    }
+    let mut diagnostics: Vec<Diagnostic> = Vec::new();
+    for text in log.lines() {
+        let mut file = None;
+        let parsed = if let Some(c) = MAPPED.captures(text) {
+            file = Some(PathBuf::from(&c[1]));
+            Some((
+                &c[4] == "error",
+                c[2].parse().ok(),
+                c[3].parse().ok(),
+                c[5].to_string(),
+            ))
+        } else if let Some(c) = UNMAPPED.captures(text) {
+            Some((&c[1] == "error", None, None, c[2].to_string()))
+        } else if let Some(c) = MESA.captures(text) {
+            Some((
+                &c[3] == "error",
+                c[1].parse().ok(),
+                c[2].parse().ok(),
+                c[4].to_string(),
+            ))
+        } else if let Some(c) = NVIDIA.captures(text) {
+            Some((&c[2] == "error", c[1].parse().ok(), None, c[3].to_string()))
+        } else if let Some(c) = AMD.captures(text) {
+            Some((&c[1] == "ERROR", c[2].parse().ok(), None, c[3].to_string()))
+        } else {
+            None
+        };
+        match parsed {
+            Some((is_error, line, column, message)) => {
+                let diagnostic = if is_error {
+                    Diagnostic::error(message)
+                } else {
+                    Diagnostic::warning(message)
+                };
+                let span = line.map(|line| {
+                    let column = column.unwrap_or(1);
+                    Span {
+                        line,
+                        column,
+                        end_line: line,
+                        end_column: column + 1,
+                    }
+                });
+                let diagnostic = diagnostic.with_span(span);
+                diagnostics.push(match file {
+                    Some(file) => diagnostic.with_file(file),
+                    None => diagnostic,
+                });
+            }
+            None if text.trim().is_empty() => (),
+            None => match diagnostics.last_mut() {
+                Some(last) => {
+                    last.message.push('\n');
+                    last.message.push_str(text.trim_end());
+                }
+                None => diagnostics.push(Diagnostic::error(text.trim_end())),
+            },
+        }
+    }
+    if diagnostics.is_empty() {
+        diagnostics.push(Diagnostic::error(log.trim()));
+    }
+    diagnostics
+}
+
+/// Writes diagnostics as JSON lines, one object per diagnostic, for editors and other tools
+pub struct JsonLines {
+    out: Box<dyn Write>,
+}
+
+impl JsonLines {
+    pub fn stderr() -> Self {
+        Self {
+            out: Box::new(io::stderr()),
+        }
+    }
+
+    pub fn create(path: &Path) -> Result<Self> {
+        let file = File::create(path).map_err(|err| Error::io(path, err))?;
+        Ok(Self {
+            out: Box::new(file),
+        })
+    }
+
+    pub fn write(&mut self, diagnostic: &Diagnostic) -> Result<()> {
+        let line = serde_json::to_string(diagnostic)
+            .map_err(|err| Error::bug(format!("Failed to serialize a diagnostic: {}", err)))?;
+        writeln!(self.out, "{}", line)
+            .and_then(|_| self.out.flush())
+            .map_err(|err| Error::io("diagnostics", err))
+    }
+}
+
+/// The closest of `candidates` to `name`, if it is close enough to be a likely typo
+pub fn suggest<'a, I: IntoIterator<Item = &'a str>>(name: &str, candidates: I) -> Option<&'a str> {
+    let max_distance = (name.chars().count() / 3).max(2);
+    candidates
+        .into_iter()
+        .map(|candidate| (edit_distance(name, candidate), candidate))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+/// Levenshtein distance, case-insensitive
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.to_lowercase().chars().collect();
+    let b: Vec<char> = b.to_lowercase().chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.iter().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + if ca == cb { 0 } else { 1 };
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// The table a key in the config belongs to
+#[derive(Debug, Clone, PartialEq)]
+enum Table {
+    Root,
+    Pass(usize),
+    Named(String),
+}
+
+#[derive(Debug)]
+struct TomlKey<'a> {
+    table: Table,
+    key: String,
+    line: usize,
+    /// Byte offset of the value within the line
+    value_start: usize,
+    text: &'a str,
+}
+
+/// Finds where tables, keys, and values appear in the config source.
+///
+/// This is a line based scan rather than a full TOML parser. It understands the
+/// `[resource]` and `[[pass]]` tables and `key = value` pairs grimoire configs are
+/// written with, and returns None for anything else.
+pub struct TomlLocator<'a> {
+    headers: Vec<(Table, usize, &'a str)>,
+    keys: Vec<TomlKey<'a>>,
+}
+
+impl<'a> TomlLocator<'a> {
+    pub fn new(source: &'a str) -> Self {
+        let mut headers = Vec::new();
+        let mut keys = Vec::new();
+        let mut table = Table::Root;
+        let mut pass_count = 0;
+        for (line, text) in source.lines().enumerate() {
+            let trimmed = text.trim();
+            if trimmed.starts_with('[') {
+                let name = trimmed
+                    .trim_start_matches('[')
+                    .split(']')
+                    .next()
+                    .unwrap_or("");
+                let name = unquote(name);
+                table = if trimmed.starts_with("[[") && name == "pass" {
+                    pass_count += 1;
+                    Table::Pass(pass_count - 1)
+                } else {
+                    Table::Named(name.to_string())
+                };
+                headers.push((table.clone(), line, text));
+            } else if !trimmed.starts_with('#') {
+                if let Some(eq) = text.find('=') {
+                    let key = unquote(&text[..eq]).to_string();
+                    if key.is_empty() {
+                        continue;
+                    }
+                    let value_start =
+                        eq + 1 + (text[eq + 1..].len() - text[eq + 1..].trim_start().len());
+                    keys.push(TomlKey {
+                        table: table.clone(),
+                        key,
+                        line,
+                        value_start,
+                        text,
+                    });
+                }
+            }
+        }
+        Self { headers, keys }
+    }
+
+    /// The `[name]` header of a resource table, or its `name = ...` key at the top of the file
+    pub fn resource(&self, name: &str) -> Option<Span> {
+        let table = Table::Named(name.to_string());
+        self.headers
+            .iter()
+            .find(|(t, _, _)| *t == table)
+            .map(|(_, line, text)| line_span(*line, text, 0, text.len()))
+            .or_else(|| self.value(&Table::Root, name))
+    }
+
+    /// The `[[pass]]` header of the pass at `index`
+    pub fn pass(&self, index: usize) -> Option<Span> {
+        let table = Table::Pass(index);
+        self.headers
+            .iter()
+            .find(|(t, _, _)| *t == table)
+            .map(|(_, line, text)| line_span(*line, text, 0, text.len()))
+    }
+
+    /// The value of `key` at the top of the file, before any table
+    pub fn root_value(&self, key: &str) -> Option<Span> {
+        self.value(&Table::Root, key)
+    }
+
+    /// The value of `key` in the pass at `index`
+    pub fn pass_value(&self, index: usize, key: &str) -> Option<Span> {
+        self.value(&Table::Pass(index), key)
+    }
+
+    /// The quoted string `needle` within the value of `key` in the pass at `index`,
+    /// such as the resource name of `iChannel0 = {resource = "buffer-a"}`
+    pub fn pass_string(&self, index: usize, key: &str, needle: &str) -> Option<Span> {
+        self.string(&Table::Pass(index), key, needle)
+    }
+
+    /// The value of `key` in the resource table `name`
+    pub fn resource_value(&self, name: &str, key: &str) -> Option<Span> {
+        self.value(&Table::Named(name.to_string()), key)
+    }
+
+    fn find(&self, table: &Table, key: &str) -> Option<&TomlKey<'a>> {
+        self.keys.iter().find(|k| k.table == *table && k.key == key)
+    }
+
+    fn value(&self, table: &Table, key: &str) -> Option<Span> {
+        self.find(table, key).map(|k| {
+            let end = value_end(k.text, k.value_start);
+            line_span(k.line, k.text, k.value_start, end)
+        })
+    }
+
+    fn string(&self, table: &Table, key: &str, needle: &str) -> Option<Span> {
+        let k = self.find(table, key)?;
+        let quoted = format!("\"{}\"", needle);
+        let start = k.value_start + k.text[k.value_start..].find(&quoted)?;
+        Some(line_span(k.line, k.text, start, start + quoted.len()))
+    }
+}
+
+/// Strip whitespace and surrounding quotes from a key or table name
+fn unquote(s: &str) -> &str {
+    s.trim().trim_matches('"').trim_matches('\'')
+}
+
+/// The end of a value on a line, excluding a trailing comment
+fn value_end(text: &str, start: usize) -> usize {
+    let mut in_string = false;
+    for (i, c) in text[start..].char_indices() {
+        match c {
+            '"' => in_string = !in_string,
+            '#' if !in_string => return start + text[start..start + i].trim_end().len(),
+            _ => {}
+        }
+    }
+    start + text[start..].trim_end().len()
+}
+
+/// Convert byte offsets within a line to a span with 1-based character columns
+fn line_span(line: usize, text: &str, start: usize, end: usize) -> Span {
+    let column = |offset: usize| text[..offset].chars().count() + 1;
+    Span {
+        line: line + 1,
+        column: column(start),
+        end_line: line + 1,
+        end_column: column(end),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn span(line: usize, column: usize, end_column: usize) -> Option<Span> {
+        Some(Span {
+            line,
+            column,
+            end_line: line,
+            end_column,
+        })
+    }
+
+    #[test]
+    fn check_toml_syntax_error() {
+        let diagnostics = check(Path::new("grim.toml"), "[[pass]\n");
+        assert_eq!(diagnostics.len(), 1);
+        assert!(diagnostics[0].is_error());
+        assert_eq!(diagnostics[0].file, Some(PathBuf::from("grim.toml")));
+        assert_eq!(diagnostics[0].span.map(|span| span.line), Some(1));
+    }
+
+    #[test]
+    fn parse_mesa_log() {
+        let log = "0:12(5): error: `foo' undeclared\n0:14(1): warning: unused variable\n";
+        let diagnostics = parse_glsl_log(log);
+        assert_eq!(diagnostics.len(), 2);
+        assert!(diagnostics[0].is_error());
+        assert_eq!(diagnostics[0].span, span(12, 5, 6));
+        assert_eq!(diagnostics[0].message, "`foo' undeclared");
+        assert_eq!(diagnostics[1].severity, Severity::Warning);
+        assert_eq!(diagnostics[1].span, span(14, 1, 2));
+    }
+
+    #[test]
+    fn parse_nvidia_log() {
+        let log = "0(12) : error C0000: syntax error, unexpected '}'\n";
+        let diagnostics = parse_glsl_log(log);
+        assert_eq!(diagnostics.len(), 1);
+        assert!(diagnostics[0].is_error());
+        assert_eq!(diagnostics[0].span, span(12, 1, 2));
+        assert_eq!(diagnostics[0].message, "syntax error, unexpected '}'");
+    }
+
+    #[test]
+    fn parse_amd_log() {
+        let log = "ERROR: 0:12: 'foo' : undeclared identifier\nERROR: 1 compilation errors.  No code generated.\n";
+        let diagnostics = parse_glsl_log(log);
+        // The summary line has no location, so it continues the previous message
+        assert_eq!(diagnostics.len(), 1);
+        assert!(diagnostics[0].is_error());
+        assert_eq!(diagnostics[0].span, span(12, 1, 2));
+        assert_eq!(
+            diagnostics[0].message,
+            "'foo' : undeclared identifier\nERROR: 1 compilation errors.  No code generated."
+        );
+    }
+
+    #[test]
+    fn parse_mapped_log() {
+        let log = "shaders/common.glsl:3:7: error: `foo' undeclared\n  note: included here\nerror: linking failed\n";
+        let diagnostics = parse_glsl_log(log);
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(
+            diagnostics[0].file,
+            Some(PathBuf::from("shaders/common.glsl"))
+        );
+        assert_eq!(diagnostics[0].span, span(3, 7, 8));
+        assert_eq!(
+            diagnostics[0].message,
+            "`foo' undeclared\n  note: included here"
+        );
+        assert_eq!(diagnostics[1].file, None);
+        assert_eq!(diagnostics[1].span, None);
+        assert_eq!(diagnostics[1].message, "linking failed");
+    }
+
+    #[test]
+    fn parse_unknown_log() {
+        let diagnostics = parse_glsl_log("  something went wrong  \n");
+        assert_eq!(diagnostics.len(), 1);
+        assert!(diagnostics[0].is_error());
+        assert_eq!(diagnostics[0].message, "  something went wrong");
+    }
+
+    #[test]
+    fn suggest_near_miss() {
+        let names = ["buffer-a", "buffer-b", "noise"];
+        assert_eq!(suggest("bufer-a", names.iter().cloned()), Some("buffer-a"));
+        assert_eq!(suggest("Noise", names.iter().cloned()), Some("noise"));
+        assert_eq!(suggest("texture", names.iter().cloned()), None);
+    }
+
+    #[test]
+    fn locate_tables_and_keys() {
+        let source = "\
+audio = {audio = \"song.mp3\"} # the soundtrack
+
+[noise]
+texture = \"noise.png\"
+
+[noise.params]
+wrap = \"repeat\"
+
+[[pass]]
+fragment = \"a.glsl\"
+iChannel0 = {resource = \"noise\"}
+
+[[pass]]
+fragment = \"b.glsl\"
+
+[[other]]
+fragment = \"c.glsl\"
+";
+        let locator = TomlLocator::new(source);
+        assert_eq!(locator.resource("audio"), span(1, 9, 29));
+        assert_eq!(locator.resource("noise"), span(3, 1, 8));
+        assert_eq!(locator.resource_value("noise", "texture"), span(4, 11, 22));
+        // Keys of a nested table don't belong to its parent
+        assert_eq!(locator.resource_value("noise", "wrap"), None);
+        assert_eq!(
+            locator.resource_value("noise.params", "wrap"),
+            span(7, 8, 16)
+        );
+        // Each [[pass]] is its own table, and other arrays of tables aren't passes
+        assert_eq!(locator.pass(0), span(9, 1, 9));
+        assert_eq!(locator.pass(1), span(13, 1, 9));
+        assert_eq!(locator.pass(2), None);
+        assert_eq!(locator.pass_value(0, "fragment"), span(10, 12, 20));
+        assert_eq!(locator.pass_value(1, "fragment"), span(14, 12, 20));
+        assert_eq!(locator.pass_value(1, "iChannel0"), None);
+        assert_eq!(
+            locator.pass_string(0, "iChannel0", "noise"),
+            span(11, 25, 32)
+        );
+        assert_eq!(locator.root_value("fragment"), None);
+    }
+}
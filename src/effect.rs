@@ -7,11 +7,16 @@ use std::default::Default;
 use std::hash::{Hash, Hasher};
 use std::time::{Duration, Instant};
 
+use crate::builtin;
+use crate::capture::{BufferAttachment, CaptureTarget, PassManifest};
 use crate::config::*;
 use crate::error::{Error, ErrorKind, Result};
 use crate::gl;
 use crate::gl::{GLRc, GLenum, GLint, GLsizei, GLuint, GLvoid};
 use crate::resource::{ResourceCubemapFace, ResourceData};
+use crate::source_map::{self, ShaderSource};
+use crate::text::strip_prefix;
+use crate::vertexshaderart;
 use failure::ResultExt;
 
 const PBO_COUNT: usize = 3;
@@ -27,21 +32,55 @@ pub struct Effect<'a> {
     staged_uniform_2f: BTreeMap<Cow<'a, str>, [f32; 2]>,
     staged_uniform_3f: BTreeMap<Cow<'a, str>, [f32; 3]>,
     staged_uniform_4f: BTreeMap<Cow<'a, str>, [f32; 4]>,
-    shader_cache: BTreeMap<String, String>,
+    shader_cache: BTreeMap<String, ShaderSource>,
     pipeline: GLPipeline,
     default_framebuffer: Framebuffer,
     vertex_buffers: BTreeMap<u64, GLVertexBuffer>,
+    storage_buffers: BTreeMap<u64, GLStorageBuffer>,
+    feedback_buffers: BTreeMap<u64, GLFeedbackBuffer>,
     resources: BTreeMap<u64, GLResource>,
     framebuffers: BTreeMap<String, Framebuffer>,
     pbo_texture_unpack_list: Vec<(GLPbo, GLResource)>,
-    config_dirty: bool,
+    // a config staged since the last draw
+    staged_config: Option<EffectConfig>,
+    // the state of a new config, built on the side until its pipeline builds
+    pending: Option<GLConfigState>,
     pipeline_dirty: bool,
+    // set when a new pipeline replaces the current one, until taken
+    pipeline_rebuilt: bool,
     first_draw: bool,
 }
 
-// The layout of this struct must match the layout of
-// the uniform block GRIM_STATE defined in file header.glsl
+/// A config and the GPU state built for it: the textures, framebuffers and buffers its
+/// pipeline reads and writes
+#[derive(Debug, Default)]
+struct GLConfigState {
+    config: EffectConfig,
+    vertex_buffers: BTreeMap<u64, GLVertexBuffer>,
+    storage_buffers: BTreeMap<u64, GLStorageBuffer>,
+    feedback_buffers: BTreeMap<u64, GLFeedbackBuffer>,
+    resources: BTreeMap<u64, GLResource>,
+    framebuffers: BTreeMap<String, Framebuffer>,
+}
+
+/// The iSampleRate given to shaders, Shadertoy's default audio rate
+pub const SAMPLE_RATE: f32 = 44100.0;
+
+/// Sound shaders are rendered in blocks of this many samples per row
+const SOUND_BLOCK_WIDTH: u32 = 512;
+const SOUND_BLOCK_HEIGHT: u32 = 64;
+/// The number of stereo samples returned by `Effect::render_sound`
+pub const SOUND_BLOCK_SAMPLES: u64 = (SOUND_BLOCK_WIDTH * SOUND_BLOCK_HEIGHT) as u64;
+/// `mainSound` takes the sample index as an int, so sounds end after about 13.5 hours
+pub const SOUND_MAX_SAMPLES: u64 = std::i32::MAX as u64 + 1;
+
+/// The uniform block of EffectState, declared before the uniforms of every profile
+const GRIM_STATE_HEADER: &str = include_str!("./grim_state.glsl");
+
+// The layout of this struct must match the layout of the uniform block GRIM_STATE
+// defined in grim_state.glsl
 #[derive(Debug)]
+#[repr(C)]
 pub struct EffectState {
     pub mouse: [f32; 4],
     pub date: [f32; 4],
@@ -50,6 +89,9 @@ pub struct EffectState {
     pub time_delta: f32,
     pub frame: f32,
     pub frame_rate: f32,
+    // iFrame as an int, for the shadertoy profile
+    pub frame_index: i32,
+    pub sample_rate: f32,
 }
 
 #[derive(Debug, Default, Clone, Copy)]
@@ -63,11 +105,55 @@ struct GLResource {
     params: GLTextureParam,
 }
 
-#[derive(Debug, Default, Clone, Copy)]
+/// The vertices of a model, drawn with its indices when ibo isn't 0
+#[derive(Debug, Default, Clone)]
 struct GLVertexBuffer {
     vbo: GLuint,
+    ibo: GLuint,
+    draws: Vec<GLDraw>,
+    stride: GLsizei,
+    attributes: Vec<GLAttribute>,
+}
+
+/// A range of indices, or of vertices when the model has no indices, drawn with its
+/// own model matrix
+#[derive(Debug, Clone)]
+struct GLDraw {
     mode: GLenum,
+    first: GLsizei,
     count: GLsizei,
+    transform: [f32; 16],
+}
+
+/// A shader storage buffer, with the contents it starts each frame with unless it persists
+#[derive(Debug, Default, Clone)]
+struct GLStorageBuffer {
+    buffer: GLuint,
+    contents: Vec<u8>,
+    persist: bool,
+}
+
+/// A pair of vertex buffers that capture passes ping-pong between, reading the vertices
+/// of one while capturing into the other
+#[derive(Debug, Default, Clone)]
+struct GLFeedbackBuffer {
+    vbos: [GLuint; 2],
+    // the index of the vbo holding the latest vertices
+    read: RefCell<usize>,
+    count: GLsizei,
+    stride: GLsizei,
+    attributes: Vec<GLAttribute>,
+    // the hash of the seed shader source that last wrote the vertices
+    seed_hash: Option<u64>,
+}
+
+/// An interleaved vertex attribute of a model or feedback buffer
+#[derive(Debug, Clone)]
+struct GLAttribute {
+    name: String,
+    components: GLint,
+    scalar: AttributeScalar,
+    offset: GLuint,
 }
 
 #[derive(Debug, Default, Clone, Copy)]
@@ -101,11 +187,46 @@ struct GLPipeline {
     // Track uniform block names to uniform buffer objects
     uniform_buffers: BTreeMap<String, GLuint>,
     passes: Vec<GLPass>,
+    // sound resources, by name
+    sounds: BTreeMap<String, GLSound>,
+    // seed shaders of feedback resources, by name
+    seeds: BTreeMap<String, GLSeed>,
+}
+
+impl GLPipeline {
+    fn programs(&self) -> impl Iterator<Item = GLuint> + '_ {
+        self.passes
+            .iter()
+            .map(|pass| pass.program)
+            .chain(self.sounds.values().map(|sound| sound.program))
+            .chain(self.seeds.values().map(|seed| seed.program))
+    }
+}
+
+/// Renders the samples of a sound shader into a two channel float texture
+#[derive(Debug, Default)]
+struct GLSound {
+    vertex_shader: GLuint,
+    fragment_shader: GLuint,
+    program: GLuint,
+    framebuffer: GLuint,
+    texture: GLuint,
+    block_start_uniform_loc: GLint,
+}
+
+/// Writes the initial vertices of a feedback resource by capturing a vertex shader
+#[derive(Debug, Default)]
+struct GLSeed {
+    vertex_shader: GLuint,
+    fragment_shader: GLuint,
+    program: GLuint,
+    source_hash: u64,
 }
 
 #[derive(Debug, Default)]
 struct GLPass {
-    vbo: Option<GLVertexBuffer>,
+    // the model this pass draws
+    model: Option<u64>,
     // program resources
     vertex_shader: GLuint,
     fragment_shader: GLuint,
@@ -113,7 +234,22 @@ struct GLPass {
     // uniforms
     resolution_uniform_loc: GLint,
     vertex_count_uniform_loc: GLint,
+    pass_index_uniform_loc: GLint,
+    model_matrix_uniform_loc: GLint,
+    // resolution, vertexCount and background of the vertexshaderart profile
+    art_resolution_uniform_loc: GLint,
+    art_vertex_count_uniform_loc: GLint,
+    art_background_uniform_loc: GLint,
     samplers: Vec<GLSampler>,
+    // compute passes dispatch compute_shader, which is 0 for passes that draw
+    compute_shader: GLuint,
+    local_size: [GLuint; 3],
+    images: Vec<GLImage>,
+    // storage buffers, in the order of their binding points
+    storage_buffers: Vec<u64>,
+    // the feedback resource this pass captures into, or the one it draws
+    capture: Option<u64>,
+    feedback: Option<u64>,
     // render state
     draw_mode: GLenum,
     draw_count: GLsizei,
@@ -131,6 +267,9 @@ struct GLSampler {
     uniform_loc: GLint,
     resolution_uniform_loc: GLint,
     playback_time_uniform_loc: GLint,
+    // iChannelResolution[N] and iChannelTime[N] of the shadertoy profile, for iChannelN
+    channel_resolution_uniform_loc: GLint,
+    channel_time_uniform_loc: GLint,
     wrap_s: GLuint,
     wrap_t: GLuint,
     wrap_r: GLuint,
@@ -138,6 +277,15 @@ struct GLSampler {
     mag_filter: GLuint,
 }
 
+/// A buffer attachment a compute pass reads and writes as an image
+#[derive(Debug, Default)]
+struct GLImage {
+    resource: u64,
+    uniform_loc: GLint,
+    resolution_uniform_loc: GLint,
+    format: GLenum,
+}
+
 impl Framebuffer {
     fn read_buffer(&self) -> &GLFramebuffer {
         match self {
@@ -182,6 +330,8 @@ impl<'a> Default for Effect<'a> {
             staged_uniform_buffer: Default::default(),
             resources: Default::default(),
             vertex_buffers: Default::default(),
+            storage_buffers: Default::default(),
+            feedback_buffers: Default::default(),
             pipeline: Default::default(),
             framebuffers: Default::default(),
             pbo_texture_unpack_list: Default::default(),
@@ -196,8 +346,10 @@ impl<'a> Default for Effect<'a> {
                 resolution: [0.0, 0.0, 0.0],
                 ..Default::default()
             }]),
-            config_dirty: true,
+            staged_config: None,
+            pending: None,
             pipeline_dirty: true,
+            pipeline_rebuilt: false,
             first_draw: true,
         }
     }
@@ -220,16 +372,27 @@ impl<'a> Effect<'a> {
 
     pub fn stage_config(&mut self, config: EffectConfig) -> Result<()> {
         debug!("[SHADER] config={:?}", config);
-        // Only mark the config as dirty if it's different from our existing config
-        if config != self.config {
-            self.config_dirty = true;
-            self.config = config;
+        // Only stage the config if it's different from the latest config
+        if config != *self.latest_config() {
+            self.staged_config = Some(config);
             self.staged_resources.clear();
         }
         Ok(())
     }
 
-    pub fn stage_shader_cache(&mut self, shader_cache: BTreeMap<String, String>) -> Result<()> {
+    /// The last staged config, which is drawn once its pipeline builds
+    fn latest_config(&self) -> &EffectConfig {
+        match (&self.staged_config, &self.pending) {
+            (Some(config), _) => config,
+            (None, Some(pending)) => &pending.config,
+            (None, None) => &self.config,
+        }
+    }
+
+    pub fn stage_shader_cache(
+        &mut self,
+        shader_cache: BTreeMap<String, ShaderSource>,
+    ) -> Result<()> {
         debug!("[SHADER] shader_cache={:?}", shader_cache);
         self.pipeline_dirty = true;
         self.shader_cache = shader_cache;
@@ -237,8 +400,22 @@ impl<'a> Effect<'a> {
     }
 
     pub fn stage_resource(&mut self, name: &str, resource: ResourceData) {
+        // The embedded textures of a model are its attachments
+        let resource = match resource {
+            ResourceData::Geometry(mut data) => {
+                for (attachment, texture) in data.textures.drain(..).enumerate() {
+                    self.stage_resource_attachment(name, attachment, ResourceData::D2(texture));
+                }
+                ResourceData::Geometry(data)
+            }
+            resource => resource,
+        };
+        self.stage_resource_attachment(name, 0, resource);
+    }
+
+    fn stage_resource_attachment(&mut self, name: &str, attachment: usize, resource: ResourceData) {
         let instant = Instant::now();
-        let hashed_name = hash_name_attachment(name, 0);
+        let hashed_name = hash_name_attachment(name, attachment);
         let resource_display = resource.to_string();
         self.staged_resources
             .entry(hashed_name)
@@ -272,28 +449,123 @@ impl<'a> Effect<'a> {
         self.staged_uniform_4f.insert(name.into(), data);
     }
 
-    pub fn snapshot(
+    /// The color attachment that holds the most recently drawn frame of the named
+    /// buffer, or of the window if `buffer` is None
+    pub fn capture_target(&self, buffer: Option<&str>, attachment: usize) -> Result<CaptureTarget> {
+        let (fbo, read_buffer) = match buffer {
+            None => {
+                let fbo = self.default_framebuffer.read_buffer();
+                let read_buffer = if fbo.framebuffer == 0 {
+                    gl::BACK
+                } else {
+                    gl::COLOR_ATTACHMENT0
+                };
+                (fbo, read_buffer)
+            }
+            Some(name) => {
+                let framebuffer = self.framebuffers.get(name).ok_or_else(|| {
+                    Error::render(format!(
+                        "Cannot capture buffer \"{}\", expected one of {:?}",
+                        name,
+                        self.framebuffers.keys().collect::<Vec<_>>()
+                    ))
+                })?;
+                let fbo = framebuffer.read_buffer();
+                if attachment >= fbo.color_attachments.len() {
+                    return Err(Error::render(format!(
+                        "Cannot capture attachment {} of buffer \"{}\", it only has {}",
+                        attachment,
+                        name,
+                        fbo.color_attachments.len()
+                    )));
+                }
+                (fbo, gl::COLOR_ATTACHMENT0 + attachment as u32)
+            }
+        };
+        Ok(CaptureTarget {
+            framebuffer: fbo.framebuffer,
+            read_buffer,
+            width: fbo.resolution[0] as u32,
+            height: fbo.resolution[1] as u32,
+        })
+    }
+
+    /// Every color attachment of every buffer, including both sides of ping-pong
+    /// buffers, followed by the window
+    pub fn buffer_attachments(&self) -> Vec<BufferAttachment> {
+        let mut attachments = Vec::new();
+        for (name, framebuffer) in &self.framebuffers {
+            let buffer = match self.config.resources.get(name) {
+                Some(ResourceConfig::Buffer(buffer)) => buffer,
+                _ => continue,
+            };
+            let read_buffer = framebuffer.read_buffer();
+            for fbo in framebuffer.all_buffers() {
+                let side = if !framebuffer.does_swap() {
+                    None
+                } else if std::ptr::eq(fbo, read_buffer) {
+                    Some("read")
+                } else {
+                    Some("write")
+                };
+                for attachment in 0..fbo.color_attachments.len() {
+                    attachments.push(BufferAttachment {
+                        name: name.clone(),
+                        side,
+                        attachment,
+                        format: buffer.attachment_format(attachment),
+                        target: CaptureTarget {
+                            framebuffer: fbo.framebuffer,
+                            read_buffer: gl::COLOR_ATTACHMENT0 + attachment as u32,
+                            width: fbo.resolution[0] as u32,
+                            height: fbo.resolution[1] as u32,
+                        },
+                    });
+                }
+            }
+        }
+        if let Ok(target) = self.capture_target(None, 0) {
+            attachments.push(BufferAttachment {
+                name: "window".to_string(),
+                side: None,
+                attachment: 0,
+                format: BufferFormat::U8,
+                target,
+            });
+        }
+        attachments
+    }
+
+    /// The passes in draw order, with the buffer and resolution each one draws into
+    pub fn pass_manifests(&self) -> Vec<PassManifest> {
+        self.config
+            .passes
+            .iter()
+            .enumerate()
+            .map(|(index, pass)| {
+                let resolution = self.framebuffer_for_pass(pass).write_buffer().resolution;
+                PassManifest {
+                    index,
+                    vertex: pass.vertex.clone(),
+                    fragment: pass.fragment.clone(),
+                    compute: pass.compute.clone(),
+                    buffer: pass.buffer.clone(),
+                    width: resolution[0] as u32,
+                    height: resolution[1] as u32,
+                    disable: pass.disable,
+                    loop_count: pass.loop_count,
+                }
+            })
+            .collect()
+    }
+
+    pub fn draw(
         &mut self,
         gl: &GLRc,
-        buffer: &mut Vec<u8>,
-        window_width: i32,
-        window_height: i32,
+        framebuffer: GLuint,
+        window_width: f32,
+        window_height: f32,
     ) -> Result<()> {
-        let format = gl::RGB;
-        let pixel_type = gl::UNSIGNED_BYTE;
-        gl.read_pixels_into_buffer(
-            0,
-            0,
-            window_width,
-            window_height,
-            format,
-            pixel_type,
-            buffer.as_mut_slice(),
-        );
-        Ok(())
-    }
-
-    pub fn draw(&mut self, gl: &GLRc, window_width: f32, window_height: f32) -> Result<()> {
         if self.first_draw {
             self.first_draw = false;
             // TODO(jshrake): Consider adding the following to the config: enables: ["multisample, framebuffer_srgb"]
@@ -303,66 +575,106 @@ impl<'a> Effect<'a> {
             gl.enable(gl::PROGRAM_POINT_SIZE);
         }
 
-        // If the config didn't validate, go no further
-        // The user needs to fix the error in their file
-        if !self.config.is_ok() {
-            return Ok(());
-        }
-
         // determine what we need to initialize, and reset various dirty flags
-        let resources_need_init = self.config_dirty;
-        let framebuffers_need_init = self.config_dirty;
+        let staged_config = self.staged_config.take();
         let pipeline_need_init = self.pipeline_dirty;
         let window_resized = (self.window_resolution[0] - window_width).abs() > std::f32::EPSILON
             || (self.window_resolution[1] - window_height).abs() > std::f32::EPSILON;
-        self.config_dirty = false;
         self.pipeline_dirty = false;
         self.window_resolution[0] = window_width;
         self.window_resolution[1] = window_height;
         self.window_resolution[2] = self.window_resolution[0] / self.window_resolution[1];
         match self.default_framebuffer {
-            Framebuffer::Simple(ref mut fbo) => fbo[0].resolution = self.window_resolution,
+            Framebuffer::Simple(ref mut fbo) => {
+                fbo[0].framebuffer = framebuffer;
+                fbo[0].resolution = self.window_resolution;
+            }
             _ => unreachable!("default framebuffer is always simple"),
         }
 
-        // delete non framebuffer resources on dirty config
-        if resources_need_init {
-            self.gpu_delete_non_buffer_resources(gl);
-        }
-
-        // build or rebuild framebuffers on resize
-        if framebuffers_need_init || window_resized {
+        // rebuild framebuffers on resize, for the current config and the pending one
+        if window_resized {
             let instant = Instant::now();
-            self.gpu_delete_buffer_resources(gl);
-            self.gpu_init_framebuffers(gl);
+            if self.config.is_ok() {
+                self.gpu_rebuild_framebuffers(gl);
+            }
+            if let Some(mut pending) = self.pending.take() {
+                if pending.config.is_ok() {
+                    self.with_config_state(&mut pending, |effect| {
+                        effect.gpu_rebuild_framebuffers(gl)
+                    });
+                }
+                self.pending = Some(pending);
+            }
             info!(
                 "[DRAW] Initializing framebuffer objects took {:?}",
                 instant.elapsed()
             );
         }
 
-        // build or rebuild the rendering pipeline
+        // A new config builds its framebuffers and buffers on the side, next to the ones
+        // of the current config, which keep drawing until the pipeline of the new config
+        // builds. A config staged before then replaces the pending one.
+        if let Some(config) = staged_config {
+            if let Some(mut pending) = self.pending.take() {
+                self.with_config_state(&mut pending, |effect| {
+                    effect.gpu_delete_config_resources(gl)
+                });
+            }
+            let mut pending = GLConfigState {
+                config,
+                ..Default::default()
+            };
+            // If the config didn't validate, build nothing for it
+            // The user needs to fix the error in their file
+            if pending.config.is_ok() {
+                let instant = Instant::now();
+                self.with_config_state(&mut pending, |effect| {
+                    effect.gpu_init_storage_buffers(gl);
+                    effect.gpu_init_feedback_buffers(gl);
+                    effect.gpu_init_framebuffers(gl);
+                });
+                info!(
+                    "[DRAW] Initializing config resources took {:?}",
+                    instant.elapsed()
+                );
+            }
+            self.pending = Some(pending);
+        }
+
+        // build or rebuild the rendering pipeline. The new pipeline is built on the side
+        // and only replaces the current one once every pass compiles and links, so the
+        // last good pipeline keeps drawing while the user fixes their shader. The build
+        // error is returned after this frame is drawn.
+        let mut pipeline_result = Ok(());
         if pipeline_need_init {
             let instant = Instant::now();
-            self.gpu_delete_pipeline_resources(gl);
-            self.gpu_stage_resources(gl);
-            self.gpu_init_pipeline(gl)?;
+            pipeline_result = self.gpu_build_pipeline(gl);
             info!(
                 "[DRAW] Initializing rendering pipeline took {:?}",
                 instant.elapsed()
             );
         }
 
-        // Return early if gpu pipeline is not ok. This indicates that gpu_init_pipeline
-        // failed and the user needs to fix the error in their shader file
-        if !self.gpu_pipeline_is_ok() {
+        // Resources streamed since a config change belong to the pending config
+        if let Some(mut pending) = self.pending.take() {
+            self.with_config_state(&mut pending, |effect| effect.gpu_stage_resources(gl));
+            self.pending = Some(pending);
+        }
+
+        // Return early if there's nothing to draw. This indicates that no config
+        // validated and built a pipeline yet, and the user needs to fix the error in
+        // their file
+        if !self.config.is_ok() || !self.gpu_pipeline_is_ok() {
             self.staged_resources.clear();
-            return Ok(());
+            return pipeline_result;
         }
 
         let instant = Instant::now();
         self.gpu_stage_resources(gl);
         self.gpu_stage_buffer_data(gl);
+        self.gpu_reset_storage_buffers(gl);
+        self.gpu_seed_feedback_buffers(gl);
         let last_call_duration = instant.elapsed();
         if last_call_duration > Duration::from_millis(1) {
             warn!(
@@ -387,11 +699,11 @@ impl<'a> Effect<'a> {
                 last_call_duration
             );
         }
-        Ok(())
+        pipeline_result
     }
 
     fn framebuffer_for_pass(&self, pass: &PassConfig) -> &Framebuffer {
-        if let Some(ref buffer_name) = pass.buffer {
+        if let Some(buffer_name) = pass.resolution_buffer() {
             self.framebuffers
                 .get(buffer_name)
                 .unwrap_or(&self.default_framebuffer)
@@ -400,6 +712,79 @@ impl<'a> Effect<'a> {
         }
     }
 
+    pub fn config(&self) -> &EffectConfig {
+        &self.config
+    }
+
+    pub fn is_ok(&self) -> bool {
+        self.staged_config.is_none()
+            && self.pending.is_none()
+            && self.config.is_ok()
+            && self.gpu_pipeline_is_ok()
+    }
+
+    /// Returns true if every pass compiled and linked and replaced the previous
+    /// pipeline since the last call
+    pub fn take_pipeline_rebuilt(&mut self) -> bool {
+        std::mem::replace(&mut self.pipeline_rebuilt, false)
+    }
+
+    /// Render `SOUND_BLOCK_SAMPLES` interleaved stereo samples of the named sound
+    /// resource, starting at the sample index `start`. Returns None if the current
+    /// pipeline has no such sound, or if the block ends past `SOUND_MAX_SAMPLES`. The
+    /// framebuffer, viewport and program bound before are bound again after.
+    pub fn render_sound(&self, gl: &GLRc, name: &str, start: u64) -> Option<Vec<f32>> {
+        let sound = self.pipeline.sounds.get(name)?;
+        if start + SOUND_BLOCK_SAMPLES > SOUND_MAX_SAMPLES {
+            return None;
+        }
+        let (mut framebuffer, mut viewport, mut program) = ([0], [0; 4], [0]);
+        unsafe {
+            gl.get_integer_v(gl::FRAMEBUFFER_BINDING, &mut framebuffer);
+            gl.get_integer_v(gl::VIEWPORT, &mut viewport);
+            gl.get_integer_v(gl::CURRENT_PROGRAM, &mut program);
+        }
+        gl.bind_framebuffer(gl::FRAMEBUFFER, sound.framebuffer);
+        gl.viewport(
+            0,
+            0,
+            SOUND_BLOCK_WIDTH as GLint,
+            SOUND_BLOCK_HEIGHT as GLint,
+        );
+        gl.disable(gl::BLEND);
+        gl.disable(gl::DEPTH_TEST);
+        gl.use_program(sound.program);
+        gl.uniform_1i(sound.block_start_uniform_loc, start as i32);
+        gl.bind_vertex_array(self.pipeline.vertex_array_object);
+        gl.draw_arrays(gl::TRIANGLES, 0, 3);
+        let mut bytes = vec![0; SOUND_BLOCK_SAMPLES as usize * 2 * 4];
+        gl.pixel_store_i(gl::PACK_ALIGNMENT, 1);
+        gl.read_buffer(gl::COLOR_ATTACHMENT0);
+        gl.read_pixels_into_buffer(
+            0,
+            0,
+            SOUND_BLOCK_WIDTH as GLint,
+            SOUND_BLOCK_HEIGHT as GLint,
+            gl::RG,
+            gl::FLOAT,
+            bytes.as_mut_slice(),
+        );
+        gl.bind_framebuffer(gl::FRAMEBUFFER, framebuffer[0] as GLuint);
+        gl.viewport(viewport[0], viewport[1], viewport[2], viewport[3]);
+        gl.use_program(program[0] as GLuint);
+        Some(
+            bytes
+                .chunks_exact(4)
+                .map(|b| f32::from_ne_bytes([b[0], b[1], b[2], b[3]]))
+                .collect(),
+        )
+    }
+
+    /// Names of the sound resources of the current pipeline
+    pub fn sound_names(&self) -> Vec<&str> {
+        self.pipeline.sounds.keys().map(String::as_str).collect()
+    }
+
     fn gpu_pipeline_is_ok(&self) -> bool {
         // Assume our pipeline is ok if the count matches the
         // number of passes defined in the config
@@ -469,14 +854,131 @@ impl<'a> Effect<'a> {
         self.framebuffers.clear();
     }
 
-    fn gpu_delete_pipeline_resources(&mut self, gl: &GLRc) {
-        gl.delete_vertex_arrays(&[self.pipeline.vertex_array_object]);
-        for pass in &self.pipeline.passes {
-            gl.delete_program(pass.program);
-            gl.delete_shader(pass.vertex_shader);
-            gl.delete_shader(pass.fragment_shader);
+    fn gpu_delete_storage_buffers(&mut self, gl: &GLRc) {
+        for storage in self.storage_buffers.values() {
+            gl.delete_buffers(&[storage.buffer]);
         }
+        self.storage_buffers.clear();
+    }
+
+    fn gpu_delete_feedback_buffers(&mut self, gl: &GLRc) {
+        for feedback in self.feedback_buffers.values() {
+            gl.delete_buffers(&feedback.vbos);
+        }
+        self.feedback_buffers.clear();
+    }
+
+    fn gpu_delete_pipeline_resources(&mut self, gl: &GLRc) {
+        gpu_delete_pipeline(gl, &self.pipeline);
+        self.pipeline.vertex_array_object = 0;
         self.pipeline.passes.clear();
+        self.pipeline.sounds.clear();
+        self.pipeline.seeds.clear();
+    }
+
+    /// Delete the framebuffers, textures and buffers of the current config
+    fn gpu_delete_config_resources(&mut self, gl: &GLRc) {
+        let textures: BTreeSet<GLuint> = self
+            .resources
+            .values()
+            .map(|resource| resource.texture)
+            .collect();
+        self.pbo_texture_unpack_list
+            .retain(|(_, resource)| !textures.contains(&resource.texture));
+        self.gpu_delete_buffer_resources(gl);
+        self.gpu_delete_non_buffer_resources(gl);
+        for vertex_buffer in self.vertex_buffers.values() {
+            gl.delete_buffers(&[vertex_buffer.vbo, vertex_buffer.ibo]);
+        }
+        self.vertex_buffers.clear();
+        self.gpu_delete_storage_buffers(gl);
+        self.gpu_delete_feedback_buffers(gl);
+    }
+
+    fn gpu_rebuild_framebuffers(&mut self, gl: &GLRc) {
+        self.gpu_delete_buffer_resources(gl);
+        self.gpu_init_framebuffers(gl);
+    }
+
+    /// Swap the config and its GPU state with `state`
+    fn swap_config_state(&mut self, state: &mut GLConfigState) {
+        std::mem::swap(&mut self.config, &mut state.config);
+        std::mem::swap(&mut self.vertex_buffers, &mut state.vertex_buffers);
+        std::mem::swap(&mut self.storage_buffers, &mut state.storage_buffers);
+        std::mem::swap(&mut self.feedback_buffers, &mut state.feedback_buffers);
+        std::mem::swap(&mut self.resources, &mut state.resources);
+        std::mem::swap(&mut self.framebuffers, &mut state.framebuffers);
+    }
+
+    /// Run `f` with `state` in place of the current config and its GPU state
+    fn with_config_state<T, F>(&mut self, state: &mut GLConfigState, f: F) -> T
+    where
+        F: FnOnce(&mut Self) -> T,
+    {
+        self.swap_config_state(state);
+        let result = f(self);
+        self.swap_config_state(state);
+        result
+    }
+
+    /// Build the pipeline of the pending config, or rebuild the pipeline of the current
+    /// config, and swap it in if it builds
+    fn gpu_build_pipeline(&mut self, gl: &GLRc) -> Result<()> {
+        match self.pending.take() {
+            Some(mut pending) => {
+                if !pending.config.is_ok() {
+                    self.pending = Some(pending);
+                    return Ok(());
+                }
+                let result = self.with_config_state(&mut pending, |effect| {
+                    effect.gpu_stage_resources(gl);
+                    effect.gpu_init_pipeline(gl)
+                });
+                match result {
+                    Ok(pipeline) => {
+                        self.gpu_swap_pipeline(gl, pipeline, Some(pending));
+                        Ok(())
+                    }
+                    Err(err) => {
+                        self.pending = Some(pending);
+                        Err(err)
+                    }
+                }
+            }
+            None if self.config.is_ok() => {
+                self.gpu_stage_resources(gl);
+                let pipeline = self.gpu_init_pipeline(gl)?;
+                self.gpu_swap_pipeline(gl, pipeline, None);
+                Ok(())
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Replace the current pipeline with one that built, and the current config and its
+    /// GPU state with `state` when the pipeline was built for a new config. Only now are
+    /// the old pipeline and the GPU state it drew with deleted.
+    fn gpu_swap_pipeline(
+        &mut self,
+        gl: &GLRc,
+        mut pipeline: GLPipeline,
+        state: Option<GLConfigState>,
+    ) {
+        self.gpu_delete_pipeline_resources(gl);
+        if let Some(mut state) = state {
+            self.swap_config_state(&mut state);
+            self.with_config_state(&mut state, |effect| effect.gpu_delete_config_resources(gl));
+        }
+        // Uniform buffers outlive the pipeline, so connect the existing
+        // uniform buffers to the new pass programs
+        pipeline.uniform_buffers = std::mem::take(&mut self.pipeline.uniform_buffers);
+        for (index, (name, buffer)) in pipeline.uniform_buffers.iter().enumerate() {
+            for program in pipeline.programs() {
+                gl::connect_uniform_buffer(gl, *buffer, program, name, index as u32);
+            }
+        }
+        self.pipeline = pipeline;
+        self.pipeline_rebuilt = true;
     }
 
     fn gpu_pbo_to_texture_transfer(&mut self, gl: &GLRc) {
@@ -515,32 +1017,36 @@ impl<'a> Effect<'a> {
                 // The lookup can fail if the user supplies a bad configuration,
                 // like a typo in the buffer value
                 let framebuffer = self.framebuffer_for_pass(&pass_config);
-                gl.bind_framebuffer(gl::FRAMEBUFFER, framebuffer.write_buffer().framebuffer);
-                // Set the viewport to match the framebuffer resolution
-                gl.viewport(
-                    0,
-                    0,
-                    framebuffer.write_buffer().resolution[0] as GLint,
-                    framebuffer.write_buffer().resolution[1] as GLint,
-                );
-                let mut clear_flag = None;
-                if let Some(clear_color) = pass.clear_color {
-                    gl.clear_color(
-                        clear_color[0],
-                        clear_color[1],
-                        clear_color[2],
-                        clear_color[3],
+                // Compute and capture passes write through their images and feedback
+                // buffers rather than a framebuffer
+                if !pass_config.is_compute() && !pass_config.is_capture() {
+                    gl.bind_framebuffer(gl::FRAMEBUFFER, framebuffer.write_buffer().framebuffer);
+                    // Set the viewport to match the framebuffer resolution
+                    gl.viewport(
+                        0,
+                        0,
+                        framebuffer.write_buffer().resolution[0] as GLint,
+                        framebuffer.write_buffer().resolution[1] as GLint,
                     );
-                    clear_flag = Some(gl::COLOR_BUFFER_BIT);
-                }
-                if let Some(clear_depth) = pass.clear_depth {
-                    gl.clear_depth(clear_depth.into());
-                    clear_flag = clear_flag.map_or(Some(gl::DEPTH_BUFFER_BIT), |flag| {
-                        Some(flag | gl::DEPTH_BUFFER_BIT)
-                    });
-                }
-                if let Some(clear_flag) = clear_flag {
-                    gl.clear(clear_flag);
+                    let mut clear_flag = None;
+                    if let Some(clear_color) = pass.clear_color {
+                        gl.clear_color(
+                            clear_color[0],
+                            clear_color[1],
+                            clear_color[2],
+                            clear_color[3],
+                        );
+                        clear_flag = Some(gl::COLOR_BUFFER_BIT);
+                    }
+                    if let Some(clear_depth) = pass.clear_depth {
+                        gl.clear_depth(clear_depth.into());
+                        clear_flag = clear_flag.map_or(Some(gl::DEPTH_BUFFER_BIT), |flag| {
+                            Some(flag | gl::DEPTH_BUFFER_BIT)
+                        });
+                    }
+                    if let Some(clear_flag) = clear_flag {
+                        gl.clear(clear_flag);
+                    }
                 }
 
                 // Bind the program for this pass
@@ -554,7 +1060,33 @@ impl<'a> Effect<'a> {
                     );
                 }
                 if pass.vertex_count_uniform_loc > -1 {
-                    gl.uniform_1i(pass.vertex_count_uniform_loc, pass.draw_count);
+                    // models count the vertices of all of their draws
+                    let vertex_count =
+                        match pass.model.and_then(|hash| self.vertex_buffers.get(&hash)) {
+                            Some(vertex_buffer) => {
+                                vertex_buffer.draws.iter().map(|draw| draw.count).sum()
+                            }
+                            None => pass.draw_count,
+                        };
+                    gl.uniform_1i(pass.vertex_count_uniform_loc, vertex_count);
+                }
+                if pass.pass_index_uniform_loc > -1 {
+                    gl.uniform_1i(pass.pass_index_uniform_loc, pass_idx as GLint);
+                }
+                if pass.art_resolution_uniform_loc > -1 {
+                    gl.uniform_2fv(
+                        pass.art_resolution_uniform_loc,
+                        &framebuffer.write_buffer().resolution[..2],
+                    );
+                }
+                if pass.art_vertex_count_uniform_loc > -1 {
+                    gl.uniform_1f(pass.art_vertex_count_uniform_loc, pass.draw_count as f32);
+                }
+                if pass.art_background_uniform_loc > -1 {
+                    gl.uniform_4fv(
+                        pass.art_background_uniform_loc,
+                        &pass.clear_color.unwrap_or([0.0, 0.0, 0.0, 1.0]),
+                    );
                 }
 
                 // Set staged uniform data
@@ -628,90 +1160,138 @@ impl<'a> Effect<'a> {
                         if sampler.playback_time_uniform_loc > -1 {
                             gl.uniform_1f(sampler.playback_time_uniform_loc as i32, resource.time);
                         }
+                        if sampler.channel_resolution_uniform_loc > -1 {
+                            gl.uniform_3fv(
+                                sampler.channel_resolution_uniform_loc as i32,
+                                &resource.resolution,
+                            );
+                        }
+                        if sampler.channel_time_uniform_loc > -1 {
+                            gl.uniform_1f(sampler.channel_time_uniform_loc as i32, resource.time);
+                        }
                     }
                 }
-                // Set the blend state
-                if let Some((src_rgb, dst_rgb, src_a, dst_a)) = pass.blend {
-                    gl.enable(gl::BLEND);
-                    gl.blend_func_separate(src_rgb, dst_rgb, src_a, dst_a);
-                } else {
-                    gl.disable(gl::BLEND);
-                }
-                // Set the depth test state
-                if let Some(depth_func) = pass.depth {
-                    gl.enable(gl::DEPTH_TEST);
-                    gl.depth_func(depth_func);
-                } else {
-                    gl.disable(gl::DEPTH_TEST);
-                }
-                gl.depth_mask(pass.depth_write);
-                // Draw!
-                if let Some(vbo) = pass.vbo {
-                    let position_loc = gl.get_attrib_location(pass.program, "position");
-                    let normal_loc = gl.get_attrib_location(pass.program, "normal");
-                    let defined_position = position_loc >= 0;
-                    let defined_normal = normal_loc >= 0;
-                    let stride = 6 * std::mem::size_of::<f32>() as i32;
-                    let position_offset = 0;
-                    let normal_offset = 3 * std::mem::size_of::<f32>() as u32;
-                    gl.bind_buffer(gl::ARRAY_BUFFER, vbo.vbo);
-                    if defined_position {
-                        gl.enable_vertex_attrib_array(position_loc as u32);
-                        gl.vertex_attrib_pointer(
-                            position_loc as u32,
-                            3,
-                            gl::FLOAT,
-                            false,
-                            stride,
-                            position_offset,
+                // Bind storage buffers to the binding points their blocks declare
+                for (binding, hash) in pass.storage_buffers.iter().enumerate() {
+                    if let Some(storage) = self.storage_buffers.get(hash) {
+                        gl.bind_buffer_base(
+                            gl::SHADER_STORAGE_BUFFER,
+                            binding as GLuint,
+                            storage.buffer,
                         );
                     }
-                    if defined_normal {
-                        gl.enable_vertex_attrib_array(normal_loc as u32);
-                        gl.vertex_attrib_pointer(
-                            normal_loc as u32,
-                            3,
-                            gl::FLOAT,
-                            false,
-                            stride,
-                            normal_offset,
-                        );
+                }
+                if pass_config.is_compute() {
+                    self.gpu_dispatch(gl, pass, pass_config, framebuffer);
+                } else if let Some(feedback) = pass
+                    .capture
+                    .and_then(|hash| self.feedback_buffers.get(&hash))
+                {
+                    gpu_capture(gl, pass.program, feedback);
+                } else {
+                    // Set the blend state
+                    if let Some((src_rgb, dst_rgb, src_a, dst_a)) = pass.blend {
+                        gl.enable(gl::BLEND);
+                        gl.blend_func_separate(src_rgb, dst_rgb, src_a, dst_a);
+                    } else {
+                        gl.disable(gl::BLEND);
                     }
-                    gl.draw_arrays_instanced(
-                        pass.draw_mode,
-                        0,
-                        pass.draw_count,
-                        pass.instance_count,
-                    );
-                    if defined_position {
-                        gl.disable_vertex_attrib_array(position_loc as u32);
+                    // Set the depth test state
+                    if let Some(depth_func) = pass.depth {
+                        gl.enable(gl::DEPTH_TEST);
+                        gl.depth_func(depth_func);
+                    } else {
+                        gl.disable(gl::DEPTH_TEST);
                     }
-                    if defined_normal {
-                        gl.disable_vertex_attrib_array(normal_loc as u32);
+                    gl.depth_mask(pass.depth_write);
+                    // Draw!
+                    if let Some(vertex_buffer) =
+                        pass.model.and_then(|hash| self.vertex_buffers.get(&hash))
+                    {
+                        let locations = gl_bind_attributes(
+                            gl,
+                            pass.program,
+                            vertex_buffer.vbo,
+                            vertex_buffer.stride,
+                            &vertex_buffer.attributes,
+                        );
+                        gl.bind_buffer(gl::ELEMENT_ARRAY_BUFFER, vertex_buffer.ibo);
+                        for draw in &vertex_buffer.draws {
+                            if pass.model_matrix_uniform_loc > -1 {
+                                gl.uniform_matrix_4fv(
+                                    pass.model_matrix_uniform_loc,
+                                    false,
+                                    &draw.transform,
+                                );
+                            }
+                            if vertex_buffer.ibo != 0 {
+                                let offset = draw.first as usize * std::mem::size_of::<u32>();
+                                gl.draw_elements_instanced(
+                                    draw.mode,
+                                    draw.count,
+                                    gl::UNSIGNED_INT,
+                                    offset as GLuint,
+                                    pass.instance_count,
+                                );
+                            } else {
+                                gl.draw_arrays_instanced(
+                                    draw.mode,
+                                    draw.first,
+                                    draw.count,
+                                    pass.instance_count,
+                                );
+                            }
+                        }
+                        gl.bind_buffer(gl::ELEMENT_ARRAY_BUFFER, 0);
+                        gl_unbind_attributes(gl, &locations);
+                    } else if let Some(feedback) = pass
+                        .feedback
+                        .and_then(|hash| self.feedback_buffers.get(&hash))
+                    {
+                        let locations = gl_bind_attributes(
+                            gl,
+                            pass.program,
+                            feedback.vbos[*feedback.read.borrow()],
+                            feedback.stride,
+                            &feedback.attributes,
+                        );
+                        gl.draw_arrays_instanced(
+                            pass.draw_mode,
+                            0,
+                            pass.draw_count,
+                            pass.instance_count,
+                        );
+                        gl_unbind_attributes(gl, &locations);
+                    } else {
+                        gl.draw_arrays(pass.draw_mode, 0, pass.draw_count);
                     }
-                    gl.bind_buffer(gl::ARRAY_BUFFER, 0);
-                } else {
-                    gl.draw_arrays(pass.draw_mode, 0, pass.draw_count);
-                }
-                // if this framebuffer swaps the read and write buffers, then
-                // swap the read + write color attachments in the self.resources map
-                if framebuffer.does_swap() {
-                    let mut swap_color_attachment_resources = Vec::new();
-                    for i in 0..framebuffer.write_buffer().color_attachments.len() {
-                        let write_hash = framebuffer.write_buffer().color_attachments[i];
-                        let read_hash = framebuffer.read_buffer().color_attachments[i];
-                        swap_color_attachment_resources.push((write_hash, read_hash));
+                    // Make the storage writes of this pass visible to the passes after it
+                    if !pass.storage_buffers.is_empty() {
+                        gl::memory_barrier(gl::SHADER_STORAGE_BARRIER_BIT);
                     }
-                    framebuffer.swap_read_write();
-                    for (write_hash, read_hash) in swap_color_attachment_resources {
-                        let write = self.resources[&write_hash];
-                        let read = self.resources[&read_hash];
-                        self.resources.insert(write_hash, read);
-                        self.resources.insert(read_hash, write);
+                    // if this framebuffer swaps the read and write buffers, then
+                    // swap the read + write color attachments in the self.resources map
+                    if framebuffer.does_swap() {
+                        let mut swap_color_attachment_resources = Vec::new();
+                        for i in 0..framebuffer.write_buffer().color_attachments.len() {
+                            let write_hash = framebuffer.write_buffer().color_attachments[i];
+                            let read_hash = framebuffer.read_buffer().color_attachments[i];
+                            swap_color_attachment_resources.push((write_hash, read_hash));
+                        }
+                        framebuffer.swap_read_write();
+                        for (write_hash, read_hash) in swap_color_attachment_resources {
+                            let write = self.resources[&write_hash];
+                            let read = self.resources[&read_hash];
+                            self.resources.insert(write_hash, read);
+                            self.resources.insert(read_hash, write);
+                        }
                     }
                 }
                 // Unbind our program to avoid spurious nvidia warnings in apitrace
                 gl.use_program(0);
+                for binding in 0..pass.storage_buffers.len() {
+                    gl.bind_buffer_base(gl::SHADER_STORAGE_BUFFER, binding as GLuint, 0);
+                }
                 // Unbind our textures to make debugging cleaner
                 for (sampler_idx, ref sampler) in pass.samplers.iter().enumerate() {
                     if sampler.uniform_loc < 0 {
@@ -735,27 +1315,173 @@ impl<'a> Effect<'a> {
         Ok(())
     }
 
-    fn gpu_init_pipeline(&mut self, gl: &GLRc) -> Result<()> {
-        self.pipeline.vertex_array_object = gl::create_vao(gl);
-        let uniform_strings = {
-            // build the list of uniform strings from the resouces config
-            let mut uniform_strings = Vec::new();
-            for (name, input) in &self.config.resources {
-                let type_str = match input {
-                    ResourceConfig::UniformFloat(_) => "float",
-                    ResourceConfig::UniformVec2(_) => "vec2",
-                    ResourceConfig::UniformVec3(_) => "vec3",
-                    ResourceConfig::UniformVec4(_) => "vec4",
-                    _ => continue,
-                };
-                uniform_strings.push(format!("uniform {} {};", type_str, name));
+    /// Bind the images of a compute pass, dispatch its workgroups, and wait for its writes
+    /// before anything else reads the images or storage buffers
+    fn gpu_dispatch(
+        &self,
+        gl: &GLRc,
+        pass: &GLPass,
+        pass_config: &PassConfig,
+        framebuffer: &Framebuffer,
+    ) {
+        for (image_idx, image) in pass.images.iter().enumerate() {
+            if image.uniform_loc < 0 {
+                continue;
+            }
+            if let Some(resource) = self.resources.get(&image.resource) {
+                gl::bind_image_texture(
+                    image_idx as GLuint,
+                    resource.texture,
+                    0,
+                    false,
+                    0,
+                    gl::READ_WRITE,
+                    image.format,
+                );
+                gl.uniform_1i(image.uniform_loc, image_idx as GLint);
+                if image.resolution_uniform_loc > -1 {
+                    gl.uniform_3fv(image.resolution_uniform_loc, &resource.resolution);
+                }
+            }
+        }
+        let workgroups = match pass_config.workgroups {
+            Some(WorkgroupsConfig::Count(count)) => count,
+            // Round up so that the workgroups cover the edges of the buffer
+            Some(WorkgroupsConfig::Buffer(_)) => {
+                let resolution = framebuffer.write_buffer().resolution;
+                let x = pass.local_size[0].max(1);
+                let y = pass.local_size[1].max(1);
+                [
+                    (resolution[0] as GLuint + x - 1) / x,
+                    (resolution[1] as GLuint + y - 1) / y,
+                    1,
+                ]
+            }
+            None => [0, 0, 0],
+        };
+        gl::dispatch_compute(workgroups[0], workgroups[1], workgroups[2]);
+        gl::memory_barrier(
+            gl::SHADER_IMAGE_ACCESS_BARRIER_BIT
+                | gl::TEXTURE_FETCH_BARRIER_BIT
+                | gl::FRAMEBUFFER_BARRIER_BIT
+                | gl::TEXTURE_UPDATE_BARRIER_BIT
+                | gl::PIXEL_BUFFER_BARRIER_BIT
+                | gl::SHADER_STORAGE_BARRIER_BIT,
+        );
+        for (image_idx, image) in pass.images.iter().enumerate() {
+            if image.uniform_loc > -1 {
+                gl::bind_image_texture(
+                    image_idx as GLuint,
+                    0,
+                    0,
+                    false,
+                    0,
+                    gl::READ_WRITE,
+                    image.format,
+                );
+            }
+        }
+    }
+
+    /// Build a pipeline from the current config and shader cache without touching the
+    /// current pipeline. If any pass fails to compile or link, the passes built so far
+    /// are deleted.
+    fn gpu_init_pipeline(&self, gl: &GLRc) -> Result<GLPipeline> {
+        // Capture passes and passes that draw feedback need transform feedback
+        if !gl::supports_transform_feedback() {
+            let feedback = self
+                .config
+                .resources
+                .iter()
+                .find(|(_, resource)| match resource {
+                    ResourceConfig::Feedback(_) => true,
+                    _ => false,
+                });
+            if let Some((name, _)) = feedback {
+                return Err(Error::render(
+                    "Feedback resources need transform feedback, which the GL context lacks",
+                ))
+                .with_context(|_| ErrorKind::BadResourceConfig(name.clone()))
+                .map_err(Error::from);
             }
-            uniform_strings
+        }
+        let mut pipeline = GLPipeline {
+            vertex_array_object: gl::create_vao(gl),
+            ..Default::default()
         };
+        let result = self
+            .gpu_init_pipeline_passes(gl, &mut pipeline)
+            .and_then(|_| self.gpu_init_pipeline_sounds(gl, &mut pipeline))
+            .and_then(|_| self.gpu_init_pipeline_seeds(gl, &mut pipeline));
+        if let Err(err) = result {
+            gpu_delete_pipeline(gl, &pipeline);
+            return Err(err);
+        }
+        Ok(pipeline)
+    }
+
+    /// The built-in uniform declarations of the config's profile, which follow the
+    /// GRIM_STATE block
+    fn uniforms_header(&self) -> &'static str {
+        match self.config.profile {
+            ProfileConfig::Grimoire => include_str!("./shadertoy_uniforms.glsl"),
+            ProfileConfig::Shadertoy => include_str!("./shadertoy_profile.glsl"),
+            ProfileConfig::VertexShaderArt => include_str!("./vertexshaderart_profile.glsl"),
+        }
+    }
+
+    /// The samplers the profile binds to a pass, by uniform and resource name, unless the
+    /// pass binds a resource to the same uniform itself
+    fn profile_samplers(&self, pass_config: &PassConfig) -> Vec<(&'static str, &'static str)> {
+        match self.config.profile {
+            ProfileConfig::VertexShaderArt => vertexshaderart::TEXTURES
+                .iter()
+                .filter(|(uniform_name, _)| {
+                    !pass_config.uniform_to_channel.contains_key(*uniform_name)
+                })
+                .cloned()
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Declarations of the uniform resources of the config
+    fn uniform_strings(&self) -> Vec<String> {
+        let mut uniform_strings = Vec::new();
+        for (name, input) in &self.config.resources {
+            let type_str = match input {
+                ResourceConfig::UniformFloat(_) => "float",
+                ResourceConfig::UniformVec2(_) => "vec2",
+                ResourceConfig::UniformVec3(_) => "vec3",
+                ResourceConfig::UniformVec4(_) => "vec4",
+                _ => continue,
+            };
+            uniform_strings.push(format!("uniform {} {};", type_str, name));
+        }
+        uniform_strings
+    }
+
+    /// The config's common shader, if it has one
+    fn common_source(&self) -> ShaderSource {
+        let mut source = ShaderSource::default();
+        if let Some(common_path) = &self.config.common {
+            source.append(
+                self.shader_cache
+                    .get(common_path)
+                    .expect("common path not found in shader_cache"),
+            );
+        }
+        source
+    }
+
+    fn gpu_init_pipeline_passes(&self, gl: &GLRc, pipeline: &mut GLPipeline) -> Result<()> {
+        let uniform_strings = self.uniform_strings();
+        let uniforms_header = self.uniforms_header();
         for (pass_index, pass_config) in self.config.passes.iter().enumerate() {
             // Build out the uniform sampler declarations for this pass
             let uniform_sampler_strings = {
                 let mut uniform_sampler_strings = Vec::new();
+                let mut storage_binding = 0;
                 for (uniform_name, channel_config) in &pass_config.uniform_to_channel {
                     let resource_name = match channel_config {
                         ChannelConfig::Simple(name) => name,
@@ -766,6 +1492,37 @@ impl<'a> Effect<'a> {
                         .resources
                         .get(resource_name)
                         .expect("expected config.validate() to catch this error");
+                    // Compute passes read and write buffers as images
+                    if let ResourceConfig::Buffer(buffer) = resource_config {
+                        if pass_config.is_compute() {
+                            let (format_str, _) =
+                                gl_image_format_from_buffer(buffer, channel_config.attachment());
+                            uniform_sampler_strings.push(format!(
+                                "layout({}) uniform image2D {};",
+                                format_str, uniform_name
+                            ));
+                            uniform_sampler_strings
+                                .push(format!("uniform vec3 {}_Resolution;", uniform_name));
+                            continue;
+                        }
+                    }
+                    // Storage buffers are declared as buffer blocks
+                    if let ResourceConfig::Storage(storage) = resource_config {
+                        if !gl::supports_storage() {
+                            return Err(Error::render("Storage buffers need --gl 430 or later"))
+                                .with_context(|_| {
+                                    ErrorKind::BadResourceConfig(resource_name.clone())
+                                })
+                                .map_err(Error::from);
+                        }
+                        uniform_sampler_strings.append(&mut storage_strings(
+                            uniform_name,
+                            storage,
+                            storage_binding,
+                        ));
+                        storage_binding += 1;
+                        continue;
+                    }
                     let sampler_str = match resource_config {
                         ResourceConfig::Image(_) => "sampler2D",
                         ResourceConfig::Video(_) => "sampler2D",
@@ -773,110 +1530,81 @@ impl<'a> Effect<'a> {
                         ResourceConfig::Keyboard(_) => "sampler2D",
                         ResourceConfig::Microphone(_) => "sampler2D",
                         ResourceConfig::Audio(_) => "sampler2D",
+                        ResourceConfig::Sound(_) => "sampler2D",
                         ResourceConfig::Texture2D(_) => "sampler2D",
                         ResourceConfig::Texture3D(_) => "sampler3D",
                         ResourceConfig::Cubemap(_) => "samplerCube",
                         ResourceConfig::GstAppSinkPipeline(_) => "sampler2D",
                         ResourceConfig::Buffer(_) => "sampler2D",
+                        ResourceConfig::Model(_) => "sampler2D",
                         _ => continue,
                     };
                     uniform_sampler_strings
                         .push(format!("uniform {} {};", sampler_str, uniform_name));
                     uniform_sampler_strings
                         .push(format!("uniform vec3 {}_Resolution;", uniform_name));
-                    uniform_sampler_strings.push(format!("uniform vec3 {}_Time;", uniform_name));
+                    uniform_sampler_strings.push(format!("uniform float {}_Time;", uniform_name));
+                }
+                for (uniform_name, _) in self.profile_samplers(pass_config) {
+                    uniform_sampler_strings.push(format!("uniform sampler2D {};", uniform_name));
                 }
                 uniform_sampler_strings
             };
-            let vertex_path = &pass_config.vertex;
-            let vertex_source = self
-                .shader_cache
-                .get(vertex_path)
-                .expect("vertex path not found in shader_cache");
-            let vertex_shader_list = {
+            let header = {
                 let mut list = Vec::new();
                 list.push(self.version.clone());
-                list.push(include_str!("./shadertoy_uniforms.glsl").to_string());
+                list.push(GRIM_STATE_HEADER.to_string());
+                list.push(uniforms_header.to_string());
                 list.append(&mut uniform_strings.clone());
                 list.append(&mut uniform_sampler_strings.clone());
-                list.push("#line 1 0".to_string());
-                list.push(vertex_source.clone());
-                list.join("\n")
+                list
             };
-            let vertex_shader =
-                gl::create_shader(gl, gl::VERTEX_SHADER, &[vertex_shader_list.as_bytes()])
-                    .map_err(|err| Error::glsl_vertex(err, vertex_path.clone()))
-                    .with_context(|_| ErrorKind::GLPass(pass_index))?;
-            assert!(vertex_shader != 0);
-
-            let fragment_path = &pass_config.fragment;
-            let fragment_source = self
-                .shader_cache
-                .get(fragment_path)
-                .expect("fragment path not found in shader_cache");
-            let fragment_shader_list = {
-                let mut list = Vec::new();
-                list.push(self.version.clone());
-                list.push(include_str!("./shadertoy_uniforms.glsl").to_string());
-                list.append(&mut uniform_strings.clone());
-                list.append(&mut uniform_sampler_strings.clone());
-                list.push("#line 1 0".to_string());
-                list.push(fragment_source.clone());
-                list.join("\n")
-            };
-            let fragment_shader =
-                gl::create_shader(gl, gl::FRAGMENT_SHADER, &[fragment_shader_list.as_bytes()])
-                    .map_err(|err| {
-                        gl.delete_shader(vertex_shader);
-                        Error::glsl_fragment(err, fragment_path.clone())
-                    })
-                    .with_context(|_| ErrorKind::GLPass(pass_index))?;
-            assert!(fragment_shader != 0);
-
-            let geometry_shader = {
-                if let Some(geometry_path) = &pass_config.geometry {
-                    let geometry_source = self
-                        .shader_cache
-                        .get(geometry_path)
-                        .expect("fragment path not found in shader_cache");
-                    let geometry_shader_list = {
-                        let mut list = Vec::new();
-                        list.push(self.version.clone());
-                        list.push(include_str!("./shadertoy_uniforms.glsl").to_string());
-                        list.append(&mut uniform_strings.clone());
-                        list.append(&mut uniform_sampler_strings.clone());
-                        list.push("#line 1 0".to_string());
-                        list.push(geometry_source.clone());
-                        list.join("\n")
-                    };
-                    let geometry_shader = gl::create_shader(
-                        gl,
-                        gl::GEOMETRY_SHADER,
-                        &[geometry_shader_list.as_bytes()],
-                    )
-                    .map_err(|err| {
-                        gl.delete_shader(vertex_shader);
-                        gl.delete_shader(fragment_shader);
-                        Error::glsl_fragment(err, geometry_path.clone())
-                    })
-                    .with_context(|_| ErrorKind::GLPass(pass_index))?;
-                    Some(geometry_shader)
+            let (vertex_shader, fragment_shader, compute_shader, program) =
+                if pass_config.is_compute() {
+                    let (compute_shader, program) =
+                        self.gpu_init_compute_program(gl, pass_index, pass_config, &header)?;
+                    (0, 0, compute_shader, program)
                 } else {
-                    None
+                    let (vertex_shader, fragment_shader, program) =
+                        self.gpu_init_draw_program(gl, pass_index, pass_config, &header)?;
+                    (vertex_shader, fragment_shader, 0, program)
+                };
+            // the local size the compute shader declares
+            let mut local_size: [GLint; 3] = [0; 3];
+            if compute_shader != 0 {
+                unsafe {
+                    gl.get_program_iv(program, gl::COMPUTE_WORK_GROUP_SIZE, &mut local_size);
                 }
-            };
-            let program = gl::create_program(gl, vertex_shader, fragment_shader, geometry_shader)
-                .map_err(|err| {
-                    gl.delete_shader(vertex_shader);
-                    gl.delete_shader(fragment_shader);
-                    Error::glsl_program(err, vertex_path.clone(), fragment_path.clone())
-                })
-                .with_context(|_| ErrorKind::GLPass(pass_index))?;
-            assert!(program != 0);
+            }
 
-            // build the samplers used to draw this pass
+            // build the samplers used to draw this pass, and the images of compute passes
             let mut samplers = Vec::new();
+            let mut images = Vec::new();
+            let mut storage_buffers = Vec::new();
             for (uniform_name, channel_config) in &pass_config.uniform_to_channel {
+                let resource_name = channel_config.resource_name();
+                if let Some(ResourceConfig::Storage(_)) = self.config.resources.get(resource_name) {
+                    storage_buffers.push(hash_name_attachment(resource_name, 0));
+                    continue;
+                }
+                if let Some(ResourceConfig::Buffer(buffer)) =
+                    self.config.resources.get(resource_name)
+                {
+                    if pass_config.is_compute() {
+                        let attachment = channel_config.attachment();
+                        let (_, format) = gl_image_format_from_buffer(buffer, attachment);
+                        images.push(GLImage {
+                            resource: hash_name_attachment(resource_name, attachment),
+                            uniform_loc: gl.get_uniform_location(program, uniform_name),
+                            resolution_uniform_loc: gl.get_uniform_location(
+                                program,
+                                &format!("{}_Resolution", &uniform_name),
+                            ),
+                            format,
+                        });
+                        continue;
+                    }
+                }
                 let uniform_loc = gl.get_uniform_location(program, &uniform_name);
                 let resolution_uniform_name = format!("{}_Resolution", &uniform_name);
                 let resolution_uniform_loc =
@@ -884,6 +1612,17 @@ impl<'a> Effect<'a> {
                 let playback_time_uniform_name = format!("{}_Time", &uniform_name);
                 let playback_time_uniform_loc =
                     gl.get_uniform_location(program, &playback_time_uniform_name);
+                let (channel_resolution_uniform_loc, channel_time_uniform_loc) =
+                    match shadertoy_channel(uniform_name) {
+                        Some(channel) => (
+                            gl.get_uniform_location(
+                                program,
+                                &format!("iChannelResolution[{}]", channel),
+                            ),
+                            gl.get_uniform_location(program, &format!("iChannelTime[{}]", channel)),
+                        ),
+                        None => (-1, -1),
+                    };
                 let (resource, wrap, min_filter, mag_filter) = match channel_config {
                     ChannelConfig::Simple(ref name) => {
                         let hash = hash_name_attachment(name, 0);
@@ -919,6 +1658,8 @@ impl<'a> Effect<'a> {
                     resource,
                     resolution_uniform_loc,
                     playback_time_uniform_loc,
+                    channel_resolution_uniform_loc,
+                    channel_time_uniform_loc,
                     uniform_loc,
                     mag_filter,
                     min_filter,
@@ -934,20 +1675,53 @@ impl<'a> Effect<'a> {
                 }
                 samplers.push(sampler);
             }
+            for (uniform_name, resource_name) in self.profile_samplers(pass_config) {
+                samplers.push(GLSampler {
+                    resource: hash_name_attachment(resource_name, 0),
+                    uniform_loc: gl.get_uniform_location(program, uniform_name),
+                    resolution_uniform_loc: -1,
+                    playback_time_uniform_loc: -1,
+                    channel_resolution_uniform_loc: -1,
+                    channel_time_uniform_loc: -1,
+                    mag_filter: gl::LINEAR,
+                    min_filter: gl::LINEAR,
+                    wrap_r: gl::CLAMP_TO_EDGE,
+                    wrap_s: gl::CLAMP_TO_EDGE,
+                    wrap_t: gl::CLAMP_TO_EDGE,
+                });
+            }
             // get per-pass uniforms for this program
             let resolution_uniform_loc = gl.get_uniform_location(program, "iResolution");
             let vertex_count_uniform_loc = gl.get_uniform_location(program, "iVertexCount");
+            let pass_index_uniform_loc = gl.get_uniform_location(program, "iPassIndex");
+            let model_matrix_uniform_loc = gl.get_uniform_location(program, "iModelMatrix");
+            let (
+                art_resolution_uniform_loc,
+                art_vertex_count_uniform_loc,
+                art_background_uniform_loc,
+            ) = match self.config.profile {
+                ProfileConfig::VertexShaderArt => (
+                    gl.get_uniform_location(program, "resolution"),
+                    gl.get_uniform_location(program, "vertexCount"),
+                    gl.get_uniform_location(program, "background"),
+                ),
+                _ => (-1, -1, -1),
+            };
 
-            // specify draw state
-            let model_name = match pass_config.draw {
+            // specify draw state, passes without a vertex shader draw a fullscreen triangle
+            let draw = if pass_config.is_main_image() {
+                DrawConfig::default()
+            } else {
+                pass_config.draw.clone()
+            };
+            let model_name = match draw {
                 DrawConfig::Model(ref m) => Some(&m.model),
                 DrawConfig::Raw(_) => None,
+                DrawConfig::Feedback(_) => None,
             };
-            let vbo = model_name
-                .map(|n| hash_name_attachment(&n, 0))
-                .and_then(|h| self.vertex_buffers.get(&h).map(|vbo| *vbo));
+            let model = model_name.map(|n| hash_name_attachment(&n, 0));
 
-            let (draw_mode, draw_count, instance_count) = match &pass_config.draw {
+            let (draw_mode, draw_count, instance_count) = match &draw {
                 DrawConfig::Raw(config) => {
                     let draw_count = config.count as i32;
                     let (draw_mode, draw_count) = match config.mode {
@@ -961,11 +1735,36 @@ impl<'a> Effect<'a> {
                     };
                     (draw_mode, draw_count, 0)
                 }
-                DrawConfig::Model(config) => match vbo {
-                    Some(vbo) => (vbo.mode, vbo.count, config.count as i32),
-                    None => (gl::TRIANGLES, 0, 0),
-                },
+                // models draw the ranges of their vertex buffer
+                DrawConfig::Model(config) => (gl::TRIANGLES, 0, config.count as i32),
+                DrawConfig::Feedback(config) => {
+                    let hash = hash_name_attachment(&config.feedback, 0);
+                    match self.feedback_buffers.get(&hash) {
+                        Some(feedback) => (
+                            gl_draw_mode_from_config(&config.mode),
+                            feedback.count,
+                            config.count as i32,
+                        ),
+                        None => (gl::POINTS, 0, 0),
+                    }
+                }
             };
+            let feedback = match draw {
+                DrawConfig::Feedback(ref config) => Some(hash_name_attachment(&config.feedback, 0)),
+                _ => None,
+            };
+            // capture passes draw each vertex of their feedback resource once, as a point
+            let capture = match pass_config.capture {
+                Some(ref capture) if pass_config.is_capture() => {
+                    Some(hash_name_attachment(&capture.buffer, 0))
+                }
+                _ => None,
+            };
+            let (draw_mode, draw_count) =
+                match capture.and_then(|hash| self.feedback_buffers.get(&hash)) {
+                    Some(feedback) => (gl::POINTS, feedback.count),
+                    None => (draw_mode, draw_count),
+                };
             let blend = match pass_config.blend {
                 None => None,
                 Some(ref blend) => match blend {
@@ -1002,8 +1801,8 @@ impl<'a> Effect<'a> {
                     DepthTestConfig::Complete { write, .. } => write,
                 })
                 .unwrap_or(true);
-            self.pipeline.passes.push(GLPass {
-                vbo,
+            pipeline.passes.push(GLPass {
+                model,
                 // shader resources
                 vertex_shader,
                 fragment_shader,
@@ -1011,7 +1810,22 @@ impl<'a> Effect<'a> {
                 // uniforms
                 resolution_uniform_loc,
                 vertex_count_uniform_loc,
+                pass_index_uniform_loc,
+                model_matrix_uniform_loc,
+                art_resolution_uniform_loc,
+                art_vertex_count_uniform_loc,
+                art_background_uniform_loc,
                 samplers,
+                compute_shader,
+                local_size: [
+                    local_size[0] as GLuint,
+                    local_size[1] as GLuint,
+                    local_size[2] as GLuint,
+                ],
+                images,
+                storage_buffers,
+                capture,
+                feedback,
                 // render state
                 draw_mode,
                 draw_count,
@@ -1023,19 +1837,336 @@ impl<'a> Effect<'a> {
                 clear_depth,
             })
         }
-        // Now that we built all the pass programs, remember to connect the existing
-        // uniform buffers to the programs
-        for (index, (name, buffer)) in self.pipeline.uniform_buffers.iter().enumerate() {
-            for pass in &self.pipeline.passes {
-                gl::connect_uniform_buffer(gl, *buffer, pass.program, name, index as u32);
+        Ok(())
+    }
+
+    /// Compile and link the vertex, fragment and optional geometry shader of a pass.
+    /// `header` is the GLSL prepended to every shader.
+    fn gpu_init_draw_program(
+        &self,
+        gl: &GLRc,
+        pass_index: usize,
+        pass_config: &PassConfig,
+        header: &[String],
+    ) -> Result<(GLuint, GLuint, GLuint)> {
+        let vertex_path = &pass_config.vertex;
+        let vertex_source = self
+            .shader_cache
+            .get(vertex_path)
+            .expect("vertex path not found in shader_cache");
+        let (vertex_shader_source, vertex_source_map) = {
+            let mut list = header.to_vec();
+            if self.config.profile == ProfileConfig::VertexShaderArt {
+                list.push(vertexshaderart::VERTEX_HEADER.to_string());
             }
+            source_map::with_header(&list, vertex_source)
+        };
+        let vertex_shader =
+            gl::create_shader(gl, gl::VERTEX_SHADER, &[vertex_shader_source.as_bytes()])
+                .map_err(|err| {
+                    Error::glsl_vertex(vertex_source_map.remap_log(&err), vertex_path.clone())
+                })
+                .with_context(|_| ErrorKind::GLPass(pass_index))?;
+        assert!(vertex_shader != 0);
+
+        let fragment_path = pass_config.fragment_shader().to_string();
+        let fragment_source = {
+            let mut source = self.common_source();
+            source.append(
+                self.shader_cache
+                    .get(&fragment_path)
+                    .expect("fragment path not found in shader_cache"),
+            );
+            if pass_config.is_main_image() {
+                source.push_generated(builtin::MAIN_IMAGE_WRAPPER);
+            }
+            source
+        };
+        let (fragment_shader_source, fragment_source_map) =
+            source_map::with_header(header, &fragment_source);
+        let fragment_shader = gl::create_shader(
+            gl,
+            gl::FRAGMENT_SHADER,
+            &[fragment_shader_source.as_bytes()],
+        )
+        .map_err(|err| {
+            gl.delete_shader(vertex_shader);
+            Error::glsl_fragment(fragment_source_map.remap_log(&err), fragment_path.clone())
+        })
+        .with_context(|_| ErrorKind::GLPass(pass_index))?;
+        assert!(fragment_shader != 0);
+
+        let geometry_shader = {
+            if let Some(geometry_path) = &pass_config.geometry {
+                let geometry_source = self
+                    .shader_cache
+                    .get(geometry_path)
+                    .expect("fragment path not found in shader_cache");
+                let (geometry_shader_source, geometry_source_map) =
+                    source_map::with_header(header, geometry_source);
+                let geometry_shader = gl::create_shader(
+                    gl,
+                    gl::GEOMETRY_SHADER,
+                    &[geometry_shader_source.as_bytes()],
+                )
+                .map_err(|err| {
+                    gl.delete_shader(vertex_shader);
+                    gl.delete_shader(fragment_shader);
+                    Error::glsl_geometry(geometry_source_map.remap_log(&err), geometry_path.clone())
+                })
+                .with_context(|_| ErrorKind::GLPass(pass_index))?;
+                Some(geometry_shader)
+            } else {
+                None
+            }
+        };
+        // Capture passes name the vertex shader outputs they capture before linking
+        let varyings = match pass_config.capture {
+            Some(ref capture) if pass_config.is_capture() => capture.outputs.clone(),
+            _ => Vec::new(),
+        };
+        let program = gl::create_feedback_program(
+            gl,
+            vertex_shader,
+            fragment_shader,
+            geometry_shader,
+            &varyings,
+        )
+        .map_err(|err| {
+            gl.delete_shader(vertex_shader);
+            gl.delete_shader(fragment_shader);
+            Error::glsl_program(err, vertex_path.clone(), fragment_path.clone())
+        })
+        .with_context(|_| ErrorKind::GLPass(pass_index))?;
+        assert!(program != 0);
+        Ok((vertex_shader, fragment_shader, program))
+    }
+
+    /// Compile and link the compute shader of a compute pass. `header` is the GLSL
+    /// prepended to the shader.
+    fn gpu_init_compute_program(
+        &self,
+        gl: &GLRc,
+        pass_index: usize,
+        pass_config: &PassConfig,
+        header: &[String],
+    ) -> Result<(GLuint, GLuint)> {
+        let compute_path = pass_config
+            .compute
+            .as_ref()
+            .expect("expected a compute pass");
+        if !gl::supports_compute() {
+            return Err(Error::glsl_compute(
+                "Compute shaders need --gl 430 or later",
+                compute_path.as_str(),
+            ))
+            .with_context(|_| ErrorKind::GLPass(pass_index))
+            .map_err(Error::from);
+        }
+        let compute_source = {
+            let mut source = self.common_source();
+            source.append(
+                self.shader_cache
+                    .get(compute_path)
+                    .expect("compute path not found in shader_cache"),
+            );
+            source
+        };
+        let (compute_shader_source, compute_source_map) =
+            source_map::with_header(header, &compute_source);
+        let compute_shader =
+            gl::create_shader(gl, gl::COMPUTE_SHADER, &[compute_shader_source.as_bytes()])
+                .map_err(|err| {
+                    Error::glsl_compute(compute_source_map.remap_log(&err), compute_path.clone())
+                })
+                .with_context(|_| ErrorKind::GLPass(pass_index))?;
+        assert!(compute_shader != 0);
+        let program = gl::create_compute_program(gl, compute_shader)
+            .map_err(|err| {
+                gl.delete_shader(compute_shader);
+                Error::glsl_compute(err, compute_path.clone())
+            })
+            .with_context(|_| ErrorKind::GLPass(pass_index))?;
+        assert!(program != 0);
+        Ok((compute_shader, program))
+    }
+
+    fn gpu_init_pipeline_sounds(&self, gl: &GLRc, pipeline: &mut GLPipeline) -> Result<()> {
+        let header = {
+            let mut list = Vec::new();
+            list.push(self.version.clone());
+            list.push(GRIM_STATE_HEADER.to_string());
+            list.push(self.uniforms_header().to_string());
+            list.append(&mut self.uniform_strings());
+            list.push(format!(
+                "#define GRIM_SOUND_BLOCK_WIDTH {}",
+                SOUND_BLOCK_WIDTH
+            ));
+            list
+        };
+        for (name, resource) in &self.config.resources {
+            let sound_path = match resource {
+                ResourceConfig::Sound(config) => &config.sound,
+                _ => continue,
+            };
+            let mut vertex_source = ShaderSource::default();
+            vertex_source.push_generated(
+                builtin::shader(builtin::FULLSCREEN_VERTEX).expect("fullscreen vertex shader"),
+            );
+            let (vertex_shader_source, _) = source_map::with_header(&header, &vertex_source);
+            let vertex_shader =
+                gl::create_shader(gl, gl::VERTEX_SHADER, &[vertex_shader_source.as_bytes()])
+                    .map_err(|err| Error::glsl_vertex(err, builtin::FULLSCREEN_VERTEX.to_string()))
+                    .with_context(|_| ErrorKind::BadResourceConfig(name.clone()))?;
+            let fragment_source = {
+                let mut source = self.common_source();
+                source.append(
+                    self.shader_cache
+                        .get(sound_path)
+                        .expect("sound path not found in shader_cache"),
+                );
+                source.push_generated(builtin::MAIN_SOUND_WRAPPER);
+                source
+            };
+            let (fragment_shader_source, fragment_source_map) =
+                source_map::with_header(&header, &fragment_source);
+            let fragment_shader = gl::create_shader(
+                gl,
+                gl::FRAGMENT_SHADER,
+                &[fragment_shader_source.as_bytes()],
+            )
+            .map_err(|err| {
+                gl.delete_shader(vertex_shader);
+                Error::glsl_fragment(fragment_source_map.remap_log(&err), sound_path.clone())
+            })
+            .with_context(|_| ErrorKind::BadResourceConfig(name.clone()))?;
+            let program = gl::create_program(gl, vertex_shader, fragment_shader, None)
+                .map_err(|err| {
+                    gl.delete_shader(vertex_shader);
+                    gl.delete_shader(fragment_shader);
+                    Error::glsl_program(
+                        err,
+                        builtin::FULLSCREEN_VERTEX.to_string(),
+                        sound_path.clone(),
+                    )
+                })
+                .with_context(|_| ErrorKind::BadResourceConfig(name.clone()))?;
+            let texture = gl::create_texture2d(
+                gl,
+                gl::RG32F as GLint,
+                SOUND_BLOCK_WIDTH as GLsizei,
+                SOUND_BLOCK_HEIGHT as GLsizei,
+                gl::RG,
+                gl::FLOAT,
+                None,
+            );
+            gl.tex_parameter_i(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::NEAREST as GLint);
+            gl.tex_parameter_i(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::NEAREST as GLint);
+            let framebuffer = gl::create_framebuffer(gl);
+            gl::attach_texture_to_framebuffer(gl, framebuffer, texture, gl::COLOR_ATTACHMENT0);
+            let block_start_uniform_loc = gl.get_uniform_location(program, "grim_SoundBlockStart");
+            pipeline.sounds.insert(
+                name.clone(),
+                GLSound {
+                    vertex_shader,
+                    fragment_shader,
+                    program,
+                    framebuffer,
+                    texture,
+                    block_start_uniform_loc,
+                },
+            );
+        }
+        Ok(())
+    }
+
+    /// Build the seed shaders of feedback resources, vertex shaders whose outputs named
+    /// after the resource's attributes are captured as its initial vertices
+    fn gpu_init_pipeline_seeds(&self, gl: &GLRc, pipeline: &mut GLPipeline) -> Result<()> {
+        let header = {
+            let mut list = Vec::new();
+            list.push(self.version.clone());
+            list.push(GRIM_STATE_HEADER.to_string());
+            list.push(self.uniforms_header().to_string());
+            list.append(&mut self.uniform_strings());
+            list
+        };
+        for (name, resource) in &self.config.resources {
+            let (feedback, seed_path) = match resource {
+                ResourceConfig::Feedback(config) => match config.seed {
+                    Some(ref seed) => (config, seed),
+                    None => continue,
+                },
+                _ => continue,
+            };
+            let vertex_source = self
+                .shader_cache
+                .get(seed_path)
+                .expect("seed path not found in shader_cache");
+            let (vertex_shader_source, vertex_source_map) =
+                source_map::with_header(&header, vertex_source);
+            let vertex_shader =
+                gl::create_shader(gl, gl::VERTEX_SHADER, &[vertex_shader_source.as_bytes()])
+                    .map_err(|err| {
+                        Error::glsl_vertex(vertex_source_map.remap_log(&err), seed_path.clone())
+                    })
+                    .with_context(|_| ErrorKind::BadResourceConfig(name.clone()))?;
+            let mut fragment_source = ShaderSource::default();
+            fragment_source.push_generated(
+                builtin::shader(builtin::DISCARD_FRAGMENT).expect("discard fragment shader"),
+            );
+            let (fragment_shader_source, _) = source_map::with_header(&header, &fragment_source);
+            let fragment_shader = gl::create_shader(
+                gl,
+                gl::FRAGMENT_SHADER,
+                &[fragment_shader_source.as_bytes()],
+            )
+            .map_err(|err| {
+                gl.delete_shader(vertex_shader);
+                Error::glsl_fragment(err, builtin::DISCARD_FRAGMENT.to_string())
+            })
+            .with_context(|_| ErrorKind::BadResourceConfig(name.clone()))?;
+            let varyings = feedback
+                .attribute_list()
+                .iter()
+                .map(|(_, name)| name.to_string())
+                .collect::<Vec<_>>();
+            let program =
+                gl::create_feedback_program(gl, vertex_shader, fragment_shader, None, &varyings)
+                    .map_err(|err| {
+                        gl.delete_shader(vertex_shader);
+                        gl.delete_shader(fragment_shader);
+                        Error::glsl_program(
+                            err,
+                            seed_path.clone(),
+                            builtin::DISCARD_FRAGMENT.to_string(),
+                        )
+                    })
+                    .with_context(|_| ErrorKind::BadResourceConfig(name.clone()))?;
+            let mut hasher = DefaultHasher::new();
+            vertex_shader_source.hash(&mut hasher);
+            pipeline.seeds.insert(
+                name.clone(),
+                GLSeed {
+                    vertex_shader,
+                    fragment_shader,
+                    program,
+                    source_hash: hasher.finish(),
+                },
+            );
         }
         Ok(())
     }
 
     fn gpu_stage_buffer_data(&mut self, gl: &GLRc) {
         for (uniform_name, data) in &self.staged_uniform_buffer {
-            let programs = self.pipeline.passes.iter().map(|pass| pass.program);
+            let programs = self
+                .pipeline
+                .passes
+                .iter()
+                .map(|pass| pass.program)
+                .chain(self.pipeline.sounds.values().map(|sound| sound.program))
+                .chain(self.pipeline.seeds.values().map(|seed| seed.program));
             let index = self.pipeline.uniform_buffers.len() as u32;
             // If this is the first time we've seen this uniform_name,
             // we'll need to create a new uniform buffer, connect
@@ -1068,6 +2199,132 @@ impl<'a> Effect<'a> {
         }
     }
 
+    /// Create the storage buffers of the config, zeroed until their contents are staged
+    fn gpu_init_storage_buffers(&mut self, gl: &GLRc) {
+        for (name, resource) in &self.config.resources {
+            let storage = match resource {
+                ResourceConfig::Storage(storage) => storage,
+                _ => continue,
+            };
+            let contents = vec![0; storage.byte_size().unwrap_or(0)];
+            let buffer = gl::create_buffer(gl);
+            gl.bind_buffer(gl::SHADER_STORAGE_BUFFER, buffer);
+            gl.buffer_data_untyped(
+                gl::SHADER_STORAGE_BUFFER,
+                contents.len() as isize,
+                contents.as_ptr() as *const GLvoid,
+                gl::DYNAMIC_COPY,
+            );
+            self.storage_buffers.insert(
+                hash_name_attachment(name, 0),
+                GLStorageBuffer {
+                    buffer,
+                    contents,
+                    persist: storage.persist,
+                },
+            );
+        }
+        gl.bind_buffer(gl::SHADER_STORAGE_BUFFER, 0);
+    }
+
+    /// Reset the storage buffers that don't persist to their initial contents
+    fn gpu_reset_storage_buffers(&self, gl: &GLRc) {
+        for storage in self.storage_buffers.values() {
+            if storage.persist {
+                continue;
+            }
+            gl.bind_buffer(gl::SHADER_STORAGE_BUFFER, storage.buffer);
+            gl.buffer_sub_data_untyped(
+                gl::SHADER_STORAGE_BUFFER,
+                0,
+                storage.contents.len() as isize,
+                storage.contents.as_ptr() as *const GLvoid,
+            );
+        }
+        gl.bind_buffer(gl::SHADER_STORAGE_BUFFER, 0);
+    }
+
+    /// Create the vertex buffer pairs of the feedback resources, zeroed until their
+    /// contents are staged or their seed shader runs
+    fn gpu_init_feedback_buffers(&mut self, gl: &GLRc) {
+        for (name, resource) in &self.config.resources {
+            let feedback = match resource {
+                ResourceConfig::Feedback(feedback) => feedback,
+                _ => continue,
+            };
+            let stride = feedback.stride().unwrap_or(0);
+            let mut attributes = Vec::new();
+            let mut offset = 0;
+            for (type_str, name) in feedback.attribute_list() {
+                if let Some((components, scalar)) = attribute_format(type_str) {
+                    attributes.push(GLAttribute {
+                        name: name.to_string(),
+                        components: components as GLint,
+                        scalar,
+                        offset,
+                    });
+                    offset += components as GLuint * 4;
+                }
+            }
+            let zeros: Vec<u8> = vec![0; stride * feedback.count as usize];
+            let mut vbos = [0; 2];
+            for vbo in &mut vbos {
+                *vbo = gl::create_buffer(gl);
+                gl.bind_buffer(gl::ARRAY_BUFFER, *vbo);
+                gl.buffer_data_untyped(
+                    gl::ARRAY_BUFFER,
+                    zeros.len() as isize,
+                    zeros.as_ptr() as *const GLvoid,
+                    gl::DYNAMIC_COPY,
+                );
+            }
+            self.feedback_buffers.insert(
+                hash_name_attachment(name, 0),
+                GLFeedbackBuffer {
+                    vbos,
+                    read: RefCell::new(0),
+                    count: feedback.count as GLsizei,
+                    stride: stride as GLsizei,
+                    attributes,
+                    seed_hash: None,
+                },
+            );
+        }
+        gl.bind_buffer(gl::ARRAY_BUFFER, 0);
+    }
+
+    /// Run the seed shaders of feedback buffers that are new or whose seed changed,
+    /// capturing the initial vertices into the buffer capture passes read next
+    fn gpu_seed_feedback_buffers(&mut self, gl: &GLRc) {
+        for (name, seed) in &self.pipeline.seeds {
+            let feedback = match self
+                .feedback_buffers
+                .get_mut(&hash_name_attachment(name, 0))
+            {
+                Some(feedback) => feedback,
+                None => continue,
+            };
+            if feedback.seed_hash == Some(seed.source_hash) {
+                continue;
+            }
+            feedback.seed_hash = Some(seed.source_hash);
+            gl.bind_vertex_array(self.pipeline.vertex_array_object);
+            gl.use_program(seed.program);
+            gl.enable(gl::RASTERIZER_DISCARD);
+            gl.bind_buffer_base(
+                gl::TRANSFORM_FEEDBACK_BUFFER,
+                0,
+                feedback.vbos[*feedback.read.borrow()],
+            );
+            gl::begin_transform_feedback(gl::POINTS);
+            gl.draw_arrays(gl::POINTS, 0, feedback.count);
+            gl::end_transform_feedback();
+            gl.bind_buffer_base(gl::TRANSFORM_FEEDBACK_BUFFER, 0, 0);
+            gl.disable(gl::RASTERIZER_DISCARD);
+            gl.use_program(0);
+        }
+    }
+
     fn gpu_init_framebuffers(&mut self, gl: &GLRc) {
         // build a map of buffer names to if it's a feedback buffer
         let mut framebuffer_kind_map = BTreeMap::new();
@@ -1103,11 +2360,7 @@ impl<'a> Effect<'a> {
                     let resolution = [width as f32, height as f32, width as f32 / height as f32];
                     let attachment_count = buffer.attachment_count();
                     for attachment_index in 0..attachment_count {
-                        let attachment_format = match buffer.buffer {
-                            BufferFormatConfig::Dumb(_) => BufferFormat::F32,
-                            BufferFormatConfig::Simple(f) => f,
-                            BufferFormatConfig::Complete(ref v) => v[attachment_index],
-                        };
+                        let attachment_format = buffer.attachment_format(attachment_index);
                         // calculate parameters for gl texture creation based on config
                         let (internal, format, data_type, bytes_per) =
                             match (&buffer.components, &attachment_format) {
@@ -1287,31 +2540,60 @@ impl<'a> Effect<'a> {
             for staged_resource in staged_resource_list.iter() {
                 match staged_resource {
                     ResourceData::Geometry(data) => {
-                        let byte_len =
-                            (data.buffer.len() as isize) * (std::mem::size_of::<f32>() as isize);
-                        let vbo = self.vertex_buffers.entry(*hash).or_insert_with(|| {
-                            let vbo = gl.gen_buffers(1)[0];
-                            let mode = gl::TRIANGLES;
-                            // The buffer is interleaved with position (vec3) + normal (vec3) data (/2)
-                            let count = ((data.buffer.len() / 2) / 3) as GLsizei;
-                            gl.bind_buffer(gl::ARRAY_BUFFER, vbo);
-                            gl.buffer_data_untyped(
-                                gl::ARRAY_BUFFER,
-                                byte_len,
-                                std::ptr::null() as *const GLvoid,
-                                gl::DYNAMIC_DRAW,
-                            );
-                            gl.bind_buffer(gl::ARRAY_BUFFER, 0);
-                            GLVertexBuffer { vbo, mode, count }
-                        });
-                        gl.bind_buffer(gl::ARRAY_BUFFER, vbo.vbo);
-                        gl.buffer_sub_data_untyped(
+                        let vertex_buffer =
+                            self.vertex_buffers
+                                .entry(*hash)
+                                .or_insert_with(|| GLVertexBuffer {
+                                    vbo: gl::create_buffer(gl),
+                                    ..Default::default()
+                                });
+                        // Reloaded models can change size, so respecify the buffers
+                        gl.bind_buffer(gl::ARRAY_BUFFER, vertex_buffer.vbo);
+                        gl.buffer_data_untyped(
                             gl::ARRAY_BUFFER,
-                            0,
-                            byte_len,
+                            (data.buffer.len() * std::mem::size_of::<f32>()) as isize,
                             data.buffer.as_ptr() as *const GLvoid,
+                            gl::DYNAMIC_DRAW,
                         );
                         gl.bind_buffer(gl::ARRAY_BUFFER, 0);
+                        if data.indices.is_empty() {
+                            gl.delete_buffers(&[vertex_buffer.ibo]);
+                            vertex_buffer.ibo = 0;
+                        } else {
+                            if vertex_buffer.ibo == 0 {
+                                vertex_buffer.ibo = gl::create_buffer(gl);
+                            }
+                            gl.bind_buffer(gl::ELEMENT_ARRAY_BUFFER, vertex_buffer.ibo);
+                            gl.buffer_data_untyped(
+                                gl::ELEMENT_ARRAY_BUFFER,
+                                (data.indices.len() * std::mem::size_of::<u32>()) as isize,
+                                data.indices.as_ptr() as *const GLvoid,
+                                gl::DYNAMIC_DRAW,
+                            );
+                            gl.bind_buffer(gl::ELEMENT_ARRAY_BUFFER, 0);
+                        }
+                        vertex_buffer.draws = data
+                            .draws
+                            .iter()
+                            .map(|draw| GLDraw {
+                                mode: gl_draw_mode_from_config(&draw.mode),
+                                first: draw.first as GLsizei,
+                                count: draw.count as GLsizei,
+                                transform: draw.transform,
+                            })
+                            .collect();
+                        let float_size = std::mem::size_of::<f32>() as GLuint;
+                        vertex_buffer.stride = (data.stride() * float_size) as GLsizei;
+                        vertex_buffer.attributes = data
+                            .attributes
+                            .iter()
+                            .map(|attribute| GLAttribute {
+                                name: attribute.name.clone(),
+                                components: attribute.components as GLint,
+                                scalar: AttributeScalar::Float,
+                                offset: attribute.offset * float_size,
+                            })
+                            .collect();
                     }
                     ResourceData::D2(data) => {
                         let params = gl_texture_params_from_texture_format(data.format);
@@ -1472,6 +2754,59 @@ impl<'a> Effect<'a> {
                         }
                         gl.generate_mipmap(gl::TEXTURE_CUBE_MAP);
                     }
+                    ResourceData::Bytes(bytes) => {
+                        // Feedback contents replace the vertices capture passes read next
+                        if let Some(feedback) = self.feedback_buffers.get(hash) {
+                            let mut contents =
+                                vec![0; feedback.stride as usize * feedback.count as usize];
+                            let len = bytes.len().min(contents.len());
+                            if len < bytes.len() {
+                                warn!(
+                                    "[DATA] Feedback contents of {} bytes are truncated to the buffer size of {} bytes",
+                                    bytes.len(),
+                                    contents.len()
+                                );
+                            }
+                            contents[..len].copy_from_slice(&bytes[..len]);
+                            gl.bind_buffer(
+                                gl::ARRAY_BUFFER,
+                                feedback.vbos[*feedback.read.borrow()],
+                            );
+                            gl.buffer_sub_data_untyped(
+                                gl::ARRAY_BUFFER,
+                                0,
+                                contents.len() as isize,
+                                contents.as_ptr() as *const GLvoid,
+                            );
+                            gl.bind_buffer(gl::ARRAY_BUFFER, 0);
+                            continue;
+                        }
+                        let storage = match self.storage_buffers.get_mut(hash) {
+                            Some(storage) => storage,
+                            None => continue,
+                        };
+                        // The contents fill the start of the buffer, and the rest is zeroed
+                        let len = bytes.len().min(storage.contents.len());
+                        if len < bytes.len() {
+                            warn!(
+                                "[DATA] Storage contents of {} bytes are truncated to the buffer size of {} bytes",
+                                bytes.len(),
+                                storage.contents.len()
+                            );
+                        }
+                        storage.contents[..len].copy_from_slice(&bytes[..len]);
+                        for byte in &mut storage.contents[len..] {
+                            *byte = 0;
+                        }
+                        gl.bind_buffer(gl::SHADER_STORAGE_BUFFER, storage.buffer);
+                        gl.buffer_sub_data_untyped(
+                            gl::SHADER_STORAGE_BUFFER,
+                            0,
+                            storage.contents.len() as isize,
+                            storage.contents.as_ptr() as *const GLvoid,
+                        );
+                        gl.bind_buffer(gl::SHADER_STORAGE_BUFFER, 0);
+                    }
                 }
             }
         }
@@ -1479,6 +2814,104 @@ impl<'a> Effect<'a> {
     }
 }
 
+fn gpu_delete_pipeline(gl: &GLRc, pipeline: &GLPipeline) {
+    gl.delete_vertex_arrays(&[pipeline.vertex_array_object]);
+    for pass in &pipeline.passes {
+        gl.delete_program(pass.program);
+        gl.delete_shader(pass.vertex_shader);
+        gl.delete_shader(pass.fragment_shader);
+        gl.delete_shader(pass.compute_shader);
+    }
+    for sound in pipeline.sounds.values() {
+        gl.delete_program(sound.program);
+        gl.delete_shader(sound.vertex_shader);
+        gl.delete_shader(sound.fragment_shader);
+        gl.delete_framebuffers(&[sound.framebuffer]);
+        gl.delete_textures(&[sound.texture]);
+    }
+    for seed in pipeline.seeds.values() {
+        gl.delete_program(seed.program);
+        gl.delete_shader(seed.vertex_shader);
+        gl.delete_shader(seed.fragment_shader);
+    }
+}
+
+/// Draw the vertices of a feedback buffer as points through the program of a capture
+/// pass, capturing its outputs into the other buffer of the pair, which is read next
+fn gpu_capture(gl: &GLRc, program: GLuint, feedback: &GLFeedbackBuffer) {
+    let read = *feedback.read.borrow();
+    gl.enable(gl::RASTERIZER_DISCARD);
+    let locations = gl_bind_attributes(
+        gl,
+        program,
+        feedback.vbos[read],
+        feedback.stride,
+        &feedback.attributes,
+    );
+    gl.bind_buffer_base(gl::TRANSFORM_FEEDBACK_BUFFER, 0, feedback.vbos[1 - read]);
+    gl::begin_transform_feedback(gl::POINTS);
+    gl.draw_arrays(gl::POINTS, 0, feedback.count);
+    gl::end_transform_feedback();
+    gl.bind_buffer_base(gl::TRANSFORM_FEEDBACK_BUFFER, 0, 0);
+    gl_unbind_attributes(gl, &locations);
+    gl.disable(gl::RASTERIZER_DISCARD);
+    feedback.read.replace(1 - read);
+}
+
+/// Point the attributes a program declares at the interleaved vertices of `vbo`, and
+/// return their locations
+fn gl_bind_attributes(
+    gl: &GLRc,
+    program: GLuint,
+    vbo: GLuint,
+    stride: GLsizei,
+    attributes: &[GLAttribute],
+) -> Vec<GLuint> {
+    let mut locations = Vec::new();
+    gl.bind_buffer(gl::ARRAY_BUFFER, vbo);
+    for attribute in attributes {
+        let location = gl.get_attrib_location(program, &attribute.name);
+        if location < 0 {
+            continue;
+        }
+        let location = location as GLuint;
+        gl.enable_vertex_attrib_array(location);
+        match attribute.scalar {
+            AttributeScalar::Float => gl.vertex_attrib_pointer(
+                location,
+                attribute.components,
+                gl::FLOAT,
+                false,
+                stride,
+                attribute.offset,
+            ),
+            AttributeScalar::Int => gl.vertex_attrib_i_pointer(
+                location,
+                attribute.components,
+                gl::INT,
+                stride,
+                attribute.offset,
+            ),
+            AttributeScalar::Uint => gl.vertex_attrib_i_pointer(
+                location,
+                attribute.components,
+                gl::UNSIGNED_INT,
+                stride,
+                attribute.offset,
+            ),
+        }
+        locations.push(location);
+    }
+    locations
+}
+
+fn gl_unbind_attributes(gl: &GLRc, locations: &[GLuint]) {
+    for location in locations {
+        gl.disable_vertex_attrib_array(*location);
+    }
+    gl.bind_buffer(gl::ARRAY_BUFFER, 0);
+}
+
 fn gl_wrap_from_config(wrap: &WrapConfig) -> GLenum {
     match wrap {
         WrapConfig::Clamp => gl::CLAMP_TO_EDGE,
@@ -1612,6 +3045,18 @@ fn gl_blend_from_config(blend: &BlendFactorConfig) -> GLenum {
     }
 }
 
+fn gl_draw_mode_from_config(mode: &DrawModeConfig) -> GLenum {
+    match mode {
+        DrawModeConfig::Triangles => gl::TRIANGLES,
+        DrawModeConfig::Points => gl::POINTS,
+        DrawModeConfig::Lines => gl::LINES,
+        DrawModeConfig::TriangleFan => gl::TRIANGLE_FAN,
+        DrawModeConfig::TriangleStrip => gl::TRIANGLE_STRIP,
+        DrawModeConfig::LineLoop => gl::LINE_LOOP,
+        DrawModeConfig::LineStrip => gl::LINE_STRIP,
+    }
+}
+
 fn gl_depth_from_config(depth: &DepthFuncConfig) -> GLenum {
     match depth {
         DepthFuncConfig::Always => gl::ALWAYS,
@@ -1625,6 +3070,48 @@ fn gl_depth_from_config(depth: &DepthFuncConfig) -> GLenum {
     }
 }
 
+/// The GLSL layout qualifier and GL format that bind a buffer attachment to an image
+/// unit. Images have no three component formats, which config validation reports.
+fn gl_image_format_from_buffer(buffer: &BufferConfig, attachment: usize) -> (&'static str, GLenum) {
+    match (buffer.components, buffer.attachment_format(attachment)) {
+        (1, BufferFormat::U8) => ("r8", gl::R8),
+        (1, BufferFormat::F16) => ("r16f", gl::R16F),
+        (1, BufferFormat::F32) => ("r32f", gl::R32F),
+        (2, BufferFormat::U8) => ("rg8", gl::RG8),
+        (2, BufferFormat::F16) => ("rg16f", gl::RG16F),
+        (2, BufferFormat::F32) => ("rg32f", gl::RG32F),
+        (_, BufferFormat::U8) => ("rgba8", gl::RGBA8),
+        (_, BufferFormat::F16) => ("rgba16f", gl::RGBA16F),
+        (_, BufferFormat::F32) => ("rgba32f", gl::RGBA32F),
+    }
+}
+
+/// The buffer block declaring a storage buffer bound to `uniform_name`, preceded by the
+/// struct of its elements if it lists struct members
+fn storage_strings(uniform_name: &str, storage: &StorageConfig, binding: usize) -> Vec<String> {
+    let mut strings = Vec::new();
+    let element = if storage.is_struct() {
+        let members = storage
+            .members()
+            .iter()
+            .map(|(type_str, name)| format!("{} {};", type_str, name))
+            .collect::<Vec<String>>()
+            .join(" ");
+        strings.push(format!(
+            "struct {}_Element {{ {} }};",
+            uniform_name, members
+        ));
+        format!("{}_Element", uniform_name)
+    } else {
+        storage.storage.trim().to_string()
+    };
+    strings.push(format!(
+        "layout(std430, binding = {}) buffer grim_Storage_{} {{ {} {}[]; }};",
+        binding, uniform_name, element, uniform_name
+    ));
+    strings
+}
+
 fn gl_configure_pbos(gl: &GLRc, data_len: usize) -> Vec<GLuint> {
     let pbos = gl.gen_buffers(PBO_COUNT as i32);
     for pbo in &pbos {
@@ -1649,6 +3136,14 @@ where
     a
 }
 
+/// N for the sampler uniform iChannelN, if it is one of Shadertoy's iChannel0 to iChannel3
+fn shadertoy_channel(uniform_name: &str) -> Option<usize> {
+    strip_prefix(uniform_name, "iChannel")?
+        .parse::<usize>()
+        .ok()
+        .filter(|n| *n < 4)
+}
+
 fn hash_name_attachment(name: &str, attachment: usize) -> u64 {
     let mut s = DefaultHasher::new();
     name.hash(&mut s);
@@ -1,15 +1,20 @@
+use crate::builtin;
+use crate::capture;
+use crate::capture::{CaptureFormat, CaptureTarget, FrameManifest};
 use crate::config::EffectConfig;
-use crate::config::ResourceConfig;
-use crate::effect::{Effect, EffectState};
+use crate::config::{ProfileConfig, ResourceConfig};
+use crate::diagnostics::Diagnostic;
+use crate::effect::{self, Effect, EffectState};
 use crate::error::{Error, ErrorKind, Result};
 use crate::file_stream::FileStream;
+use crate::isf::{self, Isf};
 use crate::mouse::Mouse;
 use crate::platform::Platform;
-use crate::stream::{ResourceStream, Stream};
+use crate::source_map::{self, ShaderInclude};
+use crate::stream::{file_uri, ResourceStream, ResourceStreamCtx, Stream};
+use crate::vertexshaderart;
 use chrono::prelude::*;
 use failure::ResultExt;
-use glsl_include::Context as GlslIncludeContex;
-use std::cell::RefCell;
 use std::collections::BTreeMap;
 use std::path::Path;
 use std::time::Duration;
@@ -19,14 +24,26 @@ pub struct EffectPlayer<'a> {
     shader_include_streams: BTreeMap<String, FileStream>,
     shader_streams: BTreeMap<String, FileStream>,
     resource_streams: BTreeMap<String, ResourceStream>,
+    // index of the next sample to render of each sound resource
+    sound_samples: BTreeMap<String, u64>,
     unexpanded_pass_shaders: BTreeMap<String, String>,
-    glsl_include_ctx: RefCell<GlslIncludeContex<'a>>,
+    shader_includes: BTreeMap<String, ShaderInclude>,
+    // set when the config is an ISF file, whose shaders need translating
+    isf: Option<Isf>,
+    // set when the config uses the vertexshaderart profile, whose textures keep a history
+    vertexshaderart: Option<vertexshaderart::History>,
     effect: Effect<'a>,
     playing: bool,
     time: Duration,
     frame: u32,
+    // frame and time of the most recent draw, tick steps forward after drawing
+    drawn_frame: u32,
+    drawn_time: f32,
     mouse: Mouse,
     restart_on_save: bool,
+    deterministic: bool,
+    // problems found in the most recently loaded config
+    diagnostics: Vec<Diagnostic>,
 }
 
 impl<'a> EffectPlayer<'a> {
@@ -34,29 +51,122 @@ impl<'a> EffectPlayer<'a> {
         config_path: &Path,
         glsl_version: String,
         shader_include_streams: BTreeMap<String, FileStream>,
-        glsl_include_ctx: GlslIncludeContex<'a>,
     ) -> Result<Self> {
         Ok(Self {
             effect: Effect::new(glsl_version),
-            glsl_include_ctx: RefCell::new(glsl_include_ctx),
             config_stream: FileStream::new(config_path)?,
             shader_include_streams,
             restart_on_save: false,
+            deterministic: false,
+            diagnostics: Default::default(),
             shader_streams: Default::default(),
             unexpanded_pass_shaders: Default::default(),
+            shader_includes: Default::default(),
+            isf: Default::default(),
+            vertexshaderart: Default::default(),
             resource_streams: Default::default(),
+            sound_samples: Default::default(),
             mouse: Default::default(),
             playing: Default::default(),
             time: Default::default(),
             frame: Default::default(),
+            drawn_frame: Default::default(),
+            drawn_time: Default::default(),
         })
     }
 
-    pub fn snapshot(&mut self, platform: &mut Platform, buffer: &mut Vec<u8>) -> Result<()> {
-        let width = platform.window_resolution.0 as i32;
-        let height = platform.window_resolution.1 as i32;
+    /// Blocking read of the most recently drawn frame of the named buffer, or of the window
+    /// if `buffer` is None. The rows are returned bottom-up.
+    pub fn snapshot(
+        &self,
+        platform: &Platform,
+        buffer: Option<&str>,
+        attachment: usize,
+        format: CaptureFormat,
+    ) -> Result<Vec<u8>> {
+        let target = self.capture_target(buffer, attachment)?;
+        Ok(capture::read_pixels(&platform.gl, target, format))
+    }
+
+    /// Blocking capture of every buffer attachment of the most recently drawn frame
+    /// into `dir`, along with a manifest.json describing the passes
+    pub fn capture_frame(&self, platform: &Platform, dir: &Path) -> Result<()> {
+        let manifest = FrameManifest {
+            frame: self.drawn_frame,
+            time: self.drawn_time,
+            passes: self.effect.pass_manifests(),
+            attachments: Vec::new(),
+        };
+        capture::capture_frame(
+            &platform.gl,
+            dir,
+            manifest,
+            &self.effect.buffer_attachments(),
+        )
+    }
+
+    pub fn capture_target(&self, buffer: Option<&str>, attachment: usize) -> Result<CaptureTarget> {
+        self.effect.capture_target(buffer, attachment)
+    }
+
+    pub fn config_path(&self) -> &Path {
+        self.config_stream.path()
+    }
+
+    /// Take the problems found in the config since the last call, to report them
+    /// somewhere other than the log
+    pub fn take_diagnostics(&mut self) -> Vec<Diagnostic> {
+        std::mem::replace(&mut self.diagnostics, Vec::new())
+    }
+
+    /// When deterministic, the mouse is ignored and iDate is derived from the
+    /// playback time rather than the wall-clock, so frames are reproducible
+    pub fn set_deterministic(&mut self, deterministic: bool) {
+        self.deterministic = deterministic;
+    }
+
+    /// Returns true if the config is valid and every pass compiled and linked
+    pub fn is_ok(&self) -> bool {
+        self.effect.is_ok()
+    }
+
+    /// Returns true if a config or shader change was built successfully since the last call
+    pub fn take_rebuilt(&mut self) -> bool {
+        self.effect.take_pipeline_rebuilt()
+    }
+
+    /// The iTime and iFrame of the most recent draw
+    pub fn drawn_time_frame(&self) -> (f32, u32) {
+        (self.drawn_time, self.drawn_frame)
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.playing
+    }
+
+    /// Names of the resources whose content depends on wall-clock time (video, audio, webcam, ...)
+    pub fn realtime_resource_names(&self) -> Vec<&str> {
+        self.resource_streams
+            .iter()
+            .filter(|(_, stream)| match stream.ctx {
+                Some(ResourceStreamCtx::Video(_)) | Some(ResourceStreamCtx::Audio(_)) => true,
+                _ => false,
+            })
+            .map(|(name, _)| name.as_str())
+            .collect()
+    }
+
+    /// URI of the first audio file resource, used as the soundtrack of encoded recordings
+    pub fn audio_uri(&self) -> Result<Option<String>> {
         self.effect
-            .snapshot(&mut platform.gl, buffer, width, height)
+            .config()
+            .resources
+            .values()
+            .find_map(|resource| match resource {
+                ResourceConfig::Audio(config) => Some(file_uri(&config.audio)),
+                _ => None,
+            })
+            .transpose()
     }
 
     pub fn play(&mut self) -> Result<()> {
@@ -90,12 +200,63 @@ impl<'a> EffectPlayer<'a> {
         info!("[PLAYBACK] RESTART");
         self.time = Default::default();
         self.frame = Default::default();
+        self.sound_samples.clear();
         for stream in &mut self.resource_streams.values_mut() {
             stream.restart()?;
         }
         Ok(())
     }
 
+    /// Names of the sound resources of the most recently built pipeline
+    pub fn sound_names(&self) -> Vec<String> {
+        self.effect
+            .sound_names()
+            .into_iter()
+            .map(String::from)
+            .collect()
+    }
+
+    /// Render a block of `effect::SOUND_BLOCK_SAMPLES` interleaved stereo samples of the
+    /// named sound resource, starting at the sample index `start`
+    pub fn render_sound(&self, platform: &Platform, name: &str, start: u64) -> Option<Vec<f32>> {
+        self.effect.render_sound(&platform.gl, name, start)
+    }
+
+    fn stream_sounds(&mut self, platform: &Platform) -> Result<()> {
+        let sample_rate = effect::SAMPLE_RATE as u64;
+        let playhead = self.time.as_secs() * sample_rate
+            + u64::from(self.time.subsec_micros()) * sample_rate / 1_000_000;
+        for name in self.effect.sound_names() {
+            let stream = match self.resource_streams.get_mut(name) {
+                Some(stream) => stream,
+                None => continue,
+            };
+            let next_sample = self
+                .sound_samples
+                .entry(name.to_string())
+                .or_insert(playhead);
+            // skip ahead if rendering fell behind, the late samples would never play
+            if *next_sample < playhead {
+                *next_sample = playhead;
+            }
+            while *next_sample < playhead + effect::SOUND_BLOCK_SAMPLES {
+                // None past effect::SOUND_MAX_SAMPLES, where the sound ends
+                let samples = match self.effect.render_sound(&platform.gl, name, *next_sample) {
+                    Some(samples) => samples,
+                    None => break,
+                };
+                stream.push_samples(*next_sample, &samples)?;
+                *next_sample += effect::SOUND_BLOCK_SAMPLES;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn seek(&mut self, time: Duration, frame: u32) {
+        self.time = time;
+        self.frame = frame;
+    }
+
     pub fn step_forward(&mut self, dt: Duration) {
         self.time += dt;
         self.frame += 1;
@@ -109,14 +270,43 @@ impl<'a> EffectPlayer<'a> {
     }
 
     pub fn tick(&mut self, platform: &mut Platform) -> Result<()> {
+        let mut pass_shader_did_change = false;
         // Configuration changes
         if let Some(config_bytes) = self.config_stream.try_recv()? {
             let config_string: String = String::from_utf8(config_bytes)
                 .map_err(|err| Error::from_utf8(self.config_stream.path(), err))?;
-            let effect_config = EffectConfig::from_toml(&config_string)?;
+            let config_path = self.config_stream.path();
+            let (effect_config, diagnostics) = if isf::is_isf(config_path) {
+                let isf = Isf::parse(config_path, &config_string)?;
+                for diagnostic in &isf.diagnostics {
+                    warn!("[ISF] {}", diagnostic);
+                }
+                let mut effect_config = isf.config.clone();
+                effect_config.validate("");
+                let mut diagnostics = isf.diagnostics.clone();
+                diagnostics.extend(effect_config.diagnostics(""));
+                self.isf = Some(isf);
+                (effect_config, diagnostics)
+            } else {
+                let effect_config = EffectConfig::from_toml(&config_string)?;
+                let diagnostics = effect_config.diagnostics(&config_string);
+                self.isf = None;
+                (effect_config, diagnostics)
+            };
+            self.diagnostics = diagnostics
+                .into_iter()
+                .map(|diagnostic| diagnostic.with_file(config_path))
+                .collect();
             self.restart_on_save = effect_config.restart_on_save;
+            self.vertexshaderart = match effect_config.profile {
+                ProfileConfig::VertexShaderArt => {
+                    Some(vertexshaderart::History::new(&effect_config))
+                }
+                _ => None,
+            };
             // Clear and repopulate resource streams
             self.resource_streams.clear();
+            self.sound_samples.clear();
             for (name, resource_config) in &effect_config.resources {
                 let stream = ResourceStream::new(name, resource_config)
                     .with_context(|_| ErrorKind::BadResourceConfig(name.to_string()))?;
@@ -142,7 +332,11 @@ impl<'a> EffectPlayer<'a> {
             // clear and repopulate shader streams
             self.shader_streams.clear();
             for pass_config in &effect_config.passes {
-                {
+                if let Some(source) = builtin::shader(&pass_config.vertex) {
+                    self.unexpanded_pass_shaders
+                        .insert(pass_config.vertex.clone(), source.to_string());
+                    pass_shader_did_change = true;
+                } else {
                     let vertex_path_str = &pass_config.vertex;
                     let vertex_path = Path::new(vertex_path_str);
                     let vertex_path = std::fs::canonicalize(vertex_path)
@@ -151,7 +345,13 @@ impl<'a> EffectPlayer<'a> {
                     self.shader_streams
                         .insert(vertex_path_str.clone(), vertex_stream);
                 }
-                {
+                if let Some(source) = builtin::shader(pass_config.fragment_shader()) {
+                    self.unexpanded_pass_shaders.insert(
+                        pass_config.fragment_shader().to_string(),
+                        source.to_string(),
+                    );
+                    pass_shader_did_change = true;
+                } else {
                     let fragment_path_str = &pass_config.fragment;
                     let fragment_path = Path::new(fragment_path_str);
                     let fragment_path = std::fs::canonicalize(fragment_path)
@@ -168,20 +368,60 @@ impl<'a> EffectPlayer<'a> {
                     self.shader_streams
                         .insert(geometry_path_str.clone(), geometry_stream);
                 }
+                if let Some(ref compute_path_str) = pass_config.compute {
+                    let compute_path = Path::new(compute_path_str);
+                    let compute_path = std::fs::canonicalize(compute_path)
+                        .map_err(|err| Error::io(compute_path, err))?;
+                    let compute_stream = FileStream::new(compute_path.as_path())?;
+                    self.shader_streams
+                        .insert(compute_path_str.clone(), compute_stream);
+                }
+            }
+            if let Some(ref common_path_str) = effect_config.common {
+                let common_path = Path::new(common_path_str);
+                let common_path = std::fs::canonicalize(common_path)
+                    .map_err(|err| Error::io(common_path, err))?;
+                let common_stream = FileStream::new(common_path.as_path())?;
+                self.shader_streams
+                    .insert(common_path_str.clone(), common_stream);
+            }
+            for resource_config in effect_config.resources.values() {
+                if let ResourceConfig::Sound(sound) = resource_config {
+                    let sound_path = Path::new(&sound.sound);
+                    let sound_path = std::fs::canonicalize(sound_path)
+                        .map_err(|err| Error::io(sound_path, err))?;
+                    let sound_stream = FileStream::new(sound_path.as_path())?;
+                    self.shader_streams
+                        .insert(sound.sound.clone(), sound_stream);
+                }
+                if let ResourceConfig::Feedback(feedback) = resource_config {
+                    if let Some(ref seed_path_str) = feedback.seed {
+                        let seed_path = Path::new(seed_path_str);
+                        let seed_path = std::fs::canonicalize(seed_path)
+                            .map_err(|err| Error::io(seed_path, err))?;
+                        let seed_stream = FileStream::new(seed_path.as_path())?;
+                        self.shader_streams
+                            .insert(seed_path_str.clone(), seed_stream);
+                    }
+                }
             }
             self.effect.stage_config(effect_config)?;
         }
 
         // Check for changes in the config or shaders
         let mut shader_include_did_change = false;
-        let mut pass_shader_did_change = false;
         // Include shader changes
         for (include_path, stream) in self.shader_include_streams.iter_mut() {
             if let Some(shader_bytes) = stream.try_recv()? {
-                let mut ctx = self.glsl_include_ctx.borrow_mut();
                 let shader_string: String = String::from_utf8(shader_bytes)
                     .map_err(|err| Error::from_utf8(stream.path(), err))?;
-                ctx.include(include_path.to_string(), shader_string);
+                self.shader_includes.insert(
+                    include_path.to_string(),
+                    ShaderInclude {
+                        path: stream.path().display().to_string(),
+                        source: shader_string,
+                    },
+                );
                 shader_include_did_change = true;
             }
         }
@@ -199,11 +439,12 @@ impl<'a> EffectPlayer<'a> {
         let shader_did_change = shader_include_did_change || pass_shader_did_change;
         if shader_did_change {
             let mut shader_cache = BTreeMap::new();
-            let ctx = self.glsl_include_ctx.borrow_mut();
             for (path, source) in self.unexpanded_pass_shaders.iter() {
-                let expanded = ctx
-                    .expand(source.clone())
-                    .expect("glsl include expansion failed");
+                let mut expanded =
+                    source_map::expand_includes(path, source, &self.shader_includes)?;
+                if let Some(isf) = &self.isf {
+                    expanded = isf.translate(path, expanded);
+                }
                 shader_cache.insert(path.clone(), expanded);
             }
             self.effect.stage_shader_cache(shader_cache)?;
@@ -214,6 +455,9 @@ impl<'a> EffectPlayer<'a> {
             match stream.tick(platform) {
                 Ok(ref mut resources) => {
                     while let Some(resource) = resources.next() {
+                        if let Some(history) = &mut self.vertexshaderart {
+                            history.observe(&name, &resource);
+                        }
                         self.effect.stage_resource(&name, resource);
                     }
                 }
@@ -225,37 +469,51 @@ impl<'a> EffectPlayer<'a> {
         if self.restart_on_save && shader_did_change {
             self.restart().unwrap();
         }
+        // Generated sounds are rendered with the pipeline of the previous draw, a block
+        // ahead of the playhead. Deterministic renders write their sound with render_sound.
+        if self.playing && !self.deterministic {
+            self.stream_sounds(platform)?;
+        }
         // effect state
         let state = {
-            let mouse = {
-                let mouse_state = platform.events.mouse_state();
-                let mouse_buttons = mouse_state.pressed_mouse_buttons().collect();
-                let ratiox =
-                    platform.window_resolution.0 as f32 / platform.mouse_resolution.0 as f32;
-                let ratioy =
-                    platform.window_resolution.1 as f32 / platform.mouse_resolution.1 as f32;
-                let mouse_x = (mouse_state.x() as f32 * ratiox) as u32;
-                let mouse_y = (mouse_state.y() as f32 * ratioy) as u32;
-                let mouse_y = if mouse_y < platform.window_resolution.1 {
-                    platform.window_resolution.1 - mouse_y
-                } else {
-                    0
-                };
-                self.mouse.update(mouse_buttons, mouse_x, mouse_y)
+            let mouse = match platform.events {
+                Some(ref events) if !self.deterministic => {
+                    let mouse_state = events.mouse_state();
+                    let mouse_buttons = mouse_state.pressed_mouse_buttons().collect();
+                    let ratiox =
+                        platform.window_resolution.0 as f32 / platform.mouse_resolution.0 as f32;
+                    let ratioy =
+                        platform.window_resolution.1 as f32 / platform.mouse_resolution.1 as f32;
+                    let mouse_x = (mouse_state.x() as f32 * ratiox) as u32;
+                    let mouse_y = (mouse_state.y() as f32 * ratioy) as u32;
+                    let mouse_y = if mouse_y < platform.window_resolution.1 {
+                        platform.window_resolution.1 - mouse_y
+                    } else {
+                        0
+                    };
+                    self.mouse.update(mouse_buttons, mouse_x, mouse_y)
+                }
+                // Without SDL there is no mouse to read
+                _ => self.mouse.state(),
             };
             fn duration_to_float_secs(duration: Duration) -> f32 {
                 duration.as_secs() as f32 + duration.subsec_nanos() as f32 * 1e-9
             }
             let time = duration_to_float_secs(self.time);
             let time_delta = duration_to_float_secs(platform.time_delta);
-            let local_date: DateTime<Local> = Local::now();
-            let year = local_date.year() as f32;
-            let month = local_date.month() as f32;
-            let day = local_date.day() as f32;
-            let sec = local_date.hour() as f32 * 60.0 * 60.0
-                + local_date.minute() as f32 * 60.0
-                + local_date.second() as f32;
-            let date = [year, month, day, sec];
+            let date = if self.deterministic {
+                // Midnight, January 1st 1970 + the playback time
+                [1970.0, 1.0, 1.0, time]
+            } else {
+                let local_date: DateTime<Local> = Local::now();
+                let year = local_date.year() as f32;
+                let month = local_date.month() as f32;
+                let day = local_date.day() as f32;
+                let sec = local_date.hour() as f32 * 60.0 * 60.0
+                    + local_date.minute() as f32 * 60.0
+                    + local_date.second() as f32;
+                [year, month, day, sec]
+            };
             let frame = self.frame as f32;
             let frame_rate = 1.0 / time_delta;
             let window_resolution = [
@@ -271,14 +529,22 @@ impl<'a> EffectPlayer<'a> {
                 frame_rate,
                 mouse,
                 window_resolution,
+                frame_index: self.frame as i32,
+                sample_rate: effect::SAMPLE_RATE,
             }
         };
+        if let Some(history) = &mut self.vertexshaderart {
+            history.stage(&mut self.effect, &state);
+        }
         self.effect.stage_state("GRIM_STATE", &state);
         self.effect.draw(
             &platform.gl,
+            platform.framebuffer,
             state.window_resolution[0],
             state.window_resolution[1],
         )?;
+        self.drawn_frame = self.frame;
+        self.drawn_time = state.time;
         if self.playing {
             self.step_forward(platform.time_delta);
         }
@@ -0,0 +1,409 @@
+use crate::error::{Error, Result};
+use crate::gst;
+use crate::gst::prelude::*;
+use crate::gst_app;
+use std::error::Error as StdError;
+use std::path::Path;
+
+/// The audio track is decoded and muxed as interleaved stereo f32 at this rate
+const AUDIO_RATE: u64 = 48_000;
+const AUDIO_CHANNELS: u64 = 2;
+const AUDIO_BYTES_PER_FRAME: u64 = AUDIO_CHANNELS * 4;
+
+/// The container and codec of a recorded video file
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum VideoFormat {
+    /// H.264 in an MP4 container, AAC audio
+    Mp4,
+    /// VP9 in a WebM container, Opus audio
+    Webm,
+    /// ProRes 422 in a QuickTime container, PCM audio
+    Mov,
+}
+
+impl VideoFormat {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "mp4" => Some(VideoFormat::Mp4),
+            "webm" => Some(VideoFormat::Webm),
+            "mov" => Some(VideoFormat::Mov),
+            _ => None,
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            VideoFormat::Mp4 => "mp4",
+            VideoFormat::Webm => "webm",
+            VideoFormat::Mov => "mov",
+        }
+    }
+
+    fn video_encoder(self) -> &'static str {
+        match self {
+            VideoFormat::Mp4 => {
+                "video/x-raw,format=I420 ! x264enc speed-preset=medium pass=quant quantizer=18 ! h264parse"
+            }
+            VideoFormat::Webm => "video/x-raw,format=I420 ! vp9enc deadline=1 cpu-used=4 end-usage=cq cq-level=20",
+            VideoFormat::Mov => "video/x-raw,format=I422_10LE ! avenc_prores_ks",
+        }
+    }
+
+    fn audio_encoder(self) -> &'static str {
+        match self {
+            VideoFormat::Mp4 => "avenc_aac ! aacparse",
+            VideoFormat::Webm => "opusenc",
+            VideoFormat::Mov => "audio/x-raw,format=S16LE",
+        }
+    }
+
+    fn muxer(self) -> &'static str {
+        match self {
+            VideoFormat::Mp4 => "mp4mux",
+            VideoFormat::Webm => "webmmux",
+            VideoFormat::Mov => "qtmux",
+        }
+    }
+}
+
+/// Encodes frames read back from GL into a video file.
+///
+/// Frames are pushed through an appsrc and timestamped by frame index, so the output
+/// plays back at exactly `fps` regardless of how long each frame took to render.
+pub struct VideoEncoder {
+    pipeline: gst::Element,
+    video_src: gst_app::AppSrc,
+    audio: Option<AudioTrack>,
+    width: u32,
+    height: u32,
+    fps: u32,
+    frame: u64,
+}
+
+/// Decodes an audio file and feeds it into the encoding pipeline, keeping just ahead of the video
+struct AudioTrack {
+    decoder: gst::Element,
+    sink: gst_app::AppSink,
+    src: gst_app::AppSrc,
+    /// Offset into the audio file, in nanoseconds, that lines up with the first video frame
+    start: u64,
+    /// Timestamp, in nanoseconds, up to which audio has been pushed into the encoder
+    pushed: u64,
+    done: bool,
+}
+
+impl VideoEncoder {
+    /// Start encoding to `path`. If `audio` is given as a (uri, start seconds) pair, that
+    /// audio file is muxed in from the start position, so that it lines up with the effect's iTime.
+    pub fn new(
+        path: &Path,
+        format: VideoFormat,
+        width: u32,
+        height: u32,
+        fps: u32,
+        audio: Option<(&str, f64)>,
+    ) -> Result<Self> {
+        let mut pipeline_str = format!(
+            "appsrc name=videosrc format=time block=true max-bytes={max_bytes} \
+                caps=video/x-raw,format=RGB,width={width},height={height},framerate={fps}/1 ! \
+             videoflip method=vertical-flip ! videoconvert ! {encoder} ! queue ! \
+             {muxer} name=mux ! filesink name=filesink",
+            max_bytes = 4 * width * height * 3,
+            width = width,
+            height = height,
+            fps = fps,
+            encoder = format.video_encoder(),
+            muxer = format.muxer(),
+        );
+        if audio.is_some() {
+            pipeline_str.push_str(&format!(
+                " appsrc name=audiosrc format=time block=true \
+                    caps=audio/x-raw,format=F32LE,layout=interleaved,channels={channels},rate={rate} ! \
+                 audioconvert ! audioresample ! {encoder} ! queue ! mux.",
+                channels = AUDIO_CHANNELS,
+                rate = AUDIO_RATE,
+                encoder = format.audio_encoder(),
+            ));
+        }
+        let pipeline = gst::parse_launch(&pipeline_str).map_err(|e| {
+            Error::gstreamer(format!(
+                "error creating the {} encoding pipeline, check that the required GStreamer plugins are installed: {}",
+                format.extension(),
+                e
+            ))
+        })?;
+        let bin = pipeline
+            .clone()
+            .dynamic_cast::<gst::Bin>()
+            .map_err(|_| Error::bug("[ENCODER] Expected the pipeline to be a bin"))?;
+        let filesink = bin
+            .get_by_name("filesink")
+            .ok_or_else(|| Error::bug("[ENCODER] Pipeline does not contain a filesink"))?;
+        filesink
+            .set_property("location", &path.to_string_lossy().to_string())
+            .map_err(|e| Error::gstreamer(e.to_string()))?;
+        let video_src = appsrc_by_name(&bin, "videosrc")?;
+        let audio = match audio {
+            Some((uri, start)) => Some(AudioTrack::new(
+                uri,
+                (start.max(0.0) * 1e9) as u64,
+                appsrc_by_name(&bin, "audiosrc")?,
+            )?),
+            None => None,
+        };
+        pipeline
+            .set_state(gst::State::Playing)
+            .map_err(|e| Error::gstreamer(e.to_string()))?;
+        info!(
+            "[ENCODER] Encoding {}x{}@{} {} to {:?}",
+            width,
+            height,
+            fps,
+            format.extension(),
+            path
+        );
+        Ok(Self {
+            pipeline,
+            video_src,
+            audio,
+            width,
+            height,
+            fps,
+            frame: 0,
+        })
+    }
+
+    pub fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    /// Encode one frame of tightly packed RGB pixels, in the bottom-up row order returned by glReadPixels
+    pub fn push_frame(&mut self, pixels: &[u8]) -> Result<()> {
+        self.check_bus()?;
+        let row_len = self.width as usize * 3;
+        if pixels.len() != row_len * self.height as usize {
+            return Err(Error::render(format!(
+                "Expected a {}x{} RGB frame, got {} bytes",
+                self.width,
+                self.height,
+                pixels.len()
+            )));
+        }
+        // GStreamer expects each row of RGB video to start on a 4 byte boundary
+        let stride = (row_len + 3) & !3;
+        let bytes = if stride == row_len {
+            pixels.to_vec()
+        } else {
+            let mut bytes = vec![0; stride * self.height as usize];
+            for (src, dst) in pixels.chunks(row_len).zip(bytes.chunks_mut(stride)) {
+                dst[..row_len].copy_from_slice(src);
+            }
+            bytes
+        };
+        let pts = self.frame_timestamp(self.frame);
+        let next_pts = self.frame_timestamp(self.frame + 1);
+        // The muxer interleaves both streams, so audio has to be ahead of the video
+        // or pushing the video frame blocks forever
+        if let Some(ref mut audio) = self.audio {
+            audio.push_until(next_pts)?;
+        }
+        let mut buffer = gst::Buffer::from_mut_slice(bytes);
+        {
+            let buffer = buffer
+                .get_mut()
+                .ok_or_else(|| Error::bug("[ENCODER] Expected a writable buffer"))?;
+            buffer.set_pts(gst::ClockTime::from_nseconds(pts));
+            buffer.set_duration(gst::ClockTime::from_nseconds(next_pts - pts));
+        }
+        self.video_src
+            .push_buffer(buffer)
+            .map_err(|e| Error::gstreamer(format!("error pushing video frame: {:?}", e)))?;
+        self.frame += 1;
+        Ok(())
+    }
+
+    /// Flush the encoder and finalize the file. Blocks until the muxer has written everything.
+    pub fn finish(mut self) -> Result<()> {
+        self.video_src
+            .end_of_stream()
+            .map_err(|e| Error::gstreamer(format!("error ending video stream: {:?}", e)))?;
+        if let Some(ref mut audio) = self.audio {
+            audio.finish()?;
+        }
+        let bus = self
+            .pipeline
+            .get_bus()
+            .ok_or_else(|| Error::bug("[ENCODER] Encoding pipeline with no bus"))?;
+        while let Some(msg) = bus.timed_pop(gst::CLOCK_TIME_NONE) {
+            use crate::gst::MessageView;
+            match msg.view() {
+                MessageView::Eos(..) => break,
+                MessageView::Error(err) => return Err(bus_error(&err)),
+                _ => {}
+            }
+        }
+        info!("[ENCODER] Encoded {} frames", self.frame);
+        Ok(())
+    }
+
+    fn frame_timestamp(&self, frame: u64) -> u64 {
+        frame * 1_000_000_000 / u64::from(self.fps)
+    }
+
+    fn check_bus(&self) -> Result<()> {
+        let bus = self
+            .pipeline
+            .get_bus()
+            .ok_or_else(|| Error::bug("[ENCODER] Encoding pipeline with no bus"))?;
+        while let Some(msg) = bus.timed_pop(gst::ClockTime::from_seconds(0)) {
+            use crate::gst::MessageView;
+            if let MessageView::Error(err) = msg.view() {
+                return Err(bus_error(&err));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Drop for VideoEncoder {
+    fn drop(&mut self) {
+        self.pipeline.set_state(gst::State::Null).ok();
+    }
+}
+
+impl AudioTrack {
+    fn new(uri: &str, start: u64, src: gst_app::AppSrc) -> Result<Self> {
+        let pipeline_str = format!(
+            "uridecodebin name=decodebin ! audioconvert ! audioresample ! \
+             audio/x-raw,format=F32LE,layout=interleaved,channels={channels},rate={rate} ! \
+             appsink name=appsink sync=false",
+            channels = AUDIO_CHANNELS,
+            rate = AUDIO_RATE,
+        );
+        let decoder =
+            gst::parse_launch(&pipeline_str).map_err(|e| Error::gstreamer(e.to_string()))?;
+        let bin = decoder
+            .clone()
+            .dynamic_cast::<gst::Bin>()
+            .map_err(|_| Error::bug("[ENCODER] Expected the pipeline to be a bin"))?;
+        bin.get_by_name("decodebin")
+            .ok_or_else(|| Error::bug("[ENCODER] Pipeline does not contain a uridecodebin"))?
+            .set_property("uri", &uri.to_string())
+            .map_err(|e| Error::gstreamer(e.to_string()))?;
+        let sink = bin
+            .get_by_name("appsink")
+            .ok_or_else(|| Error::bug("[ENCODER] Pipeline does not contain an appsink"))?
+            .dynamic_cast::<gst_app::AppSink>()
+            .map_err(|_| Error::bug("[ENCODER] Expected sink element to be an appsink"))?;
+        // Preroll, then seek to the requested start position before pulling any samples
+        decoder
+            .set_state(gst::State::Paused)
+            .map_err(|e| Error::gstreamer(e.to_string()))?;
+        let (state_change, _, _) = decoder.get_state(gst::CLOCK_TIME_NONE);
+        state_change.map_err(|e| Error::gstreamer(format!("error decoding {}: {}", uri, e)))?;
+        if start > 0 {
+            decoder
+                .seek_simple(
+                    gst::SeekFlags::FLUSH | gst::SeekFlags::ACCURATE,
+                    gst::ClockTime::from_nseconds(start),
+                )
+                .map_err(|e| Error::gstreamer(e.to_string()))?;
+        }
+        decoder
+            .set_state(gst::State::Playing)
+            .map_err(|e| Error::gstreamer(e.to_string()))?;
+        info!("[ENCODER] Muxing audio from {}", uri);
+        Ok(Self {
+            decoder,
+            sink,
+            src,
+            start,
+            pushed: 0,
+            done: false,
+        })
+    }
+
+    /// Push decoded audio into the encoder until it covers the timestamp `until` (in nanoseconds)
+    fn push_until(&mut self, until: u64) -> Result<()> {
+        while !self.done && self.pushed < until {
+            let sample = match self.sink.pull_sample() {
+                Ok(sample) => sample,
+                // The audio file ended before the video
+                Err(_) => {
+                    self.finish()?;
+                    break;
+                }
+            };
+            let buffer = sample
+                .get_buffer()
+                .ok_or_else(|| Error::gstreamer("audio sample without a buffer"))?;
+            let pts = buffer.get_pts().nanoseconds().unwrap_or(self.start + self.pushed);
+            let map = buffer
+                .map_readable()
+                .map_err(|_| Error::gstreamer("failed to map audio buffer readable"))?;
+            let bytes = map.as_slice();
+            // Drop any decoded audio from before the start position
+            let skip_frames = if pts < self.start {
+                (self.start - pts) * AUDIO_RATE / 1_000_000_000
+            } else {
+                0
+            };
+            let skip_bytes = (skip_frames * AUDIO_BYTES_PER_FRAME) as usize;
+            if skip_bytes >= bytes.len() {
+                continue;
+            }
+            let bytes = bytes[skip_bytes..].to_vec();
+            let frames = bytes.len() as u64 / AUDIO_BYTES_PER_FRAME;
+            let duration = frames * 1_000_000_000 / AUDIO_RATE;
+            let mut out = gst::Buffer::from_mut_slice(bytes);
+            {
+                let out = out
+                    .get_mut()
+                    .ok_or_else(|| Error::bug("[ENCODER] Expected a writable buffer"))?;
+                out.set_pts(gst::ClockTime::from_nseconds(self.pushed));
+                out.set_duration(gst::ClockTime::from_nseconds(duration));
+            }
+            self.src
+                .push_buffer(out)
+                .map_err(|e| Error::gstreamer(format!("error pushing audio: {:?}", e)))?;
+            self.pushed += duration;
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        if !self.done {
+            self.done = true;
+            self.src
+                .end_of_stream()
+                .map_err(|e| Error::gstreamer(format!("error ending audio stream: {:?}", e)))?;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for AudioTrack {
+    fn drop(&mut self) {
+        self.decoder.set_state(gst::State::Null).ok();
+    }
+}
+
+fn appsrc_by_name(bin: &gst::Bin, name: &str) -> Result<gst_app::AppSrc> {
+    bin.get_by_name(name)
+        .ok_or_else(|| Error::bug(format!("[ENCODER] Pipeline does not contain {}", name)))?
+        .dynamic_cast::<gst_app::AppSrc>()
+        .map_err(|_| Error::bug(format!("[ENCODER] Expected {} to be an appsrc", name)))
+}
+
+fn bus_error(err: &gst::message::Error) -> Error {
+    let src = err
+        .get_src()
+        .map(|s| s.get_path_string())
+        .unwrap_or_else(|| gst::glib::GString::from("None"));
+    let error: String = err.get_error().description().into();
+    let debug = err.get_debug();
+    Error::gstreamer(format!(
+        "bus error: {} from source element {}. debug {:?}",
+        error, src, debug
+    ))
+}
@@ -44,6 +44,13 @@ impl Error {
         ))
     }
 
+    pub(crate) fn model<P: AsRef<Path>, T: AsRef<str>>(path: P, msg: T) -> Error {
+        Error::from(ErrorKind::Model(
+            path.as_ref().to_path_buf(),
+            msg.as_ref().to_string(),
+        ))
+    }
+
     pub(crate) fn io<P: AsRef<Path>>(path: P, err: io::Error) -> Error {
         Error::from(ErrorKind::Io(path.as_ref().to_path_buf(), err.to_string()))
     }
@@ -73,6 +80,27 @@ impl Error {
         ))
     }
 
+    pub(crate) fn glsl_geometry<T: AsRef<str>>(msg: T, path: T) -> Error {
+        Error::from(ErrorKind::GlslGeometry(
+            msg.as_ref().to_string(),
+            path.as_ref().to_string(),
+        ))
+    }
+
+    pub(crate) fn glsl_compute<T: AsRef<str>>(msg: T, path: T) -> Error {
+        Error::from(ErrorKind::GlslCompute(
+            msg.as_ref().to_string(),
+            path.as_ref().to_string(),
+        ))
+    }
+
+    pub(crate) fn glsl_include<T: AsRef<str>>(msg: T, path: T) -> Error {
+        Error::from(ErrorKind::GlslInclude(
+            msg.as_ref().to_string(),
+            path.as_ref().to_string(),
+        ))
+    }
+
     pub(crate) fn glsl_program<T: AsRef<str>>(msg: T, vertex_path: T, fragment_path: T) -> Error {
         Error::from(ErrorKind::GlslProgram(
             msg.as_ref().to_string(),
@@ -89,6 +117,30 @@ impl Error {
         Error::from(ErrorKind::Gstreamer(msg.as_ref().to_string()))
     }
 
+    pub(crate) fn egl<T: AsRef<str>>(msg: T) -> Error {
+        Error::from(ErrorKind::Egl(msg.as_ref().to_string()))
+    }
+
+    pub(crate) fn render<T: AsRef<str>>(msg: T) -> Error {
+        Error::from(ErrorKind::Render(msg.as_ref().to_string()))
+    }
+
+    pub(crate) fn test<T: AsRef<str>>(msg: T) -> Error {
+        Error::from(ErrorKind::Test(msg.as_ref().to_string()))
+    }
+
+    pub(crate) fn isf<T: AsRef<str>>(msg: T) -> Error {
+        Error::from(ErrorKind::Isf(msg.as_ref().to_string()))
+    }
+
+    pub(crate) fn shadertoy<T: AsRef<str>>(msg: T) -> Error {
+        Error::from(ErrorKind::Shadertoy(msg.as_ref().to_string()))
+    }
+
+    pub(crate) fn vertexshaderart<T: AsRef<str>>(msg: T) -> Error {
+        Error::from(ErrorKind::VertexShaderArt(msg.as_ref().to_string()))
+    }
+
     pub(crate) fn bug<T: AsRef<str>>(msg: T) -> Error {
         Error::from(ErrorKind::Bug(msg.as_ref().to_string()))
     }
@@ -119,6 +171,8 @@ pub enum ErrorKind {
     BadResourceConfig(String),
     /// An error loading an image occurred.
     Image(PathBuf, String),
+    /// An error loading a model occurred.
+    Model(PathBuf, String),
     /// An unexpected I/O error occurred.
     Io(PathBuf, String),
     /// An error watching a path occurred.
@@ -135,6 +189,18 @@ pub enum ErrorKind {
     ///
     /// The data provided is the GLSL error and the path to the shader
     GlslFragment(String, String),
+    /// An error compiling a GLSL geometry shader.
+    ///
+    /// The data provided is the GLSL error and the path to the shader
+    GlslGeometry(String, String),
+    /// An error compiling or linking a GLSL compute shader.
+    ///
+    /// The data provided is the GLSL error and the path to the shader
+    GlslCompute(String, String),
+    /// An error expanding the includes of a GLSL shader.
+    ///
+    /// The data provided is the error and the path to the shader
+    GlslInclude(String, String),
     /// An error linking a GLSL program.
     ///
     /// The data provided is the GLSL error and the paths to the shaders
@@ -149,6 +215,18 @@ pub enum ErrorKind {
     Notify(String),
     /// An error with SDL2 occurred.
     SDL2(String),
+    /// An error creating a headless EGL context occurred.
+    Egl(String),
+    /// An error rendering or capturing frames occurred.
+    Render(String),
+    /// A rendered frame did not match its reference image.
+    Test(String),
+    /// An error occurred while parsing the JSON header of an ISF file
+    Isf(String),
+    /// An error importing or exporting a Shadertoy JSON export occurred.
+    Shadertoy(String),
+    /// An error importing a vertexshaderart JSON export occurred.
+    VertexShaderArt(String),
     /// An unexpected error occurred. Generally, these errors correspond
     /// to bugs in grimoire.
     Bug(String),
@@ -160,6 +238,9 @@ impl fmt::Display for ErrorKind {
             ErrorKind::Image(ref path, ref err) => {
                 write!(f, "Error loading image at {:?}: {}", path, err)
             }
+            ErrorKind::Model(ref path, ref err) => {
+                write!(f, "Error loading model at {:?}: {}", path, err)
+            }
             ErrorKind::Io(ref path, ref err) => {
                 write!(f, "Error performing I/O operation on {:?}: {}", path, err)
             }
@@ -183,6 +264,19 @@ impl fmt::Display for ErrorKind {
                 "[GLSL] Error compiling fragment shader {}: {}",
                 path, err
             ),
+            ErrorKind::GlslGeometry(ref err, ref path) => write!(
+                f,
+                "[GLSL] Error compiling geometry shader {}: {}",
+                path, err
+            ),
+            ErrorKind::GlslCompute(ref err, ref path) => {
+                write!(f, "[GLSL] Error compiling compute shader {}: {}", path, err)
+            }
+            ErrorKind::GlslInclude(ref err, ref path) => write!(
+                f,
+                "[GLSL] Error expanding includes of shader {}: {}",
+                path, err
+            ),
             ErrorKind::GlslProgram(ref err, ref vertex_path, ref fragment_path) => write!(
                 f,
                 "[GLSL] Error linking program from shaders {} and {}: {}",
@@ -191,6 +285,12 @@ impl fmt::Display for ErrorKind {
             ErrorKind::Toml(ref err) => write!(f, "[TOML] Error parsing configuration: {}", err),
             ErrorKind::GLPass(ref index) => write!(f, "Error building [[pass]] {}", index),
             ErrorKind::SDL2(ref err) => write!(f, "[SDL2]: {}", err),
+            ErrorKind::Egl(ref err) => write!(f, "[EGL]: {}", err),
+            ErrorKind::Render(ref err) => write!(f, "[RENDER] {}", err),
+            ErrorKind::Test(ref err) => write!(f, "[TEST] {}", err),
+            ErrorKind::Isf(ref err) => write!(f, "[ISF] {}", err),
+            ErrorKind::Shadertoy(ref err) => write!(f, "[SHADERTOY] {}", err),
+            ErrorKind::VertexShaderArt(ref err) => write!(f, "[VERTEXSHADERART] {}", err),
             ErrorKind::Gstreamer(ref err) => write!(
                 f,
                 "[GSTREAMER] {:?} (Run with GST_DEBUG=3 for more information)",
@@ -5,6 +5,235 @@ pub use gleam::gl::GlesFns;
 pub use gleam::gl::*;
 pub type GLRc = std::rc::Rc<dyn Gl>;
 use gleam::gl;
+use lazy_static::lazy_static;
+use std::ffi::{c_void, CString};
+use std::sync::RwLock;
+
+// GL 4.3 and GLES 3.1 enums that gleam, which binds GL 3.3 and GLES 3.0, leaves out
+pub const COMPUTE_SHADER: GLenum = 0x91B9;
+pub const COMPUTE_WORK_GROUP_SIZE: GLenum = 0x8267;
+pub const SHADER_STORAGE_BUFFER: GLenum = 0x90D2;
+pub const TEXTURE_FETCH_BARRIER_BIT: GLbitfield = 0x0000_0008;
+pub const SHADER_IMAGE_ACCESS_BARRIER_BIT: GLbitfield = 0x0000_0020;
+pub const PIXEL_BUFFER_BARRIER_BIT: GLbitfield = 0x0000_0080;
+pub const TEXTURE_UPDATE_BARRIER_BIT: GLbitfield = 0x0000_0100;
+pub const FRAMEBUFFER_BARRIER_BIT: GLbitfield = 0x0000_0400;
+pub const SHADER_STORAGE_BARRIER_BIT: GLbitfield = 0x0000_2000;
+
+type DispatchComputeFn = unsafe extern "system" fn(GLuint, GLuint, GLuint);
+type MemoryBarrierFn = unsafe extern "system" fn(GLbitfield);
+type BindImageTextureFn =
+    unsafe extern "system" fn(GLuint, GLuint, GLint, GLboolean, GLint, GLenum, GLenum);
+type TransformFeedbackVaryingsFn =
+    unsafe extern "system" fn(GLuint, GLsizei, *const *const GLchar, GLenum);
+type BeginTransformFeedbackFn = unsafe extern "system" fn(GLenum);
+type EndTransformFeedbackFn = unsafe extern "system" fn();
+
+/// Entry points that gleam does not wrap, None when the context lacks them, and the
+/// features the context supports by its version and extensions. Loaders can return
+/// stubs for entry points the driver doesn't implement, so a loaded entry point alone
+/// doesn't mean the feature is supported
+#[derive(Default, Clone, Copy)]
+struct ExtFns {
+    compute: bool,
+    storage: bool,
+    transform_feedback: bool,
+    dispatch_compute: Option<DispatchComputeFn>,
+    memory_barrier: Option<MemoryBarrierFn>,
+    bind_image_texture: Option<BindImageTextureFn>,
+    transform_feedback_varyings: Option<TransformFeedbackVaryingsFn>,
+    begin_transform_feedback: Option<BeginTransformFeedbackFn>,
+    end_transform_feedback: Option<EndTransformFeedbackFn>,
+}
+
+lazy_static! {
+    static ref EXT_FNS: RwLock<ExtFns> = RwLock::new(ExtFns::default());
+}
+
+/// Load the entry points gleam does not wrap, with the same loader given to gleam, and
+/// detect the features of the context. Must be called once the context is current.
+pub fn load_ext_with<F: Fn(&str) -> *const c_void>(gl: &GLRc, loader: F) {
+    let load = |name: &str| {
+        let f = loader(name);
+        if f.is_null() {
+            None
+        } else {
+            Some(f)
+        }
+    };
+    let (major, minor, gles) = context_version(&gl.get_string(gl::VERSION));
+    let at_least = |gl_version: (u32, u32), gles_version: (u32, u32)| {
+        let version = if gles { gles_version } else { gl_version };
+        (major, minor) >= version
+    };
+    let extensions = context_extensions(gl);
+    let has_extension = |name: &str| extensions.iter().any(|extension| extension == name);
+    let mut fns = unsafe {
+        ExtFns {
+            compute: false,
+            storage: false,
+            transform_feedback: false,
+            dispatch_compute: load("glDispatchCompute")
+                .map(|f| std::mem::transmute::<*const c_void, DispatchComputeFn>(f)),
+            memory_barrier: load("glMemoryBarrier")
+                .map(|f| std::mem::transmute::<*const c_void, MemoryBarrierFn>(f)),
+            bind_image_texture: load("glBindImageTexture")
+                .map(|f| std::mem::transmute::<*const c_void, BindImageTextureFn>(f)),
+            transform_feedback_varyings: load("glTransformFeedbackVaryings")
+                .map(|f| std::mem::transmute::<*const c_void, TransformFeedbackVaryingsFn>(f)),
+            begin_transform_feedback: load("glBeginTransformFeedback")
+                .map(|f| std::mem::transmute::<*const c_void, BeginTransformFeedbackFn>(f)),
+            end_transform_feedback: load("glEndTransformFeedback")
+                .map(|f| std::mem::transmute::<*const c_void, EndTransformFeedbackFn>(f)),
+        }
+    };
+    fns.compute = (at_least((4, 3), (3, 1)) || has_extension("GL_ARB_compute_shader"))
+        && fns.dispatch_compute.is_some()
+        && fns.memory_barrier.is_some()
+        && fns.bind_image_texture.is_some();
+    fns.storage = (at_least((4, 3), (3, 1))
+        || has_extension("GL_ARB_shader_storage_buffer_object"))
+        && fns.memory_barrier.is_some();
+    fns.transform_feedback = (at_least((3, 0), (3, 0))
+        || has_extension("GL_EXT_transform_feedback"))
+        && fns.transform_feedback_varyings.is_some()
+        && fns.begin_transform_feedback.is_some()
+        && fns.end_transform_feedback.is_some();
+    info!(
+        "GL {}.{}{}, compute shaders: {}, storage buffers: {}, transform feedback: {}",
+        major,
+        minor,
+        if gles { " ES" } else { "" },
+        fns.compute,
+        fns.storage,
+        fns.transform_feedback
+    );
+    *EXT_FNS.write().unwrap() = fns;
+}
+
+/// The major and minor version of a GL_VERSION string, like "4.6.0 NVIDIA 440.82" or
+/// "OpenGL ES 3.2 Mesa 20.0.8", and whether it is GLES
+fn context_version(version: &str) -> (u32, u32, bool) {
+    let gles = version.starts_with("OpenGL ES");
+    let number = version
+        .split_whitespace()
+        .find(|word| word.starts_with(|c: char| c.is_ascii_digit()))
+        .unwrap_or("");
+    let mut parts = number.split('.').map(|part| {
+        part.chars()
+            .take_while(char::is_ascii_digit)
+            .collect::<String>()
+            .parse()
+            .unwrap_or(0)
+    });
+    let major = parts.next().unwrap_or(0);
+    let minor = parts.next().unwrap_or(0);
+    (major, minor, gles)
+}
+
+/// The extensions of the context, listed one at a time as core profiles require
+fn context_extensions(gl: &GLRc) -> Vec<String> {
+    let mut count = [0];
+    unsafe {
+        gl.get_integer_v(gl::NUM_EXTENSIONS, &mut count);
+    }
+    (0..count[0].max(0) as GLuint)
+        .map(|index| gl.get_string_i(gl::EXTENSIONS, index))
+        .collect()
+}
+
+fn ext_fns() -> ExtFns {
+    *EXT_FNS.read().unwrap()
+}
+
+/// Whether the context can run compute shaders, which needs GL 4.3, GLES 3.1 or
+/// GL_ARB_compute_shader
+pub fn supports_compute() -> bool {
+    ext_fns().compute
+}
+
+/// Whether the context has shader storage buffers, which needs GL 4.3, GLES 3.1 or
+/// GL_ARB_shader_storage_buffer_object
+pub fn supports_storage() -> bool {
+    ext_fns().storage
+}
+
+/// Whether the context can capture vertex shader outputs with transform feedback, which
+/// needs GL 3.0, GLES 3.0 or GL_EXT_transform_feedback
+pub fn supports_transform_feedback() -> bool {
+    ext_fns().transform_feedback
+}
+
+pub fn dispatch_compute(x: GLuint, y: GLuint, z: GLuint) {
+    if let Some(dispatch_compute) = ext_fns().dispatch_compute {
+        unsafe { dispatch_compute(x, y, z) }
+    }
+}
+
+pub fn memory_barrier(barriers: GLbitfield) {
+    if let Some(memory_barrier) = ext_fns().memory_barrier {
+        unsafe { memory_barrier(barriers) }
+    }
+}
+
+pub fn bind_image_texture(
+    unit: GLuint,
+    texture: GLuint,
+    level: GLint,
+    layered: bool,
+    layer: GLint,
+    access: GLenum,
+    format: GLenum,
+) {
+    if let Some(bind_image_texture) = ext_fns().bind_image_texture {
+        unsafe {
+            bind_image_texture(
+                unit,
+                texture,
+                level,
+                layered as GLboolean,
+                layer,
+                access,
+                format,
+            )
+        }
+    }
+}
+
+/// Name the vertex shader outputs that transform feedback captures, interleaved into a
+/// single buffer. Takes effect when the program is next linked.
+pub fn transform_feedback_varyings(program: GLuint, varyings: &[String]) -> Result<(), String> {
+    if let Some(transform_feedback_varyings) = ext_fns().transform_feedback_varyings {
+        let mut names = Vec::new();
+        for varying in varyings {
+            let name = CString::new(varying.as_str())
+                .map_err(|_| format!("Captured output {:?} contains a nul byte", varying))?;
+            names.push(name);
+        }
+        let pointers: Vec<*const GLchar> = names.iter().map(|name| name.as_ptr()).collect();
+        unsafe {
+            transform_feedback_varyings(
+                program,
+                pointers.len() as GLsizei,
+                pointers.as_ptr(),
+                gl::INTERLEAVED_ATTRIBS,
+            )
+        }
+    }
+    Ok(())
+}
+
+pub fn begin_transform_feedback(primitive_mode: GLenum) {
+    if let Some(begin_transform_feedback) = ext_fns().begin_transform_feedback {
+        unsafe { begin_transform_feedback(primitive_mode) }
+    }
+}
+
+pub fn end_transform_feedback() {
+    if let Some(end_transform_feedback) = ext_fns().end_transform_feedback {
+        unsafe { end_transform_feedback() }
+    }
+}
 
 #[allow(dead_code)]
 pub fn create_buffer(gl: &GLRc) -> GLuint {
@@ -49,6 +278,19 @@ pub fn create_shader(gl: &GLRc, shader_type: GLenum, source: &[&[u8]]) -> Result
 
 #[allow(dead_code)]
 pub fn create_program(gl: &GLRc, vs: GLuint, fs: GLuint, gs: Option<GLuint>) -> Result<GLuint, String> {
+    create_feedback_program(gl, vs, fs, gs, &[])
+}
+
+/// Link a program whose vertex shader outputs named by `varyings` are captured with
+/// transform feedback
+#[allow(dead_code)]
+pub fn create_feedback_program(
+    gl: &GLRc,
+    vs: GLuint,
+    fs: GLuint,
+    gs: Option<GLuint>,
+    varyings: &[String],
+) -> Result<GLuint, String> {
     let program = gl.create_program();
     assert!(program != 0);
     gl.attach_shader(program, vs);
@@ -56,6 +298,12 @@ pub fn create_program(gl: &GLRc, vs: GLuint, fs: GLuint, gs: Option<GLuint>) ->
         gl.attach_shader(program, gs);
     }
     gl.attach_shader(program, fs);
+    if !varyings.is_empty() {
+        if let Err(err) = transform_feedback_varyings(program, varyings) {
+            gl.delete_program(program);
+            return Err(err);
+        }
+    }
     gl.link_program(program);
     let linked = unsafe {
         let mut linked: [i32; 1] = [0];
@@ -75,6 +323,26 @@ pub fn create_program(gl: &GLRc, vs: GLuint, fs: GLuint, gs: Option<GLuint>) ->
     Ok(program)
 }
 
+#[allow(dead_code)]
+pub fn create_compute_program(gl: &GLRc, cs: GLuint) -> Result<GLuint, String> {
+    let program = gl.create_program();
+    assert!(program != 0);
+    gl.attach_shader(program, cs);
+    gl.link_program(program);
+    let linked = unsafe {
+        let mut linked: [i32; 1] = [0];
+        gl.get_program_iv(program, gl::LINK_STATUS, &mut linked);
+        linked[0]
+    };
+    gl.detach_shader(program, cs);
+    if linked == 0 {
+        let log = gl.get_program_info_log(program);
+        gl.delete_program(program);
+        return Err(log.trim().to_string());
+    }
+    Ok(program)
+}
+
 #[allow(dead_code)]
 pub fn create_texture(gl: &GLRc) -> GLuint {
     let textures = gl.gen_textures(1);
@@ -0,0 +1,284 @@
+use crate::capture::{self, CaptureFormat};
+use crate::effect_player::EffectPlayer;
+use crate::error::{Error, Result};
+use crate::platform::Platform;
+use crate::render;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+#[derive(Debug, Clone)]
+pub struct TestSettings {
+    /// iFrame values to compare, sorted and without duplicates
+    pub frames: Vec<u32>,
+    pub fps: u32,
+    pub width: u32,
+    pub height: u32,
+    /// Directory of reference images, named like the output of `grimoire render`
+    pub references: PathBuf,
+    /// Directory that receives the actual and diff images of failing frames
+    pub output: PathBuf,
+    /// Largest difference allowed in any channel of a pixel, out of 255
+    pub tolerance: u8,
+    /// Save the rendered frames as the new references instead of comparing against them
+    pub update: bool,
+    /// Compare the named buffer instead of the window
+    pub buffer: Option<String>,
+    /// Color attachment index of the buffer
+    pub attachment: usize,
+}
+
+impl TestSettings {
+    fn reference_path(&self, frame: u32) -> PathBuf {
+        self.references.join(format!("{:06}.png", frame))
+    }
+
+    fn frame_time(&self, frame: u32) -> Duration {
+        Duration::from_nanos(
+            (u128::from(frame) * 1_000_000_000 / u128::from(self.fps)) as u64,
+        )
+    }
+}
+
+/// Render the project with deterministic time, mouse, and date, and compare the
+/// requested frames against their reference images.
+///
+/// Returns an error if any frame differs by more than the tolerance, after writing
+/// the actual and diff images of every failing frame to the output directory.
+pub fn test(
+    player: &mut EffectPlayer,
+    platform: &mut Platform,
+    settings: &TestSettings,
+) -> Result<()> {
+    let gl = platform.gl.clone();
+    let mut offscreen = render::prepare_offscreen(
+        player,
+        platform,
+        settings.width,
+        settings.height,
+        settings.fps,
+    );
+    let result = test_frames(player, platform, settings);
+    offscreen.delete(&gl);
+    let failures = result?;
+    if settings.update {
+        println!(
+            "updated {} reference images in {}",
+            settings.frames.len(),
+            settings.references.display()
+        );
+        return Ok(());
+    }
+    println!(
+        "test result: {} passed, {} failed",
+        settings.frames.len() - failures,
+        failures
+    );
+    if failures > 0 {
+        return Err(Error::test(format!(
+            "{} of {} frames differ from their reference images, see {}",
+            failures,
+            settings.frames.len(),
+            settings.output.display()
+        )));
+    }
+    Ok(())
+}
+
+/// Returns the number of frames that failed
+fn test_frames(
+    player: &mut EffectPlayer,
+    platform: &mut Platform,
+    settings: &TestSettings,
+) -> Result<usize> {
+    let last = match settings.frames.last() {
+        Some(last) => *last,
+        None => return Ok(0),
+    };
+    if settings.update {
+        std::fs::create_dir_all(&settings.references)
+            .map_err(|err| Error::io(&settings.references, err))?;
+    }
+    let buffer = settings.buffer.as_ref().map(String::as_str);
+    let mut failures = 0;
+    // Draw every frame up to the last one, so that feedback buffers hold
+    // the same contents they would during playback
+    for frame in 0..=last {
+        player.seek(settings.frame_time(frame), frame);
+        player.tick(platform)?;
+        if !player.is_ok() {
+            return Err(Error::render(
+                "The project failed to build, see the errors above",
+            ));
+        }
+        if settings.frames.binary_search(&frame).is_err() {
+            continue;
+        }
+        let target = player.capture_target(buffer, settings.attachment)?;
+        let (width, height) = (target.width, target.height);
+        let mut pixels =
+            player.snapshot(platform, buffer, settings.attachment, CaptureFormat::Png)?;
+        let reference_path = settings.reference_path(frame);
+        if settings.update {
+            CaptureFormat::Png.save(&reference_path, &mut pixels, width, height)?;
+            continue;
+        }
+        capture::flip_rows(&mut pixels, width as usize * 3);
+        match compare(&reference_path, &pixels, width, height, settings.tolerance) {
+            Ok(()) => println!("frame {} ... ok", frame),
+            Err((reason, diff)) => {
+                failures += 1;
+                println!("frame {} ... FAILED: {}", frame, reason);
+                let diff = diff.as_ref().map(Vec::as_slice);
+                write_failure(settings, frame, &pixels, diff, width, height)?;
+            }
+        }
+    }
+    Ok(failures)
+}
+
+/// Compare top-down RGB pixels against the reference image at `path`. On failure, returns
+/// the reason and, if the sizes match, an image marking the differing pixels in red over a
+/// faded copy of the reference.
+fn compare(
+    path: &Path,
+    pixels: &[u8],
+    width: u32,
+    height: u32,
+    tolerance: u8,
+) -> std::result::Result<(), (String, Option<Vec<u8>>)> {
+    match image::open(path) {
+        Ok(reference) => compare_image(&reference.to_rgb(), pixels, width, height, tolerance),
+        Err(err) => Err((
+            format!(
+                "cannot load {}: {}, run with --update to create it",
+                path.display(),
+                err
+            ),
+            None,
+        )),
+    }
+}
+
+/// Compare top-down RGB pixels against a loaded reference image, like `compare`
+fn compare_image(
+    reference: &image::RgbImage,
+    pixels: &[u8],
+    width: u32,
+    height: u32,
+    tolerance: u8,
+) -> std::result::Result<(), (String, Option<Vec<u8>>)> {
+    if reference.dimensions() != (width, height) {
+        return Err((
+            format!(
+                "the frame is {}x{} but the reference is {}x{}",
+                width,
+                height,
+                reference.width(),
+                reference.height()
+            ),
+            None,
+        ));
+    }
+    let mut diff = Vec::with_capacity(pixels.len());
+    let mut differing = 0;
+    let mut largest = 0;
+    for (actual, expected) in pixels.chunks(3).zip(reference.pixels()) {
+        let expected = &expected.0;
+        let delta = actual
+            .iter()
+            .zip(expected)
+            .map(|(a, e)| (i16::from(*a) - i16::from(*e)).abs() as u8)
+            .max()
+            .unwrap_or(0);
+        largest = largest.max(delta);
+        if delta > tolerance {
+            differing += 1;
+            diff.extend_from_slice(&[255, 0, 0]);
+        } else {
+            let luma: u16 = expected.iter().map(|e| u16::from(*e)).sum::<u16>() / 3;
+            let faded = (luma / 4) as u8;
+            diff.extend_from_slice(&[faded, faded, faded]);
+        }
+    }
+    if differing == 0 {
+        return Ok(());
+    }
+    Err((
+        format!(
+            "{} pixels differ by up to {}, the tolerance is {}",
+            differing, largest, tolerance
+        ),
+        Some(diff),
+    ))
+}
+
+fn write_failure(
+    settings: &TestSettings,
+    frame: u32,
+    pixels: &[u8],
+    diff: Option<&[u8]>,
+    width: u32,
+    height: u32,
+) -> Result<()> {
+    std::fs::create_dir_all(&settings.output).map_err(|err| Error::io(&settings.output, err))?;
+    let actual_path = settings.output.join(format!("{:06}.actual.png", frame));
+    image::save_buffer(&actual_path, pixels, width, height, image::RGB(8))
+        .map_err(|err| Error::io(&actual_path, err))?;
+    if let Some(diff) = diff {
+        let diff_path = settings.output.join(format!("{:06}.diff.png", frame));
+        image::save_buffer(&diff_path, diff, width, height, image::RGB(8))
+            .map_err(|err| Error::io(&diff_path, err))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A 2x2 reference: red, green, blue and mid gray
+    fn reference() -> image::RgbImage {
+        let pixels = vec![255, 0, 0, 0, 255, 0, 0, 0, 255, 120, 120, 120];
+        image::RgbImage::from_raw(2, 2, pixels).unwrap()
+    }
+
+    #[test]
+    fn compare_within_tolerance() {
+        let reference = reference();
+        assert_eq!(
+            compare_image(&reference, &reference.clone().into_raw(), 2, 2, 0),
+            Ok(())
+        );
+        let pixels = [250, 4, 0, 0, 255, 3, 5, 0, 250, 125, 115, 120];
+        assert_eq!(compare_image(&reference, &pixels, 2, 2, 5), Ok(()));
+    }
+
+    #[test]
+    fn compare_over_tolerance() {
+        let pixels = [255, 0, 0, 0, 240, 0, 0, 0, 255, 120, 126, 120];
+        let (reason, diff) = compare_image(&reference(), &pixels, 2, 2, 5).unwrap_err();
+        assert_eq!(reason, "2 pixels differ by up to 15, the tolerance is 5");
+        // Differing pixels in red, the others a faded gray of the reference
+        assert_eq!(
+            diff,
+            Some(vec![21, 21, 21, 255, 0, 0, 21, 21, 21, 255, 0, 0])
+        );
+    }
+
+    #[test]
+    fn compare_size_mismatch() {
+        let pixels = [0; 2 * 3 * 3];
+        let (reason, diff) = compare_image(&reference(), &pixels, 3, 2, 255).unwrap_err();
+        assert_eq!(reason, "the frame is 3x2 but the reference is 2x2");
+        assert_eq!(diff, None);
+    }
+
+    #[test]
+    fn compare_missing_reference() {
+        let path = Path::new("/nonexistent/grimoire/000000.png");
+        let (reason, diff) = compare(path, &[0; 3], 1, 1, 0).unwrap_err();
+        assert!(reason.starts_with("cannot load /nonexistent/grimoire/000000.png: "));
+        assert!(reason.ends_with(", run with --update to create it"));
+        assert_eq!(diff, None);
+    }
+}
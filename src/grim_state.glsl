@@ -0,0 +1,17 @@
+/*
+BEGIN: grim_state.glsl
+*/
+layout(std140) uniform GRIM_STATE {
+  vec4 iMouse;
+  vec4 iDate;
+  vec3 iWindowResolution;
+  float iTime;
+  float iTimeDelta;
+  float iFrame;
+  float iFrameRate;
+  int grim_iFrame;
+  float iSampleRate;
+};
+/*
+END: grim_state.glsl
+*/
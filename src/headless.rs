@@ -0,0 +1,303 @@
+use crate::error::{Error, Result};
+use std::os::raw::c_void;
+
+// Minimal EGL bindings, just enough to create an offscreen GL context.
+// On Linux, Mesa provides libEGL for both hardware drivers and llvmpipe
+// (LIBGL_ALWAYS_SOFTWARE=1), which lets grimoire run on machines without
+// a display server or GPU.
+#[cfg(target_os = "linux")]
+mod egl {
+    use std::os::raw::{c_char, c_void};
+
+    pub type EGLDisplay = *mut c_void;
+    pub type EGLConfig = *mut c_void;
+    pub type EGLContext = *mut c_void;
+    pub type EGLSurface = *mut c_void;
+    pub type EGLint = i32;
+    pub type EGLenum = u32;
+    pub type EGLBoolean = u32;
+
+    pub const FALSE: EGLBoolean = 0;
+    pub const NONE: EGLint = 0x3038;
+    pub const ALPHA_SIZE: EGLint = 0x3021;
+    pub const BLUE_SIZE: EGLint = 0x3022;
+    pub const GREEN_SIZE: EGLint = 0x3023;
+    pub const RED_SIZE: EGLint = 0x3024;
+    pub const DEPTH_SIZE: EGLint = 0x3025;
+    pub const SURFACE_TYPE: EGLint = 0x3033;
+    pub const RENDERABLE_TYPE: EGLint = 0x3040;
+    pub const HEIGHT: EGLint = 0x3056;
+    pub const WIDTH: EGLint = 0x3057;
+    pub const PBUFFER_BIT: EGLint = 0x0001;
+    pub const OPENGL_ES2_BIT: EGLint = 0x0004;
+    pub const OPENGL_BIT: EGLint = 0x0008;
+    pub const OPENGL_ES3_BIT: EGLint = 0x0040;
+    pub const VENDOR: EGLint = 0x3053;
+    pub const VERSION: EGLint = 0x3054;
+    pub const EXTENSIONS: EGLint = 0x3055;
+    pub const OPENGL_ES_API: EGLenum = 0x30A0;
+    pub const OPENGL_API: EGLenum = 0x30A2;
+    pub const CONTEXT_MAJOR_VERSION: EGLint = 0x3098;
+    pub const CONTEXT_MINOR_VERSION: EGLint = 0x30FB;
+    pub const CONTEXT_OPENGL_PROFILE_MASK: EGLint = 0x30FD;
+    pub const CONTEXT_OPENGL_CORE_PROFILE_BIT: EGLint = 0x0001;
+    pub const PLATFORM_SURFACELESS_MESA: EGLenum = 0x31DD;
+
+    pub type GetPlatformDisplayEXT =
+        unsafe extern "C" fn(EGLenum, *mut c_void, *const EGLint) -> EGLDisplay;
+
+    #[link(name = "EGL")]
+    extern "C" {
+        pub fn eglGetError() -> EGLint;
+        pub fn eglGetDisplay(native_display: *mut c_void) -> EGLDisplay;
+        pub fn eglInitialize(dpy: EGLDisplay, major: *mut EGLint, minor: *mut EGLint)
+            -> EGLBoolean;
+        pub fn eglTerminate(dpy: EGLDisplay) -> EGLBoolean;
+        pub fn eglQueryString(dpy: EGLDisplay, name: EGLint) -> *const c_char;
+        pub fn eglBindAPI(api: EGLenum) -> EGLBoolean;
+        pub fn eglChooseConfig(
+            dpy: EGLDisplay,
+            attrib_list: *const EGLint,
+            configs: *mut EGLConfig,
+            config_size: EGLint,
+            num_config: *mut EGLint,
+        ) -> EGLBoolean;
+        pub fn eglCreateContext(
+            dpy: EGLDisplay,
+            config: EGLConfig,
+            share_context: EGLContext,
+            attrib_list: *const EGLint,
+        ) -> EGLContext;
+        pub fn eglDestroyContext(dpy: EGLDisplay, ctx: EGLContext) -> EGLBoolean;
+        pub fn eglCreatePbufferSurface(
+            dpy: EGLDisplay,
+            config: EGLConfig,
+            attrib_list: *const EGLint,
+        ) -> EGLSurface;
+        pub fn eglDestroySurface(dpy: EGLDisplay, surface: EGLSurface) -> EGLBoolean;
+        pub fn eglMakeCurrent(
+            dpy: EGLDisplay,
+            draw: EGLSurface,
+            read: EGLSurface,
+            ctx: EGLContext,
+        ) -> EGLBoolean;
+        pub fn eglGetProcAddress(procname: *const c_char) -> *mut c_void;
+    }
+}
+
+/// An OpenGL context that is not associated with any window.
+///
+/// There is no usable default framebuffer, so callers must draw into
+/// an `OffscreenFramebuffer` instead.
+pub struct HeadlessContext {
+    #[cfg(target_os = "linux")]
+    display: egl::EGLDisplay,
+    #[cfg(target_os = "linux")]
+    context: egl::EGLContext,
+    #[cfg(target_os = "linux")]
+    surface: egl::EGLSurface,
+}
+
+#[cfg(target_os = "linux")]
+impl HeadlessContext {
+    pub fn new(gl_major: u8, gl_minor: u8, gles: bool) -> Result<Self> {
+        use std::ffi::{CStr, CString};
+        use std::ptr;
+        unsafe {
+            // Prefer the surfaceless platform (no X11/Wayland connection required),
+            // falling back to the default display
+            let client_extensions = {
+                let s = egl::eglQueryString(ptr::null_mut(), egl::EXTENSIONS);
+                if s.is_null() {
+                    String::new()
+                } else {
+                    CStr::from_ptr(s).to_string_lossy().into_owned()
+                }
+            };
+            let mut display = ptr::null_mut();
+            if client_extensions.contains("EGL_MESA_platform_surfaceless") {
+                let name = CString::new("eglGetPlatformDisplayEXT").unwrap();
+                let f = egl::eglGetProcAddress(name.as_ptr());
+                if !f.is_null() {
+                    let get_platform_display: egl::GetPlatformDisplayEXT =
+                        std::mem::transmute(f);
+                    display = get_platform_display(
+                        egl::PLATFORM_SURFACELESS_MESA,
+                        ptr::null_mut(),
+                        ptr::null(),
+                    );
+                }
+            }
+            if display.is_null() {
+                display = egl::eglGetDisplay(ptr::null_mut());
+            }
+            if display.is_null() {
+                return Err(Error::egl("eglGetDisplay returned no display"));
+            }
+            let (mut major, mut minor) = (0, 0);
+            if egl::eglInitialize(display, &mut major, &mut minor) == egl::FALSE {
+                return Err(Error::egl(format!(
+                    "eglInitialize failed with error {:#x}",
+                    egl::eglGetError()
+                )));
+            }
+            info!("EGL VERSION:  {}.{}", major, minor);
+            let query = |name| {
+                let s = egl::eglQueryString(display, name);
+                if s.is_null() {
+                    String::new()
+                } else {
+                    CStr::from_ptr(s).to_string_lossy().into_owned()
+                }
+            };
+            info!("EGL VENDOR:   {}", query(egl::VENDOR));
+            debug!("EGL VERSION STRING: {}", query(egl::VERSION));
+            debug!("EGL EXTENSIONS: {}", query(egl::EXTENSIONS));
+
+            let (api, renderable_type) = if gles {
+                let bit = if gl_major >= 3 {
+                    egl::OPENGL_ES3_BIT
+                } else {
+                    egl::OPENGL_ES2_BIT
+                };
+                (egl::OPENGL_ES_API, bit)
+            } else {
+                (egl::OPENGL_API, egl::OPENGL_BIT)
+            };
+            if egl::eglBindAPI(api) == egl::FALSE {
+                egl::eglTerminate(display);
+                return Err(Error::egl(format!(
+                    "eglBindAPI failed with error {:#x}",
+                    egl::eglGetError()
+                )));
+            }
+            let config_attribs = [
+                egl::SURFACE_TYPE,
+                egl::PBUFFER_BIT,
+                egl::RENDERABLE_TYPE,
+                renderable_type,
+                egl::RED_SIZE,
+                8,
+                egl::GREEN_SIZE,
+                8,
+                egl::BLUE_SIZE,
+                8,
+                egl::ALPHA_SIZE,
+                8,
+                egl::DEPTH_SIZE,
+                24,
+                egl::NONE,
+            ];
+            let mut config = ptr::null_mut();
+            let mut num_config = 0;
+            if egl::eglChooseConfig(
+                display,
+                config_attribs.as_ptr(),
+                &mut config,
+                1,
+                &mut num_config,
+            ) == egl::FALSE
+                || num_config == 0
+            {
+                egl::eglTerminate(display);
+                return Err(Error::egl(format!(
+                    "eglChooseConfig found no matching config (error {:#x})",
+                    egl::eglGetError()
+                )));
+            }
+            let context_attribs = if gles {
+                vec![
+                    egl::CONTEXT_MAJOR_VERSION,
+                    gl_major as egl::EGLint,
+                    egl::CONTEXT_MINOR_VERSION,
+                    gl_minor as egl::EGLint,
+                    egl::NONE,
+                ]
+            } else {
+                vec![
+                    egl::CONTEXT_MAJOR_VERSION,
+                    gl_major as egl::EGLint,
+                    egl::CONTEXT_MINOR_VERSION,
+                    gl_minor as egl::EGLint,
+                    egl::CONTEXT_OPENGL_PROFILE_MASK,
+                    egl::CONTEXT_OPENGL_CORE_PROFILE_BIT,
+                    egl::NONE,
+                ]
+            };
+            let context = egl::eglCreateContext(
+                display,
+                config,
+                ptr::null_mut(),
+                context_attribs.as_ptr(),
+            );
+            if context.is_null() {
+                egl::eglTerminate(display);
+                return Err(Error::egl(format!(
+                    "eglCreateContext failed for GL {}.{} with error {:#x}",
+                    gl_major,
+                    gl_minor,
+                    egl::eglGetError()
+                )));
+            }
+            // EGL_KHR_surfaceless_context lets us make the context current without
+            // a surface. If the driver doesn't support it, fall back to a 1x1 pbuffer.
+            let mut surface = ptr::null_mut();
+            if egl::eglMakeCurrent(display, surface, surface, context) == egl::FALSE {
+                let pbuffer_attribs = [egl::WIDTH, 1, egl::HEIGHT, 1, egl::NONE];
+                surface = egl::eglCreatePbufferSurface(display, config, pbuffer_attribs.as_ptr());
+                if surface.is_null()
+                    || egl::eglMakeCurrent(display, surface, surface, context) == egl::FALSE
+                {
+                    let err = egl::eglGetError();
+                    if !surface.is_null() {
+                        egl::eglDestroySurface(display, surface);
+                    }
+                    egl::eglDestroyContext(display, context);
+                    egl::eglTerminate(display);
+                    return Err(Error::egl(format!(
+                        "eglMakeCurrent failed with error {:#x}",
+                        err
+                    )));
+                }
+            }
+            Ok(HeadlessContext {
+                display,
+                context,
+                surface,
+            })
+        }
+    }
+
+    pub fn get_proc_address(&self, name: &str) -> *const c_void {
+        let name = std::ffi::CString::new(name).unwrap();
+        unsafe { egl::eglGetProcAddress(name.as_ptr()) as *const c_void }
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl Drop for HeadlessContext {
+    fn drop(&mut self) {
+        unsafe {
+            let none = std::ptr::null_mut();
+            egl::eglMakeCurrent(self.display, none, none, none);
+            if !self.surface.is_null() {
+                egl::eglDestroySurface(self.display, self.surface);
+            }
+            egl::eglDestroyContext(self.display, self.context);
+            egl::eglTerminate(self.display);
+        }
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+impl HeadlessContext {
+    pub fn new(_gl_major: u8, _gl_minor: u8, _gles: bool) -> Result<Self> {
+        Err(Error::egl(
+            "headless rendering is only supported on Linux (EGL)",
+        ))
+    }
+
+    pub fn get_proc_address(&self, _name: &str) -> *const c_void {
+        std::ptr::null()
+    }
+}
@@ -0,0 +1,18 @@
+/*
+BEGIN: isf.glsl
+*/
+#define ISF
+#define TIME iTime
+#define TIMEDELTA iTimeDelta
+#define DATE iDate
+#define RENDERSIZE iResolution.xy
+#define FRAMEINDEX int(iFrame)
+#define PASSINDEX iPassIndex
+#define IMG_SIZE(image) vec2(textureSize(image, 0))
+#define IMG_NORM_PIXEL(image, coord) texture(image, coord)
+#define IMG_PIXEL(image, coord) texture(image, (coord) / vec2(textureSize(image, 0)))
+#define IMG_THIS_NORM_PIXEL(image) texture(image, isf_FragNormCoord)
+#define IMG_THIS_PIXEL(image) texture(image, isf_FragNormCoord)
+/*
+END: isf.glsl
+*/
@@ -0,0 +1,699 @@
+use crate::builtin;
+use crate::config::{
+    default_audio_bands, BufferConfig, BufferFormat, BufferFormatConfig, ChannelConfig,
+    ClearConfig, CubemapConfig, EffectConfig, FilterConfig, ImageConfig, MicrophoneConfig,
+    PassConfig, ResourceConfig, UniformFloatConfig, UniformVec2Config, UniformVec4Config,
+    WebCamConfig, WrapConfig,
+};
+use crate::diagnostics::Diagnostic;
+use crate::error::{Error, Result};
+use crate::source_map::{self, ShaderSource};
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Declarations of the ISF fragment shader inputs and outputs
+const FRAGMENT_PRELUDE: &str = "in vec2 isf_FragNormCoord;
+out vec4 isf_FragColor;";
+
+/// Declarations of the ISF vertex shader outputs, and the function ISF vertex shaders call
+/// to draw a fullscreen triangle
+const VERTEX_PRELUDE: &str = "out vec2 isf_FragNormCoord;
+void isf_vertShaderInit() {
+  float x = -1.0 + float((gl_VertexID & 1) << 2);
+  float y = -1.0 + float((gl_VertexID & 2) << 1);
+  gl_Position = vec4(x, y, 0, 1);
+  isf_FragNormCoord = vec2(x, y) * 0.5 + 0.5;
+}";
+
+/// Returns true if the config at `path` is an ISF fragment shader rather than a grim.toml
+pub fn is_isf(path: &Path) -> bool {
+    path.extension().map(|ext| ext == "fs").unwrap_or(false)
+}
+
+/// An Interactive Shader Format project, https://isf.video, translated into a config
+#[derive(Debug, Clone)]
+pub struct Isf {
+    pub config: EffectConfig,
+    /// Problems with the ISF header that don't stop the project from running
+    pub diagnostics: Vec<Diagnostic>,
+    /// The ISF file, which is the fragment shader of every pass
+    fragment: String,
+    /// The `.vs` file next to the ISF file, or the built-in vertex shader
+    vertex: String,
+    /// Generated lines declaring the ISF built-ins and inputs, for each shader
+    fragment_prelude: Vec<String>,
+    vertex_prelude: Vec<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "UPPERCASE", default)]
+struct IsfJson {
+    inputs: Vec<InputJson>,
+    passes: Vec<PassJson>,
+    /// An object of `{"name": {"PATH": ...}}`, or an array of `{"NAME": ..., "PATH": ...}`
+    imported: Value,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+struct InputJson {
+    name: String,
+    #[serde(rename = "TYPE")]
+    kind: String,
+    default: Option<Value>,
+    min: Option<Value>,
+    max: Option<Value>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "UPPERCASE", default)]
+struct PassJson {
+    target: Option<String>,
+    persistent: Value,
+    float: Value,
+    width: Option<Value>,
+    height: Option<Value>,
+}
+
+impl Isf {
+    /// Translate the JSON header of the ISF file at `path` into a config.
+    ///
+    /// Float, bool, long, event, point2D and color inputs become uniform resources,
+    /// image inputs and imported images become image resources, audio inputs become
+    /// the microphone, and each pass becomes a pass drawn into its target buffer.
+    pub fn parse(path: &Path, source: &str) -> Result<Isf> {
+        let (header, header_line) = json_header(source).ok_or_else(|| {
+            Error::isf(format!(
+                "{} does not start with the /*{{ ... }}*/ JSON header of an ISF file",
+                path.display()
+            ))
+        })?;
+        let json: IsfJson = serde_json::from_str(header).map_err(|err| {
+            Error::isf(format!(
+                "Error parsing the JSON header of {} at line {} column {}: {}",
+                path.display(),
+                header_line + err.line() - 1,
+                err.column(),
+                err
+            ))
+        })?;
+        let dir = path.parent().unwrap_or_else(|| Path::new("."));
+        let file_name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let vertex_name = Path::new(&file_name).with_extension("vs");
+        let vertex = if dir.join(&vertex_name).is_file() {
+            format!("./{}", vertex_name.display())
+        } else {
+            builtin::ISF_VERTEX.to_string()
+        };
+        let mut isf = Isf {
+            config: EffectConfig::default(),
+            diagnostics: Vec::new(),
+            fragment: format!("./{}", file_name),
+            vertex,
+            fragment_prelude: vec![
+                include_str!("./isf.glsl").to_string(),
+                FRAGMENT_PRELUDE.to_string(),
+            ],
+            vertex_prelude: vec![
+                include_str!("./isf.glsl").to_string(),
+                VERTEX_PRELUDE.to_string(),
+            ],
+        };
+        let mut samplers = Vec::new();
+        for input in &json.inputs {
+            if let Some(sampler) = isf.input(dir, input) {
+                samplers.push(sampler);
+            }
+        }
+        samplers.extend(isf.imported(&json.imported));
+        let targets = json
+            .passes
+            .iter()
+            .filter_map(|pass| pass.target.clone())
+            .collect::<Vec<_>>();
+        samplers.extend(targets.iter().cloned());
+        let passes = if json.passes.is_empty() {
+            vec![PassJson::default()]
+        } else {
+            json.passes
+        };
+        for pass in &passes {
+            isf.pass(pass, &samplers);
+        }
+        Ok(isf)
+    }
+
+    fn warn(&mut self, message: String) {
+        self.diagnostics.push(Diagnostic::warning(message));
+    }
+
+    /// Add the resource of an input, and returns its name if it is sampled
+    fn input(&mut self, dir: &Path, input: &InputJson) -> Option<String> {
+        let name = input.name.clone();
+        let number = |value: &Option<Value>, or: f32| {
+            value
+                .as_ref()
+                .and_then(|value| match value {
+                    Value::Bool(b) => Some(if *b { 1.0 } else { 0.0 }),
+                    value => value.as_f64().map(|n| n as f32),
+                })
+                .unwrap_or(or)
+        };
+        let array = |value: &Option<Value>, or: &[f32]| {
+            let mut array = or.to_vec();
+            if let Some(Value::Array(values)) = value {
+                for (element, value) in array.iter_mut().zip(values) {
+                    *element = value.as_f64().map(|n| n as f32).unwrap_or(*element);
+                }
+            }
+            array
+        };
+        let resource = match input.kind.as_str() {
+            "float" => ResourceConfig::UniformFloat(UniformFloatConfig {
+                uniform: number(&input.default, 0.0),
+                min: number(&input.min, 0.0),
+                max: number(&input.max, 1.0),
+            }),
+            // grimoire uniforms are floats, redefine the name as the type ISF declares
+            "bool" | "event" => {
+                self.define(format!("#define {0} bool({0} > 0.5)", name));
+                ResourceConfig::UniformFloat(UniformFloatConfig {
+                    uniform: number(&input.default, 0.0),
+                    min: 0.0,
+                    max: 1.0,
+                })
+            }
+            "long" => {
+                self.define(format!("#define {0} int({0})", name));
+                ResourceConfig::UniformFloat(UniformFloatConfig {
+                    uniform: number(&input.default, 0.0),
+                    min: number(&input.min, 0.0),
+                    max: number(&input.max, 1.0),
+                })
+            }
+            "point2D" => {
+                let uniform = array(&input.default, &[0.0, 0.0]);
+                let min = array(&input.min, &[0.0, 0.0]);
+                let max = array(&input.max, &[1.0, 1.0]);
+                ResourceConfig::UniformVec2(UniformVec2Config {
+                    uniform: [uniform[0], uniform[1]],
+                    min: [min[0], min[1]],
+                    max: [max[0], max[1]],
+                })
+            }
+            "color" => {
+                let uniform = array(&input.default, &[0.0, 0.0, 0.0, 1.0]);
+                let min = array(&input.min, &[0.0; 4]);
+                let max = array(&input.max, &[1.0; 4]);
+                ResourceConfig::UniformVec4(UniformVec4Config {
+                    uniform: [uniform[0], uniform[1], uniform[2], uniform[3]],
+                    min: [min[0], min[1], min[2], min[3]],
+                    max: [max[0], max[1], max[2], max[3]],
+                })
+            }
+            // Hosts provide image inputs, look for an image named after the input
+            "image" => {
+                let image = ["png", "jpg", "jpeg"]
+                    .iter()
+                    .map(|ext| format!("{}.{}", name, ext))
+                    .find(|file| dir.join(file).is_file());
+                let resource = match image {
+                    Some(image) => ResourceConfig::Image(ImageConfig {
+                        image: format!("./{}", image),
+                        flipv: true,
+                        fliph: false,
+                    }),
+                    None => {
+                        self.warn(format!(
+                            "No {0}.png or {0}.jpg found next to the ISF file for the image \
+                             input \"{0}\", using the webcam",
+                            name
+                        ));
+                        ResourceConfig::WebCam(WebCamConfig { webcam: true })
+                    }
+                };
+                self.config.resources.insert(name.clone(), resource);
+                return Some(name);
+            }
+            "audio" | "audioFFT" => {
+                self.config.resources.insert(
+                    name.clone(),
+                    ResourceConfig::Microphone(MicrophoneConfig {
+                        microphone: true,
+                        bands: default_audio_bands(),
+                    }),
+                );
+                return Some(name);
+            }
+            kind => {
+                self.warn(format!(
+                    "Skipped the input \"{}\" of unknown type \"{}\"",
+                    name, kind
+                ));
+                return None;
+            }
+        };
+        self.config.resources.insert(name, resource);
+        None
+    }
+
+    /// Add the resources of the IMPORTED images, and returns their names
+    fn imported(&mut self, imported: &Value) -> Vec<String> {
+        let entries: Vec<(String, &Value)> = match imported {
+            Value::Object(map) => map.iter().map(|(name, v)| (name.clone(), v)).collect(),
+            Value::Array(array) => array
+                .iter()
+                .filter_map(|v| Some((v.get("NAME")?.as_str()?.to_string(), v)))
+                .collect(),
+            _ => Vec::new(),
+        };
+        let mut names = Vec::new();
+        for (name, entry) in entries {
+            let resource = match entry.get("PATH") {
+                Some(Value::String(path)) => ResourceConfig::Image(ImageConfig {
+                    image: format!("./{}", path),
+                    flipv: true,
+                    fliph: false,
+                }),
+                // Cubemap faces in +X, -X, +Y, -Y, +Z, -Z order
+                Some(Value::Array(faces)) if faces.len() == 6 => {
+                    let face =
+                        |index: usize| format!("./{}", faces[index].as_str().unwrap_or_default());
+                    ResourceConfig::Cubemap(CubemapConfig {
+                        right: face(0),
+                        left: face(1),
+                        top: face(2),
+                        bottom: face(3),
+                        front: face(4),
+                        back: face(5),
+                        flipv: true,
+                        fliph: false,
+                    })
+                }
+                _ => {
+                    self.warn(format!(
+                        "Skipped the imported image \"{}\", its PATH is neither a file nor six cubemap faces",
+                        name
+                    ));
+                    continue;
+                }
+            };
+            self.config.resources.insert(name.clone(), resource);
+            names.push(name);
+        }
+        names
+    }
+
+    /// Add a pass that samples every image, imported image and target buffer
+    fn pass(&mut self, pass: &PassJson, samplers: &[String]) {
+        let uniform_to_channel = samplers
+            .iter()
+            .map(|name| {
+                let channel = ChannelConfig::Complete {
+                    resource: name.clone(),
+                    attachment: 0,
+                    wrap: WrapConfig::Clamp,
+                    filter: FilterConfig::Linear,
+                };
+                (name.clone(), channel)
+            })
+            .collect::<BTreeMap<_, _>>();
+        let persistent = is_true(&pass.persistent);
+        if let Some(target) = &pass.target {
+            let (width, height, scale) = self.buffer_size(target, pass);
+            let format = if is_true(&pass.float) {
+                BufferFormat::F32
+            } else {
+                BufferFormat::U8
+            };
+            self.config.resources.insert(
+                target.clone(),
+                ResourceConfig::Buffer(BufferConfig {
+                    buffer: BufferFormatConfig::Simple(format),
+                    width,
+                    height,
+                    scale,
+                    ..Default::default()
+                }),
+            );
+        }
+        self.config.passes.push(PassConfig {
+            draw: Default::default(),
+            vertex: self.vertex.clone(),
+            fragment: self.fragment.clone(),
+            geometry: None,
+            compute: None,
+            workgroups: None,
+            capture: None,
+            uniform_to_channel,
+            buffer: pass.target.clone(),
+            // Only persistent buffers keep their contents from one frame to the next
+            clear: match (&pass.target, persistent) {
+                (Some(_), false) => Some(ClearConfig::Color([0.0, 0.0, 0.0, 0.0])),
+                _ => None,
+            },
+            blend: None,
+            depth: None,
+            disable: false,
+            loop_count: 1,
+        });
+    }
+
+    /// The width, height and scale of a target buffer. Understands sizes in pixels and
+    /// `$WIDTH` and `$HEIGHT` multiplied or divided by a number.
+    fn buffer_size(
+        &mut self,
+        target: &str,
+        pass: &PassJson,
+    ) -> (Option<u32>, Option<u32>, Option<f32>) {
+        lazy_static! {
+            static ref SCALED: Regex =
+                Regex::new(r"^(floor\()?\$(WIDTH|HEIGHT)(\s*([*/])\s*([0-9.]+))?\)?$").unwrap();
+        }
+        enum Size {
+            Pixels(u32),
+            Scale(f32),
+        }
+        let parse = |value: &Option<Value>| -> Option<Option<Size>> {
+            let value = match value {
+                Some(value) => value,
+                None => return Some(None),
+            };
+            if let Some(pixels) = value.as_f64() {
+                return Some(Some(Size::Pixels(pixels as u32)));
+            }
+            let text = value.as_str()?.trim();
+            if let Ok(pixels) = text.parse::<f64>() {
+                return Some(Some(Size::Pixels(pixels as u32)));
+            }
+            let captures = SCALED.captures(text)?;
+            let factor = match captures.get(5) {
+                Some(factor) => factor.as_str().parse::<f32>().ok()?,
+                None => 1.0,
+            };
+            match captures.get(4).map(|op| op.as_str()) {
+                Some("/") => Some(Some(Size::Scale(1.0 / factor))),
+                _ => Some(Some(Size::Scale(factor))),
+            }
+        };
+        match (parse(&pass.width), parse(&pass.height)) {
+            (Some(None), Some(None)) => (None, None, Some(1.0)),
+            (Some(Some(Size::Pixels(width))), Some(Some(Size::Pixels(height)))) => {
+                (Some(width), Some(height), None)
+            }
+            (Some(Some(Size::Scale(scale))), Some(None))
+            | (Some(None), Some(Some(Size::Scale(scale)))) => (None, None, Some(scale)),
+            (Some(Some(Size::Scale(w))), Some(Some(Size::Scale(h)))) if w == h => {
+                (None, None, Some(w))
+            }
+            _ => {
+                self.warn(format!(
+                    "The WIDTH and HEIGHT of the target \"{}\" can't be expressed as a size \
+                     in pixels or a single scale of the render size, using the render size",
+                    target
+                ));
+                (None, None, Some(1.0))
+            }
+        }
+    }
+
+    /// Add a line to the prelude of every shader
+    fn define(&mut self, line: String) {
+        self.fragment_prelude.push(line.clone());
+        self.vertex_prelude.push(line);
+    }
+
+    /// Adapt an ISF shader to the GLSL grimoire compiles: declare the ISF built-ins and
+    /// inputs ahead of it, and replace the GLSL 1.2 names ISF shaders use. Shaders that
+    /// don't belong to this ISF project are returned as is.
+    pub fn translate(&self, path: &str, shader: ShaderSource) -> ShaderSource {
+        lazy_static! {
+            static ref FRAGMENT: [(Regex, &'static str); 3] = [
+                (Regex::new(r"\bgl_FragColor\b").unwrap(), "isf_FragColor"),
+                (Regex::new(r"\btexture2D(Rect)?\b").unwrap(), "texture"),
+                (Regex::new(r"\bvarying\b").unwrap(), "in"),
+            ];
+            static ref VERTEX: [(Regex, &'static str); 2] = [
+                (Regex::new(r"\btexture2D(Rect)?\b").unwrap(), "texture"),
+                (Regex::new(r"\bvarying\b").unwrap(), "out"),
+            ];
+        }
+        let (prelude, replacements) = if path == self.fragment {
+            (&self.fragment_prelude, &FRAGMENT[..])
+        } else if path == self.vertex {
+            (&self.vertex_prelude, &VERTEX[..])
+        } else {
+            return shader;
+        };
+        let mut translated = shader;
+        for (regex, replacement) in replacements {
+            translated.source = regex
+                .replace_all(&translated.source, *replacement)
+                .into_owned();
+        }
+        let (source, map) = source_map::with_header(prelude, &translated);
+        ShaderSource { source, map }
+    }
+}
+
+/// The JSON of the `/*{ ... }*/` comment an ISF file starts with, and the 1-based line
+/// it starts on
+fn json_header(source: &str) -> Option<(&str, usize)> {
+    let start = source.find("/*")?;
+    if !source[..start].trim().is_empty() {
+        return None;
+    }
+    let end = start + source[start..].find("*/")?;
+    let header = &source[start + 2..end];
+    if !header.trim_start().starts_with('{') {
+        return None;
+    }
+    Some((header, source[..start].matches('\n').count() + 1))
+}
+
+/// ISF headers write booleans as true, 1 or "true"
+fn is_true(value: &Value) -> bool {
+    match value {
+        Value::Bool(b) => *b,
+        Value::Number(n) => n.as_f64().map(|n| n != 0.0).unwrap_or(false),
+        Value::String(s) => s == "true" || s == "1",
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Parse an ISF file with the `header` JSON, in a directory without images
+    fn parse(header: &str) -> Isf {
+        let source = format!("/*{}*/\nvoid main() {{}}\n", header);
+        Isf::parse(Path::new("/nonexistent/effect.fs"), &source).unwrap()
+    }
+
+    fn buffer(target: &str, isf: &Isf) -> BufferConfig {
+        match &isf.config.resources[target] {
+            ResourceConfig::Buffer(buffer) => buffer.clone(),
+            resource => panic!("{} is {:?}", target, resource),
+        }
+    }
+
+    fn shader(path: &str, source: &str) -> ShaderSource {
+        source_map::expand_includes(path, source, &BTreeMap::new()).unwrap()
+    }
+
+    #[test]
+    fn parse_inputs() {
+        let isf = parse(
+            r#"{
+  "INPUTS": [
+    {"NAME": "speed", "TYPE": "float", "DEFAULT": 0.5, "MIN": 0.0, "MAX": 2.0},
+    {"NAME": "invert", "TYPE": "bool", "DEFAULT": true},
+    {"NAME": "center", "TYPE": "point2D", "DEFAULT": [0.25, 0.75]},
+    {"NAME": "tint", "TYPE": "color", "DEFAULT": [1.0, 0.5, 0.0, 1.0]},
+    {"NAME": "inputImage", "TYPE": "image"},
+    {"NAME": "what", "TYPE": "text"}
+  ]
+}"#,
+        );
+        let resources = &isf.config.resources;
+        assert_eq!(
+            resources["speed"],
+            ResourceConfig::UniformFloat(UniformFloatConfig {
+                uniform: 0.5,
+                min: 0.0,
+                max: 2.0,
+            })
+        );
+        assert_eq!(
+            resources["invert"],
+            ResourceConfig::UniformFloat(UniformFloatConfig {
+                uniform: 1.0,
+                min: 0.0,
+                max: 1.0,
+            })
+        );
+        assert_eq!(
+            resources["center"],
+            ResourceConfig::UniformVec2(UniformVec2Config {
+                uniform: [0.25, 0.75],
+                min: [0.0, 0.0],
+                max: [1.0, 1.0],
+            })
+        );
+        assert_eq!(
+            resources["tint"],
+            ResourceConfig::UniformVec4(UniformVec4Config {
+                uniform: [1.0, 0.5, 0.0, 1.0],
+                min: [0.0; 4],
+                max: [1.0; 4],
+            })
+        );
+        // Without an image named after it, the image input is the webcam
+        assert_eq!(
+            resources["inputImage"],
+            ResourceConfig::WebCam(WebCamConfig { webcam: true })
+        );
+        assert!(!resources.contains_key("what"));
+        assert_eq!(isf.diagnostics.len(), 2);
+        assert!(isf
+            .fragment_prelude
+            .contains(&"#define invert bool(invert > 0.5)".to_string()));
+
+        // Without PASSES, a single pass draws to the window
+        assert_eq!(isf.config.passes.len(), 1);
+        let pass = &isf.config.passes[0];
+        assert_eq!(pass.fragment, "./effect.fs");
+        assert_eq!(pass.vertex, builtin::ISF_VERTEX);
+        assert_eq!(pass.buffer, None);
+        let channels: Vec<&str> = pass.uniform_to_channel.keys().map(String::as_str).collect();
+        assert_eq!(channels, vec!["inputImage"]);
+    }
+
+    #[test]
+    fn parse_passes() {
+        let isf = parse(
+            r#"{
+  "PASSES": [
+    {"TARGET": "history", "PERSISTENT": true, "FLOAT": true},
+    {"TARGET": "half", "WIDTH": "floor($WIDTH/2.0)", "HEIGHT": "floor($HEIGHT/2.0)"},
+    {}
+  ]
+}"#,
+        );
+        assert!(isf.diagnostics.is_empty());
+        assert_eq!(
+            buffer("history", &isf),
+            BufferConfig {
+                buffer: BufferFormatConfig::Simple(BufferFormat::F32),
+                scale: Some(1.0),
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            buffer("half", &isf),
+            BufferConfig {
+                buffer: BufferFormatConfig::Simple(BufferFormat::U8),
+                scale: Some(0.5),
+                ..Default::default()
+            }
+        );
+        let passes = &isf.config.passes;
+        assert_eq!(passes.len(), 3);
+        assert_eq!(passes[0].buffer, Some("history".to_string()));
+        assert_eq!(passes[1].buffer, Some("half".to_string()));
+        assert_eq!(passes[2].buffer, None);
+        // Persistent buffers keep their contents, and the others are cleared each frame
+        assert_eq!(passes[0].clear, None);
+        assert_eq!(passes[1].clear, Some(ClearConfig::Color([0.0; 4])));
+        assert_eq!(passes[2].clear, None);
+        // Every pass samples every target
+        for pass in passes {
+            let channels: Vec<&str> = pass.uniform_to_channel.keys().map(String::as_str).collect();
+            assert_eq!(channels, vec!["half", "history"]);
+        }
+    }
+
+    #[test]
+    fn parse_buffer_sizes() {
+        let isf = parse(
+            r#"{
+  "PASSES": [
+    {"TARGET": "pixels", "WIDTH": 256, "HEIGHT": "128"},
+    {"TARGET": "double", "WIDTH": "$WIDTH * 2"},
+    {"TARGET": "stretched", "WIDTH": "$WIDTH/2", "HEIGHT": "$HEIGHT/4"}
+  ]
+}"#,
+        );
+        let pixels = buffer("pixels", &isf);
+        assert_eq!(
+            (pixels.width, pixels.height, pixels.scale),
+            (Some(256), Some(128), None)
+        );
+        let double = buffer("double", &isf);
+        assert_eq!(
+            (double.width, double.height, double.scale),
+            (None, None, Some(2.0))
+        );
+        // Different scales for the width and height fall back to the render size
+        let stretched = buffer("stretched", &isf);
+        assert_eq!(
+            (stretched.width, stretched.height, stretched.scale),
+            (None, None, Some(1.0))
+        );
+        assert_eq!(isf.diagnostics.len(), 1);
+        assert!(isf.diagnostics[0].message.contains("\"stretched\""));
+    }
+
+    #[test]
+    fn parse_header_errors() {
+        let path = Path::new("effect.fs");
+        assert!(Isf::parse(path, "void main() {}\n").is_err());
+        let err = Isf::parse(path, "\n/*{\n  \"INPUTS\": [,]\n}*/\n").unwrap_err();
+        assert!(err.to_string().contains("at line 3"), "{}", err);
+    }
+
+    #[test]
+    fn translate_fragment() {
+        let isf = parse(r#"{"INPUTS": [{"NAME": "invert", "TYPE": "bool"}]}"#);
+        let source = "varying vec2 uv;\n\
+                      void main() {\n\
+                      \x20 gl_FragColor = texture2D(image, uv) + texture2DRect(image, uv);\n\
+                      \x20 my_gl_FragColor = vec4(0.0);\n\
+                      }\n";
+        let translated = isf.translate("./effect.fs", shader("./effect.fs", source));
+        let prelude = isf.fragment_prelude.join("\n");
+        assert!(translated.source.starts_with(&prelude));
+        assert!(translated.source.ends_with(
+            "in vec2 uv;\n\
+             void main() {\n\
+             \x20 isf_FragColor = texture(image, uv) + texture(image, uv);\n\
+             \x20 my_gl_FragColor = vec4(0.0);\n\
+             }\n"
+        ));
+        // Lines after the prelude map back to the ISF file
+        let line = translated.source.lines().count() - 1;
+        assert_eq!(translated.map.lookup(line), Some(("./effect.fs", 4)));
+    }
+
+    #[test]
+    fn translate_vertex_and_other_shaders() {
+        let isf = parse("{}");
+        let source = "varying vec2 uv;\nvoid main() { uv = texture2D(image, vec2(0.0)).xy; }\n";
+        let translated = isf.translate(builtin::ISF_VERTEX, shader(builtin::ISF_VERTEX, source));
+        assert!(translated
+            .source
+            .starts_with(&isf.vertex_prelude.join("\n")));
+        assert!(translated
+            .source
+            .ends_with("out vec2 uv;\nvoid main() { uv = texture(image, vec2(0.0)).xy; }\n"));
+        let other = isf.translate("./other.glsl", shader("./other.glsl", source));
+        assert_eq!(other.source, source);
+    }
+}
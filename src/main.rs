@@ -4,6 +4,7 @@ extern crate clap;
 #[macro_use]
 extern crate failure;
 extern crate gleam;
+extern crate gltf;
 #[macro_use]
 extern crate gstreamer as gst;
 extern crate gstreamer_app as gst_app;
@@ -18,37 +19,66 @@ extern crate notify;
 extern crate sdl2;
 #[macro_use]
 extern crate serde_derive;
-extern crate glsl_include;
 extern crate lazy_static;
+extern crate serde_json;
 extern crate tobj;
 extern crate toml;
 extern crate walkdir;
 
 mod audio;
+mod builtin;
+mod capture;
 mod config;
+mod diagnostics;
 mod effect;
 mod effect_player;
+mod encoder;
 mod error;
 mod file_stream;
 mod gl;
+mod golden;
+mod headless;
+mod isf;
 mod keyboard;
+mod model;
 mod mouse;
+mod offscreen;
+mod overlay;
 mod platform;
+mod readback;
+mod record;
+mod render;
 mod resource;
+mod shadertoy;
+mod sound;
+mod source_map;
 mod stream;
+mod text;
+mod vertexshaderart;
 mod video;
 
+use crate::diagnostics::{Diagnostic, JsonLines};
 use crate::effect_player::EffectPlayer;
 use crate::error::Error;
 use crate::file_stream::FileStream;
+use crate::golden::TestSettings;
+use crate::headless::HeadlessContext;
+use crate::offscreen::OffscreenFramebuffer;
+use crate::overlay::Overlay;
 use crate::platform::Platform;
-use clap::{App, Arg};
-use glsl_include::Context as GlslIncludeContex;
+use crate::readback::PixelReadback;
+use crate::record::{RecordFormat, Recorder};
+use crate::render::RenderSettings;
+use crate::shadertoy::{ExportSettings, ImportSettings};
+use crate::sound::SoundSettings;
+use clap::{App, Arg, ArgMatches, SubCommand};
 use sdl2::event::Event;
 use sdl2::keyboard::Keycode;
 use sdl2::video::GLProfile;
+use sdl2::EventPump;
 use std::collections::BTreeMap;
 use std::env;
+use std::path::{Path, PathBuf};
 use std::process;
 use std::result;
 use std::time::{Duration, Instant};
@@ -57,12 +87,6 @@ use walkdir::{DirEntry, WalkDir};
 /// Our type alias for handling errors throughout grimoire
 type Result<T> = result::Result<T, failure::Error>;
 
-struct RecordData {
-    width: u32,
-    height: u32,
-    data: Vec<u8>,
-}
-
 fn main() {
     if let Err(err) = try_main() {
         // Print the error, including all of its underlying causes.
@@ -90,7 +114,7 @@ fn try_main() -> Result<()> {
         .about("https://github.com/jshrake/grimoire")
         .arg(
             Arg::with_name("config")
-                .help("path to the toml configuration file, or directory containing grim.toml")
+                .help("path to the toml configuration file or ISF .fs file, or directory containing grim.toml")
                 .required(false)
                 .index(1),
         )
@@ -118,7 +142,8 @@ fn try_main() -> Result<()> {
                     "330", "400", "410", "420", "430", "440", "450", "460", "es2", "es3",
                 ])
                 .default_value("410")
-                .long("gl"),
+                .long("gl")
+                .global(true),
         )
         .arg(
             Arg::with_name("fps")
@@ -132,24 +157,328 @@ fn try_main() -> Result<()> {
                 .help("record snapshots of the framebuffer")
                 .long("record"),
         )
+        .arg(
+            Arg::with_name("record-format")
+                .help("write recorded frames as numbered images or encode them into a single video file")
+                .takes_value(true)
+                .possible_values(RECORD_FORMATS)
+                .default_value("png")
+                .long("record-format"),
+        )
+        .arg(
+            Arg::with_name("record-buffer")
+                .help("record the named buffer instead of the window")
+                .takes_value(true)
+                .long("record-buffer"),
+        )
+        .arg(
+            Arg::with_name("record-attachment")
+                .help("color attachment index of the recorded buffer")
+                .takes_value(true)
+                .default_value("0")
+                .long("record-attachment"),
+        )
+        .arg(
+            Arg::with_name("capture-frame")
+                .help("capture every buffer attachment of the given frame, like pressing F5")
+                .takes_value(true)
+                .long("capture-frame"),
+        )
+        .arg(
+            Arg::with_name("diagnostics-format")
+                .help("report config and shader errors as human readable log messages, or as JSON lines for editors")
+                .takes_value(true)
+                .possible_values(&["human", "json"])
+                .default_value("human")
+                .long("diagnostics-format")
+                .global(true),
+        )
+        .arg(
+            Arg::with_name("diagnostics-file")
+                .help("write JSON diagnostics to this file instead of stderr")
+                .takes_value(true)
+                .long("diagnostics-file")
+                .global(true),
+        )
+        .arg(
+            Arg::with_name("headless")
+                .help("render into an offscreen framebuffer using an EGL context, no window or display required")
+                .long("headless")
+                .global(true),
+        )
+        .arg(
+            Arg::with_name("frames")
+                .help("exit after drawing this many frames, 0 runs until closed")
+                .takes_value(true)
+                .default_value("0")
+                .long("frames"),
+        )
+        .subcommand(
+            SubCommand::with_name("render")
+                .about("deterministically render a range of frames to disk, independent of wall-clock time and vsync")
+                .arg(
+                    Arg::with_name("config")
+                        .help("path to the toml configuration file or ISF .fs file, or directory containing grim.toml")
+                        .required(false)
+                        .index(1),
+                )
+                .arg(
+                    Arg::with_name("output")
+                        .help("directory to write the rendered frames into, or the video file to encode")
+                        .takes_value(true)
+                        .default_value("./render")
+                        .long("output"),
+                )
+                .arg(
+                    Arg::with_name("format")
+                        .help("write numbered images or encode a single video file")
+                        .takes_value(true)
+                        .possible_values(RECORD_FORMATS)
+                        .default_value("png")
+                        .long("format"),
+                )
+                .arg(
+                    Arg::with_name("buffer")
+                        .help("render the named buffer instead of the window")
+                        .takes_value(true)
+                        .long("buffer"),
+                )
+                .arg(
+                    Arg::with_name("attachment")
+                        .help("color attachment index of the rendered buffer")
+                        .takes_value(true)
+                        .default_value("0")
+                        .long("attachment"),
+                )
+                .arg(
+                    Arg::with_name("start")
+                        .help("start time in seconds")
+                        .takes_value(true)
+                        .default_value("0")
+                        .long("start"),
+                )
+                .arg(
+                    Arg::with_name("end")
+                        .help("end time in seconds (exclusive)")
+                        .takes_value(true)
+                        .long("end")
+                        .conflicts_with("frames"),
+                )
+                .arg(
+                    Arg::with_name("frames")
+                        .help("number of frames to render")
+                        .takes_value(true)
+                        .long("frames"),
+                )
+                .arg(
+                    Arg::with_name("fps")
+                        .help("frames per second, determines iTime and iTimeDelta")
+                        .takes_value(true)
+                        .default_value("60")
+                        .long("fps"),
+                )
+                .arg(
+                    Arg::with_name("width")
+                        .help("output pixel width")
+                        .takes_value(true)
+                        .default_value("768")
+                        .long("width"),
+                )
+                .arg(
+                    Arg::with_name("height")
+                        .help("output pixel height")
+                        .takes_value(true)
+                        .default_value("432")
+                        .long("height"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("render-sound")
+                .about("render the sound resource of the project into a WAV file, independent of wall-clock time")
+                .arg(
+                    Arg::with_name("config")
+                        .help("path to the toml configuration file or ISF .fs file, or directory containing grim.toml")
+                        .required(false)
+                        .index(1),
+                )
+                .arg(
+                    Arg::with_name("output")
+                        .help("the WAV file to write")
+                        .takes_value(true)
+                        .default_value("./sound.wav")
+                        .long("output"),
+                )
+                .arg(
+                    Arg::with_name("duration")
+                        .help("length of the sound in seconds")
+                        .takes_value(true)
+                        .default_value("10")
+                        .long("duration"),
+                )
+                .arg(
+                    Arg::with_name("sound")
+                        .help("render the named sound resource, required when the project has several")
+                        .takes_value(true)
+                        .long("sound"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("check")
+                .about("validate the configuration and check that the files it references exist, without opening a window")
+                .arg(
+                    Arg::with_name("config")
+                        .help("path to the toml configuration file or ISF .fs file, or directory containing grim.toml")
+                        .required(false)
+                        .index(1),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("test")
+                .about("render frames with deterministic time and input, and compare them against reference images")
+                .arg(
+                    Arg::with_name("config")
+                        .help("path to the toml configuration file or ISF .fs file, or directory containing grim.toml")
+                        .required(false)
+                        .index(1),
+                )
+                .arg(
+                    Arg::with_name("frame")
+                        .help("comma separated list of frames to compare")
+                        .takes_value(true)
+                        .use_delimiter(true)
+                        .multiple(true)
+                        .default_value("0")
+                        .long("frame"),
+                )
+                .arg(
+                    Arg::with_name("references")
+                        .help("directory of reference images, defaults to golden/ next to the config")
+                        .takes_value(true)
+                        .long("references"),
+                )
+                .arg(
+                    Arg::with_name("output")
+                        .help("directory to write the images of failing frames into, defaults to golden-diff/ next to the config")
+                        .takes_value(true)
+                        .long("output"),
+                )
+                .arg(
+                    Arg::with_name("tolerance")
+                        .help("largest difference allowed in any channel of a pixel, from 0 to 255")
+                        .takes_value(true)
+                        .default_value("2")
+                        .long("tolerance"),
+                )
+                .arg(
+                    Arg::with_name("update")
+                        .help("save the rendered frames as the new reference images")
+                        .long("update"),
+                )
+                .arg(
+                    Arg::with_name("buffer")
+                        .help("compare the named buffer instead of the window")
+                        .takes_value(true)
+                        .long("buffer"),
+                )
+                .arg(
+                    Arg::with_name("attachment")
+                        .help("color attachment index of the compared buffer")
+                        .takes_value(true)
+                        .default_value("0")
+                        .long("attachment"),
+                )
+                .arg(
+                    Arg::with_name("fps")
+                        .help("frames per second, determines iTime and iTimeDelta")
+                        .takes_value(true)
+                        .default_value("60")
+                        .long("fps"),
+                )
+                .arg(
+                    Arg::with_name("width")
+                        .help("output pixel width")
+                        .takes_value(true)
+                        .default_value("768")
+                        .long("width"),
+                )
+                .arg(
+                    Arg::with_name("height")
+                        .help("output pixel height")
+                        .takes_value(true)
+                        .default_value("432")
+                        .long("height"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("import-shadertoy")
+                .about("create a project from a Shadertoy API JSON export")
+                .arg(
+                    Arg::with_name("json")
+                        .help("path to the JSON export of a single shader")
+                        .required(true)
+                        .index(1),
+                )
+                .arg(
+                    Arg::with_name("output")
+                        .help("project directory to create, defaults to the path of the JSON file without its extension")
+                        .takes_value(true)
+                        .long("output"),
+                )
+                .arg(
+                    Arg::with_name("media")
+                        .help("directory of downloaded Shadertoy media files to copy into the project")
+                        .takes_value(true)
+                        .long("media"),
+                )
+                .arg(
+                    Arg::with_name("force")
+                        .help("overwrite the files of an existing project")
+                        .long("force"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("export-shadertoy")
+                .about("convert a project into Shadertoy API JSON, warning about what Shadertoy cannot express")
+                .arg(
+                    Arg::with_name("config")
+                        .help("path to the toml configuration file, or directory containing grim.toml")
+                        .required(false)
+                        .index(1),
+                )
+                .arg(
+                    Arg::with_name("output")
+                        .help("JSON file to write, defaults to shadertoy.json next to the config")
+                        .takes_value(true)
+                        .long("output"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("import-vertexshaderart")
+                .about("create a project from the JSON of a vertexshaderart art page")
+                .arg(
+                    Arg::with_name("json")
+                        .help("path to the JSON of a single art page")
+                        .required(true)
+                        .index(1),
+                )
+                .arg(
+                    Arg::with_name("output")
+                        .help("project directory to create, defaults to the path of the JSON file without its extension")
+                        .takes_value(true)
+                        .long("output"),
+                )
+                .arg(
+                    Arg::with_name("force")
+                        .help("overwrite the files of an existing project")
+                        .long("force"),
+                ),
+        )
         .get_matches();
-    let width_str = matches.value_of("width").unwrap();
-    let height_str = matches.value_of("height").unwrap();
-    let config_path_str = matches.value_of("config").unwrap_or("./grim.toml");
-    let target_fps_str = matches.value_of("fps").unwrap();
-    let gl_str = matches.value_of("gl").unwrap();
-    let width = width_str
-        .parse::<u32>()
-        .expect("Expected width command-line argument to be u32");
-    let height = height_str
-        .parse::<u32>()
-        .expect("Expected height command-line argument to be u32");
-    // TODO(jshrake): should this also control the iTimeDelta uniform value?
-    let target_fps = target_fps_str
-        .parse::<u32>()
-        .expect("Expected fps command-line argument to be u32");
-    let record = matches.is_present("record");
-    let (gl_major, gl_minor, gl_profile, glsl_version) = match gl_str {
+    // Global arguments can be specified before or after the subcommand
+    let global_matches = matches.subcommand().1.unwrap_or(&matches);
+    let gl_str = global_matches.value_of("gl").unwrap();
+    let headless = global_matches.is_present("headless");
+    let gl_settings = match gl_str {
         "330" => (3, 3, GLProfile::Core, "#version 330"),
         "400" => (4, 0, GLProfile::Core, "#version 400"),
         "410" => (4, 1, GLProfile::Core, "#version 410"),
@@ -163,6 +492,30 @@ fn try_main() -> Result<()> {
         _ => unreachable!(),
     };
 
+    let mut json_diagnostics = match global_matches.value_of("diagnostics-format") {
+        Some("json") => match global_matches.value_of("diagnostics-file") {
+            Some(path) => Some(JsonLines::create(Path::new(path))?),
+            None => Some(JsonLines::stderr()),
+        },
+        _ => None,
+    };
+    let json_diagnostics = &mut json_diagnostics;
+
+    // Checking the config and importing need neither GL nor gstreamer
+    match matches.subcommand() {
+        ("check", Some(check_matches)) => return try_check(check_matches, json_diagnostics),
+        ("import-shadertoy", Some(import_matches)) => {
+            return try_import_shadertoy(import_matches, json_diagnostics)
+        }
+        ("export-shadertoy", Some(export_matches)) => {
+            return try_export_shadertoy(export_matches, json_diagnostics)
+        }
+        ("import-vertexshaderart", Some(import_matches)) => {
+            return try_import_vertexshaderart(import_matches, json_diagnostics)
+        }
+        _ => {}
+    }
+
     // Call gst::init BEFORE changing the cwd
     // On windows 10, this reduces gst::init from ~7 seconds to ~50 ms
     // TODO(jshrake): Why? Is there an issue with how we see the cwd on windows?
@@ -171,203 +524,177 @@ fn try_main() -> Result<()> {
     let gst_init_duration = gst_init_duration.elapsed();
     info!("gst::init took {:?}", gst_init_duration);
 
-    // Resolve the config path early and exit if not found
-    let mut absolute_config_path = std::path::Path::new(config_path_str)
-        .canonicalize()
-        .map_err(|err| {
-            format_err!(
-                "[PLATFORM] Error loading config file {:?}: {}",
-                config_path_str,
-                err
-            )
-        })?;
-    if absolute_config_path.is_dir() {
-        absolute_config_path.push("grim.toml");
+    match matches.subcommand() {
+        ("render", Some(render_matches)) => {
+            try_render(render_matches, gl_settings, headless, json_diagnostics)
+        }
+        ("render-sound", Some(sound_matches)) => {
+            try_render_sound(sound_matches, gl_settings, headless, json_diagnostics)
+        }
+        ("test", Some(test_matches)) => {
+            try_test(test_matches, gl_settings, headless, json_diagnostics)
+        }
+        _ => try_run(&matches, gl_settings, headless, json_diagnostics),
+    }
+}
+
+/// Values accepted by --record-format and render --format
+const RECORD_FORMATS: &[&str] = &["png", "png-rgba", "png16", "exr", "mp4", "webm", "mov"];
+
+/// The requested GL (major, minor, profile) and the matching GLSL #version directive
+type GlSettings = (u8, u8, GLProfile, &'static str);
+
+/// Owns the GL context and, unless running headless, the window and video subsystem it belongs to.
+/// SDL is only initialized for a window, so headless runs work without a display.
+struct GlContext {
+    gl: gl::GLRc,
+    window: Option<(
+        sdl2::VideoSubsystem,
+        sdl2::video::Window,
+        sdl2::video::GLContext,
+    )>,
+    _headless: Option<HeadlessContext>,
+    sdl: Option<sdl2::Sdl>,
+}
+
+impl GlContext {
+    /// The SDL event pump, or None when running headless
+    fn event_pump(&self) -> Result<Option<EventPump>> {
+        match self.sdl {
+            Some(ref sdl) => Ok(Some(sdl.event_pump().map_err(Error::sdl2)?)),
+            None => Ok(None),
+        }
     }
+}
+
+fn try_run(
+    matches: &ArgMatches,
+    gl_settings: GlSettings,
+    headless: bool,
+    json_diagnostics: &mut Option<JsonLines>,
+) -> Result<()> {
+    let width_str = matches.value_of("width").unwrap();
+    let height_str = matches.value_of("height").unwrap();
+    let config_path_str = matches.value_of("config").unwrap_or("./grim.toml");
+    let target_fps_str = matches.value_of("fps").unwrap();
+    let width = width_str
+        .parse::<u32>()
+        .expect("Expected width command-line argument to be u32");
+    let height = height_str
+        .parse::<u32>()
+        .expect("Expected height command-line argument to be u32");
+    // TODO(jshrake): should this also control the iTimeDelta uniform value?
+    let target_fps = target_fps_str
+        .parse::<u32>()
+        .expect("Expected fps command-line argument to be u32");
+    let mut record = matches.is_present("record");
+    let record_format = RecordFormat::from_name(matches.value_of("record-format").unwrap())
+        .expect("Expected a valid record-format command-line argument");
+    let record_buffer = matches.value_of("record-buffer");
+    let record_attachment = matches
+        .value_of("record-attachment")
+        .unwrap()
+        .parse::<usize>()
+        .expect("Expected record-attachment command-line argument to be usize");
+    let capture_frame = matches.value_of("capture-frame").map(|frame| {
+        frame
+            .parse::<u32>()
+            .expect("Expected capture-frame command-line argument to be u32")
+    });
+    let frame_limit = matches
+        .value_of("frames")
+        .unwrap()
+        .parse::<u64>()
+        .expect("Expected frames command-line argument to be u64");
+
+    let absolute_config_path = resolve_config_path(config_path_str)?;
     let desired_cwd = absolute_config_path
         .parent()
-        .expect("Expected config file to have parent directory");
-    env::set_current_dir(&desired_cwd).expect("env::set_current_dir failed");
-    info!("Current working directory: {:?}", desired_cwd);
+        .expect("Expected config file to have parent directory")
+        .to_path_buf();
 
-    let sdl_context = sdl2::init().map_err(Error::sdl2)?;
-    let _joystick_subsystem = sdl_context.joystick().map_err(Error::sdl2)?;
-    let video_subsystem = sdl_context.video().map_err(Error::sdl2)?;
-    let gl_attr = video_subsystem.gl_attr();
-    gl_attr.set_context_version(gl_major, gl_minor);
-    gl_attr.set_context_profile(gl_profile);
-    // TODO(jshrake): These should be config/cli driven
-    gl_attr.set_depth_size(24);
-    gl_attr.set_framebuffer_srgb_compatible(true);
-    gl_attr.set_multisample_buffers(1);
-    gl_attr.set_multisample_samples(4);
-
-    let set_width_or_height =
-        matches.occurrences_of("width") + matches.occurrences_of("height") > 0;
-    let window = if set_width_or_height {
-        video_subsystem
-            .window(
-                &format!("grimoire: {}", desired_cwd.display()),
-                width,
-                height,
-            )
-            .allow_highdpi()
-            .opengl()
-            .build()?
-    } else {
-        video_subsystem
-            .window(
-                &format!("grimoire: {}", desired_cwd.display()),
-                width,
-                height,
-            )
-            .allow_highdpi()
-            .resizable()
-            .opengl()
-            .build()?
+    let resizable = matches.occurrences_of("width") + matches.occurrences_of("height") == 0;
+    let ctx = create_gl_context(
+        gl_settings,
+        headless,
+        &format!("grimoire: {}", desired_cwd.display()),
+        width,
+        height,
+        resizable,
+        false,
+    )?;
+    let _joystick_subsystem = match ctx.sdl {
+        Some(ref sdl) => Some(sdl.joystick().map_err(Error::sdl2)?),
+        None => None,
     };
-
-    let _ctx = window.gl_create_context().map_err(Error::sdl2)?;
-    debug_assert_eq!(gl_attr.context_profile(), gl_profile);
-    debug_assert_eq!(gl_attr.context_version(), (gl_major, gl_minor));
-    let gl = unsafe {
-        gl::GlesFns::load_with(|addr| video_subsystem.gl_get_proc_address(addr) as *const _)
+    let gl = ctx.gl.clone();
+    let mut offscreen = if headless {
+        Some(OffscreenFramebuffer::new(&gl, width, height))
+    } else {
+        None
     };
-    match video_subsystem.gl_set_swap_interval(sdl2::video::SwapInterval::LateSwapTearing) {
-        Ok(_) => {
-            info!("vsync late swap tearing enabled");
-        }
-        Err(_) => match video_subsystem.gl_set_swap_interval(sdl2::video::SwapInterval::VSync) {
-            Ok(_) => {
-                info!("vsync enabled");
-            }
-            Err(_) => {
-                info!("vsync disabled");
-            }
-        },
-    }
 
-    let mut event_pump = sdl_context.event_pump().map_err(Error::sdl2)?;
+    let mut event_pump = ctx.event_pump()?;
 
-    // Log Welcome Message + GL information
-    info!(
-        "Requested GL profile: {:?}, got {:?}",
-        gl_profile,
-        gl_attr.context_profile()
-    );
-    info!(
-        "Requested GL version: {:?}, got {:?}",
-        (gl_major, gl_minor),
-        gl_attr.context_version()
-    );
-    {
-        let vendor = gl.get_string(gl::VENDOR);
-        let renderer = gl.get_string(gl::RENDERER);
-        let version = gl.get_string(gl::VERSION);
-        let shading_lang_version = gl.get_string(gl::SHADING_LANGUAGE_VERSION);
-        let extension_count = unsafe {
-            let mut extension_count: [i32; 1] = [0];
-            gl.get_integer_v(gl::NUM_EXTENSIONS, &mut extension_count);
-            extension_count[0]
-        };
-        let extensions: Vec<String> = (0..extension_count)
-            .map(|i| gl.get_string_i(gl::EXTENSIONS, i as u32))
-            .collect();
-        info!("GL VENDOR:    {}", vendor);
-        info!("GL RENDERER:  {}", renderer);
-        info!("GL VERSION:   {}", version);
-        info!("GLSL VERSION: {}", shading_lang_version);
-        debug!("EXTENSIONS: {:?}", extensions);
-    }
+    let (window_resolution, mouse_resolution) = match (&ctx.window, &offscreen) {
+        (Some((_, window, _)), _) => (window.drawable_size(), window.size()),
+        (None, Some(offscreen)) => (
+            (offscreen.width, offscreen.height),
+            (offscreen.width, offscreen.height),
+        ),
+        (None, None) => unreachable!("expected a window or an offscreen framebuffer"),
+    };
     let mut platform = Platform {
-        events: &mut event_pump,
+        events: event_pump.as_mut(),
         gl: gl.clone(),
-        window_resolution: window.drawable_size(),
-        mouse_resolution: window.size(),
+        framebuffer: offscreen.as_ref().map_or(0, |o| o.framebuffer),
+        window_resolution,
+        mouse_resolution,
         time_delta: Duration::from_secs(0),
         keyboard: [0; 256],
     };
 
-    fn is_glsl(entry: &DirEntry) -> bool {
-        entry
-            .path()
-            .extension()
-            .map(|s| s == "glsl" || s == "vert" || s == "frag" || s == "vs" || s == "fs")
-            .unwrap_or(false)
-    }
-
-    let mut shader_include_streams = BTreeMap::new();
-    for entry in WalkDir::new(".").into_iter().filter_map(|e| e.ok()) {
-        if entry.path().is_file() && is_glsl(&entry) {
-            let path = std::fs::canonicalize(&entry.path())?;
-            let glsl_include_path = String::from(entry.file_name().to_str().unwrap());
-            shader_include_streams.insert(glsl_include_path, FileStream::new(path.as_path())?);
-        }
-    }
-    let glsl_include_ctx = GlslIncludeContex::new();
     let mut player = EffectPlayer::new(
         absolute_config_path.as_path(),
-        glsl_version.to_string(),
-        shader_include_streams,
-        glsl_include_ctx,
+        gl_settings.3.to_string(),
+        shader_include_streams()?,
     )?;
     player.play()?;
 
-    let mut record_pixel_buffer = {
-        let len = (platform.window_resolution.0 * platform.window_resolution.1 * 3) as usize;
-        let mut record_pixel_buffer = Vec::with_capacity(len);
-        unsafe {
-            record_pixel_buffer.set_len(len);
-        }
-        record_pixel_buffer
+    // The overlay is only drawn in a window, and grimoire still runs without it
+    let mut overlay = if ctx.window.is_some() {
+        Overlay::new(&gl, gl_settings.3)
+            .map_err(|err| error!("{}", pretty_error(&failure::Error::from(err))))
+            .ok()
+    } else {
+        None
     };
+
+    let mut recorder: Option<Recorder> = None;
+    let mut readback = PixelReadback::new(&gl, record_format.capture_format());
     let current_timestamp = std::time::SystemTime::now()
         .duration_since(std::time::SystemTime::UNIX_EPOCH)?
         .as_millis()
         .to_string();
-    let record_directory = desired_cwd.join(current_timestamp);
-    if record {
-        if !record_directory.exists() {
-            std::fs::create_dir(&record_directory)
-                .expect("Unable to create the record directory, exiting");
-        }
-    }
-
-    let (record_tx, record_rx) = std::sync::mpsc::channel::<RecordData>();
-    let record_thread = std::thread::spawn(move || {
-        let mut ticks = 0;
-        loop {
-            match record_rx.recv() {
-                Ok(data) => {
-                    let img_path = record_directory
-                        .join(ticks.to_string())
-                        .with_extension("png");
-                    image::save_buffer(
-                        img_path,
-                        &data.data,
-                        data.width,
-                        data.height,
-                        image::RGB(8),
-                    )
-                    .unwrap();
-                    ticks += 1;
-                }
-                Err(_) => {
-                    break;
-                }
-            }
-        }
-    });
+    let record_path = match record_format {
+        RecordFormat::Video(format) => desired_cwd
+            .join(current_timestamp)
+            .with_extension(format.extension()),
+        RecordFormat::Image(_) => desired_cwd.join(current_timestamp),
+    };
+    // Recorded frames advance iTime by a fixed step, so encode them at that rate
+    let record_fps = if target_fps > 0 { target_fps } else { 60 };
 
+    let mut frames_drawn = 0;
+    let mut capture_requested = false;
+    let mut previous_frame_start: Option<Instant> = None;
+    let mut fps = 0.0;
     // SDL events
     'running: loop {
         platform.keyboard = [0; 256];
-        let scancodes: Vec<_> = platform
-            .events
-            .keyboard_state()
-            .pressed_scancodes()
-            .map(|sc| sc)
-            .collect();
+        let scancodes: Vec<_> = platform.events.as_ref().map_or_else(Vec::new, |events| {
+            events.keyboard_state().pressed_scancodes().collect()
+        });
         for scancode in scancodes {
             let keycode = sdl2::keyboard::Keycode::from_scancode(scancode);
             if let Some(kc) = keycode {
@@ -391,7 +718,11 @@ fn try_main() -> Result<()> {
             }
         }
         let now = Instant::now();
-        for event in platform.events.poll_iter() {
+        let events: Vec<_> = platform
+            .events
+            .as_mut()
+            .map_or_else(Vec::new, |events| events.poll_iter().collect());
+        for event in events {
             match event {
                 Event::Window { win_event, .. } => match win_event {
                     _ => {}
@@ -427,6 +758,20 @@ fn try_main() -> Result<()> {
                 } => {
                     player.restart()?;
                 }
+                Event::KeyDown {
+                    keycode: Some(Keycode::F5),
+                    ..
+                } => {
+                    capture_requested = true;
+                }
+                Event::KeyDown {
+                    keycode: Some(Keycode::F6),
+                    ..
+                } => {
+                    if let Some(ref mut overlay) = overlay {
+                        overlay.toggle_status();
+                    }
+                }
                 _ => {}
             }
         }
@@ -435,9 +780,30 @@ fn try_main() -> Result<()> {
             warn!("[PLATFORM] SDL2 event polling took {:?}", poll_duration,);
         }
         let frame_start = Instant::now();
-        match player.tick(&mut platform) {
-            Err(err) => error!("{}", pretty_error(&failure::Error::from(err))),
-            _ => {}
+        if let Some(previous_frame_start) = previous_frame_start {
+            let secs = duration_to_float_secs(frame_start - previous_frame_start);
+            if secs > 0.0 {
+                fps = if fps > 0.0 { 0.9 * fps + 0.1 / secs } else { 1.0 / secs };
+            }
+        }
+        previous_frame_start = Some(frame_start);
+        if let Err(err) = player.tick(&mut platform) {
+            let diagnostics = diagnostics::from_error(&err, player.config_path());
+            emit_diagnostics(json_diagnostics, &diagnostics);
+            if let Some(ref mut overlay) = overlay {
+                overlay.set_errors(&diagnostics);
+            }
+            error!("{}", pretty_error(&failure::Error::from(err)));
+        } else if player.take_rebuilt() {
+            // The overlay shows errors until the next successful build
+            if let Some(ref mut overlay) = overlay {
+                overlay.clear_errors();
+            }
+        }
+        let diagnostics = player.take_diagnostics();
+        emit_diagnostics(json_diagnostics, &diagnostics);
+        if let Some(ref mut overlay) = overlay {
+            overlay.set_errors(&diagnostics);
         }
 
         let elapsed_duration = frame_start.elapsed();
@@ -453,17 +819,83 @@ fn try_main() -> Result<()> {
             debug!("thread::sleep({:?}), target FPS = {}", sleep_duration, fps);
         }
         if record {
-            player
-                .snapshot(&mut platform, &mut record_pixel_buffer)
-                .unwrap();
-            let data = RecordData {
-                data: record_pixel_buffer.clone(),
-                width: platform.window_resolution.0,
-                height: platform.window_resolution.1,
+            // Start recording after the first tick, once the config (and its audio) is loaded
+            if recorder.is_none() {
+                recorder = Some(Recorder::spawn(
+                    record_path.clone(),
+                    record_format,
+                    record_fps,
+                    player.audio_uri()?.map(|uri| (uri, 0.0)),
+                )?);
+            }
+            // Frames are read back asynchronously, and only copied out once the ring is full
+            let sent = if readback.is_full() {
+                recorder
+                    .as_ref()
+                    .map_or(Ok(false), |r| r.send_oldest(&gl, &mut readback))
+            } else {
+                Ok(true)
             };
-            record_tx.send(data).unwrap();
+            let target = player.capture_target(record_buffer, record_attachment);
+            match (sent, target) {
+                (Ok(true), Ok(target)) => readback.read(&gl, target),
+                (sent, target) => {
+                    if let Err(err) = sent {
+                        error!("{}", pretty_error(&failure::Error::from(err)));
+                    }
+                    if let Err(err) = target {
+                        error!("{}", pretty_error(&failure::Error::from(err)));
+                    }
+                    // Report why the record thread exited, if it did, and stop recording
+                    if let Some(Err(err)) = recorder.take().map(Recorder::finish) {
+                        error!("{}", pretty_error(&failure::Error::from(err)));
+                    }
+                    record = false;
+                }
+            }
+        }
+        if capture_requested || capture_frame == Some(frames_drawn) {
+            capture_requested = false;
+            let capture_path = desired_cwd.join(format!(
+                "capture-{}",
+                std::time::SystemTime::now()
+                    .duration_since(std::time::SystemTime::UNIX_EPOCH)?
+                    .as_millis()
+            ));
+            match player.capture_frame(&platform, &capture_path) {
+                Ok(()) => info!("[CAPTURE] Captured frame to {}", capture_path.display()),
+                Err(err) => error!("{}", pretty_error(&failure::Error::from(err))),
+            }
+        }
+        // Draw the overlay last, so that it isn't recorded or captured
+        if let Some(ref mut overlay) = overlay {
+            let (time, frame) = player.drawn_time_frame();
+            let status = format!(
+                "{:.2}s  frame {}  {:.1} fps  {}",
+                time,
+                frame,
+                fps,
+                if player.is_playing() {
+                    "playing"
+                } else {
+                    "paused"
+                }
+            );
+            overlay.draw(
+                &gl,
+                platform.framebuffer,
+                platform.window_resolution.0,
+                platform.window_resolution.1,
+                &status,
+            );
+        }
+        if let Some((_, ref window, _)) = ctx.window {
+            window.gl_swap_window();
+        }
+        frames_drawn += 1;
+        if frame_limit > 0 && frames_drawn >= frame_limit {
+            break 'running;
         }
-        window.gl_swap_window();
 
         // Log a warning if the frame time took longer than expected
         let frame_duration = frame_start.elapsed();
@@ -478,18 +910,14 @@ fn try_main() -> Result<()> {
                 warn!("[PLATFORM] Frame duration took {:?}", frame_duration,);
             }
         }
-        let next_window_resolution = window.drawable_size();
-        let current_window_area = platform.window_resolution.0 * platform.window_resolution.1;
-        let next_window_area = next_window_resolution.0 * next_window_resolution.1;
-        if current_window_area != next_window_area {
-            let len = (next_window_area * 3) as usize;
-            record_pixel_buffer = Vec::with_capacity(len);
-            unsafe {
-                record_pixel_buffer.set_len(len);
-            }
-        }
+        let next_window_resolution = match ctx.window {
+            Some((_, ref window, _)) => window.drawable_size(),
+            None => platform.window_resolution,
+        };
         platform.window_resolution = next_window_resolution;
-        platform.mouse_resolution = window.size();
+        if let Some((_, ref window, _)) = ctx.window {
+            platform.mouse_resolution = window.size();
+        }
         let dt = if target_fps > 0 {
             float_secs_to_duration(1.0 / (target_fps as f32))
         } else {
@@ -497,11 +925,572 @@ fn try_main() -> Result<()> {
         };
         platform.time_delta = dt;
     }
-    drop(record_tx);
-    record_thread.join().unwrap();
+    if let Some(recorder) = recorder {
+        recorder.drain_and_finish(&gl, &mut readback)?;
+    }
+    readback.delete(&gl);
+    if let Some(ref mut overlay) = overlay {
+        overlay.delete(&gl);
+    }
+    if let Some(ref mut offscreen) = offscreen {
+        offscreen.delete(&gl);
+    }
+    drop(ctx);
     Ok(())
 }
 
+fn try_render(
+    matches: &ArgMatches,
+    gl_settings: GlSettings,
+    headless: bool,
+    json_diagnostics: &mut Option<JsonLines>,
+) -> Result<()> {
+    let config_path_str = matches.value_of("config").unwrap_or("./grim.toml");
+    let width = matches
+        .value_of("width")
+        .unwrap()
+        .parse::<u32>()
+        .expect("Expected width command-line argument to be u32");
+    let height = matches
+        .value_of("height")
+        .unwrap()
+        .parse::<u32>()
+        .expect("Expected height command-line argument to be u32");
+    let fps = matches
+        .value_of("fps")
+        .unwrap()
+        .parse::<u32>()
+        .expect("Expected fps command-line argument to be u32");
+    if fps == 0 {
+        return Err(format_err!("[RENDER] --fps must be greater than 0"));
+    }
+    let start = matches
+        .value_of("start")
+        .unwrap()
+        .parse::<f64>()
+        .expect("Expected start command-line argument to be f64");
+    let frames = match (matches.value_of("frames"), matches.value_of("end")) {
+        (Some(frames), _) => frames
+            .parse::<u64>()
+            .expect("Expected frames command-line argument to be u64"),
+        (None, Some(end)) => {
+            let end = end
+                .parse::<f64>()
+                .expect("Expected end command-line argument to be f64");
+            ((end - start) * fps as f64).ceil().max(0.0) as u64
+        }
+        (None, None) => {
+            return Err(format_err!(
+                "[RENDER] Specify the range to render with either --end or --frames"
+            ));
+        }
+    };
+    let format = RecordFormat::from_name(matches.value_of("format").unwrap())
+        .expect("Expected a valid format command-line argument");
+    let buffer = matches.value_of("buffer").map(String::from);
+    let attachment = matches
+        .value_of("attachment")
+        .unwrap()
+        .parse::<usize>()
+        .expect("Expected attachment command-line argument to be usize");
+    // Resolve the output path before changing the cwd to the config directory
+    let mut output = env::current_dir()?.join(matches.value_of("output").unwrap());
+    if let RecordFormat::Video(format) = format {
+        if output.extension().is_none() {
+            output.set_extension(format.extension());
+        }
+    }
+    let settings = RenderSettings {
+        start,
+        frames,
+        fps,
+        width,
+        height,
+        output,
+        format,
+        buffer,
+        attachment,
+    };
+
+    let absolute_config_path = resolve_config_path(config_path_str)?;
+    // Without --headless, we still need a window for the GL context, but we never show it
+    let ctx = create_gl_context(
+        gl_settings,
+        headless,
+        "grimoire render",
+        width,
+        height,
+        false,
+        true,
+    )?;
+    let mut event_pump = ctx.event_pump()?;
+    let mut platform = Platform {
+        events: event_pump.as_mut(),
+        gl: ctx.gl.clone(),
+        framebuffer: 0,
+        window_resolution: (width, height),
+        mouse_resolution: (width, height),
+        time_delta: Duration::from_secs(0),
+        keyboard: [0; 256],
+    };
+    let mut player = EffectPlayer::new(
+        absolute_config_path.as_path(),
+        gl_settings.3.to_string(),
+        shader_include_streams()?,
+    )?;
+    let result = render::render(&mut player, &mut platform, &settings);
+    report_diagnostics(json_diagnostics, &mut player, &result);
+    result?;
+    drop(player);
+    drop(ctx);
+    Ok(())
+}
+
+fn try_render_sound(
+    matches: &ArgMatches,
+    gl_settings: GlSettings,
+    headless: bool,
+    json_diagnostics: &mut Option<JsonLines>,
+) -> Result<()> {
+    let config_path_str = matches.value_of("config").unwrap_or("./grim.toml");
+    let duration = matches
+        .value_of("duration")
+        .unwrap()
+        .parse::<f64>()
+        .expect("Expected duration command-line argument to be f64");
+    // Resolve the output path before changing the cwd to the config directory
+    let output = env::current_dir()?.join(matches.value_of("output").unwrap());
+    let settings = SoundSettings {
+        output,
+        duration,
+        resource: matches.value_of("sound").map(String::from),
+    };
+
+    let absolute_config_path = resolve_config_path(config_path_str)?;
+    let ctx = create_gl_context(
+        gl_settings,
+        headless,
+        "grimoire render-sound",
+        1,
+        1,
+        false,
+        true,
+    )?;
+    let mut event_pump = ctx.event_pump()?;
+    let mut platform = Platform {
+        events: event_pump.as_mut(),
+        gl: ctx.gl.clone(),
+        framebuffer: 0,
+        window_resolution: (1, 1),
+        mouse_resolution: (1, 1),
+        time_delta: Duration::from_secs(0),
+        keyboard: [0; 256],
+    };
+    let mut player = EffectPlayer::new(
+        absolute_config_path.as_path(),
+        gl_settings.3.to_string(),
+        shader_include_streams()?,
+    )?;
+    let result = sound::render_sound(&mut player, &mut platform, &settings);
+    report_diagnostics(json_diagnostics, &mut player, &result);
+    result?;
+    drop(player);
+    drop(ctx);
+    Ok(())
+}
+
+fn try_check(matches: &ArgMatches, json_diagnostics: &mut Option<JsonLines>) -> Result<()> {
+    let config_path_str = matches.value_of("config").unwrap_or("./grim.toml");
+    let absolute_config_path = resolve_config_path(config_path_str)?;
+    let source = std::fs::read_to_string(&absolute_config_path)
+        .map_err(|err| Error::io(&absolute_config_path, err))?;
+    let diagnostics = diagnostics::check(&absolute_config_path, &source);
+    let errors = diagnostics.iter().filter(|d| d.is_error()).count();
+    if json_diagnostics.is_some() {
+        emit_diagnostics(json_diagnostics, &diagnostics);
+    } else {
+        for diagnostic in &diagnostics {
+            println!("{}\n", diagnostic);
+        }
+        println!(
+            "{}: {} errors, {} warnings",
+            absolute_config_path.display(),
+            errors,
+            diagnostics.len() - errors
+        );
+    }
+    if errors > 0 {
+        return Err(format_err!(
+            "[CHECK] {} has {} errors",
+            absolute_config_path.display(),
+            errors
+        ));
+    }
+    Ok(())
+}
+
+fn try_import_shadertoy(
+    matches: &ArgMatches,
+    json_diagnostics: &mut Option<JsonLines>,
+) -> Result<()> {
+    let json = PathBuf::from(matches.value_of("json").unwrap());
+    let output = match matches.value_of("output") {
+        Some(output) => PathBuf::from(output),
+        None => json.with_extension(""),
+    };
+    let settings = ImportSettings {
+        json,
+        output,
+        media: matches.value_of("media").map(PathBuf::from),
+        force: matches.is_present("force"),
+    };
+    let warnings = shadertoy::import(&settings)?;
+    report_created(
+        json_diagnostics,
+        &warnings,
+        &settings.output.join("grim.toml"),
+    );
+    Ok(())
+}
+
+fn try_export_shadertoy(
+    matches: &ArgMatches,
+    json_diagnostics: &mut Option<JsonLines>,
+) -> Result<()> {
+    // Resolve the output before resolve_config_path changes the cwd
+    let output = match matches.value_of("output") {
+        Some(output) => Some(env::current_dir()?.join(output)),
+        None => None,
+    };
+    let config_path_str = matches.value_of("config").unwrap_or("./grim.toml");
+    let config = resolve_config_path(config_path_str)?;
+    let output = output.unwrap_or_else(|| config.with_file_name("shadertoy.json"));
+    let settings = ExportSettings { config, output };
+    let warnings = shadertoy::export(&settings)?;
+    report_created(json_diagnostics, &warnings, &settings.output);
+    Ok(())
+}
+
+fn try_import_vertexshaderart(
+    matches: &ArgMatches,
+    json_diagnostics: &mut Option<JsonLines>,
+) -> Result<()> {
+    let json = PathBuf::from(matches.value_of("json").unwrap());
+    let output = match matches.value_of("output") {
+        Some(output) => PathBuf::from(output),
+        None => json.with_extension(""),
+    };
+    let settings = vertexshaderart::ImportSettings {
+        json,
+        output,
+        force: matches.is_present("force"),
+    };
+    let warnings = vertexshaderart::import(&settings)?;
+    report_created(
+        json_diagnostics,
+        &warnings,
+        &settings.output.join("grim.toml"),
+    );
+    Ok(())
+}
+
+fn try_test(
+    matches: &ArgMatches,
+    gl_settings: GlSettings,
+    headless: bool,
+    json_diagnostics: &mut Option<JsonLines>,
+) -> Result<()> {
+    let config_path_str = matches.value_of("config").unwrap_or("./grim.toml");
+    let width = matches
+        .value_of("width")
+        .unwrap()
+        .parse::<u32>()
+        .expect("Expected width command-line argument to be u32");
+    let height = matches
+        .value_of("height")
+        .unwrap()
+        .parse::<u32>()
+        .expect("Expected height command-line argument to be u32");
+    let fps = matches
+        .value_of("fps")
+        .unwrap()
+        .parse::<u32>()
+        .expect("Expected fps command-line argument to be u32");
+    if fps == 0 {
+        return Err(format_err!("[TEST] --fps must be greater than 0"));
+    }
+    let mut frames = matches
+        .values_of("frame")
+        .unwrap()
+        .map(|frame| {
+            frame
+                .trim()
+                .parse::<u32>()
+                .expect("Expected frame command-line argument to be a list of u32")
+        })
+        .collect::<Vec<_>>();
+    frames.sort_unstable();
+    frames.dedup();
+    let tolerance = matches
+        .value_of("tolerance")
+        .unwrap()
+        .parse::<u8>()
+        .expect("Expected tolerance command-line argument to be in the range [0, 255]");
+    let buffer = matches.value_of("buffer").map(String::from);
+    let attachment = matches
+        .value_of("attachment")
+        .unwrap()
+        .parse::<usize>()
+        .expect("Expected attachment command-line argument to be usize");
+    // Resolve the given paths before changing the cwd to the config directory
+    let cwd = env::current_dir()?;
+    let references = matches.value_of("references").map(|path| cwd.join(path));
+    let output = matches.value_of("output").map(|path| cwd.join(path));
+
+    let absolute_config_path = resolve_config_path(config_path_str)?;
+    let project_dir = absolute_config_path
+        .parent()
+        .expect("Expected config file to have parent directory");
+    let settings = TestSettings {
+        frames,
+        fps,
+        width,
+        height,
+        references: references.unwrap_or_else(|| project_dir.join("golden")),
+        output: output.unwrap_or_else(|| project_dir.join("golden-diff")),
+        tolerance,
+        update: matches.is_present("update"),
+        buffer,
+        attachment,
+    };
+    // Without --headless, we still need a window for the GL context, but we never show it
+    let ctx = create_gl_context(
+        gl_settings,
+        headless,
+        "grimoire test",
+        width,
+        height,
+        false,
+        true,
+    )?;
+    let mut event_pump = ctx.event_pump()?;
+    let mut platform = Platform {
+        events: event_pump.as_mut(),
+        gl: ctx.gl.clone(),
+        framebuffer: 0,
+        window_resolution: (width, height),
+        mouse_resolution: (width, height),
+        time_delta: Duration::from_secs(0),
+        keyboard: [0; 256],
+    };
+    let mut player = EffectPlayer::new(
+        absolute_config_path.as_path(),
+        gl_settings.3.to_string(),
+        shader_include_streams()?,
+    )?;
+    let result = golden::test(&mut player, &mut platform, &settings);
+    report_diagnostics(json_diagnostics, &mut player, &result);
+    result?;
+    drop(player);
+    drop(ctx);
+    Ok(())
+}
+
+/// Write `diagnostics` as JSON lines when running with --diagnostics-format json
+fn emit_diagnostics(json_diagnostics: &mut Option<JsonLines>, diagnostics: &[Diagnostic]) {
+    if let Some(json) = json_diagnostics {
+        for diagnostic in diagnostics {
+            if let Err(err) = json.write(diagnostic) {
+                error!("{}", pretty_error(&failure::Error::from(err)));
+                return;
+            }
+        }
+    }
+}
+
+/// Report the warnings of an import or export that created `path`, as JSON lines when
+/// running with --diagnostics-format json
+fn report_created(json_diagnostics: &mut Option<JsonLines>, warnings: &[Diagnostic], path: &Path) {
+    if json_diagnostics.is_some() {
+        emit_diagnostics(json_diagnostics, warnings);
+    } else {
+        for warning in warnings {
+            println!("{}\n", warning);
+        }
+        println!(
+            "created {} with {} warnings",
+            path.display(),
+            warnings.len()
+        );
+    }
+}
+
+/// Emit the config diagnostics of `player`, and the diagnostics of the error
+/// that stopped a render or test, if any
+fn report_diagnostics<T>(
+    json_diagnostics: &mut Option<JsonLines>,
+    player: &mut EffectPlayer,
+    result: &error::Result<T>,
+) {
+    emit_diagnostics(json_diagnostics, &player.take_diagnostics());
+    if let Err(err) = result {
+        let diagnostics = diagnostics::from_error(err, player.config_path());
+        emit_diagnostics(json_diagnostics, &diagnostics);
+    }
+}
+
+/// Resolve the config path, exiting early if not found, and change the
+/// current working directory to the directory containing the config
+fn resolve_config_path(config_path_str: &str) -> Result<PathBuf> {
+    let mut absolute_config_path = Path::new(config_path_str)
+        .canonicalize()
+        .map_err(|err| {
+            format_err!(
+                "[PLATFORM] Error loading config file {:?}: {}",
+                config_path_str,
+                err
+            )
+        })?;
+    if absolute_config_path.is_dir() {
+        absolute_config_path.push("grim.toml");
+    }
+    let desired_cwd = absolute_config_path
+        .parent()
+        .expect("Expected config file to have parent directory");
+    env::set_current_dir(&desired_cwd).expect("env::set_current_dir failed");
+    info!("Current working directory: {:?}", desired_cwd);
+    Ok(absolute_config_path)
+}
+
+/// Watch every GLSL file under the current working directory so that they can be #include'd
+fn shader_include_streams() -> Result<BTreeMap<String, FileStream>> {
+    fn is_glsl(entry: &DirEntry) -> bool {
+        entry
+            .path()
+            .extension()
+            .map(|s| s == "glsl" || s == "vert" || s == "frag" || s == "vs" || s == "fs")
+            .unwrap_or(false)
+    }
+
+    let mut shader_include_streams = BTreeMap::new();
+    for entry in WalkDir::new(".").into_iter().filter_map(|e| e.ok()) {
+        if entry.path().is_file() && is_glsl(&entry) {
+            let path = std::fs::canonicalize(&entry.path())?;
+            let glsl_include_path = String::from(entry.file_name().to_str().unwrap());
+            shader_include_streams.insert(glsl_include_path, FileStream::new(path.as_path())?);
+        }
+    }
+    Ok(shader_include_streams)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn create_gl_context(
+    gl_settings: GlSettings,
+    headless: bool,
+    title: &str,
+    width: u32,
+    height: u32,
+    resizable: bool,
+    hidden: bool,
+) -> Result<GlContext> {
+    let (gl_major, gl_minor, gl_profile, _) = gl_settings;
+    let ctx = if headless {
+        let ctx = HeadlessContext::new(gl_major, gl_minor, gl_profile == GLProfile::GLES)?;
+        let gl = unsafe { gl::GlesFns::load_with(|addr| ctx.get_proc_address(addr)) };
+        gl::load_ext_with(&gl, |addr| ctx.get_proc_address(addr));
+        GlContext {
+            gl,
+            window: None,
+            _headless: Some(ctx),
+            sdl: None,
+        }
+    } else {
+        let sdl_context = sdl2::init().map_err(Error::sdl2)?;
+        let video_subsystem = sdl_context.video().map_err(Error::sdl2)?;
+        let gl_attr = video_subsystem.gl_attr();
+        gl_attr.set_context_version(gl_major, gl_minor);
+        gl_attr.set_context_profile(gl_profile);
+        // TODO(jshrake): These should be config/cli driven
+        gl_attr.set_depth_size(24);
+        gl_attr.set_framebuffer_srgb_compatible(true);
+        gl_attr.set_multisample_buffers(1);
+        gl_attr.set_multisample_samples(4);
+
+        let mut window_builder = video_subsystem.window(title, width, height);
+        window_builder.allow_highdpi().opengl();
+        if resizable {
+            window_builder.resizable();
+        }
+        if hidden {
+            window_builder.hidden();
+        }
+        let window = window_builder.build()?;
+
+        let gl_context = window.gl_create_context().map_err(Error::sdl2)?;
+        debug_assert_eq!(gl_attr.context_profile(), gl_profile);
+        debug_assert_eq!(gl_attr.context_version(), (gl_major, gl_minor));
+        let gl = unsafe {
+            gl::GlesFns::load_with(|addr| video_subsystem.gl_get_proc_address(addr) as *const _)
+        };
+        gl::load_ext_with(&gl, |addr| {
+            video_subsystem.gl_get_proc_address(addr) as *const _
+        });
+        match video_subsystem.gl_set_swap_interval(sdl2::video::SwapInterval::LateSwapTearing) {
+            Ok(_) => {
+                info!("vsync late swap tearing enabled");
+            }
+            Err(_) => {
+                match video_subsystem.gl_set_swap_interval(sdl2::video::SwapInterval::VSync) {
+                    Ok(_) => {
+                        info!("vsync enabled");
+                    }
+                    Err(_) => {
+                        info!("vsync disabled");
+                    }
+                }
+            }
+        }
+        // Log Welcome Message + GL information
+        info!(
+            "Requested GL profile: {:?}, got {:?}",
+            gl_profile,
+            gl_attr.context_profile()
+        );
+        info!(
+            "Requested GL version: {:?}, got {:?}",
+            (gl_major, gl_minor),
+            gl_attr.context_version()
+        );
+        GlContext {
+            gl,
+            window: Some((video_subsystem, window, gl_context)),
+            _headless: None,
+            sdl: Some(sdl_context),
+        }
+    };
+    {
+        let gl = &ctx.gl;
+        let vendor = gl.get_string(gl::VENDOR);
+        let renderer = gl.get_string(gl::RENDERER);
+        let version = gl.get_string(gl::VERSION);
+        let shading_lang_version = gl.get_string(gl::SHADING_LANGUAGE_VERSION);
+        let extension_count = unsafe {
+            let mut extension_count: [i32; 1] = [0];
+            gl.get_integer_v(gl::NUM_EXTENSIONS, &mut extension_count);
+            extension_count[0]
+        };
+        let extensions: Vec<String> = (0..extension_count)
+            .map(|i| gl.get_string_i(gl::EXTENSIONS, i as u32))
+            .collect();
+        info!("GL VENDOR:    {}", vendor);
+        info!("GL RENDERER:  {}", renderer);
+        info!("GL VERSION:   {}", version);
+        info!("GLSL VERSION: {}", shading_lang_version);
+        debug!("EXTENSIONS: {:?}", extensions);
+    }
+    Ok(ctx)
+}
+
 fn duration_to_float_secs(duration: Duration) -> f32 {
     duration.as_secs() as f32 + duration.subsec_nanos() as f32 * 1e-9
 }
@@ -0,0 +1,1254 @@
+use crate::config::{DrawModeConfig, ModelConfig, ModelFormat, TextureFormat};
+use crate::error::{Error, Result};
+use crate::resource::{GeometryData, GeometryDraw, ResourceData2D, IDENTITY};
+use std::path::{Path, PathBuf};
+
+/// The vertex attributes of glTF primitives, as (name, components, the value of
+/// vertices of primitives without the attribute)
+const GLTF_ATTRIBUTES: [(&str, u32, f32); 7] = [
+    ("position", 3, 0.0),
+    ("normal", 3, 0.0),
+    ("texcoord", 2, 0.0),
+    ("tangent", 4, 0.0),
+    ("color", 4, 1.0),
+    ("joints", 4, 0.0),
+    ("weights", 4, 0.0),
+];
+
+/// Load a model resource in the format of its file extension
+pub fn load_model(config: &ModelConfig) -> Result<GeometryData> {
+    let mut data =
+        match config.format() {
+            Some(ModelFormat::Obj) => load_obj(config)?,
+            Some(ModelFormat::Gltf) => load_gltf(config)?,
+            Some(ModelFormat::Ply) => load_ply(config)?,
+            Some(ModelFormat::Stl) => load_stl(config)?,
+            Some(ModelFormat::Points) => load_points(config)?,
+            None => return Err(Error::model(
+                &config.model,
+                "Unsupported file extension, expected .obj, .gltf, .glb, .ply, .stl, .xyz or .csv",
+            )),
+        };
+    if let Some(ref mode) = config.mode {
+        for draw in &mut data.draws {
+            draw.mode = mode.clone();
+        }
+    }
+    info!(
+        "[MODEL] {} has {} vertices, {} indices, {} draws and {} textures, with the vertex shader inputs: {}",
+        config.model,
+        data.vertex_count(),
+        data.indices.len(),
+        data.draws.len(),
+        data.textures.len(),
+        data.layout()
+    );
+    Ok(data)
+}
+
+/// The external buffers and images a glTF model references, which reload the model
+/// when they change just like the model file itself
+pub fn dependencies(config: &ModelConfig) -> Vec<PathBuf> {
+    if config.format() != Some(ModelFormat::Gltf) {
+        return Vec::new();
+    }
+    // Errors are reported when the model loads
+    let document = match gltf::Gltf::open(&config.model) {
+        Ok(gltf) => gltf.document,
+        Err(_) => return Vec::new(),
+    };
+    let buffer_uris = document
+        .buffers()
+        .filter_map(|buffer| match buffer.source() {
+            gltf::buffer::Source::Uri(uri) => Some(uri),
+            gltf::buffer::Source::Bin => None,
+        });
+    let image_uris = document.images().filter_map(|image| match image.source() {
+        gltf::image::Source::Uri { uri, .. } => Some(uri),
+        gltf::image::Source::View { .. } => None,
+    });
+    let directory = Path::new(&config.model)
+        .parent()
+        .unwrap_or_else(|| Path::new(""));
+    buffer_uris
+        .chain(image_uris)
+        .filter(|uri| !uri.starts_with("data:"))
+        .map(|uri| directory.join(uri))
+        .filter(|path| path.exists())
+        .collect()
+}
+
+/// Load an object of a Wavefront OBJ file with its indices and every vertex attribute
+/// it has. Positions are always present, and normal, texcoord, color and tangent are
+/// present when the file provides what they're derived from.
+fn load_obj(config: &ModelConfig) -> Result<GeometryData> {
+    let (models, materials) = tobj::load_obj(Path::new(&config.model))
+        .map_err(|err| Error::model(&config.model, format!("{:?}", err)))?;
+    // If the user specified the object name, look for that, otherwise
+    // use the first model entry
+    let model = match config.object {
+        Some(ref object) => models.iter().find(|m| &m.name == object).or(models.first()),
+        None => models.first(),
+    };
+    let mesh = match model {
+        Some(model) => &model.mesh,
+        None => return Err(Error::model(&config.model, "The file has no objects")),
+    };
+    let vertex_count = mesh.positions.len() / 3;
+    let mut streams = vec![("position", 3, mesh.positions.clone())];
+    let has_normals = mesh.normals.len() == 3 * vertex_count;
+    let has_texcoords = mesh.texcoords.len() == 2 * vertex_count;
+    if has_normals {
+        streams.push(("normal", 3, mesh.normals.clone()));
+    }
+    if has_texcoords {
+        streams.push(("texcoord", 2, mesh.texcoords.clone()));
+    }
+    // OBJ has no vertex colors, so color is the diffuse color of the mesh's material
+    if let Some(material) = mesh.material_id.and_then(|id| materials.get(id)) {
+        let color = material.diffuse.iter().cloned().cycle();
+        streams.push(("color", 3, color.take(3 * vertex_count).collect()));
+    }
+    if has_normals && has_texcoords {
+        let tangents = tangents(
+            &mesh.positions,
+            &mesh.normals,
+            &mesh.texcoords,
+            &mesh.indices,
+        );
+        streams.push(("tangent", 4, tangents));
+    }
+    Ok(GeometryData::interleave(streams, mesh.indices.clone()))
+}
+
+/// Load the meshes of a glTF or GLB file into one vertex buffer, with a draw for each
+/// primitive of each node in the default scene, or in the node named by the object, and
+/// the embedded textures in the order of the file's textures
+fn load_gltf(config: &ModelConfig) -> Result<GeometryData> {
+    let (document, buffers, images) =
+        gltf::import(&config.model).map_err(|err| Error::model(&config.model, err.to_string()))?;
+    // Append every primitive once, vertex attributes the primitive lacks are filled with
+    // their default value so that all vertices share the same layout
+    let mut streams = vec![Vec::new(); GLTF_ATTRIBUTES.len()];
+    let mut present = vec![false; GLTF_ATTRIBUTES.len()];
+    let mut indices = Vec::new();
+    let mut mesh_draws = Vec::new();
+    for mesh in document.meshes() {
+        let mut draws = Vec::new();
+        for primitive in mesh.primitives() {
+            let reader = primitive.reader(|buffer| Some(&buffers[buffer.index()]));
+            let positions: Vec<f32> = match reader.read_positions() {
+                Some(positions) => positions.flat_map(|v| v.to_vec()).collect(),
+                None => continue,
+            };
+            let vertex_count = positions.len() / 3;
+            let base = streams[0].len() as u32 / 3;
+            let primitive_indices: Vec<u32> = match reader.read_indices() {
+                Some(indices) => indices.into_u32().collect(),
+                None => (0..vertex_count as u32).collect(),
+            };
+            let mode = gltf_draw_mode(primitive.mode());
+            let normals: Option<Vec<f32>> = reader
+                .read_normals()
+                .map(|normals| normals.flat_map(|v| v.to_vec()).collect());
+            let texcoords: Option<Vec<f32>> = reader
+                .read_tex_coords(0)
+                .map(|texcoords| texcoords.into_f32().flat_map(|v| v.to_vec()).collect());
+            let tangents = match (reader.read_tangents(), &normals, &texcoords) {
+                (Some(tangents), _, _) => Some(tangents.flat_map(|v| v.to_vec()).collect()),
+                (None, Some(normals), Some(texcoords)) if mode == DrawModeConfig::Triangles => {
+                    Some(tangents(&positions, normals, texcoords, &primitive_indices))
+                }
+                _ => None,
+            };
+            let colors = reader
+                .read_colors(0)
+                .map(|colors| colors.into_rgba_f32().flat_map(|v| v.to_vec()).collect());
+            let joints = reader.read_joints(0).map(|joints| {
+                joints
+                    .into_u16()
+                    .flat_map(|v| v.iter().map(|&j| f32::from(j)).collect::<Vec<f32>>())
+                    .collect()
+            });
+            let weights = reader
+                .read_weights(0)
+                .map(|weights| weights.into_f32().flat_map(|v| v.to_vec()).collect());
+            let primitive_streams = vec![
+                Some(positions),
+                normals,
+                texcoords,
+                tangents,
+                colors,
+                joints,
+                weights,
+            ];
+            for (i, values) in primitive_streams.into_iter().enumerate() {
+                let (_, components, default) = GLTF_ATTRIBUTES[i];
+                let len = components as usize * vertex_count;
+                match values {
+                    Some(ref values) if values.len() == len => {
+                        present[i] = true;
+                        streams[i].extend_from_slice(values);
+                    }
+                    _ => streams[i].extend(std::iter::repeat(default).take(len)),
+                }
+            }
+            draws.push(GeometryDraw {
+                mode,
+                first: indices.len() as u32,
+                count: primitive_indices.len() as u32,
+                transform: IDENTITY,
+            });
+            indices.extend(primitive_indices.iter().map(|i| base + i));
+        }
+        mesh_draws.push(draws);
+    }
+    let streams = GLTF_ATTRIBUTES
+        .iter()
+        .zip(streams)
+        .zip(present)
+        .filter(|(_, present)| *present)
+        .map(|(((name, components, _), values), _)| (*name, *components, values))
+        .collect();
+    let mut data = GeometryData::interleave(streams, indices);
+    // Draw the meshes of the nodes with their world transform
+    let roots: Vec<gltf::Node> = match config.object {
+        Some(ref object) => document
+            .nodes()
+            .filter(|node| node.name() == Some(object.as_str()))
+            .collect(),
+        None => match document
+            .default_scene()
+            .or_else(|| document.scenes().next())
+        {
+            Some(scene) => scene.nodes().collect(),
+            None => Vec::new(),
+        },
+    };
+    data.draws.clear();
+    for node in &roots {
+        gltf_node_draws(node, &IDENTITY, &mesh_draws, &mut data.draws);
+    }
+    if data.draws.is_empty() {
+        let message = match config.object {
+            Some(ref object) => format!("The file has no node named \"{}\" with meshes", object),
+            None => "The file has no scene with meshes".to_string(),
+        };
+        return Err(Error::model(&config.model, message));
+    }
+    data.textures = document
+        .textures()
+        .map(|texture| gltf_texture(&images[texture.source().index()]))
+        .collect();
+    Ok(data)
+}
+
+/// Append the draws of the meshes of a node and its children, transformed by the node's
+/// local transform and the transform of its parent
+fn gltf_node_draws(
+    node: &gltf::Node,
+    parent: &[f32; 16],
+    mesh_draws: &[Vec<GeometryDraw>],
+    draws: &mut Vec<GeometryDraw>,
+) {
+    let mut local = [0.0; 16];
+    for (column, values) in node.transform().matrix().iter().enumerate() {
+        local[4 * column..4 * column + 4].copy_from_slice(values);
+    }
+    let transform = mul(parent, &local);
+    if let Some(mesh) = node.mesh() {
+        for draw in &mesh_draws[mesh.index()] {
+            draws.push(GeometryDraw {
+                transform,
+                ..draw.clone()
+            });
+        }
+    }
+    for child in node.children() {
+        gltf_node_draws(&child, &transform, mesh_draws, draws);
+    }
+}
+
+fn gltf_draw_mode(mode: gltf::mesh::Mode) -> DrawModeConfig {
+    match mode {
+        gltf::mesh::Mode::Points => DrawModeConfig::Points,
+        gltf::mesh::Mode::Lines => DrawModeConfig::Lines,
+        gltf::mesh::Mode::LineLoop => DrawModeConfig::LineLoop,
+        gltf::mesh::Mode::LineStrip => DrawModeConfig::LineStrip,
+        gltf::mesh::Mode::Triangles => DrawModeConfig::Triangles,
+        gltf::mesh::Mode::TriangleStrip => DrawModeConfig::TriangleStrip,
+        gltf::mesh::Mode::TriangleFan => DrawModeConfig::TriangleFan,
+    }
+}
+
+/// An embedded glTF image as a texture, 16 bit images keep their most significant byte
+fn gltf_texture(image: &gltf::image::Data) -> ResourceData2D {
+    use gltf::image::Format;
+    let high_bytes = |pixels: &[u8]| {
+        pixels
+            .chunks_exact(2)
+            .map(|c| (u16::from_ne_bytes([c[0], c[1]]) >> 8) as u8)
+            .collect()
+    };
+    let (format, bytes) = match image.format {
+        Format::R8 => (TextureFormat::RU8, image.pixels.clone()),
+        Format::R8G8 => (TextureFormat::RGU8, image.pixels.clone()),
+        Format::R8G8B8 => (TextureFormat::RGBU8, image.pixels.clone()),
+        Format::R8G8B8A8 => (TextureFormat::RGBAU8, image.pixels.clone()),
+        Format::B8G8R8 => (TextureFormat::BGRU8, image.pixels.clone()),
+        Format::B8G8R8A8 => (TextureFormat::BGRAU8, image.pixels.clone()),
+        Format::R16 => (TextureFormat::RU8, high_bytes(&image.pixels)),
+        Format::R16G16 => (TextureFormat::RGU8, high_bytes(&image.pixels)),
+        Format::R16G16B16 => (TextureFormat::RGBU8, high_bytes(&image.pixels)),
+        Format::R16G16B16A16 => (TextureFormat::RGBAU8, high_bytes(&image.pixels)),
+    };
+    ResourceData2D {
+        bytes,
+        width: image.width,
+        height: image.height,
+        format,
+        xoffset: 0,
+        yoffset: 0,
+        subwidth: image.width,
+        subheight: image.height,
+        time: 0.0,
+    }
+}
+
+/// Per-vertex tangents of indexed triangles, pointing along increasing texcoord u, with
+/// the handedness of the bitangent in w
+fn tangents(positions: &[f32], normals: &[f32], texcoords: &[f32], indices: &[u32]) -> Vec<f32> {
+    let vertex_count = positions.len() / 3;
+    let position = |i: usize| [positions[3 * i], positions[3 * i + 1], positions[3 * i + 2]];
+    let texcoord = |i: usize| [texcoords[2 * i], texcoords[2 * i + 1]];
+    let mut tangents = vec![[0.0f32; 3]; vertex_count];
+    let mut bitangents = vec![[0.0f32; 3]; vertex_count];
+    for triangle in indices.chunks_exact(3) {
+        let i = [
+            triangle[0] as usize,
+            triangle[1] as usize,
+            triangle[2] as usize,
+        ];
+        let e1 = sub(position(i[1]), position(i[0]));
+        let e2 = sub(position(i[2]), position(i[0]));
+        let (uv0, uv1, uv2) = (texcoord(i[0]), texcoord(i[1]), texcoord(i[2]));
+        let du1 = [uv1[0] - uv0[0], uv1[1] - uv0[1]];
+        let du2 = [uv2[0] - uv0[0], uv2[1] - uv0[1]];
+        let det = du1[0] * du2[1] - du2[0] * du1[1];
+        if det.abs() < std::f32::EPSILON {
+            continue;
+        }
+        let r = 1.0 / det;
+        let tangent = scale(sub(scale(e1, du2[1]), scale(e2, du1[1])), r);
+        let bitangent = scale(sub(scale(e2, du1[0]), scale(e1, du2[0])), r);
+        for &v in &i {
+            tangents[v] = add(tangents[v], tangent);
+            bitangents[v] = add(bitangents[v], bitangent);
+        }
+    }
+    let mut result = Vec::with_capacity(4 * vertex_count);
+    for v in 0..vertex_count {
+        let n = [normals[3 * v], normals[3 * v + 1], normals[3 * v + 2]];
+        // Gram-Schmidt orthogonalize against the normal
+        let t = sub(tangents[v], scale(n, dot(n, tangents[v])));
+        let length = dot(t, t).sqrt();
+        let t = if length > std::f32::EPSILON {
+            scale(t, 1.0 / length)
+        } else {
+            [1.0, 0.0, 0.0]
+        };
+        let w = if dot(cross(n, t), bitangents[v]) < 0.0 {
+            -1.0
+        } else {
+            1.0
+        };
+        result.extend_from_slice(&[t[0], t[1], t[2], w]);
+    }
+    result
+}
+
+/// A scalar type of PLY properties
+#[derive(Debug, Clone, Copy)]
+enum PlyScalar {
+    I8,
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+    F32,
+    F64,
+}
+
+impl PlyScalar {
+    fn from_name(name: &str) -> Option<PlyScalar> {
+        match name {
+            "char" | "int8" => Some(PlyScalar::I8),
+            "uchar" | "uint8" => Some(PlyScalar::U8),
+            "short" | "int16" => Some(PlyScalar::I16),
+            "ushort" | "uint16" => Some(PlyScalar::U16),
+            "int" | "int32" => Some(PlyScalar::I32),
+            "uint" | "uint32" => Some(PlyScalar::U32),
+            "float" | "float32" => Some(PlyScalar::F32),
+            "double" | "float64" => Some(PlyScalar::F64),
+            _ => None,
+        }
+    }
+
+    fn size(self) -> usize {
+        match self {
+            PlyScalar::I8 | PlyScalar::U8 => 1,
+            PlyScalar::I16 | PlyScalar::U16 => 2,
+            PlyScalar::I32 | PlyScalar::U32 | PlyScalar::F32 => 4,
+            PlyScalar::F64 => 8,
+        }
+    }
+
+    /// The value of full intensity of color properties of this type
+    fn max(self) -> f32 {
+        match self {
+            PlyScalar::I8 => 127.0,
+            PlyScalar::U8 => 255.0,
+            PlyScalar::I16 => 32767.0,
+            PlyScalar::U16 => 65535.0,
+            PlyScalar::I32 => 2_147_483_647.0,
+            PlyScalar::U32 => 4_294_967_295.0,
+            PlyScalar::F32 | PlyScalar::F64 => 1.0,
+        }
+    }
+}
+
+#[derive(Debug)]
+enum PlyProperty {
+    Scalar(String, PlyScalar),
+    // the scalar types of the item count and of the items
+    List(String, PlyScalar, PlyScalar),
+}
+
+#[derive(Debug)]
+struct PlyElement {
+    name: String,
+    count: usize,
+    properties: Vec<PlyProperty>,
+}
+
+/// Reads the values of PLY elements, from the text of ascii files or the bytes of
+/// binary files
+enum PlyReader<'a> {
+    Ascii(std::str::SplitWhitespace<'a>),
+    Binary {
+        bytes: &'a [u8],
+        position: usize,
+        big_endian: bool,
+    },
+}
+
+impl<'a> PlyReader<'a> {
+    fn read(&mut self, scalar: PlyScalar) -> Option<f64> {
+        match self {
+            PlyReader::Ascii(words) => words.next()?.parse().ok(),
+            PlyReader::Binary {
+                bytes,
+                position,
+                big_endian,
+            } => {
+                let size = scalar.size();
+                let mut b = [0u8; 8];
+                b[..size].copy_from_slice(bytes.get(*position..*position + size)?);
+                *position += size;
+                if *big_endian {
+                    b[..size].reverse();
+                }
+                Some(match scalar {
+                    PlyScalar::I8 => f64::from(b[0] as i8),
+                    PlyScalar::U8 => f64::from(b[0]),
+                    PlyScalar::I16 => f64::from(i16::from_le_bytes([b[0], b[1]])),
+                    PlyScalar::U16 => f64::from(u16::from_le_bytes([b[0], b[1]])),
+                    PlyScalar::I32 => f64::from(i32::from_le_bytes([b[0], b[1], b[2], b[3]])),
+                    PlyScalar::U32 => f64::from(u32::from_le_bytes([b[0], b[1], b[2], b[3]])),
+                    PlyScalar::F32 => f64::from(f32::from_le_bytes([b[0], b[1], b[2], b[3]])),
+                    PlyScalar::F64 => f64::from_le_bytes(b),
+                })
+            }
+        }
+    }
+}
+
+/// The values of a named vertex property of a PLY file, or of a column of a point file
+struct Column {
+    name: String,
+    values: Vec<f32>,
+    // the value of full intensity when the column is a color channel
+    max: f32,
+}
+
+fn load_ply(config: &ModelConfig) -> Result<GeometryData> {
+    let bytes = std::fs::read(&config.model).map_err(|err| Error::io(&config.model, err))?;
+    parse_ply(&config.model, &bytes)
+}
+
+/// Parse the vertices of an ascii or binary PLY file, with its faces as triangles. Files
+/// without faces are drawn as points. Errors refer to the file at `path`
+fn parse_ply(path: &str, bytes: &[u8]) -> Result<GeometryData> {
+    let error = |message: String| Error::model(path, message);
+    // The header is the text before the line that ends it
+    let header_end = bytes
+        .windows(b"end_header".len())
+        .position(|window| window == b"end_header")
+        .ok_or_else(|| error("The file has no end_header line".to_string()))?;
+    let body_start = bytes[header_end..]
+        .iter()
+        .position(|&b| b == b'\n')
+        .map_or(bytes.len(), |i| header_end + i + 1);
+    let header = String::from_utf8_lossy(&bytes[..header_end]);
+    let mut lines = header.lines().map(str::trim);
+    if lines.next() != Some("ply") {
+        return Err(error("The file does not start with ply".to_string()));
+    }
+    let scalar = |name: &str| {
+        PlyScalar::from_name(name).ok_or_else(|| error(format!("Unknown property type {}", name)))
+    };
+    let mut big_endian = None;
+    let mut elements: Vec<PlyElement> = Vec::new();
+    for line in lines {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        match words.as_slice() {
+            ["format", "ascii", _] => big_endian = None,
+            ["format", "binary_little_endian", _] => big_endian = Some(false),
+            ["format", "binary_big_endian", _] => big_endian = Some(true),
+            ["format", format, _] => return Err(error(format!("Unknown format {}", format))),
+            ["element", name, count] => elements.push(PlyElement {
+                name: name.to_string(),
+                count: count
+                    .parse()
+                    .map_err(|_| error(format!("Bad count of element {}", name)))?,
+                properties: Vec::new(),
+            }),
+            ["property", "list", count, item, name] => match elements.last_mut() {
+                Some(element) => element.properties.push(PlyProperty::List(
+                    name.to_string(),
+                    scalar(count)?,
+                    scalar(item)?,
+                )),
+                None => return Err(error(format!("Property {} has no element", name))),
+            },
+            ["property", scalar_name, name] => match elements.last_mut() {
+                Some(element) => element
+                    .properties
+                    .push(PlyProperty::Scalar(name.to_string(), scalar(scalar_name)?)),
+                None => return Err(error(format!("Property {} has no element", name))),
+            },
+            // comments and obj_info
+            _ => (),
+        }
+    }
+    let mut reader = match big_endian {
+        Some(big_endian) => PlyReader::Binary {
+            bytes: &bytes[body_start..],
+            position: 0,
+            big_endian,
+        },
+        None => PlyReader::Ascii(
+            std::str::from_utf8(&bytes[body_start..])
+                .map_err(|err| error(err.to_string()))?
+                .split_whitespace(),
+        ),
+    };
+    // Read every element in order, keeping the scalar properties of vertices, custom
+    // properties included, and the vertex indices of faces
+    let mut columns = Vec::new();
+    let mut indices = Vec::new();
+    for element in &elements {
+        let is_vertex = element.name == "vertex";
+        let is_face = element.name == "face";
+        // Counts come from the header, so the values grow as they are read rather
+        // than trusting a corrupt count with an allocation up front
+        if is_vertex {
+            columns = element
+                .properties
+                .iter()
+                .filter_map(|property| match property {
+                    PlyProperty::Scalar(name, scalar) => Some(Column {
+                        name: identifier(name),
+                        values: Vec::new(),
+                        max: scalar.max(),
+                    }),
+                    PlyProperty::List(..) => None,
+                })
+                .collect();
+        }
+        let truncated = || error(format!("The {} elements end early", element.name));
+        for _ in 0..element.count {
+            let mut column = 0;
+            for property in &element.properties {
+                match property {
+                    PlyProperty::Scalar(_, scalar) => {
+                        let value = reader.read(*scalar).ok_or_else(truncated)?;
+                        if is_vertex {
+                            columns[column].values.push(value as f32);
+                            column += 1;
+                        }
+                    }
+                    PlyProperty::List(name, count, item) => {
+                        let count = reader.read(*count).ok_or_else(truncated)? as usize;
+                        let mut items = Vec::new();
+                        for _ in 0..count {
+                            items.push(reader.read(*item).ok_or_else(truncated)? as u32);
+                        }
+                        // Polygons are triangulated as fans
+                        if is_face && (name == "vertex_indices" || name == "vertex_index") {
+                            for i in 2..items.len() {
+                                indices.extend_from_slice(&[items[0], items[i - 1], items[i]]);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+    let vertex_count = columns.first().map_or(0, |column| column.values.len());
+    if indices.iter().any(|&i| i as usize >= vertex_count) {
+        return Err(error("A face has a vertex index out of range".to_string()));
+    }
+    let streams = column_streams(&columns)
+        .ok_or_else(|| error("The vertices have no x, y and z properties".to_string()))?;
+    let mut data = GeometryData::interleave(streams, indices);
+    if data.indices.is_empty() {
+        data.draws[0].mode = DrawModeConfig::Points;
+    }
+    Ok(data)
+}
+
+fn load_stl(config: &ModelConfig) -> Result<GeometryData> {
+    let bytes = std::fs::read(&config.model).map_err(|err| Error::io(&config.model, err))?;
+    parse_stl(&config.model, &bytes)
+}
+
+/// Parse the triangles of an ascii or binary STL file, each vertex with the normal of its
+/// facet. Errors refer to the file at `path`
+fn parse_stl(path: &str, bytes: &[u8]) -> Result<GeometryData> {
+    let mut positions = Vec::new();
+    let mut normals = Vec::new();
+    // Binary files have an 80 byte header, a triangle count and 50 bytes per triangle.
+    // Some start with "solid" like ascii files, so check the size first
+    let binary_count = bytes
+        .get(80..84)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as usize);
+    match binary_count {
+        Some(count) if bytes.len() == 84 + 50 * count => {
+            for triangle in bytes[84..].chunks_exact(50) {
+                let floats: Vec<f32> = triangle[..48]
+                    .chunks_exact(4)
+                    .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+                    .collect();
+                stl_facet(&floats[..3], &floats[3..], &mut positions, &mut normals);
+            }
+        }
+        _ => {
+            let text = std::str::from_utf8(bytes)
+                .map_err(|_| Error::model(path, "The file is neither binary nor ascii STL"))?;
+            let mut normal = vec![0.0; 3];
+            let mut vertices = Vec::new();
+            for (line_index, line) in text.lines().enumerate() {
+                let words: Vec<&str> = line.split_whitespace().collect();
+                let bad_line = || Error::model(path, format!("Bad line {}", line_index + 1));
+                match words.as_slice() {
+                    ["facet", "normal", values @ ..] => {
+                        normal = parse_floats(values, 3).ok_or_else(bad_line)?
+                    }
+                    ["vertex", values @ ..] => {
+                        vertices.append(&mut parse_floats(values, 3).ok_or_else(bad_line)?)
+                    }
+                    ["endfacet"] => {
+                        if vertices.len() != 9 {
+                            return Err(bad_line());
+                        }
+                        stl_facet(&normal, &vertices, &mut positions, &mut normals);
+                        vertices.clear();
+                    }
+                    _ => (),
+                }
+            }
+        }
+    }
+    if positions.is_empty() {
+        return Err(Error::model(path, "The file has no facets"));
+    }
+    let streams = vec![("position", 3, positions), ("normal", 3, normals)];
+    Ok(GeometryData::interleave(streams, Vec::new()))
+}
+
+/// Append the vertices of a facet, computing its normal from the vertices when the file
+/// leaves it zeroed
+fn stl_facet(normal: &[f32], vertices: &[f32], positions: &mut Vec<f32>, normals: &mut Vec<f32>) {
+    let vertex = |i: usize| [vertices[3 * i], vertices[3 * i + 1], vertices[3 * i + 2]];
+    let mut normal = [normal[0], normal[1], normal[2]];
+    if dot(normal, normal) < std::f32::EPSILON {
+        let n = cross(sub(vertex(1), vertex(0)), sub(vertex(2), vertex(0)));
+        let length = dot(n, n).sqrt();
+        if length > std::f32::EPSILON {
+            normal = scale(n, 1.0 / length);
+        }
+    }
+    positions.extend_from_slice(&vertices[..9]);
+    for _ in 0..3 {
+        normals.extend_from_slice(&normal);
+    }
+}
+
+fn load_points(config: &ModelConfig) -> Result<GeometryData> {
+    let text =
+        std::fs::read_to_string(&config.model).map_err(|err| Error::io(&config.model, err))?;
+    parse_points(&config.model, &text)
+}
+
+/// Parse a point per line of an XYZ or CSV file, with values separated by commas or
+/// semicolons, or else by whitespace. A first line that isn't numbers names the columns,
+/// otherwise the columns are x, y, z, then red, green and blue, then column6 and on.
+/// Errors refer to the file at `path`
+fn parse_points(path: &str, text: &str) -> Result<GeometryData> {
+    let mut names = None;
+    let mut rows: Vec<Vec<f32>> = Vec::new();
+    for (line_index, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with("//") {
+            continue;
+        }
+        // CSV header names can have spaces
+        let fields: Vec<&str> = if line.contains(&[',', ';'][..]) {
+            line.split(&[',', ';'][..]).map(str::trim).collect()
+        } else {
+            line.split_whitespace().collect()
+        };
+        match fields.iter().map(|field| field.parse()).collect() {
+            Ok(row) => rows.push(row),
+            Err(_) if rows.is_empty() && names.is_none() => {
+                let header: Vec<String> = fields
+                    .iter()
+                    .map(|field| identifier(&field.trim_matches('"').to_lowercase()))
+                    .collect();
+                names = Some(header);
+            }
+            Err(_) => {
+                return Err(Error::model(
+                    path,
+                    format!("Line {} is not a row of numbers", line_index + 1),
+                ))
+            }
+        }
+    }
+    let width = rows.first().map_or(0, Vec::len);
+    if let Some(index) = rows.iter().position(|row| row.len() != width) {
+        return Err(Error::model(
+            path,
+            format!(
+                "Point {} has {} values instead of {}",
+                index + 1,
+                rows[index].len(),
+                width
+            ),
+        ));
+    }
+    let names = names.unwrap_or_else(|| {
+        let defaults = ["x", "y", "z", "red", "green", "blue"];
+        (0..width)
+            .map(|i| match defaults.get(i) {
+                Some(name) => name.to_string(),
+                None => format!("column{}", i),
+            })
+            .collect()
+    });
+    let columns: Vec<Column> = names
+        .into_iter()
+        .take(width)
+        .enumerate()
+        .map(|(i, name)| {
+            let values: Vec<f32> = rows.iter().map(|row| row[i]).collect();
+            // colors are either normalized or 0 to 255
+            let max = if values.iter().any(|&v| v > 1.0) {
+                255.0
+            } else {
+                1.0
+            };
+            Column { name, values, max }
+        })
+        .collect();
+    let streams = column_streams(&columns)
+        .ok_or_else(|| Error::model(path, "The points have no x, y and z columns"))?;
+    let mut data = GeometryData::interleave(streams, Vec::new());
+    data.draws[0].mode = DrawModeConfig::Points;
+    Ok(data)
+}
+
+/// Group columns into the vertex attributes they are the components of: position from
+/// x, y and z, normal from nx, ny and nz, texcoord from u and v or s and t, and a vec4
+/// color from red, green, blue and alpha or r, g, b and a, normalized to 0 to 1. Every
+/// other column is a float attribute with the name of the column. None when there is
+/// no position
+fn column_streams(columns: &[Column]) -> Option<Vec<(&str, u32, Vec<f32>)>> {
+    let find = |names: &[&str]| {
+        columns
+            .iter()
+            .position(|column| names.contains(&column.name.as_str()))
+    };
+    let groups = [
+        ("position", vec![vec!["x"], vec!["y"], vec!["z"]]),
+        ("normal", vec![vec!["nx"], vec!["ny"], vec!["nz"]]),
+        (
+            "texcoord",
+            vec![vec!["u", "s", "texture_u"], vec!["v", "t", "texture_v"]],
+        ),
+        (
+            "color",
+            vec![vec!["red", "r"], vec!["green", "g"], vec!["blue", "b"]],
+        ),
+    ];
+    let vertex_count = columns.first().map_or(0, |column| column.values.len());
+    let mut used = vec![false; columns.len()];
+    let mut streams = Vec::new();
+    for (name, group) in &groups {
+        let found: Option<Vec<usize>> = group.iter().map(|names| find(names)).collect();
+        let mut found = match found {
+            Some(found) => found,
+            None => continue,
+        };
+        let is_color = *name == "color";
+        // colors without alpha are opaque
+        if is_color {
+            if let Some(alpha) = find(&["alpha", "a"]) {
+                found.push(alpha);
+            }
+        }
+        let components = if is_color { 4 } else { found.len() };
+        let mut values = Vec::with_capacity(components * vertex_count);
+        for vertex in 0..vertex_count {
+            for &i in &found {
+                let column = &columns[i];
+                if is_color {
+                    values.push(column.values[vertex] / column.max);
+                } else {
+                    values.push(column.values[vertex]);
+                }
+            }
+            if found.len() < components {
+                values.push(1.0);
+            }
+        }
+        for &i in &found {
+            used[i] = true;
+        }
+        streams.push((*name, components as u32, values));
+    }
+    match streams.first() {
+        Some(&("position", _, _)) => (),
+        _ => return None,
+    }
+    for (column, used) in columns.iter().zip(used) {
+        if !used {
+            streams.push((column.name.as_str(), 1, column.values.clone()));
+        }
+    }
+    Some(streams)
+}
+
+/// The GLSL identifier of a property or column name
+fn identifier(name: &str) -> String {
+    let name: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    match name.chars().next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => name,
+        _ => format!("_{}", name),
+    }
+}
+
+/// Parse exactly count floats
+fn parse_floats(words: &[&str], count: usize) -> Option<Vec<f32>> {
+    if words.len() != count {
+        return None;
+    }
+    words.iter().map(|word| word.parse().ok()).collect()
+}
+
+/// The product of two column-major 4x4 matrices
+fn mul(a: &[f32; 16], b: &[f32; 16]) -> [f32; 16] {
+    let mut result = [0.0; 16];
+    for column in 0..4 {
+        for row in 0..4 {
+            result[4 * column + row] = (0..4).map(|k| a[4 * k + row] * b[4 * column + k]).sum();
+        }
+    }
+    result
+}
+
+fn add(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [a[0] + b[0], a[1] + b[1], a[2] + b[2]]
+}
+
+fn sub(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn scale(a: [f32; 3], s: f32) -> [f32; 3] {
+    [a[0] * s, a[1] * s, a[2] * s]
+}
+
+fn dot(a: [f32; 3], b: [f32; 3]) -> f32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn cross(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A quad with a color and an intensity per vertex, as (x, y, z, red, green, blue, intensity)
+    const QUAD: [(f32, f32, f32, u8, u8, u8, f32); 4] = [
+        (0.0, 0.0, 0.0, 255, 0, 0, 0.5),
+        (1.0, 0.0, 0.0, 0, 255, 0, 1.0),
+        (1.0, 1.0, 0.0, 0, 0, 255, 1.5),
+        (0.0, 1.0, 0.0, 255, 51, 255, 2.0),
+    ];
+
+    fn ply_header(format: &str) -> String {
+        format!(
+            "ply\nformat {} 1.0\ncomment a quad\nelement vertex 4\n\
+             property float x\nproperty float y\nproperty float z\n\
+             property uchar red\nproperty uchar green\nproperty uchar blue\n\
+             property float intensity\nelement face 1\n\
+             property list uchar int vertex_indices\nend_header\n",
+            format
+        )
+    }
+
+    fn binary_ply(big_endian: bool) -> Vec<u8> {
+        let format = if big_endian {
+            "binary_big_endian"
+        } else {
+            "binary_little_endian"
+        };
+        let mut bytes = ply_header(format).into_bytes();
+        let float = |bytes: &mut Vec<u8>, value: f32| {
+            if big_endian {
+                bytes.extend_from_slice(&value.to_be_bytes())
+            } else {
+                bytes.extend_from_slice(&value.to_le_bytes())
+            }
+        };
+        for &(x, y, z, r, g, b, intensity) in &QUAD {
+            float(&mut bytes, x);
+            float(&mut bytes, y);
+            float(&mut bytes, z);
+            bytes.extend_from_slice(&[r, g, b]);
+            float(&mut bytes, intensity);
+        }
+        bytes.push(4);
+        for index in 0..4i32 {
+            if big_endian {
+                bytes.extend_from_slice(&index.to_be_bytes())
+            } else {
+                bytes.extend_from_slice(&index.to_le_bytes())
+            }
+        }
+        bytes
+    }
+
+    fn assert_quad(data: &GeometryData) {
+        assert_eq!(data.layout(), "vec3 position, vec4 color, float intensity");
+        assert_eq!(data.vertex_count(), 4);
+        // The quad is triangulated as a fan
+        assert_eq!(data.indices, vec![0, 1, 2, 0, 2, 3]);
+        assert_eq!(data.draws[0].mode, DrawModeConfig::Triangles);
+        assert_eq!(data.draws[0].count, 6);
+        assert_eq!(&data.buffer[..8], &[0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 0.5]);
+        assert_eq!(
+            &data.buffer[24..],
+            &[0.0, 1.0, 0.0, 1.0, 0.2, 1.0, 1.0, 2.0]
+        );
+    }
+
+    #[test]
+    fn ascii_ply() {
+        let mut text = ply_header("ascii");
+        for &(x, y, z, r, g, b, intensity) in &QUAD {
+            text.push_str(&format!(
+                "{} {} {} {} {} {} {}\n",
+                x, y, z, r, g, b, intensity
+            ));
+        }
+        text.push_str("4 0 1 2 3\n");
+        assert_quad(&parse_ply("quad.ply", text.as_bytes()).unwrap());
+    }
+
+    #[test]
+    fn binary_little_endian_ply() {
+        assert_quad(&parse_ply("quad.ply", &binary_ply(false)).unwrap());
+    }
+
+    #[test]
+    fn binary_big_endian_ply() {
+        assert_quad(&parse_ply("quad.ply", &binary_ply(true)).unwrap());
+    }
+
+    #[test]
+    fn ply_without_faces_draws_points() {
+        let text = "ply\nformat ascii 1.0\nelement vertex 2\nproperty double x\n\
+                    property double y\nproperty double z\nend_header\n0 0 0\n1 2 3\n";
+        let data = parse_ply("points.ply", text.as_bytes()).unwrap();
+        assert_eq!(data.layout(), "vec3 position");
+        assert!(data.indices.is_empty());
+        assert_eq!(data.draws[0].mode, DrawModeConfig::Points);
+        assert_eq!(data.buffer, vec![0.0, 0.0, 0.0, 1.0, 2.0, 3.0]);
+    }
+
+    #[test]
+    fn truncated_ply_is_an_error() {
+        let mut bytes = binary_ply(false);
+        bytes.truncate(bytes.len() - 1);
+        assert!(parse_ply("quad.ply", &bytes).is_err());
+        // A corrupt count fails when the elements run out instead of allocating
+        let text = ply_header("ascii").replace("element vertex 4", "element vertex 4000000000");
+        assert!(parse_ply("quad.ply", text.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn binary_stl_with_solid_header() {
+        let mut bytes = b"solid but actually binary".to_vec();
+        bytes.resize(80, b' ');
+        bytes.extend_from_slice(&1u32.to_le_bytes());
+        let floats = [
+            0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 2.0, 0.0, 0.0, 0.0, 2.0, 0.0f32,
+        ];
+        for value in &floats {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        bytes.extend_from_slice(&[0, 0]);
+        let data = parse_stl("facet.stl", &bytes).unwrap();
+        assert_eq!(data.layout(), "vec3 position, vec3 normal");
+        assert_eq!(data.vertex_count(), 3);
+        assert_eq!(
+            data.buffer,
+            vec![
+                0.0, 0.0, 0.0, 0.0, 0.0, 1.0, //
+                2.0, 0.0, 0.0, 0.0, 0.0, 1.0, //
+                0.0, 2.0, 0.0, 0.0, 0.0, 1.0,
+            ]
+        );
+    }
+
+    #[test]
+    fn ascii_stl_computes_zero_normals() {
+        let text = "solid facet\n\
+                    facet normal 0 0 0\n\
+                    outer loop\n\
+                    vertex 0 0 0\n\
+                    vertex 0 3 0\n\
+                    vertex 0 0 3\n\
+                    endloop\n\
+                    endfacet\n\
+                    endsolid facet\n";
+        let data = parse_stl("facet.stl", text.as_bytes()).unwrap();
+        assert_eq!(data.vertex_count(), 3);
+        for vertex in 0..3 {
+            assert_eq!(
+                &data.buffer[6 * vertex + 3..6 * vertex + 6],
+                &[1.0, 0.0, 0.0]
+            );
+        }
+        assert!(parse_stl("empty.stl", b"solid empty\nendsolid empty\n").is_err());
+    }
+
+    #[test]
+    fn csv_with_header() {
+        let text = "X, Y, Z, \"Temp C\"\n1, 2, 3, 20.5\n4; 5; 6; 25\n";
+        let data = parse_points("points.csv", text).unwrap();
+        assert_eq!(data.layout(), "vec3 position, float temp_c");
+        assert_eq!(data.draws[0].mode, DrawModeConfig::Points);
+        assert_eq!(data.buffer, vec![1.0, 2.0, 3.0, 20.5, 4.0, 5.0, 6.0, 25.0]);
+    }
+
+    #[test]
+    fn headerless_xyz() {
+        let text = "# x y z r g b\n0 0 0 255 51 0\n1 1 1 0 0 255\n";
+        let data = parse_points("points.xyz", text).unwrap();
+        assert_eq!(data.layout(), "vec3 position, vec4 color");
+        // 0 to 255 colors are normalized, and opaque without alpha
+        assert_eq!(
+            data.buffer,
+            vec![
+                0.0, 0.0, 0.0, 1.0, 0.2, 0.0, 1.0, //
+                1.0, 1.0, 1.0, 0.0, 0.0, 1.0, 1.0,
+            ]
+        );
+    }
+
+    #[test]
+    fn normalized_colors_are_kept() {
+        let data = parse_points("points.xyz", "0 0 0 0.5 0.25 1\n").unwrap();
+        assert_eq!(data.buffer, vec![0.0, 0.0, 0.0, 0.5, 0.25, 1.0, 1.0]);
+    }
+
+    #[test]
+    fn ragged_points_are_an_error() {
+        assert!(parse_points("points.xyz", "0 0 0\n1 1\n").is_err());
+        assert!(parse_points("points.csv", "a,b\n1,2\n").is_err());
+    }
+
+    fn base64(bytes: &[u8]) -> String {
+        const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+        let mut encoded = String::new();
+        for chunk in bytes.chunks(3) {
+            let b = [
+                chunk[0],
+                *chunk.get(1).unwrap_or(&0),
+                *chunk.get(2).unwrap_or(&0),
+            ];
+            let n = (u32::from(b[0]) << 16) | (u32::from(b[1]) << 8) | u32::from(b[2]);
+            for i in 0..4 {
+                if i <= chunk.len() {
+                    encoded.push(ALPHABET[(n >> (18 - 6 * i) & 63) as usize] as char);
+                } else {
+                    encoded.push('=');
+                }
+            }
+        }
+        encoded
+    }
+
+    /// A mesh of two triangles, the second without normals, drawn by a translated node
+    /// and by its scaled child. The buffer is embedded as a data URI.
+    fn write_gltf(name: &str) -> String {
+        let triangle = [0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0f32];
+        let normals = [0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0f32];
+        let mut buffer = Vec::new();
+        for value in triangle.iter().chain(&normals).chain(&triangle) {
+            buffer.extend_from_slice(&value.to_le_bytes());
+        }
+        for index in &[0u16, 1, 2, 0, 0, 2, 1] {
+            buffer.extend_from_slice(&index.to_le_bytes());
+        }
+        let gltf = format!(
+            r#"{{
+  "asset": {{"version": "2.0"}},
+  "buffers": [{{"byteLength": {}, "uri": "data:application/octet-stream;base64,{}"}}],
+  "bufferViews": [
+    {{"buffer": 0, "byteOffset": 0, "byteLength": 108}},
+    {{"buffer": 0, "byteOffset": 108, "byteLength": 6}},
+    {{"buffer": 0, "byteOffset": 116, "byteLength": 6}}
+  ],
+  "accessors": [
+    {{"bufferView": 0, "byteOffset": 0, "componentType": 5126, "count": 3, "type": "VEC3",
+      "min": [0, 0, 0], "max": [1, 1, 0]}},
+    {{"bufferView": 0, "byteOffset": 36, "componentType": 5126, "count": 3, "type": "VEC3"}},
+    {{"bufferView": 0, "byteOffset": 72, "componentType": 5126, "count": 3, "type": "VEC3",
+      "min": [0, 0, 0], "max": [1, 1, 0]}},
+    {{"bufferView": 1, "componentType": 5123, "count": 3, "type": "SCALAR"}},
+    {{"bufferView": 2, "componentType": 5123, "count": 3, "type": "SCALAR"}}
+  ],
+  "meshes": [{{"primitives": [
+    {{"attributes": {{"POSITION": 0, "NORMAL": 1}}, "indices": 3}},
+    {{"attributes": {{"POSITION": 2}}, "indices": 4}}
+  ]}}],
+  "nodes": [
+    {{"name": "parent", "mesh": 0, "translation": [1, 0, 0], "children": [1]}},
+    {{"name": "child", "mesh": 0, "scale": [2, 2, 2]}}
+  ],
+  "scenes": [{{"nodes": [0]}}],
+  "scene": 0
+}}"#,
+            buffer.len(),
+            base64(&buffer)
+        );
+        let path = std::env::temp_dir().join(format!("grimoire-{}-{}", std::process::id(), name));
+        std::fs::write(&path, gltf).unwrap();
+        path.to_str().unwrap().to_string()
+    }
+
+    fn gltf_config(model: &str, object: Option<&str>) -> ModelConfig {
+        ModelConfig {
+            model: model.to_string(),
+            object: object.map(String::from),
+            mode: None,
+        }
+    }
+
+    const TRANSLATE: [f32; 16] = [
+        1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 1.0, 0.0, 0.0, 1.0,
+    ];
+    const SCALE: [f32; 16] = [
+        2.0, 0.0, 0.0, 0.0, 0.0, 2.0, 0.0, 0.0, 0.0, 0.0, 2.0, 0.0, 0.0, 0.0, 0.0, 1.0,
+    ];
+
+    #[test]
+    fn gltf_scene() {
+        let path = write_gltf("scene.gltf");
+        let data = load_gltf(&gltf_config(&path, None));
+        std::fs::remove_file(&path).unwrap();
+        let data = data.unwrap();
+        assert_eq!(data.layout(), "vec3 position, vec3 normal");
+        assert_eq!(data.vertex_count(), 6);
+        // The second primitive's vertices follow the first's, with zeroed normals
+        assert_eq!(data.indices, vec![0, 1, 2, 3, 5, 4]);
+        assert_eq!(&data.buffer[6..12], &[1.0, 0.0, 0.0, 0.0, 0.0, 1.0]);
+        assert_eq!(&data.buffer[24..30], &[1.0, 0.0, 0.0, 0.0, 0.0, 0.0]);
+        let draws: Vec<(u32, u32, [f32; 16])> = data
+            .draws
+            .iter()
+            .map(|draw| (draw.first, draw.count, draw.transform))
+            .collect();
+        // The child is scaled, then translated by its parent
+        let child = [
+            2.0, 0.0, 0.0, 0.0, 0.0, 2.0, 0.0, 0.0, 0.0, 0.0, 2.0, 0.0, 1.0, 0.0, 0.0, 1.0,
+        ];
+        assert_eq!(
+            draws,
+            vec![
+                (0, 3, TRANSLATE),
+                (3, 3, TRANSLATE),
+                (0, 3, child),
+                (3, 3, child)
+            ]
+        );
+        assert!(data.textures.is_empty());
+    }
+
+    #[test]
+    fn gltf_object() {
+        let path = write_gltf("object.gltf");
+        let child = load_gltf(&gltf_config(&path, Some("child")));
+        let missing = load_gltf(&gltf_config(&path, Some("missing")));
+        // Embedded buffers are not dependencies
+        let dependencies = dependencies(&gltf_config(&path, None));
+        std::fs::remove_file(&path).unwrap();
+        let transforms: Vec<[f32; 16]> = child
+            .unwrap()
+            .draws
+            .iter()
+            .map(|draw| draw.transform)
+            .collect();
+        assert_eq!(transforms, vec![SCALE, SCALE]);
+        assert!(missing.is_err());
+        assert!(dependencies.is_empty());
+    }
+
+    #[test]
+    fn mul_composes_column_major_matrices() {
+        assert_eq!(mul(&IDENTITY, &SCALE), SCALE);
+        assert_eq!(mul(&SCALE, &IDENTITY), SCALE);
+        // Translating after scaling keeps the translation, scaling after translating
+        // scales it
+        let translated_then_scaled = [
+            2.0, 0.0, 0.0, 0.0, 0.0, 2.0, 0.0, 0.0, 0.0, 0.0, 2.0, 0.0, 2.0, 0.0, 0.0, 1.0,
+        ];
+        assert_eq!(mul(&SCALE, &TRANSLATE), translated_then_scaled);
+        assert_eq!(mul(&TRANSLATE, &SCALE)[12..], [1.0, 0.0, 0.0, 1.0]);
+    }
+}
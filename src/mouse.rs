@@ -21,6 +21,10 @@ impl Mouse {
         Default::default()
     }
 
+    pub fn state(&self) -> [f32; 4] {
+        self.state
+    }
+
     pub fn update(&mut self, buttons: HashSet<MouseButton>, x: u32, y: u32) -> [f32; 4] {
         let new_buttons = &buttons - &self.buttons_last_update;
         let old_buttons = &self.buttons_last_update - &buttons;
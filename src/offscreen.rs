@@ -0,0 +1,56 @@
+use crate::gl;
+use crate::gl::{GLRc, GLuint};
+
+/// A framebuffer object with an RGBA8 color and 24-bit depth attachment
+/// that stands in for the window's default framebuffer
+#[derive(Debug)]
+pub struct OffscreenFramebuffer {
+    pub framebuffer: GLuint,
+    color: GLuint,
+    depth: GLuint,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl OffscreenFramebuffer {
+    pub fn new(gl: &GLRc, width: u32, height: u32) -> Self {
+        let framebuffer = gl::create_framebuffer(gl);
+        let color = gl::create_renderbuffer(gl, gl::RGBA8, width as i32, height as i32);
+        let depth = gl::create_renderbuffer(gl, gl::DEPTH_COMPONENT24, width as i32, height as i32);
+        gl::attach_renderbuffer_to_framebuffer(gl, framebuffer, color, gl::COLOR_ATTACHMENT0);
+        gl::attach_renderbuffer_to_framebuffer(gl, framebuffer, depth, gl::DEPTH_ATTACHMENT);
+        gl.draw_buffers(&[gl::COLOR_ATTACHMENT0]);
+        let fbo_status = gl::check_framebuffer_status(gl, framebuffer);
+        if fbo_status != gl::FRAMEBUFFER_COMPLETE {
+            error!(
+                "[PLATFORM] Error creating offscreen framebuffer. status: {:?}",
+                fbo_status
+            );
+        }
+        gl.bind_renderbuffer(gl::RENDERBUFFER, 0);
+        gl.bind_framebuffer(gl::FRAMEBUFFER, 0);
+        Self {
+            framebuffer,
+            color,
+            depth,
+            width,
+            height,
+        }
+    }
+
+    pub fn resize(&mut self, gl: &GLRc, width: u32, height: u32) {
+        if width == self.width && height == self.height {
+            return;
+        }
+        self.delete(gl);
+        *self = OffscreenFramebuffer::new(gl, width, height);
+    }
+
+    pub fn delete(&mut self, gl: &GLRc) {
+        gl.delete_renderbuffers(&[self.color, self.depth]);
+        gl.delete_framebuffers(&[self.framebuffer]);
+        self.framebuffer = 0;
+        self.color = 0;
+        self.depth = 0;
+    }
+}
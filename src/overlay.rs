@@ -0,0 +1,443 @@
+use crate::diagnostics::Diagnostic;
+use crate::error::{Error, Result};
+use crate::gl;
+use crate::gl::{GLRc, GLint, GLuint, GLvoid};
+
+/// Width and height of a glyph in the built-in font, in texels
+const GLYPH_SIZE: u32 = 8;
+/// Vertical distance between the tops of two lines of text, in glyph texels
+const LINE_HEIGHT: u32 = 10;
+/// Margin around text, in glyph texels
+const MARGIN: u32 = 4;
+/// position (2), texcoord (2), color (4)
+const FLOATS_PER_VERTEX: usize = 8;
+/// Index of the fully lit cell that follows the printable ASCII glyphs in the font texture,
+/// used to draw solid backgrounds
+const SOLID_GLYPH: usize = FONT.len();
+
+const TEXT_COLOR: [f32; 4] = [0.9, 0.9, 0.9, 1.0];
+const ERROR_COLOR: [f32; 4] = [1.0, 0.45, 0.4, 1.0];
+const WARNING_COLOR: [f32; 4] = [1.0, 0.8, 0.3, 1.0];
+const BACKGROUND_COLOR: [f32; 4] = [0.0, 0.0, 0.0, 0.75];
+
+const VERTEX_SHADER: &str = "
+in vec2 position;
+in vec2 texcoord;
+in vec4 color;
+uniform vec2 resolution;
+out vec2 uv;
+out vec4 tint;
+void main() {
+    uv = texcoord;
+    tint = color;
+    vec2 ndc = position / resolution * 2.0 - 1.0;
+    gl_Position = vec4(ndc.x, -ndc.y, 0.0, 1.0);
+}
+";
+
+const FRAGMENT_SHADER: &str = "
+#ifdef GL_ES
+precision mediump float;
+#endif
+uniform sampler2D font;
+in vec2 uv;
+in vec4 tint;
+out vec4 fragColor;
+void main() {
+    fragColor = vec4(tint.rgb, tint.a * texture(font, uv).r);
+}
+";
+
+/// Draws the current config or shader errors and a status line over the window.
+///
+/// Text is drawn with a built-in 8x8 bitmap font by a GL pass of its own, after the
+/// effect has drawn and before the window is swapped, so it never shows up in
+/// recordings, captures, or buffers the effect samples.
+pub struct Overlay {
+    program: GLuint,
+    vertex_shader: GLuint,
+    fragment_shader: GLuint,
+    vertex_array_object: GLuint,
+    vertex_buffer: GLuint,
+    texture: GLuint,
+    resolution_uniform_loc: GLint,
+    font_uniform_loc: GLint,
+    position_loc: GLint,
+    texcoord_loc: GLint,
+    color_loc: GLint,
+    vertices: Vec<f32>,
+    /// Lines of text describing the current errors, and the color of each
+    errors: Vec<(String, [f32; 4])>,
+    show_status: bool,
+}
+
+impl Overlay {
+    /// `glsl_version` is the #version directive the effect shaders are compiled with
+    pub fn new(gl: &GLRc, glsl_version: &str) -> Result<Self> {
+        let vertex_source = format!("{}\n{}", glsl_version, VERTEX_SHADER);
+        let vertex_shader = gl::create_shader(gl, gl::VERTEX_SHADER, &[vertex_source.as_bytes()])
+            .map_err(|err| Error::glsl_vertex(err.as_str(), "overlay"))?;
+        let fragment_source = format!("{}\n{}", glsl_version, FRAGMENT_SHADER);
+        let fragment_shader =
+            gl::create_shader(gl, gl::FRAGMENT_SHADER, &[fragment_source.as_bytes()]).map_err(
+                |err| {
+                    gl.delete_shader(vertex_shader);
+                    Error::glsl_fragment(err.as_str(), "overlay")
+                },
+            )?;
+        let program =
+            gl::create_program(gl, vertex_shader, fragment_shader, None).map_err(|err| {
+                gl.delete_shader(vertex_shader);
+                gl.delete_shader(fragment_shader);
+                Error::glsl_program(err.as_str(), "overlay", "overlay")
+            })?;
+
+        // One row of glyphs, each texel either 0 or 255
+        let glyph_count = FONT.len() + 1;
+        let width = glyph_count * GLYPH_SIZE as usize;
+        let mut texels = vec![0u8; width * GLYPH_SIZE as usize];
+        for glyph in 0..glyph_count {
+            for row in 0..GLYPH_SIZE as usize {
+                let bits = FONT.get(glyph).map_or(0xFF, |rows| rows[row]);
+                for column in 0..GLYPH_SIZE as usize {
+                    // The least significant bit is the leftmost texel
+                    if bits & (1 << column) != 0 {
+                        texels[row * width + glyph * GLYPH_SIZE as usize + column] = 255;
+                    }
+                }
+            }
+        }
+        gl.pixel_store_i(gl::UNPACK_ALIGNMENT, 1);
+        let texture = gl::create_texture2d(
+            gl,
+            gl::R8 as GLint,
+            width as i32,
+            GLYPH_SIZE as i32,
+            gl::RED,
+            gl::UNSIGNED_BYTE,
+            Some(&texels),
+        );
+        gl.tex_parameter_i(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::NEAREST as GLint);
+        gl.tex_parameter_i(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::NEAREST as GLint);
+        gl.tex_parameter_i(
+            gl::TEXTURE_2D,
+            gl::TEXTURE_WRAP_S,
+            gl::CLAMP_TO_EDGE as GLint,
+        );
+        gl.tex_parameter_i(
+            gl::TEXTURE_2D,
+            gl::TEXTURE_WRAP_T,
+            gl::CLAMP_TO_EDGE as GLint,
+        );
+        gl.bind_texture(gl::TEXTURE_2D, 0);
+
+        Ok(Self {
+            program,
+            vertex_shader,
+            fragment_shader,
+            vertex_array_object: gl::create_vao(gl),
+            vertex_buffer: gl::create_buffer(gl),
+            texture,
+            resolution_uniform_loc: gl.get_uniform_location(program, "resolution"),
+            font_uniform_loc: gl.get_uniform_location(program, "font"),
+            position_loc: gl.get_attrib_location(program, "position"),
+            texcoord_loc: gl.get_attrib_location(program, "texcoord"),
+            color_loc: gl.get_attrib_location(program, "color"),
+            vertices: Vec::new(),
+            errors: Vec::new(),
+            show_status: false,
+        })
+    }
+
+    /// Show the errors among `diagnostics` in place of the current errors.
+    /// Does nothing if none of the diagnostics are errors.
+    pub fn set_errors(&mut self, diagnostics: &[Diagnostic]) {
+        if !diagnostics.iter().any(Diagnostic::is_error) {
+            return;
+        }
+        self.errors.clear();
+        for diagnostic in diagnostics {
+            let color = if diagnostic.is_error() {
+                ERROR_COLOR
+            } else {
+                WARNING_COLOR
+            };
+            for (index, line) in diagnostic.to_string().lines().enumerate() {
+                let color = if index == 0 { color } else { TEXT_COLOR };
+                self.errors.push((line.to_string(), color));
+            }
+        }
+    }
+
+    pub fn clear_errors(&mut self) {
+        self.errors.clear();
+    }
+
+    /// Toggle showing the status line when there are no errors
+    pub fn toggle_status(&mut self) {
+        self.show_status = !self.show_status;
+    }
+
+    /// Draw the errors, if any, at the top of the framebuffer and the `status` line at
+    /// the bottom. The status line is shown whenever there are errors, or if toggled on.
+    pub fn draw(&mut self, gl: &GLRc, framebuffer: GLuint, width: u32, height: u32, status: &str) {
+        if self.errors.is_empty() && !self.show_status {
+            return;
+        }
+        // Double the glyph size on high resolution framebuffers
+        let scale = if height >= 1200 { 2 } else { 1 };
+        let glyph = GLYPH_SIZE * scale;
+        let line_height = LINE_HEIGHT * scale;
+        let margin = MARGIN * scale;
+        let columns = (width.saturating_sub(2 * margin) / glyph).max(1) as usize;
+        self.vertices.clear();
+
+        if !self.errors.is_empty() {
+            // Leave room for the status line
+            let max_lines =
+                (height.saturating_sub(2 * margin + 2 * line_height) / line_height).max(1) as usize;
+            let mut lines: Vec<(String, [f32; 4])> = Vec::new();
+            for (text, color) in &self.errors {
+                for chunk in wrap(text, columns) {
+                    lines.push((chunk, *color));
+                }
+            }
+            if lines.len() > max_lines {
+                lines.truncate(max_lines - 1);
+                lines.push(("...".to_string(), TEXT_COLOR));
+            }
+            let panel_height = lines.len() as u32 * line_height + 2 * margin;
+            self.push_rect(0, 0, width, panel_height, BACKGROUND_COLOR);
+            for (index, (text, color)) in lines.iter().enumerate() {
+                let y = margin + index as u32 * line_height;
+                self.push_text(margin, y, glyph, text, *color);
+            }
+        }
+
+        let status_top = height.saturating_sub(line_height + 2 * margin);
+        self.push_rect(0, status_top, width, height, BACKGROUND_COLOR);
+        let status: String = status.chars().take(columns).collect();
+        self.push_text(margin, status_top + margin, glyph, &status, TEXT_COLOR);
+
+        gl.bind_framebuffer(gl::FRAMEBUFFER, framebuffer);
+        gl.viewport(0, 0, width as i32, height as i32);
+        gl.disable(gl::DEPTH_TEST);
+        gl.enable(gl::BLEND);
+        gl.blend_func(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
+        gl.use_program(self.program);
+        gl.uniform_2f(self.resolution_uniform_loc, width as f32, height as f32);
+        gl.active_texture(gl::TEXTURE0);
+        gl.bind_texture(gl::TEXTURE_2D, self.texture);
+        gl.uniform_1i(self.font_uniform_loc, 0);
+        gl.bind_vertex_array(self.vertex_array_object);
+        gl.bind_buffer(gl::ARRAY_BUFFER, self.vertex_buffer);
+        gl.buffer_data_untyped(
+            gl::ARRAY_BUFFER,
+            (self.vertices.len() * std::mem::size_of::<f32>()) as isize,
+            self.vertices.as_ptr() as *const GLvoid,
+            gl::STREAM_DRAW,
+        );
+        let stride = (FLOATS_PER_VERTEX * std::mem::size_of::<f32>()) as i32;
+        let attributes = [
+            (self.position_loc, 2, 0),
+            (self.texcoord_loc, 2, 2),
+            (self.color_loc, 4, 4),
+        ];
+        for (loc, size, offset) in attributes.iter() {
+            if *loc >= 0 {
+                gl.enable_vertex_attrib_array(*loc as u32);
+                gl.vertex_attrib_pointer(
+                    *loc as u32,
+                    *size,
+                    gl::FLOAT,
+                    false,
+                    stride,
+                    (*offset * std::mem::size_of::<f32>()) as u32,
+                );
+            }
+        }
+        gl.draw_arrays(
+            gl::TRIANGLES,
+            0,
+            (self.vertices.len() / FLOATS_PER_VERTEX) as i32,
+        );
+        gl.bind_buffer(gl::ARRAY_BUFFER, 0);
+        gl.bind_vertex_array(0);
+        gl.bind_texture(gl::TEXTURE_2D, 0);
+        gl.use_program(0);
+        gl.disable(gl::BLEND);
+    }
+
+    pub fn delete(&mut self, gl: &GLRc) {
+        gl.delete_program(self.program);
+        gl.delete_shader(self.vertex_shader);
+        gl.delete_shader(self.fragment_shader);
+        gl.delete_vertex_arrays(&[self.vertex_array_object]);
+        gl.delete_buffers(&[self.vertex_buffer]);
+        gl.delete_textures(&[self.texture]);
+    }
+
+    /// A solid rectangle, in pixels from the top left corner
+    fn push_rect(&mut self, x0: u32, y0: u32, x1: u32, y1: u32, color: [f32; 4]) {
+        self.push_quad(
+            [x0 as f32, y0 as f32, x1 as f32, y1 as f32],
+            glyph_texcoords(SOLID_GLYPH),
+            color,
+        );
+    }
+
+    /// A line of text with its top left corner at (x, y). Characters outside of
+    /// printable ASCII are drawn as '?'.
+    fn push_text(&mut self, x: u32, y: u32, glyph: u32, text: &str, color: [f32; 4]) {
+        for (index, c) in text.chars().enumerate() {
+            let c = if c == '\t' { ' ' } else { c };
+            let glyph_index = if (' '..='~').contains(&c) {
+                c as usize - ' ' as usize
+            } else {
+                '?' as usize - ' ' as usize
+            };
+            let x0 = x + index as u32 * glyph;
+            self.push_quad(
+                [x0 as f32, y as f32, (x0 + glyph) as f32, (y + glyph) as f32],
+                glyph_texcoords(glyph_index),
+                color,
+            );
+        }
+    }
+
+    fn push_quad(&mut self, rect: [f32; 4], uv: [f32; 4], color: [f32; 4]) {
+        let [x0, y0, x1, y1] = rect;
+        let [u0, v0, u1, v1] = uv;
+        for (x, y, u, v) in &[
+            (x0, y0, u0, v0),
+            (x1, y0, u1, v0),
+            (x1, y1, u1, v1),
+            (x0, y0, u0, v0),
+            (x1, y1, u1, v1),
+            (x0, y1, u0, v1),
+        ] {
+            self.vertices.extend_from_slice(&[*x, *y, *u, *v]);
+            self.vertices.extend_from_slice(&color);
+        }
+    }
+}
+
+/// The (u0, v0, u1, v1) texture coordinates of a glyph in the font texture
+fn glyph_texcoords(glyph: usize) -> [f32; 4] {
+    let count = (FONT.len() + 1) as f32;
+    [glyph as f32 / count, 0.0, (glyph + 1) as f32 / count, 1.0]
+}
+
+/// Split a line into chunks of at most `columns` characters
+fn wrap(text: &str, columns: usize) -> Vec<String> {
+    let chars: Vec<char> = text.chars().collect();
+    if chars.is_empty() {
+        return vec![String::new()];
+    }
+    chars
+        .chunks(columns)
+        .map(|chunk| chunk.iter().collect())
+        .collect()
+}
+
+/// 8x8 glyphs for printable ASCII, U+0020 to U+007E, one byte per row from top to bottom
+/// with the least significant bit as the leftmost pixel. Derived from the public domain
+/// font8x8 by Daniel Hepper, itself based on the IBM PC BIOS font.
+#[rustfmt::skip]
+const FONT: [[u8; 8]; 95] = [
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // U+0020 (space)
+    [0x18, 0x3C, 0x3C, 0x18, 0x18, 0x00, 0x18, 0x00], // U+0021 (!)
+    [0x36, 0x36, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // U+0022 (")
+    [0x36, 0x36, 0x7F, 0x36, 0x7F, 0x36, 0x36, 0x00], // U+0023 (#)
+    [0x0C, 0x3E, 0x03, 0x1E, 0x30, 0x1F, 0x0C, 0x00], // U+0024 ($)
+    [0x00, 0x63, 0x33, 0x18, 0x0C, 0x66, 0x63, 0x00], // U+0025 (%)
+    [0x1C, 0x36, 0x1C, 0x6E, 0x3B, 0x33, 0x6E, 0x00], // U+0026 (&)
+    [0x06, 0x06, 0x03, 0x00, 0x00, 0x00, 0x00, 0x00], // U+0027 (')
+    [0x18, 0x0C, 0x06, 0x06, 0x06, 0x0C, 0x18, 0x00], // U+0028 (()
+    [0x06, 0x0C, 0x18, 0x18, 0x18, 0x0C, 0x06, 0x00], // U+0029 ())
+    [0x00, 0x66, 0x3C, 0xFF, 0x3C, 0x66, 0x00, 0x00], // U+002A (*)
+    [0x00, 0x0C, 0x0C, 0x3F, 0x0C, 0x0C, 0x00, 0x00], // U+002B (+)
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x0C, 0x0C, 0x06], // U+002C (,)
+    [0x00, 0x00, 0x00, 0x3F, 0x00, 0x00, 0x00, 0x00], // U+002D (-)
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x0C, 0x0C, 0x00], // U+002E (.)
+    [0x60, 0x30, 0x18, 0x0C, 0x06, 0x03, 0x01, 0x00], // U+002F (/)
+    [0x3E, 0x63, 0x73, 0x7B, 0x6F, 0x67, 0x3E, 0x00], // U+0030 (0)
+    [0x0C, 0x0E, 0x0C, 0x0C, 0x0C, 0x0C, 0x3F, 0x00], // U+0031 (1)
+    [0x1E, 0x33, 0x30, 0x1C, 0x06, 0x33, 0x3F, 0x00], // U+0032 (2)
+    [0x1E, 0x33, 0x30, 0x1C, 0x30, 0x33, 0x1E, 0x00], // U+0033 (3)
+    [0x38, 0x3C, 0x36, 0x33, 0x7F, 0x30, 0x78, 0x00], // U+0034 (4)
+    [0x3F, 0x03, 0x1F, 0x30, 0x30, 0x33, 0x1E, 0x00], // U+0035 (5)
+    [0x1C, 0x06, 0x03, 0x1F, 0x33, 0x33, 0x1E, 0x00], // U+0036 (6)
+    [0x3F, 0x33, 0x30, 0x18, 0x0C, 0x0C, 0x0C, 0x00], // U+0037 (7)
+    [0x1E, 0x33, 0x33, 0x1E, 0x33, 0x33, 0x1E, 0x00], // U+0038 (8)
+    [0x1E, 0x33, 0x33, 0x3E, 0x30, 0x18, 0x0E, 0x00], // U+0039 (9)
+    [0x00, 0x0C, 0x0C, 0x00, 0x00, 0x0C, 0x0C, 0x00], // U+003A (:)
+    [0x00, 0x0C, 0x0C, 0x00, 0x00, 0x0C, 0x0C, 0x06], // U+003B (;)
+    [0x18, 0x0C, 0x06, 0x03, 0x06, 0x0C, 0x18, 0x00], // U+003C (<)
+    [0x00, 0x00, 0x3F, 0x00, 0x00, 0x3F, 0x00, 0x00], // U+003D (=)
+    [0x06, 0x0C, 0x18, 0x30, 0x18, 0x0C, 0x06, 0x00], // U+003E (>)
+    [0x1E, 0x33, 0x30, 0x18, 0x0C, 0x00, 0x0C, 0x00], // U+003F (?)
+    [0x3E, 0x63, 0x7B, 0x7B, 0x7B, 0x03, 0x1E, 0x00], // U+0040 (@)
+    [0x0C, 0x1E, 0x33, 0x33, 0x3F, 0x33, 0x33, 0x00], // U+0041 (A)
+    [0x3F, 0x66, 0x66, 0x3E, 0x66, 0x66, 0x3F, 0x00], // U+0042 (B)
+    [0x3C, 0x66, 0x03, 0x03, 0x03, 0x66, 0x3C, 0x00], // U+0043 (C)
+    [0x1F, 0x36, 0x66, 0x66, 0x66, 0x36, 0x1F, 0x00], // U+0044 (D)
+    [0x7F, 0x46, 0x16, 0x1E, 0x16, 0x46, 0x7F, 0x00], // U+0045 (E)
+    [0x7F, 0x46, 0x16, 0x1E, 0x16, 0x06, 0x0F, 0x00], // U+0046 (F)
+    [0x3C, 0x66, 0x03, 0x03, 0x73, 0x66, 0x7C, 0x00], // U+0047 (G)
+    [0x33, 0x33, 0x33, 0x3F, 0x33, 0x33, 0x33, 0x00], // U+0048 (H)
+    [0x1E, 0x0C, 0x0C, 0x0C, 0x0C, 0x0C, 0x1E, 0x00], // U+0049 (I)
+    [0x78, 0x30, 0x30, 0x30, 0x33, 0x33, 0x1E, 0x00], // U+004A (J)
+    [0x67, 0x66, 0x36, 0x1E, 0x36, 0x66, 0x67, 0x00], // U+004B (K)
+    [0x0F, 0x06, 0x06, 0x06, 0x46, 0x66, 0x7F, 0x00], // U+004C (L)
+    [0x63, 0x77, 0x7F, 0x7F, 0x6B, 0x63, 0x63, 0x00], // U+004D (M)
+    [0x63, 0x67, 0x6F, 0x7B, 0x73, 0x63, 0x63, 0x00], // U+004E (N)
+    [0x1C, 0x36, 0x63, 0x63, 0x63, 0x36, 0x1C, 0x00], // U+004F (O)
+    [0x3F, 0x66, 0x66, 0x3E, 0x06, 0x06, 0x0F, 0x00], // U+0050 (P)
+    [0x1E, 0x33, 0x33, 0x33, 0x3B, 0x1E, 0x38, 0x00], // U+0051 (Q)
+    [0x3F, 0x66, 0x66, 0x3E, 0x36, 0x66, 0x67, 0x00], // U+0052 (R)
+    [0x1E, 0x33, 0x07, 0x0E, 0x38, 0x33, 0x1E, 0x00], // U+0053 (S)
+    [0x3F, 0x2D, 0x0C, 0x0C, 0x0C, 0x0C, 0x1E, 0x00], // U+0054 (T)
+    [0x33, 0x33, 0x33, 0x33, 0x33, 0x33, 0x3F, 0x00], // U+0055 (U)
+    [0x33, 0x33, 0x33, 0x33, 0x33, 0x1E, 0x0C, 0x00], // U+0056 (V)
+    [0x63, 0x63, 0x63, 0x6B, 0x7F, 0x77, 0x63, 0x00], // U+0057 (W)
+    [0x63, 0x63, 0x36, 0x1C, 0x1C, 0x36, 0x63, 0x00], // U+0058 (X)
+    [0x33, 0x33, 0x33, 0x1E, 0x0C, 0x0C, 0x1E, 0x00], // U+0059 (Y)
+    [0x7F, 0x63, 0x31, 0x18, 0x4C, 0x66, 0x7F, 0x00], // U+005A (Z)
+    [0x1E, 0x06, 0x06, 0x06, 0x06, 0x06, 0x1E, 0x00], // U+005B ([)
+    [0x03, 0x06, 0x0C, 0x18, 0x30, 0x60, 0x40, 0x00], // U+005C (\)
+    [0x1E, 0x18, 0x18, 0x18, 0x18, 0x18, 0x1E, 0x00], // U+005D (])
+    [0x08, 0x1C, 0x36, 0x63, 0x00, 0x00, 0x00, 0x00], // U+005E (^)
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xFF], // U+005F (_)
+    [0x0C, 0x0C, 0x18, 0x00, 0x00, 0x00, 0x00, 0x00], // U+0060 (`)
+    [0x00, 0x00, 0x1E, 0x30, 0x3E, 0x33, 0x6E, 0x00], // U+0061 (a)
+    [0x07, 0x06, 0x06, 0x3E, 0x66, 0x66, 0x3B, 0x00], // U+0062 (b)
+    [0x00, 0x00, 0x1E, 0x33, 0x03, 0x33, 0x1E, 0x00], // U+0063 (c)
+    [0x38, 0x30, 0x30, 0x3E, 0x33, 0x33, 0x6E, 0x00], // U+0064 (d)
+    [0x00, 0x00, 0x1E, 0x33, 0x3F, 0x03, 0x1E, 0x00], // U+0065 (e)
+    [0x1C, 0x36, 0x06, 0x0F, 0x06, 0x06, 0x0F, 0x00], // U+0066 (f)
+    [0x00, 0x00, 0x6E, 0x33, 0x33, 0x3E, 0x30, 0x1F], // U+0067 (g)
+    [0x07, 0x06, 0x36, 0x6E, 0x66, 0x66, 0x67, 0x00], // U+0068 (h)
+    [0x0C, 0x00, 0x0E, 0x0C, 0x0C, 0x0C, 0x1E, 0x00], // U+0069 (i)
+    [0x30, 0x00, 0x30, 0x30, 0x30, 0x33, 0x33, 0x1E], // U+006A (j)
+    [0x07, 0x06, 0x66, 0x36, 0x1E, 0x36, 0x67, 0x00], // U+006B (k)
+    [0x0E, 0x0C, 0x0C, 0x0C, 0x0C, 0x0C, 0x1E, 0x00], // U+006C (l)
+    [0x00, 0x00, 0x33, 0x7F, 0x7F, 0x6B, 0x63, 0x00], // U+006D (m)
+    [0x00, 0x00, 0x1F, 0x33, 0x33, 0x33, 0x33, 0x00], // U+006E (n)
+    [0x00, 0x00, 0x1E, 0x33, 0x33, 0x33, 0x1E, 0x00], // U+006F (o)
+    [0x00, 0x00, 0x3B, 0x66, 0x66, 0x3E, 0x06, 0x0F], // U+0070 (p)
+    [0x00, 0x00, 0x6E, 0x33, 0x33, 0x3E, 0x30, 0x78], // U+0071 (q)
+    [0x00, 0x00, 0x3B, 0x6E, 0x66, 0x06, 0x0F, 0x00], // U+0072 (r)
+    [0x00, 0x00, 0x3E, 0x03, 0x1E, 0x30, 0x1F, 0x00], // U+0073 (s)
+    [0x08, 0x0C, 0x3E, 0x0C, 0x0C, 0x2C, 0x18, 0x00], // U+0074 (t)
+    [0x00, 0x00, 0x33, 0x33, 0x33, 0x33, 0x6E, 0x00], // U+0075 (u)
+    [0x00, 0x00, 0x33, 0x33, 0x33, 0x1E, 0x0C, 0x00], // U+0076 (v)
+    [0x00, 0x00, 0x63, 0x6B, 0x7F, 0x7F, 0x36, 0x00], // U+0077 (w)
+    [0x00, 0x00, 0x63, 0x36, 0x1C, 0x36, 0x63, 0x00], // U+0078 (x)
+    [0x00, 0x00, 0x33, 0x33, 0x33, 0x3E, 0x30, 0x1F], // U+0079 (y)
+    [0x00, 0x00, 0x3F, 0x19, 0x0C, 0x26, 0x3F, 0x00], // U+007A (z)
+    [0x38, 0x0C, 0x0C, 0x07, 0x0C, 0x0C, 0x38, 0x00], // U+007B ({)
+    [0x18, 0x18, 0x18, 0x00, 0x18, 0x18, 0x18, 0x00], // U+007C (|)
+    [0x07, 0x0C, 0x0C, 0x38, 0x0C, 0x0C, 0x07, 0x00], // U+007D (})
+    [0x6E, 0x3B, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // U+007E (~)
+];
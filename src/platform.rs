@@ -1,10 +1,14 @@
-use crate::gl::GLRc;
+use crate::gl::{GLRc, GLuint};
 use sdl2::EventPump;
 use std::time::Duration;
 
 pub struct Platform<'a> {
-  pub events: &'a mut EventPump,
+  // SDL events, or None when running headless without SDL
+  pub events: Option<&'a mut EventPump>,
   pub gl: GLRc,
+  // The framebuffer that passes without a buffer draw into:
+  // 0 for the window, or an offscreen framebuffer when running headless
+  pub framebuffer: GLuint,
   pub window_resolution: (u32, u32),
   pub mouse_resolution: (u32, u32),
   pub time_delta: Duration,
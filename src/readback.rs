@@ -0,0 +1,130 @@
+use crate::capture::{self, CaptureFormat, CaptureTarget};
+use crate::error::{Error, Result};
+use crate::gl;
+use crate::gl::{GLRc, GLuint};
+use std::collections::VecDeque;
+
+/// Number of frames that can be in flight between glReadPixels and the CPU
+pub const READBACK_PBO_COUNT: usize = 3;
+
+#[derive(Debug, Default, Clone, Copy)]
+struct ReadbackPbo {
+    pbo: GLuint,
+    /// Allocated size of the pbo in bytes
+    capacity: usize,
+    width: u32,
+    height: u32,
+}
+
+/// Asynchronously reads frames back from the GPU through a ring of pixel pack buffers.
+///
+/// glReadPixels into a bound PIXEL_PACK_BUFFER returns immediately, and the pixels are
+/// only mapped once the ring is full, by which point the GPU has had `READBACK_PBO_COUNT - 1`
+/// frames to finish the transfer. This keeps the render loop from stalling on every frame.
+#[derive(Debug)]
+pub struct PixelReadback {
+    pbos: [ReadbackPbo; READBACK_PBO_COUNT],
+    format: CaptureFormat,
+    /// Indices into pbos that contain a frame that hasn't been mapped yet, oldest first
+    pending: VecDeque<usize>,
+    next: usize,
+}
+
+impl PixelReadback {
+    pub fn new(gl: &GLRc, format: CaptureFormat) -> Self {
+        let buffers = gl.gen_buffers(READBACK_PBO_COUNT as i32);
+        let mut pbos: [ReadbackPbo; READBACK_PBO_COUNT] = Default::default();
+        for (pbo, buffer) in pbos.iter_mut().zip(buffers) {
+            pbo.pbo = buffer;
+        }
+        Self {
+            pbos,
+            format,
+            pending: VecDeque::with_capacity(READBACK_PBO_COUNT),
+            next: 0,
+        }
+    }
+
+    /// True if every pbo holds a frame, and the oldest must be mapped before reading another
+    pub fn is_full(&self) -> bool {
+        self.pending.len() == READBACK_PBO_COUNT
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    /// Start an asynchronous read of the pixels of `target` into the next pbo.
+    /// The ring must not be full.
+    pub fn read(&mut self, gl: &GLRc, target: CaptureTarget) {
+        let (width, height) = (target.width, target.height);
+        assert!(!self.is_full(), "PixelReadback::read called on a full ring");
+        let idx = self.next;
+        self.next = (self.next + 1) % READBACK_PBO_COUNT;
+        let pbo = &mut self.pbos[idx];
+        let len = width as usize * height as usize * self.format.bytes_per_pixel();
+        gl.bind_buffer(gl::PIXEL_PACK_BUFFER, pbo.pbo);
+        if len > pbo.capacity {
+            gl.buffer_data_untyped(
+                gl::PIXEL_PACK_BUFFER,
+                len as isize,
+                std::ptr::null(),
+                gl::STREAM_READ,
+            );
+            pbo.capacity = len;
+        }
+        pbo.width = width;
+        pbo.height = height;
+        capture::bind_read_target(gl, target);
+        unsafe {
+            gl.read_pixels_into_pbo(
+                0,
+                0,
+                width as i32,
+                height as i32,
+                self.format.gl_format(),
+                self.format.gl_type(),
+            );
+        }
+        gl.bind_buffer(gl::PIXEL_PACK_BUFFER, 0);
+        gl.bind_framebuffer(gl::READ_FRAMEBUFFER, 0);
+        self.pending.push_back(idx);
+    }
+
+    /// Map the oldest pending frame and pass its bottom-up rows, width and height to `f`.
+    /// Returns false if there was no pending frame, and an error if the frame can't be mapped.
+    pub fn map_oldest<F: FnOnce(&[u8], u32, u32)>(&mut self, gl: &GLRc, f: F) -> Result<bool> {
+        let idx = match self.pending.pop_front() {
+            Some(idx) => idx,
+            None => return Ok(false),
+        };
+        let pbo = self.pbos[idx];
+        let len = pbo.width as usize * pbo.height as usize * self.format.bytes_per_pixel();
+        gl.bind_buffer(gl::PIXEL_PACK_BUFFER, pbo.pbo);
+        let ptr = gl.map_buffer_range(
+            gl::PIXEL_PACK_BUFFER,
+            0,
+            len as isize,
+            gl::MAP_READ_BIT,
+        );
+        if ptr.is_null() {
+            gl.bind_buffer(gl::PIXEL_PACK_BUFFER, 0);
+            return Err(Error::render(format!(
+                "glMapBufferRange failed to map a {}x{} frame read back from the GPU",
+                pbo.width, pbo.height
+            )));
+        }
+        let pixels = unsafe { std::slice::from_raw_parts(ptr as *const u8, len) };
+        f(pixels, pbo.width, pbo.height);
+        gl.unmap_buffer(gl::PIXEL_PACK_BUFFER);
+        gl.bind_buffer(gl::PIXEL_PACK_BUFFER, 0);
+        Ok(true)
+    }
+
+    pub fn delete(&mut self, gl: &GLRc) {
+        let buffers: Vec<GLuint> = self.pbos.iter().map(|pbo| pbo.pbo).collect();
+        gl.delete_buffers(&buffers);
+        self.pbos = Default::default();
+        self.pending.clear();
+    }
+}
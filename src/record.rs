@@ -0,0 +1,217 @@
+use crate::capture::CaptureFormat;
+use crate::encoder::{VideoEncoder, VideoFormat};
+use crate::error::{Error, Result};
+use crate::gl::GLRc;
+use crate::readback::PixelReadback;
+use std::path::PathBuf;
+use std::sync::mpsc::{channel, sync_channel, Receiver, SyncSender, TrySendError};
+use std::thread::JoinHandle;
+
+/// Maximum number of frames waiting to be written. When the record thread falls
+/// this far behind, the render loop blocks rather than growing memory without bound.
+const RECORD_QUEUE_LEN: usize = 8;
+
+/// Whether recorded frames are saved as individual images or encoded into a video
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RecordFormat {
+    Image(CaptureFormat),
+    Video(VideoFormat),
+}
+
+impl RecordFormat {
+    pub fn from_name(name: &str) -> Option<Self> {
+        CaptureFormat::from_name(name)
+            .map(RecordFormat::Image)
+            .or_else(|| VideoFormat::from_name(name).map(RecordFormat::Video))
+    }
+
+    /// The format frames are read back from the GPU in. Video is always encoded from 8-bit RGB.
+    pub fn capture_format(self) -> CaptureFormat {
+        match self {
+            RecordFormat::Image(format) => format,
+            RecordFormat::Video(_) => CaptureFormat::Png,
+        }
+    }
+}
+
+pub struct RecordData {
+    pub width: u32,
+    pub height: u32,
+    /// Tightly packed rows, bottom-up as returned by glReadPixels
+    pub data: Vec<u8>,
+}
+
+/// Writes recorded frames on a background thread, either as numbered images
+/// into a directory or into a single encoded video file
+pub struct Recorder {
+    sender: SyncSender<RecordData>,
+    /// Pixel buffers handed back by the record thread once written, so they can be reused
+    recycled: Receiver<Vec<u8>>,
+    thread: JoinHandle<Result<()>>,
+}
+
+impl Recorder {
+    /// `path` is the output directory for images, or the video file.
+    /// `audio` is the (uri, start seconds) of the soundtrack to mux into the video.
+    pub fn spawn(
+        path: PathBuf,
+        format: RecordFormat,
+        fps: u32,
+        audio: Option<(String, f64)>,
+    ) -> Result<Self> {
+        if let RecordFormat::Image(_) = format {
+            std::fs::create_dir_all(&path).map_err(|err| Error::io(&path, err))?;
+        }
+        let bytes_per_pixel = format.capture_format().bytes_per_pixel();
+        let (sender, receiver) = sync_channel::<RecordData>(RECORD_QUEUE_LEN);
+        let (recycle_sender, recycled) = channel::<Vec<u8>>();
+        let thread = std::thread::spawn(move || {
+            let mut encoder: Option<VideoEncoder> = None;
+            for (ticks, mut data) in receiver.iter().enumerate() {
+                match format {
+                    RecordFormat::Image(format) => {
+                        let img_path = path.join(format!("{:06}.{}", ticks, format.extension()));
+                        format.save(&img_path, &mut data.data, data.width, data.height)?;
+                    }
+                    RecordFormat::Video(format) => {
+                        if encoder.is_none() {
+                            encoder = Some(VideoEncoder::new(
+                                &path,
+                                format,
+                                data.width,
+                                data.height,
+                                fps,
+                                audio.as_ref().map(|(uri, start)| (uri.as_str(), *start)),
+                            )?);
+                        }
+                        let encoder = encoder.as_mut().unwrap();
+                        let (width, height) = encoder.size();
+                        if (width, height) == (data.width, data.height) {
+                            encoder.push_frame(&data.data)?;
+                        } else {
+                            // The window was resized, so center the frame in the video
+                            encoder.push_frame(&letterbox(
+                                &data.data,
+                                (data.width, data.height),
+                                (width, height),
+                                bytes_per_pixel,
+                            ))?;
+                        }
+                    }
+                }
+                recycle_sender.send(data.data).ok();
+            }
+            if let Some(encoder) = encoder {
+                encoder.finish()?;
+            }
+            Ok(())
+        });
+        Ok(Self {
+            sender,
+            recycled,
+            thread,
+        })
+    }
+
+    /// Copy the oldest pending frame out of `readback` and queue it for writing,
+    /// blocking while the queue is full. Returns false if the record thread exited,
+    /// call `finish` to find out why, and an error if the frame can't be read back.
+    pub fn send_oldest(&self, gl: &GLRc, readback: &mut PixelReadback) -> Result<bool> {
+        let mut data = None;
+        readback.map_oldest(gl, |pixels, width, height| {
+            let mut buffer = self.recycled.try_recv().unwrap_or_default();
+            buffer.clear();
+            buffer.extend_from_slice(pixels);
+            data = Some(RecordData {
+                width,
+                height,
+                data: buffer,
+            });
+        })?;
+        let data = match data {
+            Some(data) => data,
+            None => return Ok(true),
+        };
+        match self.sender.try_send(data) {
+            Ok(()) => Ok(true),
+            Err(TrySendError::Full(data)) => {
+                debug!("[RECORD] Record queue is full, waiting on the record thread");
+                Ok(self.sender.send(data).is_ok())
+            }
+            Err(TrySendError::Disconnected(_)) => Ok(false),
+        }
+    }
+
+    /// Queue the frames still pending in `readback`, then `finish`. An error of the
+    /// record thread is returned before an error reading back a frame.
+    pub fn drain_and_finish(self, gl: &GLRc, readback: &mut PixelReadback) -> Result<()> {
+        let mut result = Ok(());
+        while !readback.is_empty() {
+            match self.send_oldest(gl, readback) {
+                Ok(true) => {}
+                Ok(false) => break,
+                Err(err) => {
+                    result = Err(err);
+                    break;
+                }
+            }
+        }
+        self.finish().and(result)
+    }
+
+    /// Wait for every queued frame to be written and the video file, if any, to be finalized
+    pub fn finish(self) -> Result<()> {
+        drop(self.sender);
+        self.thread
+            .join()
+            .map_err(|_| Error::bug("[RECORD] The record thread panicked"))?
+    }
+}
+
+/// Center the bottom-up rows of a `from` sized frame in a black `to` sized frame, cropping
+/// the edges that don't fit
+fn letterbox(pixels: &[u8], from: (u32, u32), to: (u32, u32), bytes_per_pixel: usize) -> Vec<u8> {
+    let (from_width, from_height) = (from.0 as usize, from.1 as usize);
+    let (to_width, to_height) = (to.0 as usize, to.1 as usize);
+    let mut letterboxed = vec![0; to_width * to_height * bytes_per_pixel];
+    let width = from_width.min(to_width);
+    let height = from_height.min(to_height);
+    // Offsets of the copied region in the source and destination frames
+    let (from_x, to_x) = ((from_width - width) / 2, (to_width - width) / 2);
+    let (from_y, to_y) = ((from_height - height) / 2, (to_height - height) / 2);
+    let row_len = width * bytes_per_pixel;
+    for row in 0..height {
+        let from_start = ((from_y + row) * from_width + from_x) * bytes_per_pixel;
+        let to_start = ((to_y + row) * to_width + to_x) * bytes_per_pixel;
+        letterboxed[to_start..to_start + row_len]
+            .copy_from_slice(&pixels[from_start..from_start + row_len]);
+    }
+    letterboxed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn letterbox_smaller_frame() {
+        // A 1x1 frame centered in a 3x3 frame of one byte pixels
+        let letterboxed = letterbox(&[9], (1, 1), (3, 3), 1);
+        assert_eq!(letterboxed, vec![0, 0, 0, 0, 9, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn letterbox_larger_frame() {
+        // The center 2x1 of a 4x3 frame of two byte pixels
+        let pixels: Vec<u8> = (0..24).collect();
+        let letterboxed = letterbox(&pixels, (4, 3), (2, 1), 2);
+        assert_eq!(letterboxed, vec![10, 11, 12, 13]);
+    }
+
+    #[test]
+    fn letterbox_wider_and_shorter_frame() {
+        // A 3x1 frame in a 1x3 frame keeps its center pixel in the middle row
+        let letterboxed = letterbox(&[1, 2, 3], (3, 1), (1, 3), 1);
+        assert_eq!(letterboxed, vec![0, 2, 0]);
+    }
+}
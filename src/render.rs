@@ -0,0 +1,157 @@
+use crate::effect_player::EffectPlayer;
+use crate::error::{Error, Result};
+use crate::offscreen::OffscreenFramebuffer;
+use crate::platform::Platform;
+use crate::readback::PixelReadback;
+use crate::record::{RecordFormat, Recorder};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone)]
+pub struct RenderSettings {
+    /// Start time in seconds
+    pub start: f64,
+    /// Number of frames to render
+    pub frames: u64,
+    pub fps: u32,
+    pub width: u32,
+    pub height: u32,
+    /// Directory that receives one image per frame, or the video file to encode
+    pub output: PathBuf,
+    pub format: RecordFormat,
+    /// Render the named buffer instead of the window
+    pub buffer: Option<String>,
+    /// Color attachment index of the buffer
+    pub attachment: usize,
+}
+
+impl RenderSettings {
+    /// The playback time of the nth rendered frame. Computed from the frame index rather than
+    /// accumulated so that rounding errors don't drift over long renders.
+    pub fn frame_time(&self, n: u64) -> Duration {
+        let start_nanos = (self.start * 1e9).round() as u64;
+        let frame_nanos = (u128::from(n) * 1_000_000_000 / u128::from(self.fps)) as u64;
+        Duration::from_nanos(start_nanos + frame_nanos)
+    }
+
+    /// The iFrame value of the nth rendered frame
+    pub fn frame_index(&self, n: u64) -> u32 {
+        ((self.start * f64::from(self.fps)).round() as u64 + n) as u32
+    }
+}
+
+/// Step the player frame-by-frame at a fixed time step and write every frame to disk.
+///
+/// Time, date, mouse, and keyboard input are all derived from the frame index, so rendering
+/// the same project with the same settings produces the same images.
+pub fn render(
+    player: &mut EffectPlayer,
+    platform: &mut Platform,
+    settings: &RenderSettings,
+) -> Result<()> {
+    if let RecordFormat::Video(_) = settings.format {
+        if let Some(output_dir) = settings.output.parent() {
+            std::fs::create_dir_all(output_dir).map_err(|err| Error::io(output_dir, err))?;
+        }
+    }
+    let gl = platform.gl.clone();
+    let mut offscreen = prepare_offscreen(
+        player,
+        platform,
+        settings.width,
+        settings.height,
+        settings.fps,
+    );
+    let mut readback = PixelReadback::new(&gl, settings.format.capture_format());
+
+    let instant = Instant::now();
+    let result = render_frames(player, platform, settings, &mut readback);
+    readback.delete(&gl);
+    offscreen.delete(&gl);
+    result?;
+    info!(
+        "[RENDER] Rendered {} frames to {:?} in {:?}",
+        settings.frames,
+        settings.output,
+        instant.elapsed()
+    );
+    Ok(())
+}
+
+/// Draw into a new `width` x `height` offscreen framebuffer instead of the window, and make
+/// the player deterministic with a fixed time step of 1 / `fps`. The caller deletes the framebuffer.
+pub fn prepare_offscreen(
+    player: &mut EffectPlayer,
+    platform: &mut Platform,
+    width: u32,
+    height: u32,
+    fps: u32,
+) -> OffscreenFramebuffer {
+    let offscreen = OffscreenFramebuffer::new(&platform.gl, width, height);
+    platform.framebuffer = offscreen.framebuffer;
+    platform.window_resolution = (width, height);
+    platform.mouse_resolution = (width, height);
+    platform.keyboard = [0; 256];
+    platform.time_delta = Duration::from_nanos(1_000_000_000 / u64::from(fps));
+    player.set_deterministic(true);
+    offscreen
+}
+
+fn render_frames(
+    player: &mut EffectPlayer,
+    platform: &mut Platform,
+    settings: &RenderSettings,
+    readback: &mut PixelReadback,
+) -> Result<()> {
+    let gl = platform.gl.clone();
+    let mut recorder: Option<Recorder> = None;
+    let mut result = Ok(());
+    for n in 0..settings.frames {
+        player.seek(settings.frame_time(n), settings.frame_index(n));
+        player.tick(platform)?;
+        if !player.is_ok() {
+            return Err(Error::render(
+                "The project failed to build, see the errors above",
+            ));
+        }
+        // The resources, including the soundtrack, are only known after the first tick loads the config
+        if recorder.is_none() {
+            for name in player.realtime_resource_names() {
+                warn!(
+                    "[RENDER] Resource \"{}\" streams in real time, so its contents are not deterministic",
+                    name
+                );
+            }
+            recorder = Some(Recorder::spawn(
+                settings.output.clone(),
+                settings.format,
+                settings.fps,
+                player.audio_uri()?.map(|uri| (uri, settings.start)),
+            )?);
+        }
+        let sent = if readback.is_full() {
+            recorder.as_ref().unwrap().send_oldest(&gl, readback)
+        } else {
+            Ok(true)
+        };
+        match sent {
+            Ok(true) => {}
+            Ok(false) => break,
+            Err(err) => {
+                result = Err(err);
+                break;
+            }
+        }
+        let target = player.capture_target(
+            settings.buffer.as_ref().map(String::as_str),
+            settings.attachment,
+        )?;
+        readback.read(&gl, target);
+        debug!("[RENDER] Rendered frame {}", n);
+    }
+    if let Some(recorder) = recorder {
+        // Reports the error if the record thread exited early
+        recorder.drain_and_finish(&gl, readback)?;
+    }
+    result
+}
@@ -1,4 +1,4 @@
-use crate::config::TextureFormat;
+use crate::config::{DrawModeConfig, TextureFormat};
 use std::fmt;
 
 #[derive(Debug)]
@@ -7,13 +7,40 @@ pub enum ResourceData {
     D2(ResourceData2D),
     D3(ResourceData3D),
     Cube(Vec<(ResourceCubemapFace, ResourceData2D)>),
+    // the raw contents of a storage or feedback buffer
+    Bytes(Vec<u8>),
 }
 
 #[derive(Debug)]
 pub struct GeometryData {
+    // interleaved float vertex attributes
     pub buffer: Vec<f32>,
-    pub pos_stride_off: (u32, u32), // Assumes vec3
-    pub nrm_stride_off: (u32, u32), // Assumes vec3
+    pub attributes: Vec<GeometryAttribute>,
+    // triangle indices into the vertices, empty to draw the vertices in order
+    pub indices: Vec<u32>,
+    // the ranges of indices, or of vertices, that draw the model
+    pub draws: Vec<GeometryDraw>,
+    // embedded textures, staged as the attachments of the model resource
+    pub textures: Vec<ResourceData2D>,
+}
+
+/// A range of a model drawn with its own primitive mode and model matrix
+#[derive(Debug, Clone)]
+pub struct GeometryDraw {
+    pub mode: DrawModeConfig,
+    pub first: u32,
+    pub count: u32,
+    // column-major model matrix
+    pub transform: [f32; 16],
+}
+
+/// A vertex attribute of a model, bound to the vertex shader input of the same name
+#[derive(Debug, Clone)]
+pub struct GeometryAttribute {
+    pub name: String,
+    pub components: u32,
+    // offset in floats from the start of a vertex
+    pub offset: u32,
 }
 
 #[derive(Debug)]
@@ -59,6 +86,83 @@ pub enum ResourceCubemapFace {
     Back,
 }
 
+pub const IDENTITY: [f32; 16] = [
+    1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0,
+];
+
+impl GeometryData {
+    /// Interleave per-vertex attribute streams, given as (name, components, values)
+    pub fn interleave(streams: Vec<(&str, u32, Vec<f32>)>, indices: Vec<u32>) -> GeometryData {
+        let mut attributes = Vec::new();
+        let mut offset = 0;
+        for (name, components, _) in &streams {
+            attributes.push(GeometryAttribute {
+                name: name.to_string(),
+                components: *components,
+                offset,
+            });
+            offset += components;
+        }
+        let vertex_count = streams
+            .first()
+            .map(|(_, components, values)| values.len() / *components as usize)
+            .unwrap_or(0);
+        let mut buffer = Vec::with_capacity(vertex_count * offset as usize);
+        for vertex in 0..vertex_count {
+            for (_, components, values) in &streams {
+                let components = *components as usize;
+                buffer.extend_from_slice(&values[vertex * components..(vertex + 1) * components]);
+            }
+        }
+        let count = match indices.len() {
+            0 => vertex_count,
+            n => n,
+        };
+        GeometryData {
+            buffer,
+            attributes,
+            indices,
+            draws: vec![GeometryDraw {
+                mode: DrawModeConfig::Triangles,
+                first: 0,
+                count: count as u32,
+                transform: IDENTITY,
+            }],
+            textures: Vec::new(),
+        }
+    }
+
+    /// The number of floats per vertex
+    pub fn stride(&self) -> u32 {
+        self.attributes
+            .iter()
+            .map(|attribute| attribute.components)
+            .sum()
+    }
+
+    pub fn vertex_count(&self) -> usize {
+        match self.stride() {
+            0 => 0,
+            stride => self.buffer.len() / stride as usize,
+        }
+    }
+
+    /// The vertex shader inputs the attributes bind to, like "vec3 position, vec2 texcoord"
+    pub fn layout(&self) -> String {
+        self.attributes
+            .iter()
+            .map(|attribute| {
+                let type_str = match attribute.components {
+                    1 => "float".to_string(),
+                    n => format!("vec{}", n),
+                };
+                format!("{} {}", type_str, attribute.name)
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
 impl fmt::Display for ResourceData {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
@@ -73,7 +177,16 @@ impl fmt::Display for ResourceData {
                 data.width, data.height, data.depth, data.format
             ),
             ResourceData::Cube(faces) => write!(f, "TextureCubemap({:?})", faces),
-            _ => fmt::Result::Ok(()),
+            ResourceData::Geometry(data) => write!(
+                f,
+                "Geometry(vertices={}, indices={}, draws={}, textures={}, layout=\"{}\")",
+                data.vertex_count(),
+                data.indices.len(),
+                data.draws.len(),
+                data.textures.len(),
+                data.layout()
+            ),
+            ResourceData::Bytes(bytes) => write!(f, "Bytes(size={})", bytes.len()),
         }
     }
 }
@@ -0,0 +1,1495 @@
+use crate::builtin;
+use crate::config::{
+    ChannelConfig, DrawConfig, DrawModeConfig, DrawRawConfig, EffectConfig, FilterConfig,
+    PassConfig, ResourceConfig, WrapConfig,
+};
+use crate::diagnostics::Diagnostic;
+use crate::error::{Error, Result};
+use crate::source_map::{self, ShaderInclude};
+use crate::text::strip_prefix;
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+/// The fullscreen vertex shader that projects written before passes could leave out
+/// their vertex shader ship with
+const FULLSCREEN_TRIANGLE: &str = "void main() {
+  float x = -1.0 + float((gl_VertexID & 1) << 2);
+  float y = -1.0 + float((gl_VertexID & 2) << 1);
+  gl_Position = vec4(x, y, 0, 1);
+}
+";
+
+/// Output id of the Image tab
+const IMAGE_OUTPUT_ID: &str = "4dfGRr";
+
+/// Output ids of the Buffer A-D tabs, in the current and the old numeric API format
+const BUFFER_OUTPUT_IDS: [(&str, &str); 4] = [
+    ("4dXGR8", "257"),
+    ("XsXGR8", "258"),
+    ("4sXGR8", "259"),
+    ("XdfGR8", "260"),
+];
+
+#[derive(Debug, Clone)]
+pub struct ImportSettings {
+    /// Shadertoy API JSON export of a single shader
+    pub json: PathBuf,
+    /// Project directory to create
+    pub output: PathBuf,
+    /// Directory to copy the media files of textures, cubemaps, videos and music from
+    pub media: Option<PathBuf>,
+    /// Overwrite the files of an existing project
+    pub force: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct ExportSettings {
+    /// grim.toml of the project to export
+    pub config: PathBuf,
+    /// JSON file to write
+    pub output: PathBuf,
+}
+
+/// The `{"Shader": {...}}` object returned by the Shadertoy API
+#[derive(Debug, Serialize)]
+struct ExportJson<'a> {
+    #[serde(rename = "Shader")]
+    shader: &'a ShaderJson,
+}
+
+/// A shader as returned by the Shadertoy API, `https://www.shadertoy.com/api/v1/shaders/<id>`
+#[derive(Debug, Deserialize, Serialize)]
+struct ShaderJson {
+    #[serde(default)]
+    ver: String,
+    #[serde(default)]
+    info: InfoJson,
+    renderpass: Vec<RenderPassJson>,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+struct InfoJson {
+    id: String,
+    name: String,
+    username: String,
+    description: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct RenderPassJson {
+    #[serde(default)]
+    inputs: Vec<InputJson>,
+    #[serde(default)]
+    outputs: Vec<OutputJson>,
+    code: String,
+    #[serde(default)]
+    name: String,
+    #[serde(rename = "type")]
+    kind: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct InputJson {
+    id: Value,
+    /// Media path on shadertoy.com, named filepath in older exports
+    #[serde(alias = "filepath")]
+    src: String,
+    /// Named type in older exports
+    #[serde(alias = "type")]
+    ctype: String,
+    channel: usize,
+    #[serde(default)]
+    sampler: SamplerJson,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct OutputJson {
+    id: Value,
+    #[serde(default)]
+    channel: usize,
+}
+
+/// Shadertoy stores every sampler setting as a string
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+struct SamplerJson {
+    filter: String,
+    wrap: String,
+    vflip: String,
+    srgb: String,
+    internal: String,
+}
+
+/// Create a grimoire project from a Shadertoy API JSON export.
+///
+/// Writes grim.toml, one shader per renderpass that only defines `mainImage`, common.glsl
+/// when the shader has a Common tab, and the media files found in the media directory. Returns a warning
+/// for every input that could not be imported and every media file left to download.
+pub fn import(settings: &ImportSettings) -> Result<Vec<Diagnostic>> {
+    let text =
+        std::fs::read_to_string(&settings.json).map_err(|err| Error::io(&settings.json, err))?;
+    let shader = parse_export(&text).map_err(|err| {
+        Error::shadertoy(format!(
+            "{} is not a Shadertoy JSON export: {}",
+            settings.json.display(),
+            err
+        ))
+    })?;
+    let config_path = settings.output.join("grim.toml");
+    if config_path.exists() && !settings.force {
+        return Err(Error::shadertoy(format!(
+            "{} already exists, pass --force to overwrite it",
+            config_path.display()
+        )));
+    }
+    let mut importer = Importer::new(&shader, settings);
+    importer.import();
+    let files = importer.files()?;
+    for (name, contents) in &files {
+        let path = settings.output.join(name);
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(|err| Error::io(dir, err))?;
+        }
+        std::fs::write(&path, contents).map_err(|err| Error::io(&path, err))?;
+    }
+    importer.copy_media()?;
+    Ok(importer.warnings)
+}
+
+/// Accepts the `{"Shader": {...}}` object returned by the API, an array of shaders
+/// as exported by browser extensions, or the bare shader object
+fn parse_export(text: &str) -> serde_json::Result<ShaderJson> {
+    let mut value: Value = serde_json::from_str(text)?;
+    if let Value::Array(shaders) = value {
+        value = shaders.into_iter().next().unwrap_or(Value::Null);
+    }
+    if let Some(shader) = value.get_mut("Shader") {
+        value = shader.take();
+    }
+    serde_json::from_value(value)
+}
+
+/// The string form of an input or output id, which older exports store as numbers
+fn id_string(id: &Value) -> String {
+    match id {
+        Value::String(id) => id.clone(),
+        id => id.to_string(),
+    }
+}
+
+/// The letter of a Buffer A-D tab, from its name or its output id
+fn buffer_letter(pass: &RenderPassJson) -> Option<char> {
+    let from_name = strip_prefix(&pass.name, "Buf")
+        .and_then(|rest| rest.chars().last())
+        .filter(char::is_ascii_alphabetic)
+        .map(|c| c.to_ascii_lowercase());
+    from_name.or_else(|| {
+        let id = id_string(&pass.outputs.first()?.id);
+        let index = BUFFER_OUTPUT_IDS
+            .iter()
+            .position(|(new, old)| id == *new || id == *old)?;
+        Some((b'a' + index as u8) as char)
+    })
+}
+
+/// Quote a string as a TOML basic string
+fn toml_string(s: &str) -> String {
+    let mut quoted = String::with_capacity(s.len() + 2);
+    quoted.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04X}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+struct Importer<'a> {
+    shader: &'a ShaderJson,
+    settings: &'a ImportSettings,
+    /// Resource tables in the order they were first referenced
+    resources: Vec<(String, String)>,
+    /// Resource name for each imported input, keyed by ctype, src and vflip
+    inputs: BTreeMap<String, String>,
+    /// Buffer resource name for each buffer output id
+    buffers: BTreeMap<String, String>,
+    /// [[pass]] tables in draw order
+    passes: Vec<String>,
+    /// Whether common.glsl was imported from the Common tab
+    common: bool,
+    /// Shader file name and contents
+    shaders: Vec<(String, String)>,
+    /// Shadertoy media paths to copy into the project
+    media: Vec<String>,
+    warnings: Vec<Diagnostic>,
+}
+
+impl<'a> Importer<'a> {
+    fn new(shader: &'a ShaderJson, settings: &'a ImportSettings) -> Self {
+        Self {
+            shader,
+            settings,
+            resources: Vec::new(),
+            inputs: BTreeMap::new(),
+            buffers: BTreeMap::new(),
+            passes: Vec::new(),
+            common: false,
+            shaders: Vec::new(),
+            media: Vec::new(),
+            warnings: Vec::new(),
+        }
+    }
+
+    fn warn(&mut self, message: String) {
+        self.warnings
+            .push(Diagnostic::warning(message).with_file(&self.settings.json));
+    }
+
+    fn import(&mut self) {
+        let shader = self.shader;
+        let common = shader.renderpass.iter().find(|pass| pass.kind == "common");
+        if let Some(common) = common {
+            let mut source = common.code.clone();
+            if !source.ends_with('\n') {
+                source.push('\n');
+            }
+            self.shaders.push(("common.glsl".to_string(), source));
+            self.common = true;
+        }
+        // Shadertoy draws Buffer A to D in order, and then the Image tab
+        let mut buffers = Vec::new();
+        let mut image = None;
+        let mut sound = None;
+        for pass in &shader.renderpass {
+            match pass.kind.as_str() {
+                "common" => {}
+                "image" => image = Some(pass),
+                "sound" => sound = Some(pass),
+                "buffer" => match buffer_letter(pass) {
+                    Some(letter) => buffers.push((letter, pass)),
+                    None => self.warn(format!(
+                        "Skipped \"{}\", it is not one of Buffer A to D",
+                        pass.name
+                    )),
+                },
+                "cubemap" => self.warn(format!(
+                    "Skipped the \"{}\" tab, grimoire has no cubemap buffers",
+                    pass.name
+                )),
+                kind => self.warn(format!(
+                    "Skipped \"{}\", unknown renderpass type \"{}\"",
+                    pass.name, kind
+                )),
+            }
+        }
+        buffers.sort_by_key(|(letter, _)| *letter);
+        for (letter, pass) in &buffers {
+            let name = format!("buffer-{}", letter);
+            for output in &pass.outputs {
+                self.buffers.insert(id_string(&output.id), name.clone());
+            }
+            self.resources.push((name, "buffer = true\n".to_string()));
+        }
+        for (letter, pass) in &buffers {
+            let name = format!("buffer-{}", letter);
+            self.import_pass(pass, Some(&name));
+        }
+        match image {
+            Some(pass) => self.import_pass(pass, None),
+            None => self.warn("The shader has no Image tab, nothing is drawn to the window".into()),
+        }
+        if let Some(pass) = sound {
+            self.import_sound(pass);
+        }
+    }
+
+    /// The Sound tab becomes a sound resource, which no pass reads
+    fn import_sound(&mut self, pass: &RenderPassJson) {
+        if !pass.inputs.is_empty() {
+            self.warn(format!(
+                "Skipped the inputs of the \"{}\" tab, grimoire sound shaders have no channels",
+                pass.name
+            ));
+        }
+        self.resources.push((
+            "sound".to_string(),
+            "sound = \"./sound.glsl\"\n".to_string(),
+        ));
+        let mut source = format!("// {} of {}\n", pass.name, self.title());
+        source.push_str(&pass.code);
+        if !source.ends_with('\n') {
+            source.push('\n');
+        }
+        self.shaders.push(("sound.glsl".to_string(), source));
+    }
+
+    fn import_pass(&mut self, pass: &RenderPassJson, buffer: Option<&str>) {
+        let file_name = format!("{}.glsl", buffer.unwrap_or("image"));
+        let mut table = String::new();
+        if let Some(buffer) = buffer {
+            writeln!(table, "buffer    = {}", toml_string(buffer)).unwrap();
+        }
+        table.push_str("clear     = [0.0, 0.0, 0.0, 1.0]\n");
+        writeln!(
+            table,
+            "fragment  = {}",
+            toml_string(&format!("./{}", file_name))
+        )
+        .unwrap();
+        let mut inputs = pass.inputs.iter().collect::<Vec<_>>();
+        inputs.sort_by_key(|input| input.channel);
+        for input in inputs {
+            if let Some(resource) = self.import_input(pass, input) {
+                let (filter, wrap) = self.sampler(pass, input);
+                writeln!(
+                    table,
+                    "iChannel{} = {{resource = {}, filter = \"{}\", wrap = \"{}\"}}",
+                    input.channel,
+                    toml_string(&resource),
+                    filter,
+                    wrap
+                )
+                .unwrap();
+            }
+        }
+        self.passes.push(table);
+
+        let mut source = format!("// {} of {}\n", pass.name, self.title());
+        source.push_str(&pass.code);
+        if !source.ends_with('\n') {
+            source.push('\n');
+        }
+        self.shaders.push((file_name, source));
+    }
+
+    /// Returns the name of the resource bound to the input, or None if it was skipped
+    fn import_input(&mut self, pass: &RenderPassJson, input: &InputJson) -> Option<String> {
+        if input.ctype == "buffer" {
+            let resource = self
+                .buffers
+                .get(&id_string(&input.id))
+                .cloned()
+                .or_else(|| {
+                    // Older exports only reference buffers by their preview image,
+                    // /media/previz/buffer00.png for Buffer A
+                    let index = strip_prefix(&input.src, "/media/previz/buffer")?
+                        .get(..2)?
+                        .parse::<u8>()
+                        .ok()?;
+                    let name = format!("buffer-{}", (b'a' + index) as char);
+                    self.buffers.values().find(|b| **b == name).cloned()
+                });
+            if resource.is_none() {
+                self.warn(format!(
+                    "Skipped iChannel{} of \"{}\", it reads a buffer that was not imported",
+                    input.channel, pass.name
+                ));
+            }
+            return resource;
+        }
+        let flipv = input.sampler.vflip != "false";
+        let key = format!("{} {} {}", input.ctype, input.src, flipv);
+        if let Some(resource) = self.inputs.get(&key) {
+            return Some(resource.clone());
+        }
+        let (prefix, table) = match input.ctype.as_str() {
+            "texture" => {
+                let image = self.media_path(&input.src);
+                (
+                    "texture",
+                    format!("image = {}\nflipv = {}\n", toml_string(&image), flipv),
+                )
+            }
+            "cubemap" if input.src.starts_with("/media/previz/") => {
+                self.warn(format!(
+                    "Skipped iChannel{} of \"{}\", grimoire has no cubemap buffers",
+                    input.channel, pass.name
+                ));
+                return None;
+            }
+            "cubemap" => {
+                // Shadertoy stores the +X face at src and the others at src_1 to src_5,
+                // in +X, -X, +Y, -Y, +Z, -Z order
+                let src = Path::new(&input.src);
+                let stem = src.file_stem()?.to_str()?;
+                let extension = src.extension().and_then(|e| e.to_str()).unwrap_or("png");
+                let dir = input.src.rfind('/').map_or("", |slash| &input.src[..slash]);
+                let mut table = String::new();
+                let faces = ["right", "left", "top", "bottom", "front", "back"];
+                for (index, face) in faces.iter().enumerate() {
+                    let face_src = if index == 0 {
+                        input.src.clone()
+                    } else {
+                        format!("{}/{}_{}.{}", dir, stem, index, extension)
+                    };
+                    let face_path = self.media_path(&face_src);
+                    writeln!(table, "{:<6} = {}", face, toml_string(&face_path)).unwrap();
+                }
+                writeln!(table, "flipv  = {}", flipv).unwrap();
+                ("cubemap", table)
+            }
+            "video" => {
+                let video = self.media_path(&input.src);
+                ("video", format!("video = {}\n", toml_string(&video)))
+            }
+            "music" => {
+                let audio = self.media_path(&input.src);
+                ("audio", format!("audio = {}\n", toml_string(&audio)))
+            }
+            "keyboard" => ("keyboard", "keyboard = true\n".to_string()),
+            "webcam" => ("webcam", "webcam = true\n".to_string()),
+            "mic" => ("microphone", "microphone = true\n".to_string()),
+            "musicstream" => {
+                self.warn(format!(
+                    "Skipped iChannel{} of \"{}\", grimoire cannot stream {}, \
+                     save it and bind it as an audio resource",
+                    input.channel, pass.name, input.src
+                ));
+                return None;
+            }
+            "volume" => {
+                self.warn(format!(
+                    "Skipped iChannel{} of \"{}\", Shadertoy volumes use their own binary \
+                     format, convert {} to a texture3D resource",
+                    input.channel, pass.name, input.src
+                ));
+                return None;
+            }
+            ctype => {
+                self.warn(format!(
+                    "Skipped iChannel{} of \"{}\", unknown input type \"{}\"",
+                    input.channel, pass.name, ctype
+                ));
+                return None;
+            }
+        };
+        // Single instance devices keep their own name, media are numbered
+        let name = match prefix {
+            "keyboard" | "webcam" | "microphone" => prefix.to_string(),
+            _ => {
+                let count = self
+                    .resources
+                    .iter()
+                    .filter(|(name, _)| name.starts_with(prefix))
+                    .count();
+                format!("{}-{}", prefix, count)
+            }
+        };
+        self.resources.push((name.clone(), table));
+        self.inputs.insert(key, name.clone());
+        Some(name)
+    }
+
+    /// The filter and wrap of an input, falling back to linear and clamp
+    fn sampler(
+        &mut self,
+        pass: &RenderPassJson,
+        input: &InputJson,
+    ) -> (&'static str, &'static str) {
+        let filter = match input.sampler.filter.as_str() {
+            "nearest" => "nearest",
+            "mipmap" => "mipmap",
+            "linear" | "" => "linear",
+            filter => {
+                self.warn(format!(
+                    "iChannel{} of \"{}\" uses the unknown filter \"{}\", using linear",
+                    input.channel, pass.name, filter
+                ));
+                "linear"
+            }
+        };
+        let wrap = match input.sampler.wrap.as_str() {
+            "repeat" => "repeat",
+            "clamp" | "" => "clamp",
+            wrap => {
+                self.warn(format!(
+                    "iChannel{} of \"{}\" uses the unknown wrap \"{}\", using clamp",
+                    input.channel, pass.name, wrap
+                ));
+                "clamp"
+            }
+        };
+        (filter, wrap)
+    }
+
+    /// The project relative path a Shadertoy media file is copied to
+    fn media_path(&mut self, src: &str) -> String {
+        if !self.media.iter().any(|m| m == src) {
+            self.media.push(src.to_string());
+        }
+        format!("./media/{}", media_file_name(src))
+    }
+
+    fn title(&self) -> String {
+        let info = &self.shader.info;
+        let mut title = format!("\"{}\"", info.name);
+        if !info.username.is_empty() {
+            write!(title, " by {}", info.username).unwrap();
+        }
+        if !info.id.is_empty() {
+            write!(title, ", https://www.shadertoy.com/view/{}", info.id).unwrap();
+        }
+        title
+    }
+
+    /// Every file of the project, with grim.toml first
+    fn files(&self) -> Result<Vec<(String, String)>> {
+        let mut config = String::new();
+        writeln!(config, "# {}", self.title()).unwrap();
+        writeln!(
+            config,
+            "# Imported from {}",
+            self.settings
+                .json
+                .file_name()
+                .map(|name| name.to_string_lossy())
+                .unwrap_or_default()
+        )
+        .unwrap();
+        for line in self.shader.info.description.lines() {
+            writeln!(config, "#   {}", line).unwrap();
+        }
+        config.push_str("\nprofile = \"shadertoy\"\n");
+        if self.common {
+            config.push_str("common = \"./common.glsl\"\n");
+        }
+        for (name, table) in &self.resources {
+            write!(config, "\n[{}]\n{}", name, table).unwrap();
+        }
+        for table in &self.passes {
+            write!(config, "\n[[pass]]\n{}", table).unwrap();
+        }
+        if let Err(err) = EffectConfig::parse(&config) {
+            return Err(Error::bug(format!(
+                "The imported grim.toml is invalid: {}\n{}",
+                err, config
+            )));
+        }
+        let mut files = vec![("grim.toml".to_string(), config)];
+        files.extend(self.shaders.iter().cloned());
+        Ok(files)
+    }
+
+    /// Copy the media files found in the media directory into the project, and warn
+    /// about the ones left to download
+    fn copy_media(&mut self) -> Result<()> {
+        if self.media.is_empty() {
+            return Ok(());
+        }
+        let dir = self.settings.output.join("media");
+        std::fs::create_dir_all(&dir).map_err(|err| Error::io(&dir, err))?;
+        for src in self.media.clone() {
+            let file_name = media_file_name(&src);
+            // Look for the file by name, and in a mirror of the site layout
+            let found = self.settings.media.as_ref().and_then(|media| {
+                let candidates = [
+                    media.join(file_name),
+                    media.join(src.trim_start_matches('/')),
+                ];
+                candidates.iter().find(|path| path.is_file()).cloned()
+            });
+            match found {
+                Some(from) => {
+                    let to = dir.join(file_name);
+                    std::fs::copy(&from, &to).map_err(|err| Error::io(&from, err))?;
+                }
+                None => self.warn(format!(
+                    "Download https://www.shadertoy.com{} to media/{}",
+                    src, file_name
+                )),
+            }
+        }
+        Ok(())
+    }
+}
+
+fn media_file_name(src: &str) -> &str {
+    src.rsplit('/').next().unwrap_or(src)
+}
+
+/// Convert a project into a Shadertoy API JSON export.
+///
+/// The passes drawn to buffers become Buffer A to D, in the order they are drawn, and
+/// the last pass drawn to the window becomes the Image tab. Includes are inlined, except
+/// for the common shader of the config, or else common.glsl, which becomes the Common tab.
+/// Returns a warning for everything Shadertoy cannot express.
+pub fn export(settings: &ExportSettings) -> Result<Vec<Diagnostic>> {
+    let source = std::fs::read_to_string(&settings.config)
+        .map_err(|err| Error::io(&settings.config, err))?;
+    let config = EffectConfig::from_toml(&source)?;
+    if !config.is_ok() {
+        return Err(Error::shadertoy(format!(
+            "{} has errors, run grimoire check to see them",
+            settings.config.display()
+        )));
+    }
+    let dir = settings.config.parent().unwrap_or_else(|| Path::new("."));
+    let mut exporter = Exporter {
+        settings,
+        config: &config,
+        dir,
+        includes: shader_includes(dir)?,
+        common: None,
+        buffers: Vec::new(),
+        warnings: Vec::new(),
+    };
+    let shader = exporter.export()?;
+    let json = serde_json::to_string_pretty(&ExportJson { shader: &shader })
+        .map_err(|err| Error::bug(format!("Cannot serialize the Shadertoy export: {}", err)))?;
+    std::fs::write(&settings.output, json).map_err(|err| Error::io(&settings.output, err))?;
+    Ok(exporter.warnings)
+}
+
+/// Every GLSL file under `dir`, keyed by file name like the includes of a running project
+fn shader_includes(dir: &Path) -> Result<BTreeMap<String, ShaderInclude>> {
+    let mut includes = BTreeMap::new();
+    for entry in WalkDir::new(dir).into_iter().filter_map(|e| e.ok()) {
+        let path = entry.path();
+        let is_glsl = path
+            .extension()
+            .map(|s| s == "glsl" || s == "vert" || s == "frag" || s == "vs" || s == "fs")
+            .unwrap_or(false);
+        if !path.is_file() || !is_glsl {
+            continue;
+        }
+        let source = std::fs::read_to_string(path).map_err(|err| Error::io(path, err))?;
+        includes.insert(
+            entry.file_name().to_string_lossy().into_owned(),
+            ShaderInclude {
+                path: path.display().to_string(),
+                source,
+            },
+        );
+    }
+    Ok(includes)
+}
+
+/// Remove the `main` function that calls `mainImage`, and the output it writes to, since
+/// Shadertoy provides both. Returns None if there is no such function.
+fn strip_main(code: &str) -> Option<String> {
+    lazy_static! {
+        static ref MAIN: Regex =
+            Regex::new(r"(?m)^[ \t]*void\s+main\s*\(\s*(void)?\s*\)\s*\{").unwrap();
+        static ref OUTPUT: Regex =
+            Regex::new(r"(?m)^[ \t]*(layout\s*\([^)]*\)\s*)?out\s+vec4\s+\w+\s*;[ \t]*\n?")
+                .unwrap();
+    }
+    let start = MAIN.find(code)?;
+    let mut depth = 0;
+    let mut end = None;
+    for (offset, c) in code[start.end() - 1..].char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    end = Some(start.end() - 1 + offset + 1);
+                    break;
+                }
+            }
+            _ => {}
+        }
+    }
+    let end = end?;
+    if !code[start.start()..end].contains("mainImage") {
+        return None;
+    }
+    let stripped = format!("{}{}", &code[..start.start()], &code[end..]);
+    let stripped = OUTPUT.replace_all(&stripped, "");
+    Some(format!("{}\n", stripped.trim_end()))
+}
+
+/// Whether two shaders are the same, ignoring whitespace
+fn same_shader(a: &str, b: &str) -> bool {
+    let a = a.chars().filter(|c| !c.is_whitespace());
+    let b = b.chars().filter(|c| !c.is_whitespace());
+    a.eq(b)
+}
+
+/// Shadertoy serves its media files under their SHA-256, like
+/// 79520a3d3a0f4d3caa440802ef4362e99d54e12b1392973e4ea321840970a88a.jpg
+fn is_shadertoy_media(path: &str) -> bool {
+    let stem = Path::new(path)
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or("");
+    let hash = stem.split('_').next().unwrap_or("");
+    hash.len() == 64 && hash.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+struct Exporter<'a> {
+    settings: &'a ExportSettings,
+    config: &'a EffectConfig,
+    /// Directory of the config, which shader and media paths are relative to
+    dir: &'a Path,
+    includes: BTreeMap<String, ShaderInclude>,
+    /// The shader that becomes the Common tab, keyed by the name passes include it by
+    common: Option<(String, ShaderInclude)>,
+    /// Exported buffer names, in Buffer A to D order
+    buffers: Vec<String>,
+    warnings: Vec<Diagnostic>,
+}
+
+impl<'a> Exporter<'a> {
+    fn warn(&mut self, pass: Option<usize>, message: String) {
+        self.warnings.push(
+            Diagnostic::warning(message)
+                .with_file(&self.settings.config)
+                .with_pass(pass),
+        );
+    }
+
+    fn export(&mut self) -> Result<ShaderJson> {
+        let config = self.config;
+        for (name, resource) in &config.resources {
+            let unsupported = match resource {
+                ResourceConfig::Model(_) => "Shadertoy has no models",
+                ResourceConfig::Texture3D(_) => "Shadertoy only has its own 3D textures",
+                ResourceConfig::Texture2D(_) => "Shadertoy cannot load raw textures",
+                ResourceConfig::GstAppSinkPipeline(_) => "Shadertoy has no gstreamer pipelines",
+                ResourceConfig::Storage(_) => "Shadertoy has no storage buffers",
+                ResourceConfig::Feedback(_) => "Shadertoy has no transform feedback",
+                ResourceConfig::UniformFloat(_)
+                | ResourceConfig::UniformVec2(_)
+                | ResourceConfig::UniformVec3(_)
+                | ResourceConfig::UniformVec4(_) => {
+                    "Shadertoy has no uniform resources, declare it as a constant instead"
+                }
+                _ => continue,
+            };
+            self.warn(None, format!("Skipped [{}], {}", name, unsupported));
+        }
+
+        // Shadertoy runs one pass per buffer, Buffer A to D, and then the Image tab
+        let mut buffer_passes = Vec::new();
+        let mut image = None;
+        for (index, pass) in config.passes.iter().enumerate() {
+            if pass.disable {
+                continue;
+            }
+            if pass.is_compute() {
+                self.warn(
+                    Some(index),
+                    "Skipped, Shadertoy has no compute shaders".into(),
+                );
+                continue;
+            }
+            if pass.is_capture() {
+                self.warn(
+                    Some(index),
+                    "Skipped, Shadertoy has no transform feedback".into(),
+                );
+                continue;
+            }
+            let buffer = match &pass.buffer {
+                Some(buffer) => buffer,
+                None => {
+                    if let Some(previous) = image.replace(index) {
+                        self.warn(
+                            Some(previous),
+                            "Skipped, only the last pass drawn to the window becomes the Image tab"
+                                .into(),
+                        );
+                    }
+                    continue;
+                }
+            };
+            if self.buffers.contains(buffer) {
+                self.warn(
+                    Some(index),
+                    format!(
+                        "Skipped, Shadertoy draws each buffer once and an earlier pass draws \"{}\"",
+                        buffer
+                    ),
+                );
+            } else if self.buffers.len() == BUFFER_OUTPUT_IDS.len() {
+                self.warn(
+                    Some(index),
+                    format!(
+                        "Skipped, \"{}\" would be a fifth buffer and Shadertoy only has Buffer A to D",
+                        buffer
+                    ),
+                );
+            } else {
+                self.buffers.push(buffer.clone());
+                buffer_passes.push(index);
+            }
+        }
+
+        // The common shader of the config, or else common.glsl, becomes the Common tab, and
+        // is left out of the passes that include it
+        self.common = match &config.common {
+            Some(path) => {
+                let name = Path::new(path)
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_else(|| path.clone());
+                let include = ShaderInclude {
+                    path: self.dir.join(path).display().to_string(),
+                    source: self.read(path)?,
+                };
+                Some((name, include))
+            }
+            None => self
+                .includes
+                .get("common.glsl")
+                .map(|common| ("common.glsl".to_string(), common.clone())),
+        };
+        let mut pass_includes = self.includes.clone();
+        if let Some((name, common)) = &self.common {
+            pass_includes.insert(
+                name.clone(),
+                ShaderInclude {
+                    path: common.path.clone(),
+                    source: String::new(),
+                },
+            );
+        }
+        let mut renderpass = Vec::new();
+        let mut uses_common = config.common.is_some();
+        for (letter, index) in buffer_passes.iter().enumerate() {
+            let name = format!("Buffer {}", (b'A' + letter as u8) as char);
+            let output = BUFFER_OUTPUT_IDS[letter].0;
+            let (pass, includes_common) =
+                self.export_pass(*index, &name, "buffer", output, &pass_includes)?;
+            uses_common |= includes_common;
+            renderpass.push(pass);
+        }
+        match image {
+            Some(index) => {
+                let (pass, includes_common) =
+                    self.export_pass(index, "Image", "image", IMAGE_OUTPUT_ID, &pass_includes)?;
+                uses_common |= includes_common;
+                renderpass.push(pass);
+            }
+            None => self.warn(
+                None,
+                "No pass draws to the window, the Image tab is empty".into(),
+            ),
+        }
+        let mut sounds = config
+            .resources
+            .iter()
+            .filter_map(|(name, resource)| match resource {
+                ResourceConfig::Sound(sound) => Some((name, sound)),
+                _ => None,
+            });
+        if let Some((_, sound)) = sounds.next() {
+            let source = self.read(&sound.sound)?;
+            let expanded = source_map::expand_includes(&sound.sound, &source, &pass_includes)?;
+            renderpass.push(RenderPassJson {
+                inputs: Vec::new(),
+                outputs: Vec::new(),
+                code: expanded.source,
+                name: "Sound".to_string(),
+                kind: "sound".to_string(),
+            });
+        }
+        for (name, _) in sounds {
+            self.warn(
+                None,
+                format!("Skipped [{}], Shadertoy only has one Sound tab", name),
+            );
+        }
+        if let (Some((_, common)), true) = (self.common.clone(), uses_common) {
+            let expanded =
+                source_map::expand_includes(&common.path, &common.source, &self.includes)?;
+            renderpass.insert(
+                0,
+                RenderPassJson {
+                    inputs: Vec::new(),
+                    outputs: Vec::new(),
+                    code: expanded.source,
+                    name: "Common".to_string(),
+                    kind: "common".to_string(),
+                },
+            );
+        }
+
+        let name = self
+            .dir
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        Ok(ShaderJson {
+            ver: "0.1".to_string(),
+            info: InfoJson {
+                name,
+                description: "Exported from grimoire".to_string(),
+                ..Default::default()
+            },
+            renderpass,
+        })
+    }
+
+    /// Returns the tab and whether the pass includes the common shader
+    fn export_pass(
+        &mut self,
+        index: usize,
+        name: &str,
+        kind: &str,
+        output: &str,
+        pass_includes: &BTreeMap<String, ShaderInclude>,
+    ) -> Result<(RenderPassJson, bool)> {
+        lazy_static! {
+            static ref CHANNEL_UNIFORM: Regex =
+                Regex::new(r"\biChannel([0-3])_(Resolution|Time)\b").unwrap();
+        }
+        let config = self.config;
+        let pass: &PassConfig = &config.passes[index];
+        let fullscreen = DrawConfig::Raw(DrawRawConfig {
+            mode: DrawModeConfig::Triangles,
+            count: 1,
+        });
+        match &pass.draw {
+            DrawConfig::Model(_) => self.warn(
+                Some(index),
+                "Draws a model, Shadertoy only draws fullscreen fragment shaders".into(),
+            ),
+            DrawConfig::Feedback(_) => self.warn(
+                Some(index),
+                "Draws captured vertices, Shadertoy only draws fullscreen fragment shaders".into(),
+            ),
+            _ if pass.is_main_image() => {}
+            draw => {
+                let vertex = self.read(&pass.vertex)?;
+                if *draw != fullscreen || !same_shader(&vertex, FULLSCREEN_TRIANGLE) {
+                    self.warn(
+                        Some(index),
+                        format!(
+                            "Uses the custom vertex shader {}, Shadertoy only draws fullscreen \
+                             fragment shaders",
+                            pass.vertex
+                        ),
+                    );
+                }
+            }
+        }
+        if let Some(geometry) = &pass.geometry {
+            self.warn(
+                Some(index),
+                format!(
+                    "Skipped the geometry shader {}, Shadertoy has none",
+                    geometry
+                ),
+            );
+        }
+
+        let source = match builtin::shader(&pass.fragment) {
+            Some(source) => {
+                self.warn(
+                    Some(index),
+                    "Draws the v_color of its vertex shader, Shadertoy only draws fragment shaders"
+                        .into(),
+                );
+                source.to_string()
+            }
+            None => self.read(&pass.fragment)?,
+        };
+        let includes_common = match &self.common {
+            Some((name, common)) => {
+                let mut includes = self.includes.clone();
+                includes.insert(name.clone(), common.clone());
+                source_map::expand_includes(&pass.fragment, &source, &includes)?
+                    .map
+                    .contains_file(&common.path)
+            }
+            None => false,
+        };
+        let expanded = source_map::expand_includes(&pass.fragment, &source, pass_includes)?;
+        let code = match strip_main(&expanded.source) {
+            Some(code) => code,
+            None => {
+                if !expanded.source.contains("mainImage") {
+                    self.warn(
+                        Some(index),
+                        format!(
+                            "{} has no mainImage function, which Shadertoy calls",
+                            pass.fragment
+                        ),
+                    );
+                }
+                expanded.source
+            }
+        };
+        let code = CHANNEL_UNIFORM
+            .replace_all(&code, "iChannel${2}[${1}]")
+            .into_owned();
+
+        let mut inputs = Vec::new();
+        for (uniform, channel) in &pass.uniform_to_channel {
+            let channel_index = strip_prefix(uniform, "iChannel")
+                .and_then(|n| n.parse::<usize>().ok())
+                .filter(|n| *n < 4);
+            let channel_index = match channel_index {
+                Some(channel_index) => channel_index,
+                None => {
+                    self.warn(
+                        Some(index),
+                        format!(
+                            "Skipped {}, Shadertoy only has iChannel0 to iChannel3",
+                            uniform
+                        ),
+                    );
+                    continue;
+                }
+            };
+            if let Some(input) = self.export_input(index, uniform, channel, channel_index) {
+                inputs.push(input);
+            }
+        }
+        Ok((
+            RenderPassJson {
+                inputs,
+                outputs: vec![OutputJson {
+                    id: Value::String(output.to_string()),
+                    channel: 0,
+                }],
+                code,
+                name: name.to_string(),
+                kind: kind.to_string(),
+            },
+            includes_common,
+        ))
+    }
+
+    fn export_input(
+        &mut self,
+        index: usize,
+        uniform: &str,
+        channel: &ChannelConfig,
+        channel_index: usize,
+    ) -> Option<InputJson> {
+        let (name, attachment, wrap, filter) = match channel {
+            ChannelConfig::Simple(name) => {
+                (name, 0, WrapConfig::default(), FilterConfig::default())
+            }
+            ChannelConfig::Complete {
+                resource,
+                attachment,
+                wrap,
+                filter,
+            } => (resource, *attachment, wrap.clone(), filter.clone()),
+        };
+        let filter = match filter {
+            FilterConfig::Linear => "linear",
+            FilterConfig::Nearest => "nearest",
+            FilterConfig::Mipmap => "mipmap",
+        };
+        let wrap = match wrap {
+            WrapConfig::Clamp => "clamp",
+            WrapConfig::Repeat => "repeat",
+        };
+        // Unknown resources are reported by grimoire check
+        let config = self.config;
+        let resource = config.resources.get(name)?;
+        let mut vflip = true;
+        let (id, src, ctype) = match resource {
+            ResourceConfig::Buffer(_) => {
+                let letter = match self.buffers.iter().position(|b| b == name) {
+                    Some(letter) => letter,
+                    None => {
+                        self.warn(
+                            Some(index),
+                            format!("Skipped {}, no exported pass draws \"{}\"", uniform, name),
+                        );
+                        return None;
+                    }
+                };
+                if attachment != 0 {
+                    self.warn(
+                        Some(index),
+                        format!(
+                            "{} reads attachment {} of \"{}\", Shadertoy buffers have only one",
+                            uniform, attachment, name
+                        ),
+                    );
+                }
+                (
+                    BUFFER_OUTPUT_IDS[letter].0.to_string(),
+                    format!("/media/previz/buffer{:02}.png", letter),
+                    "buffer",
+                )
+            }
+            ResourceConfig::Image(image) => {
+                vflip = image.flipv;
+                let src = self.media_src(index, uniform, &image.image);
+                (name.clone(), src, "texture")
+            }
+            ResourceConfig::Cubemap(cubemap) => {
+                vflip = cubemap.flipv;
+                let src = self.media_src(index, uniform, &cubemap.right);
+                (name.clone(), src, "cubemap")
+            }
+            ResourceConfig::Video(video) => {
+                let src = self.media_src(index, uniform, &video.video);
+                (name.clone(), src, "video")
+            }
+            ResourceConfig::Audio(audio) => {
+                let src = self.media_src(index, uniform, &audio.audio);
+                (name.clone(), src, "music")
+            }
+            ResourceConfig::Keyboard(_) => (
+                "4dXGRr".to_string(),
+                "/presets/tex00.jpg".to_string(),
+                "keyboard",
+            ),
+            ResourceConfig::WebCam(_) => {
+                (name.clone(), "/presets/webcam.png".to_string(), "webcam")
+            }
+            ResourceConfig::Microphone(_) => (name.clone(), "/presets/mic.png".to_string(), "mic"),
+            ResourceConfig::Sound(_) => {
+                self.warn(
+                    Some(index),
+                    format!(
+                        "Skipped {}, Shadertoy passes cannot read the Sound tab",
+                        uniform
+                    ),
+                );
+                return None;
+            }
+            // Already reported as unsupported resources
+            _ => return None,
+        };
+        Some(InputJson {
+            id: Value::String(id),
+            src,
+            ctype: ctype.to_string(),
+            channel: channel_index,
+            sampler: SamplerJson {
+                filter: filter.to_string(),
+                wrap: wrap.to_string(),
+                vflip: vflip.to_string(),
+                srgb: "false".to_string(),
+                internal: "byte".to_string(),
+            },
+        })
+    }
+
+    /// The Shadertoy media path of a project file
+    fn media_src(&mut self, index: usize, uniform: &str, path: &str) -> String {
+        if !is_shadertoy_media(path) {
+            self.warn(
+                Some(index),
+                format!(
+                    "{} reads {}, which must be replaced with a file from the Shadertoy media library",
+                    uniform, path
+                ),
+            );
+        }
+        format!("/media/a/{}", media_file_name(path))
+    }
+
+    fn read(&self, path: &str) -> Result<String> {
+        let path = self.dir.join(path);
+        std::fs::read_to_string(&path).map_err(|err| Error::io(&path, err))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ProfileConfig;
+
+    const TEXTURE: &str =
+        "/media/a/0c7bf5fe9462d5bffbd11126e82908e39be3ce56220d900f633d58fb432e56f5.png";
+    const CUBEMAP: &str =
+        "/media/a/585f9546c092f53ded45332b343144396c0b2d70d9965f585ebc172080d8aa58.jpg";
+
+    /// An export with a Common tab, Buffer A reading itself and a texture, and an Image
+    /// tab reading Buffer A by the id of an older export, a cubemap, a cubemap buffer
+    /// and the keyboard
+    fn export_json() -> String {
+        format!(
+            r#"{{"Shader": {{
+  "ver": "0.1",
+  "info": {{"id": "XsX3RB", "name": "Test", "username": "someone", "description": "A test"}},
+  "renderpass": [
+    {{"inputs": [], "outputs": [], "code": "float twice(float x) {{ return 2.0 * x; }}", "name": "Common", "type": "common"}},
+    {{"inputs": [
+        {{"id": "4dXGR8", "src": "/media/previz/buffer00.png", "ctype": "buffer", "channel": 0,
+          "sampler": {{"filter": "linear", "wrap": "clamp", "vflip": "true", "srgb": "false", "internal": "byte"}}}},
+        {{"id": "XdX3Rn", "src": "{texture}", "ctype": "texture", "channel": 1,
+          "sampler": {{"filter": "mipmap", "wrap": "repeat", "vflip": "true", "srgb": "false", "internal": "byte"}}}}
+      ],
+      "outputs": [{{"id": "4dXGR8", "channel": 0}}],
+      "code": "void mainImage(out vec4 c, in vec2 p) {{ c = texture(iChannel1, p); }}",
+      "name": "Buffer A", "type": "buffer"}},
+    {{"inputs": [
+        {{"id": 257, "filepath": "/media/previz/buffer00.png", "type": "buffer", "channel": 0}},
+        {{"id": "4sf3Rr", "src": "{cubemap}", "ctype": "cubemap", "channel": 1,
+          "sampler": {{"filter": "linear", "wrap": "clamp", "vflip": "false", "srgb": "false", "internal": "byte"}}}},
+        {{"id": "4sXGRr", "src": "/media/previz/cubemap00.png", "ctype": "cubemap", "channel": 2}},
+        {{"id": "4dXGRr", "src": "/presets/tex00.jpg", "ctype": "keyboard", "channel": 3}}
+      ],
+      "outputs": [{{"id": "4dfGRr", "channel": 0}}],
+      "code": "void mainImage(out vec4 c, in vec2 p) {{ c = texture(iChannel0, p / iChannelResolution[0].xy); }}",
+      "name": "Image", "type": "image"}}
+  ]
+}}}}"#,
+            texture = TEXTURE,
+            cubemap = CUBEMAP
+        )
+    }
+
+    fn import_settings(output: PathBuf) -> ImportSettings {
+        ImportSettings {
+            json: PathBuf::from("XsX3RB.json"),
+            output,
+            media: None,
+            force: true,
+        }
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("grimoire-{}-{}", std::process::id(), name));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn channel(resource: &str, filter: FilterConfig, wrap: WrapConfig) -> ChannelConfig {
+        ChannelConfig::Complete {
+            resource: resource.to_string(),
+            attachment: 0,
+            wrap,
+            filter,
+        }
+    }
+
+    #[test]
+    fn import_config() {
+        let shader = parse_export(&export_json()).unwrap();
+        let settings = import_settings(PathBuf::from("project"));
+        let mut importer = Importer::new(&shader, &settings);
+        importer.import();
+        let files = importer.files().unwrap();
+        let names: Vec<&str> = files.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec!["grim.toml", "common.glsl", "buffer-a.glsl", "image.glsl"]
+        );
+        // Only the cubemap buffer is skipped
+        assert_eq!(importer.warnings.len(), 1);
+        assert!(importer.warnings[0]
+            .message
+            .starts_with("Skipped iChannel2 of \"Image\""));
+
+        let toml = &files[0].1;
+        assert!(toml.starts_with("# \"Test\" by someone, https://www.shadertoy.com/view/XsX3RB\n"));
+        let config = EffectConfig::from_toml(toml).unwrap();
+        assert!(config.is_ok());
+        assert_eq!(config.profile, ProfileConfig::Shadertoy);
+        assert_eq!(config.common, Some("./common.glsl".to_string()));
+        let resources: Vec<&str> = config.resources.keys().map(String::as_str).collect();
+        assert_eq!(
+            resources,
+            vec!["buffer-a", "cubemap-0", "keyboard", "texture-0"]
+        );
+        match &config.resources["texture-0"] {
+            ResourceConfig::Image(image) => {
+                assert_eq!(image.image, format!("./media/{}", media_file_name(TEXTURE)));
+                assert!(image.flipv);
+            }
+            resource => panic!("texture-0 is {:?}", resource),
+        }
+        // The other faces of a cubemap are numbered after the +X face
+        let stem = media_file_name(CUBEMAP).trim_end_matches(".jpg");
+        match &config.resources["cubemap-0"] {
+            ResourceConfig::Cubemap(cubemap) => {
+                assert_eq!(cubemap.right, format!("./media/{}.jpg", stem));
+                assert_eq!(cubemap.left, format!("./media/{}_1.jpg", stem));
+                assert_eq!(cubemap.back, format!("./media/{}_5.jpg", stem));
+                assert!(!cubemap.flipv);
+            }
+            resource => panic!("cubemap-0 is {:?}", resource),
+        }
+        assert_eq!(
+            importer.media,
+            vec![TEXTURE.to_string(), CUBEMAP.to_string()]
+                .into_iter()
+                .chain((1..6).map(|face| format!("/media/a/{}_{}.jpg", stem, face)))
+                .collect::<Vec<_>>()
+        );
+
+        // Buffer A is drawn before the Image tab, which reads it by its preview image
+        assert_eq!(config.passes.len(), 2);
+        let buffer = &config.passes[0];
+        assert_eq!(buffer.buffer, Some("buffer-a".to_string()));
+        assert_eq!(buffer.fragment, "./buffer-a.glsl");
+        assert!(buffer.is_main_image());
+        assert_eq!(
+            buffer.uniform_to_channel["iChannel0"],
+            channel("buffer-a", FilterConfig::Linear, WrapConfig::Clamp)
+        );
+        assert_eq!(
+            buffer.uniform_to_channel["iChannel1"],
+            channel("texture-0", FilterConfig::Mipmap, WrapConfig::Repeat)
+        );
+        let image = &config.passes[1];
+        assert_eq!(image.buffer, None);
+        let channels: Vec<&str> = image
+            .uniform_to_channel
+            .keys()
+            .map(String::as_str)
+            .collect();
+        assert_eq!(channels, vec!["iChannel0", "iChannel1", "iChannel3"]);
+        assert_eq!(
+            image.uniform_to_channel["iChannel0"],
+            channel("buffer-a", FilterConfig::Linear, WrapConfig::Clamp)
+        );
+        assert_eq!(
+            image.uniform_to_channel["iChannel1"],
+            channel("cubemap-0", FilterConfig::Linear, WrapConfig::Clamp)
+        );
+        assert_eq!(
+            image.uniform_to_channel["iChannel3"],
+            channel("keyboard", FilterConfig::Linear, WrapConfig::Clamp)
+        );
+
+        assert_eq!(files[1].1, "float twice(float x) { return 2.0 * x; }\n");
+        assert_eq!(
+            files[3].1,
+            "// Image of \"Test\" by someone, https://www.shadertoy.com/view/XsX3RB\n\
+             void mainImage(out vec4 c, in vec2 p) { c = texture(iChannel0, p / iChannelResolution[0].xy); }\n"
+        );
+    }
+
+    #[test]
+    fn export_imported_project() {
+        let dir = temp_dir("shadertoy-round-trip");
+        let mut import = import_settings(dir.clone());
+        import.json = dir.join("XsX3RB.json");
+        std::fs::write(&import.json, export_json()).unwrap();
+        super::import(&import).unwrap();
+        let settings = ExportSettings {
+            config: dir.join("grim.toml"),
+            output: dir.join("export.json"),
+        };
+        let warnings = super::export(&settings).unwrap();
+        let text = std::fs::read_to_string(&settings.output).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        // Every media file is from the Shadertoy media library, so nothing needs replacing
+        assert!(warnings.is_empty(), "{:?}", warnings);
+
+        let original = parse_export(&export_json()).unwrap();
+        let shader = parse_export(&text).unwrap();
+        let kinds: Vec<&str> = shader
+            .renderpass
+            .iter()
+            .map(|pass| pass.kind.as_str())
+            .collect();
+        assert_eq!(kinds, vec!["common", "buffer", "image"]);
+        assert_eq!(
+            shader.renderpass[0].code,
+            "float twice(float x) { return 2.0 * x; }\n"
+        );
+        for (exported, original) in shader.renderpass.iter().zip(&original.renderpass).skip(1) {
+            assert_eq!(exported.name, original.name);
+            assert!(exported.code.ends_with(&format!("{}\n", original.code)));
+            assert_eq!(
+                id_string(&exported.outputs[0].id),
+                id_string(&original.outputs[0].id)
+            );
+        }
+
+        let inputs = |pass: &RenderPassJson| -> Vec<(usize, String, String, String)> {
+            pass.inputs
+                .iter()
+                .map(|input| {
+                    (
+                        input.channel,
+                        input.ctype.clone(),
+                        input.src.clone(),
+                        input.sampler.vflip.clone(),
+                    )
+                })
+                .collect()
+        };
+        let input = |channel: usize, ctype: &str, src: &str, vflip: &str| {
+            (
+                channel,
+                ctype.to_string(),
+                src.to_string(),
+                vflip.to_string(),
+            )
+        };
+        assert_eq!(
+            inputs(&shader.renderpass[1]),
+            vec![
+                input(0, "buffer", "/media/previz/buffer00.png", "true"),
+                input(1, "texture", TEXTURE, "true"),
+            ]
+        );
+        assert_eq!(id_string(&shader.renderpass[1].inputs[0].id), "4dXGR8");
+        assert_eq!(shader.renderpass[1].inputs[1].sampler.filter, "mipmap");
+        assert_eq!(shader.renderpass[1].inputs[1].sampler.wrap, "repeat");
+        assert_eq!(
+            inputs(&shader.renderpass[2]),
+            vec![
+                input(0, "buffer", "/media/previz/buffer00.png", "true"),
+                input(1, "cubemap", CUBEMAP, "false"),
+                input(3, "keyboard", "/presets/tex00.jpg", "true"),
+            ]
+        );
+    }
+
+    #[test]
+    fn export_main_and_channel_uniforms() {
+        let dir = temp_dir("shadertoy-export");
+        std::fs::write(
+            dir.join("grim.toml"),
+            "[[pass]]\nfragment = \"./image.glsl\"\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("image.glsl"),
+            "out vec4 fragColor;\n\
+             void mainImage(out vec4 c, in vec2 p) { c = vec4(p / iChannel0_Resolution.xy, iChannel1_Time, 1.0); }\n\
+             void main() {\n    mainImage(fragColor, gl_FragCoord.xy);\n}\n",
+        )
+        .unwrap();
+        let settings = ExportSettings {
+            config: dir.join("grim.toml"),
+            output: dir.join("export.json"),
+        };
+        let warnings = super::export(&settings).unwrap();
+        let text = std::fs::read_to_string(&settings.output).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(warnings.is_empty(), "{:?}", warnings);
+        let shader = parse_export(&text).unwrap();
+        assert_eq!(shader.renderpass.len(), 1);
+        // Shadertoy provides main and its output, and indexes the channel uniforms
+        assert_eq!(
+            shader.renderpass[0].code,
+            "void mainImage(out vec4 c, in vec2 p) { c = vec4(p / iChannelResolution[0].xy, iChannelTime[1], 1.0); }\n"
+        );
+    }
+
+    #[test]
+    fn strip_main_only_when_it_calls_main_image() {
+        assert_eq!(
+            strip_main("void mainImage(out vec4 c, in vec2 p) {}\nvoid main(void) { if (true) { mainImage(o, p); } }\n"),
+            Some("void mainImage(out vec4 c, in vec2 p) {}\n".to_string())
+        );
+        assert_eq!(
+            strip_main("void main() { gl_FragColor = vec4(1.0); }\n"),
+            None
+        );
+        assert_eq!(
+            strip_main("void mainImage(out vec4 c, in vec2 p) {}\n"),
+            None
+        );
+    }
+}
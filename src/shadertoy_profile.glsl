@@ -0,0 +1,16 @@
+/*
+BEGIN: shadertoy.glsl
+*/
+// iFrame is the int member of GRIM_STATE
+#define iFrame grim_iFrame
+uniform vec3 iResolution;
+uniform int iVertexCount;
+uniform int iPassIndex;
+uniform mat4 iModelMatrix;
+uniform vec3 iChannelResolution[4];
+uniform float iChannelTime[4];
+
+#define GRIMOIRE
+/*
+END: shadertoy.glsl
+*/
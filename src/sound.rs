@@ -0,0 +1,183 @@
+use crate::effect;
+use crate::effect_player::EffectPlayer;
+use crate::error::{Error, Result};
+use crate::platform::Platform;
+use crate::render;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone)]
+pub struct SoundSettings {
+    /// The WAV file to write
+    pub output: PathBuf,
+    /// Length of the sound in seconds
+    pub duration: f64,
+    /// Render the named sound resource instead of the only one
+    pub resource: Option<String>,
+}
+
+/// Render a sound resource of the project into a 16-bit stereo WAV file, as fast as the
+/// GPU allows rather than in real time.
+pub fn render_sound(
+    player: &mut EffectPlayer,
+    platform: &mut Platform,
+    settings: &SoundSettings,
+) -> Result<()> {
+    let gl = platform.gl.clone();
+    // The sound shaders only need a pipeline, so draw the passes as small as possible
+    let mut offscreen = render::prepare_offscreen(player, platform, 1, 1, 60);
+    let result = render_samples(player, platform, settings);
+    offscreen.delete(&gl);
+    let samples = result?;
+    if let Some(output_dir) = settings.output.parent() {
+        std::fs::create_dir_all(output_dir).map_err(|err| Error::io(output_dir, err))?;
+    }
+    write_wav(&settings.output, &samples, effect::SAMPLE_RATE as u32)
+}
+
+fn render_samples(
+    player: &mut EffectPlayer,
+    platform: &mut Platform,
+    settings: &SoundSettings,
+) -> Result<Vec<f32>> {
+    player.seek(Duration::default(), 0);
+    player.tick(platform)?;
+    if !player.is_ok() {
+        return Err(Error::render(
+            "The project failed to build, see the errors above",
+        ));
+    }
+    let names = player.sound_names();
+    let name = match (&settings.resource, names.as_slice()) {
+        (Some(name), _) if names.contains(name) => name.clone(),
+        (Some(name), _) => {
+            return Err(Error::render(format!(
+                "The project has no sound resource named \"{}\"",
+                name
+            )))
+        }
+        (None, [name]) => name.clone(),
+        (None, []) => return Err(Error::render("The project has no sound resource")),
+        (None, _) => {
+            return Err(Error::render(format!(
+                "The project has several sound resources, choose one of {:?} with --sound",
+                names
+            )))
+        }
+    };
+    let instant = Instant::now();
+    let sample_count = (settings.duration * f64::from(effect::SAMPLE_RATE)).round() as u64;
+    if sample_count > effect::SOUND_MAX_SAMPLES {
+        return Err(Error::render(format!(
+            "Sounds can be at most {:.0} seconds long",
+            effect::SOUND_MAX_SAMPLES as f64 / f64::from(effect::SAMPLE_RATE)
+        )));
+    }
+    let mut samples = Vec::with_capacity(sample_count as usize * 2);
+    let mut start = 0;
+    while start < sample_count {
+        let block = player
+            .render_sound(platform, &name, start)
+            .ok_or_else(|| Error::bug("[GRIMOIRE/SOUND] Sound resource disappeared"))?;
+        samples.extend_from_slice(&block);
+        start += effect::SOUND_BLOCK_SAMPLES;
+    }
+    samples.truncate(sample_count as usize * 2);
+    info!(
+        "[SOUND] Rendered {} samples of \"{}\" in {:?}",
+        sample_count,
+        name,
+        instant.elapsed()
+    );
+    Ok(samples)
+}
+
+/// Write interleaved stereo samples in [-1, 1] as a 16-bit PCM WAV file
+fn write_wav(path: &Path, samples: &[f32], sample_rate: u32) -> Result<()> {
+    let file = File::create(path).map_err(|err| Error::io(path, err))?;
+    let mut writer = BufWriter::new(file);
+    let bytes = wav_bytes(samples, sample_rate);
+    writer
+        .write_all(&bytes)
+        .and_then(|_| writer.flush())
+        .map_err(|err| Error::io(path, err))
+}
+
+/// The 16-bit PCM WAV file of interleaved stereo samples, clamped to [-1, 1]
+fn wav_bytes(samples: &[f32], sample_rate: u32) -> Vec<u8> {
+    let channels: u16 = 2;
+    let bytes_per_sample: u16 = 2;
+    let data_len = samples.len() as u32 * u32::from(bytes_per_sample);
+    let mut bytes = Vec::with_capacity(44 + data_len as usize);
+    bytes.extend_from_slice(b"RIFF");
+    bytes.extend_from_slice(&(36 + data_len).to_le_bytes());
+    bytes.extend_from_slice(b"WAVE");
+    bytes.extend_from_slice(b"fmt ");
+    bytes.extend_from_slice(&16u32.to_le_bytes());
+    // PCM
+    bytes.extend_from_slice(&1u16.to_le_bytes());
+    bytes.extend_from_slice(&channels.to_le_bytes());
+    bytes.extend_from_slice(&sample_rate.to_le_bytes());
+    let block_align = channels * bytes_per_sample;
+    bytes.extend_from_slice(&(sample_rate * u32::from(block_align)).to_le_bytes());
+    bytes.extend_from_slice(&block_align.to_le_bytes());
+    bytes.extend_from_slice(&(bytes_per_sample * 8).to_le_bytes());
+    bytes.extend_from_slice(b"data");
+    bytes.extend_from_slice(&data_len.to_le_bytes());
+    for sample in samples {
+        let sample = (sample.max(-1.0).min(1.0) * f32::from(i16::max_value())) as i16;
+        bytes.extend_from_slice(&sample.to_le_bytes());
+    }
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wav_header() {
+        let bytes = wav_bytes(&[0.0, 0.0, 0.0, 0.0], 44100);
+        let mut expected = Vec::new();
+        expected.extend_from_slice(b"RIFF");
+        expected.extend_from_slice(&44u32.to_le_bytes());
+        expected.extend_from_slice(b"WAVEfmt ");
+        expected.extend_from_slice(&[16, 0, 0, 0, 1, 0, 2, 0]);
+        expected.extend_from_slice(&44100u32.to_le_bytes());
+        expected.extend_from_slice(&176_400u32.to_le_bytes());
+        expected.extend_from_slice(&[4, 0, 16, 0]);
+        expected.extend_from_slice(b"data");
+        expected.extend_from_slice(&8u32.to_le_bytes());
+        expected.extend_from_slice(&[0; 8]);
+        assert_eq!(bytes, expected);
+    }
+
+    #[test]
+    fn wav_samples_are_clamped() {
+        let bytes = wav_bytes(&[1.0, -1.0, 2.0, -2.0, 0.5, -0.5], 48000);
+        let samples: Vec<i16> = bytes[44..]
+            .chunks(2)
+            .map(|b| i16::from_le_bytes([b[0], b[1]]))
+            .collect();
+        assert_eq!(samples, vec![32767, -32767, 32767, -32767, 16383, -16383]);
+    }
+
+    #[test]
+    fn wav_round_trip() {
+        let samples: Vec<f32> = (0..64).map(|i| (i as f32 * 0.37).sin()).collect();
+        let path = std::env::temp_dir().join(format!("grimoire-{}.wav", std::process::id()));
+        write_wav(&path, &samples, 44100).unwrap();
+        let bytes = std::fs::read(&path);
+        std::fs::remove_file(&path).unwrap();
+        let bytes = bytes.unwrap();
+        assert_eq!(bytes, wav_bytes(&samples, 44100));
+        let data_len = u32::from_le_bytes([bytes[40], bytes[41], bytes[42], bytes[43]]);
+        assert_eq!(data_len as usize, bytes.len() - 44);
+        for (sample, b) in samples.iter().zip(bytes[44..].chunks(2)) {
+            let read = f32::from(i16::from_le_bytes([b[0], b[1]])) / 32767.0;
+            assert!((read - sample).abs() <= 1.0 / 32767.0);
+        }
+    }
+}
@@ -0,0 +1,347 @@
+use crate::diagnostics;
+use crate::error::{Error, Result};
+use crate::text::strip_prefix;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// A shader file included by pass shaders with `#include "name"`
+#[derive(Debug, Clone)]
+pub struct ShaderInclude {
+    /// The path reported in compile errors
+    pub path: String,
+    pub source: String,
+}
+
+/// A shader with its includes expanded, and the file and line each of its lines came from
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ShaderSource {
+    pub source: String,
+    pub map: SourceMap,
+}
+
+impl ShaderSource {
+    /// Append the lines of `other`
+    pub fn append(&mut self, other: &ShaderSource) {
+        self.source.push_str(&other.source);
+        self.map.append(&other.map);
+    }
+
+    /// Append `text`, which grimoire generated rather than read from a file
+    pub fn push_generated(&mut self, text: &str) {
+        self.source.push_str(text);
+        self.map.push_generated(text.lines().count());
+    }
+}
+
+/// Maps the lines of generated shader source back to the files and lines they came from.
+///
+/// Lines that grimoire generated itself, such as the uniform declarations injected
+/// ahead of every shader, have no original location.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SourceMap {
+    files: Vec<String>,
+    /// (index into files, 1-based line) for each generated line
+    lines: Vec<Option<(usize, usize)>>,
+}
+
+impl SourceMap {
+    /// Append `count` lines that were generated rather than read from a file
+    pub fn push_generated(&mut self, count: usize) {
+        self.lines.extend(std::iter::repeat(None).take(count));
+    }
+
+    /// Append a line read from `line` of `file`
+    pub fn push_line(&mut self, file: &str, line: usize) {
+        let index = match self.files.iter().position(|f| f == file) {
+            Some(index) => index,
+            None => {
+                self.files.push(file.to_string());
+                self.files.len() - 1
+            }
+        };
+        self.lines.push(Some((index, line)));
+    }
+
+    /// Append every line of `other`
+    pub fn append(&mut self, other: &SourceMap) {
+        for line in &other.lines {
+            match line {
+                Some((file, line)) => self.push_line(&other.files[*file], *line),
+                None => self.lines.push(None),
+            }
+        }
+    }
+
+    /// The file and line that 1-based generated `line` came from
+    pub fn lookup(&self, line: usize) -> Option<(&str, usize)> {
+        let entry = self.lines.get(line.checked_sub(1)?)?;
+        entry.map(|(file, line)| (self.files[file].as_str(), line))
+    }
+
+    /// Whether any line came from `file`
+    pub fn contains_file(&self, file: &str) -> bool {
+        self.files.iter().any(|f| f == file)
+    }
+
+    /// Rewrite a driver's shader info log so that every message refers to the file and
+    /// line it came from, as `path:line:column: severity: message`.
+    ///
+    /// Messages about generated lines keep their generated line number, and messages
+    /// without a line number are passed through.
+    pub fn remap_log(&self, log: &str) -> String {
+        diagnostics::parse_glsl_log(log)
+            .into_iter()
+            .map(|diagnostic| {
+                let span = match diagnostic.span {
+                    Some(span) => span,
+                    None => return format!("{}: {}", diagnostic.severity, diagnostic.message),
+                };
+                match self.lookup(span.line) {
+                    Some((file, line)) => format!(
+                        "{}:{}:{}: {}: {}",
+                        file, line, span.column, diagnostic.severity, diagnostic.message
+                    ),
+                    None => format!(
+                        "{}: generated line {}: {}",
+                        diagnostic.severity, span.line, diagnostic.message
+                    ),
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// Prepend generated `header` lines, such as the version and uniform declarations, to a
+/// shader and return the complete source along with a source map covering both.
+pub fn with_header(header: &[String], shader: &ShaderSource) -> (String, SourceMap) {
+    let header = header.join("\n");
+    let mut map = SourceMap::default();
+    map.push_generated(header.matches('\n').count() + 1);
+    map.append(&shader.map);
+    (format!("{}\n{}", header, shader.source), map)
+}
+
+/// Inline the `#include "name"` and `#include <name>` directives of the shader at `path`.
+///
+/// Includes are looked up by name, and then by the file name of the include path. Each
+/// file is inlined at most once per shader, and an include cycle is an error.
+pub fn expand_includes(
+    path: &str,
+    source: &str,
+    includes: &BTreeMap<String, ShaderInclude>,
+) -> Result<ShaderSource> {
+    let mut expander = Expander {
+        includes,
+        stack: Vec::new(),
+        included: BTreeSet::new(),
+        shader: Default::default(),
+    };
+    expander.expand(path, source)?;
+    Ok(expander.shader)
+}
+
+struct Expander<'a> {
+    includes: &'a BTreeMap<String, ShaderInclude>,
+    /// Paths of the files being expanded, outermost first
+    stack: Vec<String>,
+    /// Paths of the files already inlined
+    included: BTreeSet<String>,
+    shader: ShaderSource,
+}
+
+impl<'a> Expander<'a> {
+    fn expand(&mut self, path: &str, source: &str) -> Result<()> {
+        self.stack.push(path.to_string());
+        for (index, text) in source.lines().enumerate() {
+            let line = index + 1;
+            let (column, name) = match parse_include(text) {
+                Some(include) => include,
+                None => {
+                    self.shader.source.push_str(text);
+                    self.shader.source.push('\n');
+                    self.shader.map.push_line(path, line);
+                    continue;
+                }
+            };
+            let include = self.find(name).ok_or_else(|| {
+                self.error(
+                    path,
+                    line,
+                    column,
+                    format!("Cannot find include \"{}\"", name),
+                )
+            })?;
+            if self.stack.contains(&include.path) {
+                return Err(self.error(
+                    path,
+                    line,
+                    column,
+                    format!("\"{}\" includes itself", name),
+                ));
+            }
+            if self.included.insert(include.path.clone()) {
+                self.expand(&include.path, &include.source)?;
+            }
+        }
+        self.stack.pop();
+        Ok(())
+    }
+
+    fn find(&self, name: &str) -> Option<&'a ShaderInclude> {
+        self.includes.get(name).or_else(|| {
+            let file_name = std::path::Path::new(name).file_name()?.to_str()?;
+            self.includes.get(file_name)
+        })
+    }
+
+    fn error(&self, path: &str, line: usize, column: usize, message: String) -> Error {
+        Error::glsl_include(
+            format!("{}:{}:{}: error: {}", path, line, column, message),
+            self.stack[0].clone(),
+        )
+    }
+}
+
+/// The 1-based column and name of an `#include` directive
+fn parse_include(text: &str) -> Option<(usize, &str)> {
+    let trimmed = text.trim_start();
+    let column = text.len() - trimmed.len() + 1;
+    let rest = strip_prefix(trimmed, "#")?.trim_start();
+    let rest = strip_prefix(rest, "include")?.trim();
+    let quoted = rest.starts_with('"') && rest.ends_with('"');
+    let angled = rest.starts_with('<') && rest.ends_with('>');
+    if rest.len() < 2 || !(quoted || angled) {
+        return None;
+    }
+    Some((column, &rest[1..rest.len() - 1]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+
+    fn includes(files: &[(&str, &str, &str)]) -> BTreeMap<String, ShaderInclude> {
+        files
+            .iter()
+            .map(|&(name, path, source)| {
+                let include = ShaderInclude {
+                    path: path.to_string(),
+                    source: source.to_string(),
+                };
+                (name.to_string(), include)
+            })
+            .collect()
+    }
+
+    fn include_error(err: Error) -> String {
+        match err.kind() {
+            ErrorKind::GlslInclude(message, path) => {
+                assert_eq!(path, "main.frag");
+                message.clone()
+            }
+            kind => panic!("expected an include error, got {:?}", kind),
+        }
+    }
+
+    #[test]
+    fn parse_include_quoted_and_angled() {
+        assert_eq!(parse_include("#include \"a.glsl\""), Some((1, "a.glsl")));
+        assert_eq!(
+            parse_include("#include <lib/b.glsl>"),
+            Some((1, "lib/b.glsl"))
+        );
+        assert_eq!(
+            parse_include("  #  include\t\"c.glsl\"  "),
+            Some((3, "c.glsl"))
+        );
+        assert_eq!(parse_include("#include\"d.glsl\""), Some((1, "d.glsl")));
+    }
+
+    #[test]
+    fn parse_include_rejects_other_lines() {
+        assert_eq!(parse_include("#define include 1"), None);
+        assert_eq!(parse_include("// #include \"a.glsl\""), None);
+        assert_eq!(parse_include("#include \"a.glsl>"), None);
+        assert_eq!(parse_include("#include <a.glsl\""), None);
+        assert_eq!(parse_include("#include \""), None);
+        assert_eq!(parse_include("#include a.glsl"), None);
+    }
+
+    #[test]
+    fn expand_nested_includes() {
+        let includes = includes(&[
+            ("a.glsl", "lib/a.glsl", "// a\n#include <b.glsl>\nfloat a;"),
+            ("b.glsl", "lib/b.glsl", "float b;"),
+        ]);
+        let main = "#version 330\n#include \"a.glsl\"\nvoid main() {}";
+        let shader = expand_includes("main.frag", main, &includes).unwrap();
+        assert_eq!(
+            shader.source,
+            "#version 330\n// a\nfloat b;\nfloat a;\nvoid main() {}\n"
+        );
+        assert_eq!(shader.map.lookup(1), Some(("main.frag", 1)));
+        assert_eq!(shader.map.lookup(2), Some(("lib/a.glsl", 1)));
+        assert_eq!(shader.map.lookup(3), Some(("lib/b.glsl", 1)));
+        assert_eq!(shader.map.lookup(4), Some(("lib/a.glsl", 3)));
+        assert_eq!(shader.map.lookup(5), Some(("main.frag", 3)));
+        assert_eq!(shader.map.lookup(6), None);
+    }
+
+    #[test]
+    fn expand_includes_each_file_once() {
+        let includes = includes(&[
+            ("a.glsl", "a.glsl", "#include \"common.glsl\"\nfloat a;"),
+            ("common.glsl", "common.glsl", "float common;"),
+        ]);
+        // The include is found by the file name of its path
+        let main = "#include \"a.glsl\"\n#include \"lib/common.glsl\"";
+        let shader = expand_includes("main.frag", main, &includes).unwrap();
+        assert_eq!(shader.source, "float common;\nfloat a;\n");
+    }
+
+    #[test]
+    fn expand_includes_reports_cycles() {
+        let includes = includes(&[
+            ("a.glsl", "a.glsl", "#include \"b.glsl\""),
+            ("b.glsl", "b.glsl", "\n  #include \"a.glsl\""),
+        ]);
+        let err = expand_includes("main.frag", "#include \"a.glsl\"", &includes).unwrap_err();
+        assert_eq!(
+            include_error(err),
+            "b.glsl:2:3: error: \"a.glsl\" includes itself"
+        );
+    }
+
+    #[test]
+    fn expand_includes_reports_missing_files() {
+        let err =
+            expand_includes("main.frag", "\n#include <missing.glsl>", &includes(&[])).unwrap_err();
+        assert_eq!(
+            include_error(err),
+            "main.frag:2:1: error: Cannot find include \"missing.glsl\""
+        );
+    }
+
+    #[test]
+    fn remap_driver_log() {
+        let includes = includes(&[("a.glsl", "a.glsl", "float a;\nfloat b")]);
+        let shader = expand_includes("main.frag", "#include \"a.glsl\"\n", &includes).unwrap();
+        let header = vec!["#version 330".to_string(), "uniform float t;".to_string()];
+        let (_, map) = with_header(&header, &shader);
+        let log = "0:4(8): error: syntax error, unexpected end of file\n\
+                   0:1(1): warning: extension unsupported\n\
+                   error: linking failed";
+        assert_eq!(
+            map.remap_log(log),
+            "a.glsl:2:8: error: syntax error, unexpected end of file\n\
+             warning: generated line 1: extension unsupported\n\
+             error: linking failed"
+        );
+        // NVIDIA logs carry no column
+        assert_eq!(
+            map.remap_log("0(3) : error C0000: syntax error"),
+            "a.glsl:1:1: error: syntax error"
+        );
+    }
+}
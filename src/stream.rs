@@ -1,11 +1,11 @@
 use crate::audio::Audio;
 use crate::config::{ResourceConfig, TextureFormat};
+use crate::effect;
 use crate::error::{Error, Result};
 use crate::keyboard::Keyboard;
+use crate::model;
 use crate::platform::Platform;
-use crate::resource::{
-    GeometryData, ResourceCubemapFace, ResourceData, ResourceData2D, ResourceData3D,
-};
+use crate::resource::{ResourceCubemapFace, ResourceData, ResourceData2D, ResourceData3D};
 use crate::video::Video;
 use image;
 use image::GenericImageView;
@@ -60,17 +60,7 @@ impl ResourceStream {
         let (tx, rx) = channel();
         let ctx = match config {
             ResourceConfig::Video(config) => {
-                let uri = PathBuf::from(&config.video);
-                let uri = uri
-                    .canonicalize()
-                    .expect("Could not canonicalize file name");
-                let mut uri = uri.to_str().unwrap();
-                let windows_unc_prefix = r"\\?\";
-                if uri.starts_with(windows_unc_prefix) {
-                    uri = &uri[windows_unc_prefix.len()..];
-                }
-                let uri = format!("file:///{}", uri);
-                let uri = uri.replace(r"\", "/");
+                let uri = file_uri(&config.video)?;
                 let mut video = Video::new_video(&uri)?;
                 video.play()?;
                 Some(ResourceStreamCtx::Video(video))
@@ -81,21 +71,16 @@ impl ResourceStream {
                 Some(ResourceStreamCtx::Video(webcam))
             }
             ResourceConfig::Audio(config) => {
-                let uri = PathBuf::from(&config.audio);
-                let uri = uri
-                    .canonicalize()
-                    .expect("Could not canonicalize file name");
-                let mut uri = uri.to_str().unwrap();
-                let windows_unc_prefix = r"\\?\";
-                if uri.starts_with(windows_unc_prefix) {
-                    uri = &uri[windows_unc_prefix.len()..];
-                }
-                let uri = format!("file:///{}", uri);
-                let uri = uri.replace(r"\", "/");
+                let uri = file_uri(&config.audio)?;
                 let mut audio = Audio::new_audio(&uri, config.bands)?;
                 audio.play()?;
                 Some(ResourceStreamCtx::Audio(audio))
             }
+            ResourceConfig::Sound(config) => {
+                let mut sound = Audio::new_sound(effect::SAMPLE_RATE as u32, config.bands)?;
+                sound.play()?;
+                Some(ResourceStreamCtx::Audio(sound))
+            }
             ResourceConfig::Microphone(config) => {
                 let mut microphone = Audio::new_microphone(config.bands)?;
                 microphone.play()?;
@@ -132,6 +117,15 @@ impl ResourceStream {
         self.stream_to(&sender)?;
         Ok(self.receiver.try_iter())
     }
+
+    /// Queue interleaved stereo samples of a generated sound, starting at the sample
+    /// index `start`
+    pub fn push_samples(&mut self, start: u64, samples: &[f32]) -> Result<()> {
+        match self.ctx {
+            Some(ResourceStreamCtx::Audio(ref mut audio)) => audio.push_samples(start, samples),
+            _ => Ok(()),
+        }
+    }
 }
 
 impl ResourceWatch {
@@ -152,6 +146,9 @@ impl ResourceWatch {
         match config {
             ResourceConfig::Model(ref config) => {
                 watch_path(&mut watcher, &config.model)?;
+                for path in model::dependencies(config) {
+                    watch_path(&mut watcher, &path.to_string_lossy())?;
+                }
             }
             ResourceConfig::Image(ref config) => {
                 watch_path(&mut watcher, &config.image)?;
@@ -180,7 +177,18 @@ impl ResourceWatch {
                 watch_path(&mut watcher, &config.top)?;
                 watch_path(&mut watcher, &config.bottom)?;
             }
+            ResourceConfig::Storage(ref config) => {
+                if let Some(ref contents) = config.contents {
+                    watch_path(&mut watcher, contents)?;
+                }
+            }
+            ResourceConfig::Feedback(ref config) => {
+                if let (Some(ref contents), None) = (&config.contents, &config.seed) {
+                    watch_path(&mut watcher, contents)?;
+                }
+            }
             ResourceConfig::WebCam(_) => (),
+            ResourceConfig::Sound(_) => (),
             ResourceConfig::Microphone(_) => (),
             ResourceConfig::Keyboard(_) => (),
             ResourceConfig::GstAppSinkPipeline(_) => (),
@@ -284,53 +292,25 @@ impl Stream for ResourceWatch {
     }
 }
 
+/// Convert a path relative to the config directory into a file:/// URI for GStreamer
+pub fn file_uri(path: &str) -> Result<String> {
+    let uri = PathBuf::from(path)
+        .canonicalize()
+        .map_err(|err| Error::io(path, err))?;
+    let uri = uri.to_string_lossy();
+    let mut uri: &str = &uri;
+    let windows_unc_prefix = r"\\?\";
+    if uri.starts_with(windows_unc_prefix) {
+        uri = &uri[windows_unc_prefix.len()..];
+    }
+    let uri = format!("file:///{}", uri);
+    Ok(uri.replace(r"\", "/"))
+}
+
 fn resource_from_config(config: &ResourceConfig) -> Result<Option<ResourceData>> {
     match config {
         ResourceConfig::Model(config) => {
-            let (models, _materials) = tobj::load_obj(&Path::new(&config.model))
-                .map_err(|_| Error::bug("tobj::load_obj error"))?;
-            // If the user specified the object name, look for that, otherwise
-            // use the first model entry
-            let model = {
-                if let Some(object) = &config.object {
-                    models
-                        .iter()
-                        .find(|&m| &m.name == object)
-                        .unwrap_or(&models[0])
-                } else {
-                    &models[0]
-                }
-            };
-            let mut vertex_data: Vec<f32> = Vec::new();
-            let mesh = &model.mesh;
-            for idx in &model.mesh.indices {
-                let i = *idx as usize;
-                let position = if mesh.positions.is_empty() {
-                    [0.0, 0.0, 0.0]
-                } else {
-                    [
-                        mesh.positions[3 * i + 0],
-                        mesh.positions[3 * i + 1],
-                        mesh.positions[3 * i + 2],
-                    ]
-                };
-                let normal = if mesh.normals.is_empty() {
-                    [0.0, 0.0, 0.0]
-                } else {
-                    [
-                        mesh.normals[3 * i + 0],
-                        mesh.normals[3 * i + 1],
-                        mesh.normals[3 * i + 2],
-                    ]
-                };
-                vertex_data.extend(&position);
-                vertex_data.extend(&normal);
-            }
-            Ok(Some(ResourceData::Geometry(GeometryData {
-                buffer: vertex_data,
-                pos_stride_off: (0, 0),
-                nrm_stride_off: (0, 3),
-            })))
+            Ok(Some(ResourceData::Geometry(model::load_model(config)?)))
         }
         ResourceConfig::Image(config) => {
             let mut image =
@@ -457,9 +437,25 @@ fn resource_from_config(config: &ResourceConfig) -> Result<Option<ResourceData>>
                 time: 0.0,
             })))
         }
+        ResourceConfig::Storage(config) => match config.contents {
+            Some(ref contents) => {
+                let bytes = std::fs::read(contents).map_err(|err| Error::io(contents, err))?;
+                Ok(Some(ResourceData::Bytes(bytes)))
+            }
+            None => Ok(None),
+        },
+        // The seed shader replaces the contents, if there is one
+        ResourceConfig::Feedback(config) => match (&config.contents, &config.seed) {
+            (Some(contents), None) => {
+                let bytes = std::fs::read(contents).map_err(|err| Error::io(contents, err))?;
+                Ok(Some(ResourceData::Bytes(bytes)))
+            }
+            _ => Ok(None),
+        },
         ResourceConfig::Video(_) => Ok(None),
         ResourceConfig::WebCam(_) => Ok(None),
         ResourceConfig::Audio(_) => Ok(None),
+        ResourceConfig::Sound(_) => Ok(None),
         ResourceConfig::Microphone(_) => Ok(None),
         ResourceConfig::Keyboard(_) => Ok(None),
         ResourceConfig::GstAppSinkPipeline(_) => Ok(None),
@@ -0,0 +1,22 @@
+/// `text` after `prefix`, if it starts with it. str::strip_prefix needs Rust 1.45.
+pub(crate) fn strip_prefix<'a>(text: &'a str, prefix: &str) -> Option<&'a str> {
+    if text.starts_with(prefix) {
+        Some(&text[prefix.len()..])
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strip_prefix_matches() {
+        assert_eq!(strip_prefix("iChannel2", "iChannel"), Some("2"));
+        assert_eq!(strip_prefix("iChannel", "iChannel"), Some(""));
+        assert_eq!(strip_prefix("iChanne", "iChannel"), None);
+        assert_eq!(strip_prefix("Buffer A", "Buf"), Some("fer A"));
+        assert_eq!(strip_prefix("é#include", "#"), None);
+    }
+}
@@ -0,0 +1,425 @@
+use crate::audio::{MAX_DB, MIN_DB};
+use crate::config::{self, EffectConfig, TextureFormat};
+use crate::diagnostics::Diagnostic;
+use crate::effect::{Effect, EffectState};
+use crate::error::{Error, Result};
+use crate::resource::{ResourceData, ResourceData2D};
+use serde_json::Value;
+use std::fmt::Write;
+use std::path::PathBuf;
+
+/// Rows of history in the sound, floatSound, volume and touch textures, 4 seconds at 60 fps
+const HISTORY: usize = 240;
+/// Columns of the touch texture, one per touch point
+const TOUCH_COLUMNS: usize = 32;
+/// Columns of the volume texture
+const VOLUME_COLUMNS: usize = 4;
+
+/// The history textures of the vertexshaderart profile, by uniform name and the name of
+/// the resource that holds them
+pub const TEXTURES: [(&str, &str); 4] = [
+    ("sound", "grim_vertexshaderart_sound"),
+    ("floatSound", "grim_vertexshaderart_floatSound"),
+    ("volume", "grim_vertexshaderart_volume"),
+    ("touch", "grim_vertexshaderart_touch"),
+];
+
+/// Added to the vertex shaders of the vertexshaderart profile, which write `v_color`
+/// for the default fragment shader
+pub const VERTEX_HEADER: &str = "out vec4 v_color;\n#define vertexId float(gl_VertexID)\n";
+
+#[derive(Debug, Clone)]
+pub struct ImportSettings {
+    /// JSON of a single vertexshaderart art page
+    pub json: PathBuf,
+    /// Project directory to create
+    pub output: PathBuf,
+    /// Overwrite the files of an existing project
+    pub force: bool,
+}
+
+/// The sound, floatSound, volume and touch textures of the vertexshaderart profile.
+///
+/// Every frame, the history moves one row up and the newest values are written to the
+/// first row, so `v = 0` is the current frame and `v = 1` four seconds ago. The sound
+/// textures follow the first audio, microphone or sound resource of the project, and
+/// the first column of the touch texture follows the mouse.
+#[derive(Debug)]
+pub struct History {
+    /// Name of the resource the sound textures follow
+    source: Option<String>,
+    bands: usize,
+    /// The most recent spectrum and waveform of the source
+    fft: Vec<u8>,
+    wave: Vec<u8>,
+    sound: Vec<u8>,
+    float_sound: Vec<f32>,
+    volume: Vec<u8>,
+    touch: Vec<f32>,
+}
+
+impl History {
+    pub fn new(config: &EffectConfig) -> Self {
+        let source = config.first_sound_resource();
+        let bands = source.map_or(config::default_audio_bands(), |(_, bands)| bands);
+        Self {
+            source: source.map(|(name, _)| name.clone()),
+            bands,
+            fft: vec![0; bands],
+            wave: vec![128; bands],
+            sound: vec![0; bands * HISTORY],
+            float_sound: vec![MIN_DB; bands * HISTORY],
+            volume: vec![0; VOLUME_COLUMNS * HISTORY],
+            touch: vec![0.0; 4 * TOUCH_COLUMNS * HISTORY],
+        }
+    }
+
+    /// Keep the spectrum and waveform rows of the audio texture the sound textures follow
+    pub fn observe(&mut self, name: &str, resource: &ResourceData) {
+        if self.source.as_ref().map(String::as_str) != Some(name) {
+            return;
+        }
+        if let ResourceData::D2(data) = resource {
+            let row = match data.yoffset {
+                0 => &mut self.fft,
+                1 => &mut self.wave,
+                _ => return,
+            };
+            let len = row.len().min(data.bytes.len());
+            row[..len].copy_from_slice(&data.bytes[..len]);
+        }
+    }
+
+    /// Add a row for the current frame, and stage the textures and the uniforms that
+    /// are the same for every pass
+    pub fn stage(&mut self, effect: &mut Effect, state: &EffectState) {
+        let bands = self.bands;
+        self.sound.rotate_right(bands);
+        self.sound[..bands].copy_from_slice(&self.fft);
+        self.float_sound.rotate_right(bands);
+        for (db, byte) in self.float_sound.iter_mut().zip(&self.fft) {
+            *db = MAX_DB + (255.0 - f32::from(*byte)) / 255.0 * (MIN_DB - MAX_DB);
+        }
+        // The waveform is centered on 128
+        let levels = self
+            .wave
+            .iter()
+            .map(|byte| (i32::from(*byte) - 128).abs() * 2);
+        let peak = levels.clone().max().unwrap_or(0).min(255);
+        let average = levels.sum::<i32>() / bands.max(1) as i32;
+        self.volume.rotate_right(VOLUME_COLUMNS);
+        self.volume[..VOLUME_COLUMNS].copy_from_slice(&[peak as u8, average as u8, 0, 0]);
+        // iMouse.z is positive while a button is down
+        let resolution = [state.window_resolution[0], state.window_resolution[1]];
+        let mouse = [
+            state.mouse[0] / resolution[0] * 2.0 - 1.0,
+            state.mouse[1] / resolution[1] * 2.0 - 1.0,
+        ];
+        let pressure = if state.mouse[2] > 0.0 { 1.0 } else { 0.0 };
+        self.touch.rotate_right(4 * TOUCH_COLUMNS);
+        for texel in self.touch[..4 * TOUCH_COLUMNS].iter_mut() {
+            *texel = 0.0;
+        }
+        self.touch[..4].copy_from_slice(&[mouse[0], mouse[1], pressure, state.time]);
+
+        let float_bytes = |floats: &[f32]| {
+            let mut bytes = Vec::with_capacity(floats.len() * 4);
+            for float in floats {
+                bytes.extend_from_slice(&float.to_ne_bytes());
+            }
+            bytes
+        };
+        let textures = [
+            (self.sound.clone(), bands, TextureFormat::RU8),
+            (float_bytes(&self.float_sound), bands, TextureFormat::RF32),
+            (self.volume.clone(), VOLUME_COLUMNS, TextureFormat::RU8),
+            (
+                float_bytes(&self.touch),
+                TOUCH_COLUMNS,
+                TextureFormat::RGBAF32,
+            ),
+        ];
+        for ((_, name), (bytes, width, format)) in TEXTURES.iter().zip(textures.iter()) {
+            effect.stage_resource(
+                name,
+                ResourceData::D2(ResourceData2D {
+                    bytes: bytes.clone(),
+                    width: *width as u32,
+                    height: HISTORY as u32,
+                    format: *format,
+                    xoffset: 0,
+                    yoffset: 0,
+                    subwidth: *width as u32,
+                    subheight: HISTORY as u32,
+                    time: state.time,
+                }),
+            );
+        }
+        effect.stage_uniform1f("time", state.time);
+        effect.stage_uniform2f("mouse", mouse);
+        effect.stage_uniform2f("soundRes", [bands as f32, HISTORY as f32]);
+    }
+}
+
+/// The JSON of an art page of vertexshaderart.com, with its settings and the vertex shader
+#[derive(Debug, Deserialize)]
+struct ArtJson {
+    #[serde(default, alias = "_id")]
+    id: String,
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    username: String,
+    /// An object, or the same object as a JSON string in older pages
+    settings: Value,
+}
+
+#[derive(Debug, Deserialize)]
+struct SettingsJson {
+    shader: String,
+    #[serde(default = "default_settings_num")]
+    num: u32,
+    #[serde(default = "default_settings_mode")]
+    mode: String,
+    /// SoundCloud URL of the music, empty if the art has none
+    #[serde(default)]
+    sound: String,
+    #[serde(
+        default = "default_settings_background_color",
+        rename = "backgroundColor"
+    )]
+    background_color: [f32; 4],
+}
+
+fn default_settings_num() -> u32 {
+    10000
+}
+
+fn default_settings_mode() -> String {
+    "LINES".to_string()
+}
+
+fn default_settings_background_color() -> [f32; 4] {
+    [0.0, 0.0, 0.0, 1.0]
+}
+
+/// Accepts the art object, an array of them, or the bare settings object
+fn parse_art(text: &str) -> std::result::Result<(ArtJson, SettingsJson), String> {
+    let mut value: Value = serde_json::from_str(text).map_err(|err| err.to_string())?;
+    if let Value::Array(arts) = value {
+        value = arts.into_iter().next().unwrap_or(Value::Null);
+    }
+    if value.get("settings").is_none() {
+        let mut art = serde_json::Map::new();
+        art.insert("settings".to_string(), value);
+        value = Value::Object(art);
+    }
+    let mut art: ArtJson = serde_json::from_value(value).map_err(|err| err.to_string())?;
+    if let Value::String(settings) = &art.settings {
+        art.settings = serde_json::from_str(settings).map_err(|err| err.to_string())?;
+    }
+    let settings = serde_json::from_value(art.settings.take()).map_err(|err| err.to_string())?;
+    Ok((art, settings))
+}
+
+/// Create a grimoire project from the JSON of a vertexshaderart art page.
+///
+/// Writes grim.toml, using the vertexshaderart profile, and art.glsl, the vertex shader.
+/// Returns a warning for every setting that could not be imported.
+pub fn import(settings: &ImportSettings) -> Result<Vec<Diagnostic>> {
+    let text =
+        std::fs::read_to_string(&settings.json).map_err(|err| Error::io(&settings.json, err))?;
+    let (art, art_settings) = parse_art(&text).map_err(|err| {
+        Error::vertexshaderart(format!(
+            "{} is not a vertexshaderart JSON export: {}",
+            settings.json.display(),
+            err
+        ))
+    })?;
+    let config_path = settings.output.join("grim.toml");
+    if config_path.exists() && !settings.force {
+        return Err(Error::vertexshaderart(format!(
+            "{} already exists, pass --force to overwrite it",
+            config_path.display()
+        )));
+    }
+    let mut warnings = Vec::new();
+    let mut warn = |message: String| {
+        warnings.push(Diagnostic::warning(message).with_file(&settings.json));
+    };
+
+    let mut title = format!("\"{}\"", art.name);
+    if !art.username.is_empty() {
+        write!(title, " by {}", art.username).unwrap();
+    }
+    if !art.id.is_empty() {
+        write!(title, ", https://www.vertexshaderart.com/art/{}", art.id).unwrap();
+    }
+    // grimoire counts primitives rather than vertices
+    let num = art_settings.num;
+    let (mode, count) = match art_settings.mode.as_str() {
+        "POINTS" => ("points", num),
+        "LINES" => ("lines", num / 2),
+        "LINE_STRIP" => ("line-strip", num / 2),
+        "LINE_LOOP" => ("line-loop", num / 2),
+        "TRIANGLES" => ("triangles", num / 3),
+        "TRIANGLE_STRIP" => ("triangle-strip", num.saturating_sub(2)),
+        "TRIANGLE_FAN" => ("triangle-fan", num / 3),
+        mode => {
+            warn(format!("Unknown draw mode \"{}\", drawing lines", mode));
+            ("lines", num / 2)
+        }
+    };
+    if !art_settings.sound.is_empty() {
+        warn(format!(
+            "Skipped the music {}, grimoire cannot stream it, save it and add an audio \
+             resource to feed sound, floatSound and volume",
+            art_settings.sound
+        ));
+    }
+
+    let mut config = String::new();
+    writeln!(config, "# {}", title).unwrap();
+    writeln!(
+        config,
+        "# Imported from {}",
+        settings
+            .json
+            .file_name()
+            .map(|name| name.to_string_lossy())
+            .unwrap_or_default()
+    )
+    .unwrap();
+    config.push_str("\nprofile = \"vertexshaderart\"\n");
+    config.push_str("\n[[pass]]\n");
+    let [r, g, b, a] = art_settings.background_color;
+    writeln!(config, "clear  = [{:?}, {:?}, {:?}, {:?}, 1.0]", r, g, b, a).unwrap();
+    config.push_str("vertex = \"./art.glsl\"\n");
+    writeln!(
+        config,
+        "draw   = {{mode = \"{}\", count = {}}}",
+        mode, count
+    )
+    .unwrap();
+    config.push_str("depth  = \"less\"\n");
+    config.push_str("blend  = {src = \"one\", dst = \"one-minus-src-alpha\"}\n");
+
+    let mut source = format!("// {}\n", title);
+    source.push_str(&art_settings.shader);
+    if !source.ends_with('\n') {
+        source.push('\n');
+    }
+    std::fs::create_dir_all(&settings.output).map_err(|err| Error::io(&settings.output, err))?;
+    for (name, contents) in &[("grim.toml", config), ("art.glsl", source)] {
+        let path = settings.output.join(name);
+        std::fs::write(&path, contents).map_err(|err| Error::io(&path, err))?;
+    }
+    Ok(warnings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{
+        ClearConfig, DepthFuncConfig, DepthTestConfig, DrawConfig, DrawModeConfig, ProfileConfig,
+    };
+
+    /// Imports `json` into a fresh project and returns its parsed config, its vertex
+    /// shader and the import warnings
+    fn import_art(name: &str, json: &str) -> (EffectConfig, String, Vec<Diagnostic>) {
+        let dir = std::env::temp_dir().join(format!("grimoire-{}-{}", std::process::id(), name));
+        std::fs::create_dir_all(&dir).unwrap();
+        let json_path = dir.join("art.json");
+        std::fs::write(&json_path, json).unwrap();
+        let output = dir.join("project");
+        let warnings = import(&ImportSettings {
+            json: json_path,
+            output: output.clone(),
+            force: true,
+        })
+        .unwrap();
+        let config = std::fs::read_to_string(output.join("grim.toml")).unwrap();
+        let source = std::fs::read_to_string(output.join("art.glsl")).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        (EffectConfig::from_toml(&config).unwrap(), source, warnings)
+    }
+
+    fn draw(config: &EffectConfig) -> (DrawModeConfig, u32) {
+        match &config.passes[0].draw {
+            DrawConfig::Raw(draw) => (draw.mode.clone(), draw.count),
+            draw => panic!("expected a raw draw, got {:?}", draw),
+        }
+    }
+
+    #[test]
+    fn import_config() {
+        let json = r#"{
+  "_id": "abc123", "name": "Dots", "username": "someone",
+  "settings": {"shader": "void main() { gl_Position = vec4(0); }", "num": 3000,
+               "mode": "TRIANGLES", "sound": "", "backgroundColor": [0.25, 0.5, 0.75, 1]}
+}"#;
+        let (config, source, warnings) = import_art("vsa-import", json);
+        assert!(warnings.is_empty(), "{:?}", warnings);
+        assert!(config.is_ok());
+        assert_eq!(config.profile, ProfileConfig::VertexShaderArt);
+        assert_eq!(config.passes.len(), 1);
+        let pass = &config.passes[0];
+        assert_eq!(pass.vertex, "./art.glsl");
+        assert_eq!(
+            pass.clear,
+            Some(ClearConfig::ColorDepth([0.25, 0.5, 0.75, 1.0, 1.0]))
+        );
+        assert_eq!(
+            pass.depth,
+            Some(DepthTestConfig::Simple(DepthFuncConfig::Less))
+        );
+        assert_eq!(draw(&config), (DrawModeConfig::Triangles, 1000));
+        assert_eq!(
+            source,
+            "// \"Dots\" by someone, https://www.vertexshaderart.com/art/abc123\n\
+             void main() { gl_Position = vec4(0); }\n"
+        );
+    }
+
+    #[test]
+    fn import_modes() {
+        let modes = [
+            ("POINTS", DrawModeConfig::Points, 1000),
+            ("LINES", DrawModeConfig::Lines, 500),
+            ("LINE_STRIP", DrawModeConfig::LineStrip, 500),
+            ("LINE_LOOP", DrawModeConfig::LineLoop, 500),
+            ("TRIANGLES", DrawModeConfig::Triangles, 333),
+            ("TRIANGLE_STRIP", DrawModeConfig::TriangleStrip, 998),
+            ("TRIANGLE_FAN", DrawModeConfig::TriangleFan, 333),
+        ];
+        for (mode, expected, count) in modes.iter() {
+            let json = format!(
+                r#"{{"shader": "void main() {{}}", "num": 1000, "mode": "{}"}}"#,
+                mode
+            );
+            let (config, _, warnings) = import_art(&format!("vsa-{}", mode), &json);
+            assert!(warnings.is_empty(), "{}: {:?}", mode, warnings);
+            assert_eq!(draw(&config), (expected.clone(), *count), "{}", mode);
+        }
+    }
+
+    #[test]
+    fn import_defaults_and_warnings() {
+        // Older pages keep the settings as a JSON string, in an array of arts
+        let json = r#"[{"name": "Old",
+  "settings": "{\"shader\": \"void main() {}\", \"mode\": \"QUADS\", \"sound\": \"https://soundcloud.com/a/b\"}"
+}]"#;
+        let (config, source, warnings) = import_art("vsa-warnings", json);
+        assert!(config.is_ok());
+        assert_eq!(
+            config.passes[0].clear,
+            Some(ClearConfig::ColorDepth([0.0, 0.0, 0.0, 1.0, 1.0]))
+        );
+        assert_eq!(draw(&config), (DrawModeConfig::Lines, 5000));
+        assert!(source.starts_with("// \"Old\"\n"));
+        let messages: Vec<_> = warnings.iter().map(|w| w.message.as_str()).collect();
+        assert_eq!(messages.len(), 2, "{:?}", messages);
+        assert_eq!(messages[0], "Unknown draw mode \"QUADS\", drawing lines");
+        assert!(messages[1].starts_with("Skipped the music https://soundcloud.com/a/b"));
+    }
+}
@@ -0,0 +1,19 @@
+/*
+BEGIN: vertexshaderart.glsl
+*/
+uniform vec3 iResolution;
+uniform int iVertexCount;
+uniform int iPassIndex;
+uniform mat4 iModelMatrix;
+uniform float time;
+uniform vec2 resolution;
+uniform vec2 mouse;
+uniform vec4 background;
+uniform float vertexCount;
+uniform vec2 soundRes;
+#define texture2D texture
+
+#define GRIMOIRE
+/*
+END: vertexshaderart.glsl
+*/